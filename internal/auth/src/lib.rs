@@ -1,3 +1 @@
-pub mod errors;
-pub mod jwt;
-pub mod result;
+pub mod magic_link;
@@ -1,3 +0,0 @@
-use crate::errors;
-
-pub type AuthResult<T> = std::result::Result<T, errors::AuthError>;
@@ -0,0 +1,3 @@
+pub mod command;
+pub mod content_service;
+pub mod query;
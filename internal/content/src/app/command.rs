@@ -0,0 +1,6 @@
+pub mod create_comment;
+pub mod create_post;
+pub mod delete_comment;
+pub mod delete_post;
+pub mod update_comment;
+pub mod update_post;
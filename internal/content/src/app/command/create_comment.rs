@@ -0,0 +1,182 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    command_handler::CommandHanlder,
+    errors::{app::AppError, content::ContentDomainError},
+    guards::permissions::ContentPermission,
+    types::AppResult,
+};
+
+use crate::domain::comment::{Comment, CommentNode};
+use crate::guards::ContentGuards;
+use crate::infra::repository::{
+    comment_repository::CommentRepository, post_repository::PostRepository,
+};
+
+/// A comment on a post, or a reply to `parent_id` when given.
+#[derive(Debug, Clone, Deserialize, InputObject)]
+pub struct CreateComment {
+    pub post_id: String,
+    pub parent_id: Option<String>,
+    pub body: String,
+}
+
+pub struct CreateCommentHandler {
+    post_repo: Arc<PostRepository>,
+    comment_repo: Arc<CommentRepository>,
+    guard: Arc<dyn ContentGuards>,
+}
+
+impl CreateCommentHandler {
+    pub fn new(
+        post_repo: Arc<PostRepository>,
+        comment_repo: Arc<CommentRepository>,
+        guard: Arc<dyn ContentGuards>,
+    ) -> Self {
+        Self {
+            post_repo,
+            comment_repo,
+            guard,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<CreateComment, AppError, CommentNode> for CreateCommentHandler {
+    #[tracing::instrument(name = "command.create_comment", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: CreateComment) -> AppResult<CommentNode> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &ContentPermission::CreateComment)?;
+        if self.post_repo.get_post(&cmd.post_id).await?.is_none() {
+            return Err(ContentDomainError::PostNotFound.into());
+        }
+        let parent = match &cmd.parent_id {
+            Some(parent_id) => Some(
+                self.comment_repo
+                    .get_comment(parent_id)
+                    .await?
+                    .ok_or(ContentDomainError::CommentNotFound)?,
+            ),
+            None => None,
+        };
+        let comment = Comment::new(
+            cmd.post_id,
+            auth_user.0.id.clone(),
+            parent.as_ref(),
+            cmd.body,
+        )?;
+        self.comment_repo.create_comment(comment.clone()).await?;
+        Ok(CommentNode::unexpanded(comment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::post::Post;
+    use crate::guards::MockContentGuards;
+    use crate::infra::repository::{
+        comment_repository_trait::MockCommentRepositoryTrait,
+        post_repository_trait::MockPostRepositoryTrait,
+    };
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn reply_to_comment_success() {
+        let mut mock_post_repo = MockPostRepositoryTrait::new();
+        mock_post_repo
+            .expect_get_post()
+            .returning(|_| Ok(Some(Post::new_test_post("author-id"))));
+        let mut mock_comment_repo = MockCommentRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_comment_repo
+            .expect_get_comment()
+            .withf(|id| id == "parent-id")
+            .returning(|_| Ok(Some(Comment::new_test_comment("post-id", "author-id"))));
+        mock_comment_repo
+            .expect_create_comment()
+            .withf(|comment| *comment.depth() == 1 && comment.author_id() == "test-user-id")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let handler = CreateCommentHandler::new(
+            Arc::new(PostRepository::Mock(mock_post_repo)),
+            Arc::new(CommentRepository::Mock(mock_comment_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = CreateComment {
+            post_id: "post-id".into(),
+            parent_id: Some("parent-id".into()),
+            body: "Agreed".into(),
+        };
+        let node = handler.handle(&ctx, cmd).await.unwrap();
+        assert_eq!(node.replies.total_count, 0);
+    }
+
+    #[tokio::test]
+    async fn comment_on_missing_post() {
+        let mut mock_post_repo = MockPostRepositoryTrait::new();
+        mock_post_repo.expect_get_post().returning(|_| Ok(None));
+        let mut mock_comment_repo = MockCommentRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_comment_repo.expect_create_comment().never();
+
+        let handler = CreateCommentHandler::new(
+            Arc::new(PostRepository::Mock(mock_post_repo)),
+            Arc::new(CommentRepository::Mock(mock_comment_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = CreateComment {
+            post_id: "post-id".into(),
+            parent_id: None,
+            body: "Agreed".into(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(AppError::Content(ContentDomainError::PostNotFound))
+        ));
+    }
+
+    #[tokio::test]
+    async fn reply_to_missing_comment() {
+        let mut mock_post_repo = MockPostRepositoryTrait::new();
+        mock_post_repo
+            .expect_get_post()
+            .returning(|_| Ok(Some(Post::new_test_post("author-id"))));
+        let mut mock_comment_repo = MockCommentRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_comment_repo
+            .expect_get_comment()
+            .returning(|_| Ok(None));
+        mock_comment_repo.expect_create_comment().never();
+
+        let handler = CreateCommentHandler::new(
+            Arc::new(PostRepository::Mock(mock_post_repo)),
+            Arc::new(CommentRepository::Mock(mock_comment_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = CreateComment {
+            post_id: "post-id".into(),
+            parent_id: Some("parent-id".into()),
+            body: "Agreed".into(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(AppError::Content(ContentDomainError::CommentNotFound))
+        ));
+    }
+}
@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    command_handler::CommandHanlder,
+    errors::app::AppError,
+    guards::permissions::ContentPermission,
+    types::AppResult,
+};
+
+use crate::domain::post::Post;
+use crate::guards::ContentGuards;
+use crate::infra::repository::post_repository::PostRepository;
+
+#[derive(Debug, Clone, Deserialize, InputObject)]
+pub struct CreatePost {
+    pub title: String,
+    pub body: String,
+}
+
+pub struct CreatePostHandler {
+    post_repo: Arc<PostRepository>,
+    guard: Arc<dyn ContentGuards>,
+}
+
+impl CreatePostHandler {
+    pub fn new(post_repo: Arc<PostRepository>, guard: Arc<dyn ContentGuards>) -> Self {
+        Self { post_repo, guard }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<CreatePost, AppError, Post> for CreatePostHandler {
+    #[tracing::instrument(name = "command.create_post", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: CreatePost) -> AppResult<Post> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &ContentPermission::CreatePost)?;
+        let post = Post::new(auth_user.0.id.clone(), cmd.title, cmd.body)?;
+        self.post_repo.create_post(post.clone()).await?;
+        Ok(post)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guards::MockContentGuards;
+    use crate::infra::repository::post_repository_trait::MockPostRepositoryTrait;
+    use mockall::predicate::eq;
+    use shared::{auth::AuthUser, errors::content::ContentDomainError, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn create_post_success() {
+        let mut mock_repo = MockPostRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard
+            .expect_authorize()
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(ContentPermission::CreatePost),
+            )
+            .returning(|_, _| Ok(()));
+        mock_repo
+            .expect_create_post()
+            .withf(|post| post.author_id() == "test-user-id" && post.title() == "Hello")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let handler = CreatePostHandler::new(
+            Arc::new(PostRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = CreatePost {
+            title: " Hello ".into(),
+            body: "First post".into(),
+        };
+        let post = handler.handle(&ctx, cmd).await.unwrap();
+        assert_eq!(post.title(), "Hello");
+    }
+
+    #[tokio::test]
+    async fn create_post_unauthorized() {
+        let mut mock_repo = MockPostRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard
+            .expect_authorize()
+            .returning(|_, _| Err(ContentDomainError::Unauthorized.into()));
+        mock_repo.expect_create_post().never();
+
+        let handler = CreatePostHandler::new(
+            Arc::new(PostRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let cmd = CreatePost {
+            title: "Hello".into(),
+            body: "First post".into(),
+        };
+        assert!(handler.handle(&ctx, cmd).await.is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    command_handler::CommandHanlder,
+    errors::{app::AppError, content::ContentDomainError},
+    guards::permissions::ContentPermission,
+    types::AppResult,
+};
+
+use crate::guards::ContentGuards;
+use crate::infra::repository::comment_repository::CommentRepository;
+
+/// Soft-deletes a comment: its body is replaced by a placeholder and its
+/// replies stay. Deleting a deleted comment does nothing.
+#[derive(Debug, Clone, Deserialize, InputObject)]
+pub struct DeleteComment {
+    pub id: String,
+}
+
+pub struct DeleteCommentHandler {
+    comment_repo: Arc<CommentRepository>,
+    guard: Arc<dyn ContentGuards>,
+}
+
+impl DeleteCommentHandler {
+    pub fn new(comment_repo: Arc<CommentRepository>, guard: Arc<dyn ContentGuards>) -> Self {
+        Self {
+            comment_repo,
+            guard,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<DeleteComment, AppError> for DeleteCommentHandler {
+    #[tracing::instrument(name = "command.delete_comment", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: DeleteComment) -> AppResult<()> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &ContentPermission::DeleteComment)?;
+        let mut comment = self
+            .comment_repo
+            .get_comment(&cmd.id)
+            .await?
+            .ok_or(ContentDomainError::CommentNotFound)?;
+        self.guard
+            .can_delete_comment(comment.author_id(), auth_user)?;
+        if *comment.deleted() {
+            return Ok(());
+        }
+        comment.soft_delete();
+        self.comment_repo.update_comment(comment).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::comment::{Comment, DELETED_COMMENT_BODY};
+    use crate::guards::MockContentGuards;
+    use crate::infra::repository::comment_repository_trait::MockCommentRepositoryTrait;
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn moderator_deletes_comment() {
+        let mut mock_repo = MockCommentRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_guard
+            .expect_can_delete_comment()
+            .withf(|author_id, _| author_id == "author-id")
+            .returning(|_, _| Ok(()));
+        mock_repo
+            .expect_get_comment()
+            .returning(|_| Ok(Some(Comment::new_test_comment("post-id", "author-id"))));
+        mock_repo
+            .expect_update_comment()
+            .withf(|comment| *comment.deleted() && comment.body() == DELETED_COMMENT_BODY)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let handler = DeleteCommentHandler::new(
+            Arc::new(CommentRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let cmd = DeleteComment {
+            id: "comment-id".into(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Moderator));
+        assert!(handler.handle(&ctx, cmd).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn deleting_twice_writes_once() {
+        let mut mock_repo = MockCommentRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_guard
+            .expect_can_delete_comment()
+            .returning(|_, _| Ok(()));
+        mock_repo.expect_get_comment().returning(|_| {
+            let mut comment = Comment::new_test_comment("post-id", "test-user-id");
+            comment.soft_delete();
+            Ok(Some(comment))
+        });
+        mock_repo.expect_update_comment().never();
+
+        let handler = DeleteCommentHandler::new(
+            Arc::new(CommentRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let cmd = DeleteComment {
+            id: "comment-id".into(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        assert!(handler.handle(&ctx, cmd).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn delete_comment_refused_by_guard() {
+        let mut mock_repo = MockCommentRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_guard
+            .expect_can_delete_comment()
+            .returning(|_, _| Err(ContentDomainError::Unauthorized.into()));
+        mock_repo
+            .expect_get_comment()
+            .returning(|_| Ok(Some(Comment::new_test_comment("post-id", "author-id"))));
+        mock_repo.expect_update_comment().never();
+
+        let handler = DeleteCommentHandler::new(
+            Arc::new(CommentRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let cmd = DeleteComment {
+            id: "comment-id".into(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        assert!(handler.handle(&ctx, cmd).await.is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    command_handler::CommandHanlder,
+    errors::{app::AppError, content::ContentDomainError},
+    guards::permissions::ContentPermission,
+    types::AppResult,
+};
+
+use crate::guards::ContentGuards;
+use crate::infra::repository::{
+    comment_repository::CommentRepository, post_repository::PostRepository,
+};
+
+#[derive(Debug, Clone, Deserialize, InputObject)]
+pub struct DeletePost {
+    pub id: String,
+}
+
+/// Deletes the post along with its comments.
+pub struct DeletePostHandler {
+    post_repo: Arc<PostRepository>,
+    comment_repo: Arc<CommentRepository>,
+    guard: Arc<dyn ContentGuards>,
+}
+
+impl DeletePostHandler {
+    pub fn new(
+        post_repo: Arc<PostRepository>,
+        comment_repo: Arc<CommentRepository>,
+        guard: Arc<dyn ContentGuards>,
+    ) -> Self {
+        Self {
+            post_repo,
+            comment_repo,
+            guard,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<DeletePost, AppError> for DeletePostHandler {
+    #[tracing::instrument(name = "command.delete_post", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: DeletePost) -> AppResult<()> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &ContentPermission::DeletePost)?;
+        let post = self
+            .post_repo
+            .get_post(&cmd.id)
+            .await?
+            .ok_or(ContentDomainError::PostNotFound)?;
+        self.guard.can_delete_post(post.author_id(), auth_user)?;
+        if !self.post_repo.delete_post(&cmd.id).await? {
+            return Err(ContentDomainError::PostNotFound.into());
+        }
+        self.comment_repo.delete_post_comments(&cmd.id).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::post::Post;
+    use crate::guards::MockContentGuards;
+    use crate::infra::repository::{
+        comment_repository_trait::MockCommentRepositoryTrait,
+        post_repository_trait::MockPostRepositoryTrait,
+    };
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn delete_post_success() {
+        let mut mock_repo = MockPostRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_guard
+            .expect_can_delete_post()
+            .withf(|author_id, _| author_id == "author-id")
+            .returning(|_, _| Ok(()));
+        mock_repo
+            .expect_get_post()
+            .returning(|_| Ok(Some(Post::new_test_post("author-id"))));
+        mock_repo
+            .expect_delete_post()
+            .times(1)
+            .returning(|_| Ok(true));
+        let mut mock_comment_repo = MockCommentRepositoryTrait::new();
+        mock_comment_repo
+            .expect_delete_post_comments()
+            .withf(|post_id| post_id == "post-id")
+            .times(1)
+            .returning(|_| Ok(3));
+
+        let handler = DeletePostHandler::new(
+            Arc::new(PostRepository::Mock(mock_repo)),
+            Arc::new(CommentRepository::Mock(mock_comment_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Moderator));
+        let cmd = DeletePost {
+            id: "post-id".into(),
+        };
+        assert!(handler.handle(&ctx, cmd).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn delete_post_refused_by_guard() {
+        let mut mock_repo = MockPostRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_guard
+            .expect_can_delete_post()
+            .returning(|_, _| Err(ContentDomainError::Unauthorized.into()));
+        mock_repo
+            .expect_get_post()
+            .returning(|_| Ok(Some(Post::new_test_post("author-id"))));
+        mock_repo.expect_delete_post().never();
+        let mut mock_comment_repo = MockCommentRepositoryTrait::new();
+        mock_comment_repo.expect_delete_post_comments().never();
+
+        let handler = DeletePostHandler::new(
+            Arc::new(PostRepository::Mock(mock_repo)),
+            Arc::new(CommentRepository::Mock(mock_comment_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = DeletePost {
+            id: "post-id".into(),
+        };
+        assert!(handler.handle(&ctx, cmd).await.is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    command_handler::CommandHanlder,
+    errors::{app::AppError, content::ContentDomainError},
+    guards::permissions::ContentPermission,
+    types::AppResult,
+};
+
+use crate::domain::comment::CommentNode;
+use crate::guards::ContentGuards;
+use crate::infra::repository::comment_repository::CommentRepository;
+
+/// Replaces the body. Only the author may edit a comment, and deleted comments stay deleted.
+#[derive(Debug, Clone, Deserialize, InputObject)]
+pub struct UpdateComment {
+    pub id: String,
+    pub body: String,
+}
+
+pub struct UpdateCommentHandler {
+    comment_repo: Arc<CommentRepository>,
+    guard: Arc<dyn ContentGuards>,
+}
+
+impl UpdateCommentHandler {
+    pub fn new(comment_repo: Arc<CommentRepository>, guard: Arc<dyn ContentGuards>) -> Self {
+        Self {
+            comment_repo,
+            guard,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<UpdateComment, AppError, CommentNode> for UpdateCommentHandler {
+    #[tracing::instrument(name = "command.update_comment", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: UpdateComment) -> AppResult<CommentNode> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &ContentPermission::UpdateComment)?;
+        let mut comment = self
+            .comment_repo
+            .get_comment(&cmd.id)
+            .await?
+            .ok_or(ContentDomainError::CommentNotFound)?;
+        self.guard
+            .can_update_comment(comment.author_id(), auth_user)?;
+        comment.edit(cmd.body)?;
+        self.comment_repo.update_comment(comment.clone()).await?;
+        Ok(CommentNode::unexpanded(comment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::comment::Comment;
+    use crate::guards::MockContentGuards;
+    use crate::infra::repository::comment_repository_trait::MockCommentRepositoryTrait;
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn update_comment_success() {
+        let mut mock_repo = MockCommentRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_guard
+            .expect_can_update_comment()
+            .withf(|author_id, _| author_id == "test-user-id")
+            .returning(|_, _| Ok(()));
+        mock_repo
+            .expect_get_comment()
+            .returning(|_| Ok(Some(Comment::new_test_comment("post-id", "test-user-id"))));
+        mock_repo
+            .expect_update_comment()
+            .withf(|comment| comment.body() == "Edited")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let handler = UpdateCommentHandler::new(
+            Arc::new(CommentRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let cmd = UpdateComment {
+            id: "comment-id".into(),
+            body: "Edited".into(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let node = handler.handle(&ctx, cmd).await.unwrap();
+        assert_eq!(node.comment.body(), "Edited");
+    }
+
+    #[tokio::test]
+    async fn deleted_comment_cannot_be_edited() {
+        let mut mock_repo = MockCommentRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_guard
+            .expect_can_update_comment()
+            .returning(|_, _| Ok(()));
+        mock_repo.expect_get_comment().returning(|_| {
+            let mut comment = Comment::new_test_comment("post-id", "test-user-id");
+            comment.soft_delete();
+            Ok(Some(comment))
+        });
+        mock_repo.expect_update_comment().never();
+
+        let handler = UpdateCommentHandler::new(
+            Arc::new(CommentRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let cmd = UpdateComment {
+            id: "comment-id".into(),
+            body: "Edited".into(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(AppError::Content(ContentDomainError::CommentDeleted))
+        ));
+    }
+
+    #[tokio::test]
+    async fn update_comment_by_someone_else_is_refused() {
+        let mut mock_repo = MockCommentRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_guard
+            .expect_can_update_comment()
+            .returning(|_, _| Err(ContentDomainError::Unauthorized.into()));
+        mock_repo
+            .expect_get_comment()
+            .returning(|_| Ok(Some(Comment::new_test_comment("post-id", "author-id"))));
+        mock_repo.expect_update_comment().never();
+
+        let handler = UpdateCommentHandler::new(
+            Arc::new(CommentRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let cmd = UpdateComment {
+            id: "comment-id".into(),
+            body: "Edited".into(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Moderator));
+        assert!(handler.handle(&ctx, cmd).await.is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    command_handler::CommandHanlder,
+    errors::{app::AppError, content::ContentDomainError},
+    guards::permissions::ContentPermission,
+    types::AppResult,
+};
+
+use crate::domain::post::Post;
+use crate::guards::ContentGuards;
+use crate::infra::repository::post_repository::PostRepository;
+
+/// Replaces the title, the body, or both. Only the author may edit a post.
+#[derive(Debug, Clone, Deserialize, InputObject)]
+pub struct UpdatePost {
+    pub id: String,
+    pub title: Option<String>,
+    pub body: Option<String>,
+}
+
+pub struct UpdatePostHandler {
+    post_repo: Arc<PostRepository>,
+    guard: Arc<dyn ContentGuards>,
+}
+
+impl UpdatePostHandler {
+    pub fn new(post_repo: Arc<PostRepository>, guard: Arc<dyn ContentGuards>) -> Self {
+        Self { post_repo, guard }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<UpdatePost, AppError, Post> for UpdatePostHandler {
+    #[tracing::instrument(name = "command.update_post", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: UpdatePost) -> AppResult<Post> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &ContentPermission::UpdatePost)?;
+        let mut post = self
+            .post_repo
+            .get_post(&cmd.id)
+            .await?
+            .ok_or(ContentDomainError::PostNotFound)?;
+        self.guard.can_update_post(post.author_id(), auth_user)?;
+        post.edit(cmd.title, cmd.body)?;
+        self.post_repo.update_post(post.clone()).await?;
+        Ok(post)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guards::MockContentGuards;
+    use crate::infra::repository::post_repository_trait::MockPostRepositoryTrait;
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn update_post_success() {
+        let mut mock_repo = MockPostRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_guard
+            .expect_can_update_post()
+            .withf(|author_id, _| author_id == "test-user-id")
+            .returning(|_, _| Ok(()));
+        mock_repo
+            .expect_get_post()
+            .returning(|_| Ok(Some(Post::new_test_post("test-user-id"))));
+        mock_repo
+            .expect_update_post()
+            .withf(|post| post.title() == "Edited" && post.body() == "First post")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let handler = UpdatePostHandler::new(
+            Arc::new(PostRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let cmd = UpdatePost {
+            id: "post-id".into(),
+            title: Some("Edited".into()),
+            body: None,
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let post = handler.handle(&ctx, cmd).await.unwrap();
+        assert_eq!(post.title(), "Edited");
+    }
+
+    #[tokio::test]
+    async fn update_post_by_someone_else_is_refused() {
+        let mut mock_repo = MockPostRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_guard
+            .expect_can_update_post()
+            .returning(|_, _| Err(ContentDomainError::Unauthorized.into()));
+        mock_repo
+            .expect_get_post()
+            .returning(|_| Ok(Some(Post::new_test_post("author-id"))));
+        mock_repo.expect_update_post().never();
+
+        let handler = UpdatePostHandler::new(
+            Arc::new(PostRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let cmd = UpdatePost {
+            id: "post-id".into(),
+            title: Some("Edited".into()),
+            body: None,
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(AppError::Content(ContentDomainError::Unauthorized))
+        ));
+    }
+
+    #[tokio::test]
+    async fn update_missing_post() {
+        let mut mock_repo = MockPostRepositoryTrait::new();
+        let mut mock_guard = MockContentGuards::new();
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_repo.expect_get_post().returning(|_| Ok(None));
+
+        let handler = UpdatePostHandler::new(
+            Arc::new(PostRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let cmd = UpdatePost {
+            id: "post-id".into(),
+            title: Some("Edited".into()),
+            body: None,
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(AppError::Content(ContentDomainError::PostNotFound))
+        ));
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use crate::guards::ContentGuards;
+use crate::infra::repository::{
+    comment_repository::CommentRepository, post_repository::PostRepository,
+};
+
+use super::{
+    command::{
+        create_comment::CreateCommentHandler, create_post::CreatePostHandler,
+        delete_comment::DeleteCommentHandler, delete_post::DeletePostHandler,
+        update_comment::UpdateCommentHandler, update_post::UpdatePostHandler,
+    },
+    query::{comments::GetCommentsHandler, post_by_id::GetPostByIdHandler, posts::GetPostsHandler},
+};
+
+pub struct ContentService {
+    pub command_handler: CommandHandler,
+    pub query_handler: QueryHandler,
+}
+
+impl ContentService {
+    pub fn new(
+        post_repo: Arc<PostRepository>,
+        comment_repo: Arc<CommentRepository>,
+        guard: Arc<dyn ContentGuards>,
+    ) -> Self {
+        Self {
+            command_handler: CommandHandler {
+                create_post: CreatePostHandler::new(post_repo.clone(), guard.clone()),
+                update_post: UpdatePostHandler::new(post_repo.clone(), guard.clone()),
+                delete_post: DeletePostHandler::new(
+                    post_repo.clone(),
+                    comment_repo.clone(),
+                    guard.clone(),
+                ),
+                create_comment: CreateCommentHandler::new(
+                    post_repo.clone(),
+                    comment_repo.clone(),
+                    guard.clone(),
+                ),
+                update_comment: UpdateCommentHandler::new(comment_repo.clone(), guard.clone()),
+                delete_comment: DeleteCommentHandler::new(comment_repo.clone(), guard),
+            },
+            query_handler: QueryHandler {
+                get_post_by_id: GetPostByIdHandler::new(post_repo.clone()),
+                get_posts: GetPostsHandler::new(post_repo.clone()),
+                get_comments: GetCommentsHandler::new(post_repo, comment_repo),
+            },
+        }
+    }
+}
+
+pub struct CommandHandler {
+    pub create_post: CreatePostHandler,
+    pub update_post: UpdatePostHandler,
+    pub delete_post: DeletePostHandler,
+    pub create_comment: CreateCommentHandler,
+    pub update_comment: UpdateCommentHandler,
+    pub delete_comment: DeleteCommentHandler,
+}
+
+pub struct QueryHandler {
+    pub get_post_by_id: GetPostByIdHandler,
+    pub get_posts: GetPostsHandler,
+    pub get_comments: GetCommentsHandler,
+}
@@ -0,0 +1,3 @@
+pub mod comments;
+pub mod post_by_id;
+pub mod posts;
@@ -0,0 +1,242 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use validator::Validate;
+
+use shared::{
+    auth::AppContext,
+    errors::{app::AppError, content::ContentDomainError},
+    query_handler::QueryHandler,
+    types::AppResult,
+};
+
+use crate::domain::comment::{
+    Comment, CommentNode, CommentPage, CommentPosition, GetCommentsOptions,
+    MAX_COMMENT_TREE_LEVELS, MAX_COMMENTS_PAGE_SIZE, MAX_REPLIES_PAGE_SIZE,
+};
+use crate::infra::repository::{
+    comment_repository::CommentRepository, post_repository::PostRepository,
+};
+
+/// A page of a comment thread, oldest first: the top level comments of a post,
+/// or the replies to `parent_id`. Each comment comes with the first
+/// `replies_first` of its replies, down to `levels` levels. Every level has its
+/// own count and cursor; continue one with its parent as `parent_id`.
+/// Comments are public, so reading them needs no permission.
+#[derive(Debug, Clone, Validate, InputObject)]
+pub struct GetComments {
+    pub post_id: String,
+    pub parent_id: Option<String>,
+    #[graphql(default = 20)]
+    #[validate(range(min = 1, max = MAX_COMMENTS_PAGE_SIZE))]
+    pub first: u32,
+    pub after: Option<String>,
+    #[graphql(default = 2)]
+    #[validate(range(min = 1, max = MAX_COMMENT_TREE_LEVELS))]
+    pub levels: u32,
+    #[graphql(default = 5)]
+    #[validate(range(min = 1, max = MAX_REPLIES_PAGE_SIZE))]
+    pub replies_first: u32,
+}
+
+pub struct GetCommentsHandler {
+    post_repo: Arc<PostRepository>,
+    comment_repo: Arc<CommentRepository>,
+}
+
+impl GetCommentsHandler {
+    pub fn new(post_repo: Arc<PostRepository>, comment_repo: Arc<CommentRepository>) -> Self {
+        Self {
+            post_repo,
+            comment_repo,
+        }
+    }
+}
+
+#[async_trait]
+impl QueryHandler<GetComments, CommentPage, AppError> for GetCommentsHandler {
+    #[tracing::instrument(name = "query.comments", skip_all)]
+    async fn handle(&self, _ctx: &AppContext, cmd: GetComments) -> AppResult<CommentPage> {
+        cmd.validate()?;
+        if self.post_repo.get_post(&cmd.post_id).await?.is_none() {
+            return Err(ContentDomainError::PostNotFound.into());
+        }
+        let opts = GetCommentsOptions {
+            post_id: cmd.post_id,
+            parent_id: cmd.parent_id,
+            first: cmd.first,
+            after: cmd
+                .after
+                .as_deref()
+                .map(CommentPosition::from_cursor)
+                .transpose()?,
+        };
+        let top = self.comment_repo.list_comments(&opts).await?;
+        let total_count = self
+            .comment_repo
+            .count_comments(&opts.post_id, opts.parent_id)
+            .await?;
+
+        // One query per level below the first, for every parent on it at once.
+        let mut replies = HashMap::new();
+        let mut parent_ids = with_replies(top.comments.iter());
+        for _ in 1..cmd.levels {
+            if parent_ids.is_empty() {
+                break;
+            }
+            let level = self
+                .comment_repo
+                .list_replies(&parent_ids, cmd.replies_first)
+                .await?;
+            parent_ids = with_replies(level.values().flat_map(|l| l.comments.iter()));
+            replies.extend(level);
+        }
+
+        Ok(CommentPage {
+            comments: top
+                .comments
+                .into_iter()
+                .map(|comment| CommentNode::build(comment, &mut replies))
+                .collect(),
+            total_count,
+            has_next: top.has_next,
+        })
+    }
+}
+
+/// Ids of the comments that have replies to load.
+fn with_replies<'a>(comments: impl Iterator<Item = &'a Comment>) -> Vec<String> {
+    comments
+        .filter(|c| *c.reply_count() > 0)
+        .map(|c| c.id().clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{comment::ListCommentsResult, post::Post};
+    use crate::infra::repository::{
+        comment_repository_trait::MockCommentRepositoryTrait,
+        post_repository_trait::MockPostRepositoryTrait,
+    };
+
+    #[tokio::test]
+    async fn get_comments_loads_replies_level_by_level() {
+        let root = Comment::new_test_comment("post-id", "author-id").with_reply_count(1);
+        let leaf = Comment::new_test_comment("post-id", "author-id");
+        let reply = Comment::new(
+            "post-id".into(),
+            "author-id".into(),
+            Some(&root),
+            "Yes".into(),
+        )
+        .unwrap()
+        .with_reply_count(4);
+        let root_id = root.id().clone();
+        let reply_id = reply.id().clone();
+
+        let mut mock_repo = MockCommentRepositoryTrait::new();
+        mock_repo.expect_list_comments().returning(move |_| {
+            Ok(ListCommentsResult {
+                comments: vec![root.clone(), leaf.clone()],
+                has_next: false,
+            })
+        });
+        mock_repo
+            .expect_count_comments()
+            .withf(|post_id, parent_id| post_id == "post-id" && parent_id.is_none())
+            .returning(|_, _| Ok(2));
+        let expected_parents = vec![root_id.clone()];
+        mock_repo
+            .expect_list_replies()
+            .withf(move |parent_ids, first| parent_ids == expected_parents && *first == 5)
+            .times(1)
+            .returning(move |_, _| {
+                Ok(HashMap::from([(
+                    root_id.clone(),
+                    ListCommentsResult {
+                        comments: vec![reply.clone()],
+                        has_next: false,
+                    },
+                )]))
+            });
+
+        let mut mock_post_repo = MockPostRepositoryTrait::new();
+        mock_post_repo
+            .expect_get_post()
+            .returning(|_| Ok(Some(Post::new_test_post("author-id"))));
+
+        let cmd = GetComments {
+            post_id: "post-id".into(),
+            parent_id: None,
+            first: 20,
+            after: None,
+            levels: 2,
+            replies_first: 5,
+        };
+        let handler = GetCommentsHandler::new(
+            Arc::new(PostRepository::Mock(mock_post_repo)),
+            Arc::new(CommentRepository::Mock(mock_repo)),
+        );
+        let page = handler.handle(&AppContext::new(), cmd).await.unwrap();
+
+        assert_eq!(page.total_count, 2);
+        assert_eq!(page.comments.len(), 2);
+        let replies = &page.comments[0].replies;
+        assert_eq!(replies.total_count, 1);
+        assert_eq!(replies.comments[0].comment.id(), &reply_id);
+        // The last level is counted but not loaded.
+        assert_eq!(replies.comments[0].replies.total_count, 4);
+        assert!(replies.comments[0].replies.comments.is_empty());
+        assert!(page.comments[1].replies.comments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn get_comments_rejects_too_many_levels() {
+        let mut mock_repo = MockCommentRepositoryTrait::new();
+        mock_repo.expect_list_comments().never();
+        let cmd = GetComments {
+            post_id: "post-id".into(),
+            parent_id: None,
+            first: 20,
+            after: None,
+            levels: MAX_COMMENT_TREE_LEVELS + 1,
+            replies_first: 5,
+        };
+        let handler = GetCommentsHandler::new(
+            Arc::new(PostRepository::Mock(MockPostRepositoryTrait::new())),
+            Arc::new(CommentRepository::Mock(mock_repo)),
+        );
+
+        let result = handler.handle(&AppContext::new(), cmd).await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn get_comments_on_missing_post() {
+        let mut mock_post_repo = MockPostRepositoryTrait::new();
+        mock_post_repo.expect_get_post().returning(|_| Ok(None));
+        let mut mock_repo = MockCommentRepositoryTrait::new();
+        mock_repo.expect_list_comments().never();
+        let cmd = GetComments {
+            post_id: "post-id".into(),
+            parent_id: None,
+            first: 20,
+            after: None,
+            levels: 2,
+            replies_first: 5,
+        };
+        let handler = GetCommentsHandler::new(
+            Arc::new(PostRepository::Mock(mock_post_repo)),
+            Arc::new(CommentRepository::Mock(mock_repo)),
+        );
+
+        let result = handler.handle(&AppContext::new(), cmd).await;
+        assert!(matches!(
+            result,
+            Err(AppError::Content(ContentDomainError::PostNotFound))
+        ));
+    }
+}
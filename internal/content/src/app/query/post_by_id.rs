@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use shared::{
+    auth::AppContext,
+    errors::{app::AppError, content::ContentDomainError},
+    query_handler::QueryHandler,
+    types::AppResult,
+};
+
+use crate::domain::post::Post;
+use crate::infra::repository::post_repository::PostRepository;
+
+/// Posts are public, so reading one needs no permission.
+pub struct GetPostById {
+    pub id: String,
+}
+
+pub struct GetPostByIdHandler {
+    post_repo: Arc<PostRepository>,
+}
+
+impl GetPostByIdHandler {
+    pub fn new(post_repo: Arc<PostRepository>) -> Self {
+        Self { post_repo }
+    }
+}
+
+#[async_trait]
+impl QueryHandler<GetPostById, Post, AppError> for GetPostByIdHandler {
+    #[tracing::instrument(name = "query.post_by_id", skip_all)]
+    async fn handle(&self, _ctx: &AppContext, cmd: GetPostById) -> AppResult<Post> {
+        self.post_repo
+            .get_post(&cmd.id)
+            .await?
+            .ok_or(ContentDomainError::PostNotFound.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::repository::post_repository_trait::MockPostRepositoryTrait;
+
+    #[tokio::test]
+    async fn get_post_by_id_success() {
+        let mut mock_repo = MockPostRepositoryTrait::new();
+        mock_repo
+            .expect_get_post()
+            .withf(|id| id == "post-id")
+            .returning(|_| Ok(Some(Post::new_test_post("author-id"))));
+
+        let handler = GetPostByIdHandler::new(Arc::new(PostRepository::Mock(mock_repo)));
+        let cmd = GetPostById {
+            id: "post-id".into(),
+        };
+        let result = handler.handle(&AppContext::new(), cmd).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn get_post_by_id_not_found() {
+        let mut mock_repo = MockPostRepositoryTrait::new();
+        mock_repo.expect_get_post().returning(|_| Ok(None));
+
+        let handler = GetPostByIdHandler::new(Arc::new(PostRepository::Mock(mock_repo)));
+        let cmd = GetPostById {
+            id: "post-id".into(),
+        };
+        let result = handler.handle(&AppContext::new(), cmd).await;
+        assert!(matches!(
+            result,
+            Err(AppError::Content(ContentDomainError::PostNotFound))
+        ));
+    }
+}
@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use validator::Validate;
+
+use shared::{
+    auth::AppContext, errors::app::AppError, query_handler::QueryHandler, types::AppResult,
+};
+
+use crate::domain::post::{GetPostsOptions, MAX_POSTS_PAGE_SIZE, PostPage, PostPosition};
+use crate::infra::repository::post_repository::PostRepository;
+
+/// A page of posts, newest first, optionally by one author. Posts are public,
+/// so listing them needs no permission.
+#[derive(Debug, Clone, Validate, InputObject)]
+pub struct GetPosts {
+    pub author_id: Option<String>,
+    #[graphql(default = 20)]
+    #[validate(range(min = 1, max = MAX_POSTS_PAGE_SIZE))]
+    pub first: u32,
+    pub after: Option<String>,
+}
+
+pub struct GetPostsHandler {
+    post_repo: Arc<PostRepository>,
+}
+
+impl GetPostsHandler {
+    pub fn new(post_repo: Arc<PostRepository>) -> Self {
+        Self { post_repo }
+    }
+}
+
+#[async_trait]
+impl QueryHandler<GetPosts, PostPage, AppError> for GetPostsHandler {
+    #[tracing::instrument(name = "query.posts", skip_all)]
+    async fn handle(&self, _ctx: &AppContext, cmd: GetPosts) -> AppResult<PostPage> {
+        cmd.validate()?;
+        let opts = GetPostsOptions {
+            author_id: cmd.author_id,
+            first: cmd.first,
+            after: cmd
+                .after
+                .as_deref()
+                .map(PostPosition::from_cursor)
+                .transpose()?,
+        };
+        self.post_repo.list_posts(&opts).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::post::Post;
+    use crate::infra::repository::post_repository_trait::MockPostRepositoryTrait;
+
+    #[tokio::test]
+    async fn get_posts_decodes_cursor() {
+        let last = Post::new_test_post("author-id");
+        let expected = GetPostsOptions {
+            author_id: Some("author-id".into()),
+            first: 10,
+            after: Some(PostPosition::from_cursor(&last.cursor()).unwrap()),
+        };
+        let mut mock_repo = MockPostRepositoryTrait::new();
+        mock_repo
+            .expect_list_posts()
+            .withf(move |opts| opts == &expected)
+            .returning(|_| {
+                Ok(PostPage {
+                    posts: vec![Post::new_test_post("author-id")],
+                    has_next: false,
+                })
+            });
+
+        let handler = GetPostsHandler::new(Arc::new(PostRepository::Mock(mock_repo)));
+        let cmd = GetPosts {
+            author_id: Some("author-id".into()),
+            first: 10,
+            after: Some(last.cursor()),
+        };
+        let page = handler.handle(&AppContext::new(), cmd).await.unwrap();
+        assert_eq!(page.posts.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn get_posts_rejects_bad_page_size_and_cursor() {
+        let mut mock_repo = MockPostRepositoryTrait::new();
+        mock_repo.expect_list_posts().never();
+        let handler = GetPostsHandler::new(Arc::new(PostRepository::Mock(mock_repo)));
+
+        let too_many = GetPosts {
+            author_id: None,
+            first: MAX_POSTS_PAGE_SIZE + 1,
+            after: None,
+        };
+        assert!(matches!(
+            handler.handle(&AppContext::new(), too_many).await,
+            Err(AppError::Validation(_))
+        ));
+
+        let bad_cursor = GetPosts {
+            author_id: None,
+            first: 10,
+            after: Some("bogus".into()),
+        };
+        assert!(matches!(
+            handler.handle(&AppContext::new(), bad_cursor).await,
+            Err(AppError::Validation(_))
+        ));
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use getset::Getters;
+use shared::{errors::content::ContentDomainError, pagination::encode_cursor, types::AppResult};
+use uuid::Uuid;
+
+use super::post::PostPosition;
+
+pub const MAX_COMMENT_LENGTH: usize = 5_000;
+/// Top level comments have depth 0. Replies below this depth are refused.
+pub const MAX_COMMENT_DEPTH: u32 = 6;
+pub const MAX_COMMENTS_PAGE_SIZE: u32 = 50;
+pub const MAX_REPLIES_PAGE_SIZE: u32 = 20;
+/// Levels of a thread one query returns, the requested level included.
+pub const MAX_COMMENT_TREE_LEVELS: u32 = 4;
+/// Shown instead of the body of a deleted comment.
+pub const DELETED_COMMENT_BODY: &str = "[deleted]";
+
+#[derive(Debug, Clone, Getters, PartialEq)]
+#[getset(get = "pub")]
+pub struct Comment {
+    id: String,
+    post_id: String,
+    /// `None` for top level comments.
+    parent_id: Option<String>,
+    author_id: String,
+    body: String,
+    depth: u32,
+    /// Direct replies, deleted ones included.
+    reply_count: u64,
+    deleted: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl Comment {
+    /// A new comment on `post_id`, or a reply to `parent` when given.
+    pub fn new(
+        post_id: String,
+        author_id: String,
+        parent: Option<&Comment>,
+        body: String,
+    ) -> AppResult<Self> {
+        let depth = match parent {
+            Some(parent) => {
+                if parent.post_id != post_id {
+                    return Err(ContentDomainError::CommentNotFound.into());
+                }
+                if parent.deleted {
+                    return Err(ContentDomainError::CommentDeleted.into());
+                }
+                if parent.depth >= MAX_COMMENT_DEPTH {
+                    return Err(ContentDomainError::CommentTooDeep.into());
+                }
+                parent.depth + 1
+            }
+            None => 0,
+        };
+        let now = Utc::now();
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            post_id,
+            parent_id: parent.map(|p| p.id.clone()),
+            author_id,
+            body: validate_body(body)?,
+            depth,
+            reply_count: 0,
+            deleted: false,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_all_fields(
+        id: String,
+        post_id: String,
+        parent_id: Option<String>,
+        author_id: String,
+        body: String,
+        depth: u32,
+        reply_count: u64,
+        deleted: bool,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id,
+            post_id,
+            parent_id,
+            author_id,
+            body,
+            depth,
+            reply_count,
+            deleted,
+            created_at,
+            updated_at,
+        }
+    }
+
+    pub fn edit(&mut self, body: String) -> AppResult<()> {
+        if self.deleted {
+            return Err(ContentDomainError::CommentDeleted.into());
+        }
+        self.body = validate_body(body)?;
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Drops the body but keeps the comment, so its replies stay in place.
+    pub fn soft_delete(&mut self) {
+        if self.deleted {
+            return;
+        }
+        self.body = DELETED_COMMENT_BODY.to_string();
+        self.deleted = true;
+        self.updated_at = Utc::now();
+    }
+
+    /// Opaque cursor pointing just after this comment, oldest first.
+    pub fn cursor(&self) -> String {
+        encode_cursor(&format!("{}|{}", self.created_at.to_rfc3339(), self.id))
+    }
+
+    pub fn new_test_comment(post_id: &str, author_id: &str) -> Self {
+        Self::new(post_id.into(), author_id.into(), None, "Nice post".into()).unwrap()
+    }
+
+    pub fn with_reply_count(mut self, reply_count: u64) -> Self {
+        self.reply_count = reply_count;
+        self
+    }
+}
+
+fn validate_body(body: String) -> AppResult<String> {
+    if body.trim().is_empty() || body.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(ContentDomainError::InvalidBody.into());
+    }
+    Ok(body)
+}
+
+/// Decoded form of [`Comment::cursor`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommentPosition {
+    pub created_at: DateTime<Utc>,
+    pub id: String,
+}
+
+impl CommentPosition {
+    pub fn from_cursor(cursor: &str) -> AppResult<Self> {
+        let PostPosition { created_at, id } = PostPosition::from_cursor(cursor)?;
+        Ok(Self { created_at, id })
+    }
+}
+
+/// Comments under one parent, or top level comments when `parent_id` is
+/// `None`, oldest first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetCommentsOptions {
+    pub post_id: String,
+    pub parent_id: Option<String>,
+    pub first: u32,
+    pub after: Option<CommentPosition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListCommentsResult {
+    pub comments: Vec<Comment>,
+    pub has_next: bool,
+}
+
+/// One level of a thread. `total_count` counts every comment on the level,
+/// not just the ones on this page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommentPage {
+    pub comments: Vec<CommentNode>,
+    pub total_count: u64,
+    pub has_next: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommentNode {
+    pub comment: Comment,
+    pub replies: CommentPage,
+}
+
+impl CommentNode {
+    /// A comment whose replies were not loaded. They are counted, and
+    /// fetched from the start of the level with the comment as parent.
+    pub fn unexpanded(comment: Comment) -> Self {
+        let reply_count = comment.reply_count;
+        Self {
+            comment,
+            replies: CommentPage {
+                comments: vec![],
+                total_count: reply_count,
+                has_next: reply_count > 0,
+            },
+        }
+    }
+
+    /// Attaches the loaded replies in `replies`, keyed by parent id, below `comment`.
+    pub fn build(comment: Comment, replies: &mut HashMap<String, ListCommentsResult>) -> Self {
+        match replies.remove(&comment.id) {
+            Some(level) => Self {
+                replies: CommentPage {
+                    comments: level
+                        .comments
+                        .into_iter()
+                        .map(|reply| Self::build(reply, replies))
+                        .collect(),
+                    total_count: comment.reply_count,
+                    has_next: level.has_next,
+                },
+                comment,
+            },
+            None => Self::unexpanded(comment),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::errors::app::AppError;
+
+    #[test]
+    fn replies_are_one_level_deeper() {
+        let parent = Comment::new_test_comment("post", "author");
+        let reply =
+            Comment::new("post".into(), "other".into(), Some(&parent), "Yes".into()).unwrap();
+        assert_eq!(*reply.depth(), 1);
+        assert_eq!(reply.parent_id().as_deref(), Some(parent.id().as_str()));
+    }
+
+    #[test]
+    fn replies_are_refused_below_max_depth_or_on_another_post() {
+        let mut parent = Comment::new_test_comment("post", "author");
+        parent.depth = MAX_COMMENT_DEPTH;
+        let result = Comment::new("post".into(), "other".into(), Some(&parent), "Yes".into());
+        assert!(matches!(
+            result,
+            Err(AppError::Content(ContentDomainError::CommentTooDeep))
+        ));
+
+        parent.depth = 0;
+        let result = Comment::new(
+            "other-post".into(),
+            "other".into(),
+            Some(&parent),
+            "Yes".into(),
+        );
+        assert!(matches!(
+            result,
+            Err(AppError::Content(ContentDomainError::CommentNotFound))
+        ));
+    }
+
+    #[test]
+    fn soft_delete_leaves_placeholder() {
+        let mut comment = Comment::new_test_comment("post", "author");
+        comment.soft_delete();
+        assert!(*comment.deleted());
+        assert_eq!(comment.body(), DELETED_COMMENT_BODY);
+
+        assert!(matches!(
+            comment.edit("Back".into()),
+            Err(AppError::Content(ContentDomainError::CommentDeleted))
+        ));
+        let result = Comment::new("post".into(), "other".into(), Some(&comment), "Yes".into());
+        assert!(matches!(
+            result,
+            Err(AppError::Content(ContentDomainError::CommentDeleted))
+        ));
+    }
+
+    #[test]
+    fn build_attaches_loaded_replies() {
+        let root = Comment::new_test_comment("post", "author").with_reply_count(3);
+        let reply = Comment::new("post".into(), "other".into(), Some(&root), "Yes".into())
+            .unwrap()
+            .with_reply_count(2);
+        let mut replies = HashMap::from([(
+            root.id().clone(),
+            ListCommentsResult {
+                comments: vec![reply.clone()],
+                has_next: true,
+            },
+        )]);
+
+        let node = CommentNode::build(root, &mut replies);
+        assert_eq!(node.replies.total_count, 3);
+        assert!(node.replies.has_next);
+        assert_eq!(node.replies.comments.len(), 1);
+
+        let reply_node = &node.replies.comments[0];
+        assert_eq!(reply_node.comment, reply);
+        assert!(reply_node.replies.comments.is_empty());
+        assert_eq!(reply_node.replies.total_count, 2);
+        assert!(reply_node.replies.has_next);
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::{DateTime, Utc};
+use getset::Getters;
+use shared::{
+    errors::{app::AppError, content::ContentDomainError, validation::FieldError},
+    pagination::{decode_cursor, encode_cursor},
+    types::AppResult,
+};
+use uuid::Uuid;
+
+pub const MAX_TITLE_LENGTH: usize = 200;
+pub const MAX_BODY_LENGTH: usize = 20_000;
+pub const MAX_POSTS_PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Clone, Getters, PartialEq)]
+#[getset(get = "pub")]
+pub struct Post {
+    id: String,
+    author_id: String,
+    title: String,
+    body: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl Post {
+    /// A new post by `author_id`. Surrounding whitespace of the title is dropped.
+    pub fn new(author_id: String, title: String, body: String) -> AppResult<Self> {
+        let now = Utc::now();
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            author_id,
+            title: validate_title(title)?,
+            body: validate_body(body)?,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub fn new_with_all_fields(
+        id: String,
+        author_id: String,
+        title: String,
+        body: String,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id,
+            author_id,
+            title,
+            body,
+            created_at,
+            updated_at,
+        }
+    }
+
+    /// Replaces the given parts. Nothing changes when either is invalid.
+    pub fn edit(&mut self, title: Option<String>, body: Option<String>) -> AppResult<()> {
+        let title = title.map(validate_title).transpose()?;
+        let body = body.map(validate_body).transpose()?;
+        if let Some(title) = title {
+            self.title = title;
+        }
+        if let Some(body) = body {
+            self.body = body;
+        }
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Opaque cursor pointing just after this post, newest first.
+    pub fn cursor(&self) -> String {
+        encode_cursor(&format!("{}|{}", self.created_at.to_rfc3339(), self.id))
+    }
+
+    pub fn new_test_post(author_id: &str) -> Self {
+        Self::new(author_id.into(), "Hello".into(), "First post".into()).unwrap()
+    }
+}
+
+fn validate_title(title: String) -> AppResult<String> {
+    let title = title.trim();
+    if title.is_empty() || title.chars().count() > MAX_TITLE_LENGTH {
+        return Err(ContentDomainError::InvalidTitle.into());
+    }
+    Ok(title.to_string())
+}
+
+fn validate_body(body: String) -> AppResult<String> {
+    if body.trim().is_empty() || body.chars().count() > MAX_BODY_LENGTH {
+        return Err(ContentDomainError::InvalidBody.into());
+    }
+    Ok(body)
+}
+
+/// Decoded form of [`Post::cursor`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostPosition {
+    pub created_at: DateTime<Utc>,
+    pub id: String,
+}
+
+impl PostPosition {
+    pub fn from_cursor(cursor: &str) -> AppResult<Self> {
+        let invalid = || {
+            AppError::Validation(vec![FieldError {
+                field: "after".into(),
+                code: "cursor".into(),
+                message: Some("Invalid cursor".into()),
+            }])
+        };
+        let decoded = decode_cursor(cursor).map_err(|_| invalid())?;
+        let (created_at, id) = decoded.split_once('|').ok_or_else(invalid)?;
+        let created_at = DateTime::parse_from_rfc3339(created_at)
+            .map_err(|_| invalid())?
+            .with_timezone(&Utc);
+        Ok(Self {
+            created_at,
+            id: id.to_string(),
+        })
+    }
+}
+
+/// Posts are listed newest first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetPostsOptions {
+    pub author_id: Option<String>,
+    pub first: u32,
+    pub after: Option<PostPosition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostPage {
+    pub posts: Vec<Post>,
+    pub has_next: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_post_trims_title() {
+        let post = Post::new("author".into(), "  Hello  ".into(), "Body".into()).unwrap();
+        assert_eq!(post.title(), "Hello");
+        assert_eq!(post.created_at(), post.updated_at());
+    }
+
+    #[test]
+    fn new_post_rejects_blank_or_long_title() {
+        for title in ["   ".to_string(), "a".repeat(MAX_TITLE_LENGTH + 1)] {
+            let result = Post::new("author".into(), title, "Body".into());
+            assert!(matches!(
+                result,
+                Err(AppError::Content(ContentDomainError::InvalidTitle))
+            ));
+        }
+    }
+
+    #[test]
+    fn edit_keeps_post_when_invalid() {
+        let mut post = Post::new_test_post("author");
+        let result = post.edit(Some("New title".into()), Some(" ".into()));
+        assert!(matches!(
+            result,
+            Err(AppError::Content(ContentDomainError::InvalidBody))
+        ));
+        assert_eq!(post.title(), "Hello");
+
+        post.edit(None, Some("Edited".into())).unwrap();
+        assert_eq!(post.title(), "Hello");
+        assert_eq!(post.body(), "Edited");
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let post = Post::new_test_post("author");
+        let position = PostPosition::from_cursor(&post.cursor()).unwrap();
+        assert_eq!(&position.created_at, post.created_at());
+        assert_eq!(&position.id, post.id());
+        assert!(PostPosition::from_cursor("not a cursor").is_err());
+    }
+}
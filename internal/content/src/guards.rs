@@ -0,0 +1,15 @@
+use shared::{auth::AuthUser, guards::permissions::ContentPermission, types::AppResult};
+
+#[cfg_attr(test, mockall::automock)]
+pub trait ContentGuards: Send + Sync {
+    /// Checks the role's permissions and, for scoped tokens, the token's scopes.
+    fn authorize(&self, auth_user: &AuthUser, perm: &ContentPermission) -> AppResult<()>;
+    /// Only the author may edit a post.
+    fn can_update_post(&self, author_id: &str, auth_user: &AuthUser) -> AppResult<()>;
+    /// Authors may delete their own posts, moderators any post.
+    fn can_delete_post(&self, author_id: &str, auth_user: &AuthUser) -> AppResult<()>;
+    /// Only the author may edit a comment.
+    fn can_update_comment(&self, author_id: &str, auth_user: &AuthUser) -> AppResult<()>;
+    /// Authors may delete their own comments, moderators any comment.
+    fn can_delete_comment(&self, author_id: &str, auth_user: &AuthUser) -> AppResult<()>;
+}
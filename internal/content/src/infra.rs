@@ -0,0 +1,2 @@
+pub mod mongoimpl;
+pub mod repository;
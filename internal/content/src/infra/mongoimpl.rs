@@ -0,0 +1,2 @@
+pub mod comment_repository;
+pub mod post_repository;
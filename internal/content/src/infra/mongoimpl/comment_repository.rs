@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use mongodb::{
+    Collection, Database,
+    bson::{Bson, Document, doc},
+};
+use serde::{Deserialize, Serialize};
+use shared::types::AppResult;
+
+use crate::domain::comment::{Comment, GetCommentsOptions, ListCommentsResult};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommentDocument {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub post_id: String,
+    pub parent_id: Option<String>,
+    pub author_id: String,
+    pub body: String,
+    pub depth: u32,
+    pub reply_count: u64,
+    pub deleted: bool,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<CommentDocument> for Comment {
+    fn from(value: CommentDocument) -> Self {
+        Comment::new_with_all_fields(
+            value.id,
+            value.post_id,
+            value.parent_id,
+            value.author_id,
+            value.body,
+            value.depth,
+            value.reply_count,
+            value.deleted,
+            value.created_at,
+            value.updated_at,
+        )
+    }
+}
+
+impl From<Comment> for CommentDocument {
+    fn from(value: Comment) -> Self {
+        CommentDocument {
+            id: value.id().clone(),
+            post_id: value.post_id().clone(),
+            parent_id: value.parent_id().clone(),
+            author_id: value.author_id().clone(),
+            body: value.body().clone(),
+            depth: *value.depth(),
+            reply_count: *value.reply_count(),
+            deleted: *value.deleted(),
+            created_at: *value.created_at(),
+            updated_at: *value.updated_at(),
+        }
+    }
+}
+
+/// Replies of one parent, as grouped by `list_replies`.
+#[derive(Debug, Deserialize)]
+struct RepliesGroup {
+    #[serde(rename = "_id")]
+    parent_id: String,
+    comments: Vec<CommentDocument>,
+}
+
+pub struct MongoCommentRepository {
+    collection: Collection<CommentDocument>,
+}
+
+impl MongoCommentRepository {
+    pub fn new(db: Database) -> Self {
+        let collection = db.collection::<CommentDocument>("comments");
+        Self { collection }
+    }
+
+    pub async fn create_comment(&self, comment: Comment) -> AppResult<()> {
+        let parent_id = comment.parent_id().clone();
+        self.collection
+            .insert_one(CommentDocument::from(comment))
+            .await?;
+        if let Some(parent_id) = parent_id {
+            self.collection
+                .update_one(doc! {"_id": parent_id}, doc! {"$inc": {"reply_count": 1}})
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_comment(&self, id: &str) -> AppResult<Option<Comment>> {
+        let comment = self
+            .collection
+            .find_one(doc! {"_id": id})
+            .await?
+            .map(Into::into);
+        Ok(comment)
+    }
+
+    pub async fn update_comment(&self, comment: Comment) -> AppResult<()> {
+        self.collection
+            .update_one(
+                doc! {"_id": comment.id()},
+                doc! {"$set": {
+                    "body": comment.body(),
+                    "deleted": comment.deleted(),
+                    "updated_at": bson::DateTime::from_chrono(*comment.updated_at()),
+                }},
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_comments(&self, opts: &GetCommentsOptions) -> AppResult<ListCommentsResult> {
+        let mut query = level_query(&opts.post_id, opts.parent_id.clone());
+        if let Some(after) = &opts.after {
+            let after_at = bson::DateTime::from_chrono(after.created_at);
+            query.insert(
+                "$or",
+                vec![
+                    doc! {"created_at": {"$gt": after_at}},
+                    doc! {"created_at": after_at, "_id": {"$gt": &after.id}},
+                ],
+            );
+        }
+        let mut cursor = self
+            .collection
+            .find(query)
+            .sort(doc! {"created_at": 1, "_id": 1})
+            .limit(opts.first as i64 + 1)
+            .await?;
+        let mut comments: Vec<Comment> = vec![];
+        while cursor.advance().await? {
+            comments.push(cursor.deserialize_current()?.into());
+        }
+        let has_next = comments.len() > opts.first as usize;
+        comments.truncate(opts.first as usize);
+        Ok(ListCommentsResult { comments, has_next })
+    }
+
+    pub async fn list_replies(
+        &self,
+        parent_ids: &[String],
+        first: u32,
+    ) -> AppResult<HashMap<String, ListCommentsResult>> {
+        if parent_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        // Numbers the replies of each parent and drops those past the page
+        // before grouping, so a parent with many replies is never held whole.
+        let pipeline = vec![
+            doc! {"$match": {"parent_id": {"$in": parent_ids}}},
+            doc! {"$setWindowFields": {
+                "partitionBy": "$parent_id",
+                "sortBy": {"created_at": 1, "_id": 1},
+                "output": {"position": {"$documentNumber": {}}},
+            }},
+            doc! {"$match": {"position": {"$lte": first as i64 + 1}}},
+            doc! {"$unset": "position"},
+            doc! {"$sort": {"created_at": 1, "_id": 1}},
+            doc! {"$group": {"_id": "$parent_id", "comments": {"$push": "$$ROOT"}}},
+        ];
+        let mut cursor = self
+            .collection
+            .aggregate(pipeline)
+            .with_type::<RepliesGroup>()
+            .await?;
+        let mut replies = HashMap::new();
+        while cursor.advance().await? {
+            let group = cursor.deserialize_current()?;
+            let mut comments: Vec<Comment> = group.comments.into_iter().map(Into::into).collect();
+            let has_next = comments.len() > first as usize;
+            comments.truncate(first as usize);
+            replies.insert(group.parent_id, ListCommentsResult { comments, has_next });
+        }
+        Ok(replies)
+    }
+
+    pub async fn count_comments(&self, post_id: &str, parent_id: Option<String>) -> AppResult<u64> {
+        let count = self
+            .collection
+            .count_documents(level_query(post_id, parent_id))
+            .await?;
+        Ok(count)
+    }
+
+    pub async fn delete_post_comments(&self, post_id: &str) -> AppResult<u64> {
+        let result = self
+            .collection
+            .delete_many(doc! {"post_id": post_id})
+            .await?;
+        Ok(result.deleted_count)
+    }
+}
+
+fn level_query(post_id: &str, parent_id: Option<String>) -> Document {
+    let parent_id = match parent_id {
+        Some(parent_id) => Bson::String(parent_id),
+        None => Bson::Null,
+    };
+    doc! {"post_id": post_id, "parent_id": parent_id}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::comment::CommentPosition;
+    use shared::test_utils;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_comment_replies_and_soft_delete() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let repo = MongoCommentRepository::new(db);
+
+        let root = Comment::new_test_comment("post-1", "author-1");
+        repo.create_comment(root.clone()).await.unwrap();
+        let reply = Comment::new(
+            "post-1".into(),
+            "author-2".into(),
+            Some(&root),
+            "Yes".into(),
+        )
+        .unwrap();
+        repo.create_comment(reply.clone()).await.unwrap();
+
+        let mut root = repo.get_comment(root.id()).await.unwrap().unwrap();
+        assert_eq!(*root.reply_count(), 1);
+
+        root.soft_delete();
+        repo.update_comment(root.clone()).await.unwrap();
+        let found = repo.get_comment(root.id()).await.unwrap().unwrap();
+        assert!(*found.deleted());
+        assert_eq!(found.body(), root.body());
+
+        assert_eq!(repo.count_comments("post-1", None).await.unwrap(), 1);
+        assert_eq!(
+            repo.count_comments("post-1", Some(root.id().clone()))
+                .await
+                .unwrap(),
+            1
+        );
+
+        assert_eq!(repo.delete_post_comments("post-1").await.unwrap(), 2);
+        assert!(repo.get_comment(root.id()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_comments_and_replies_oldest_first() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let repo = MongoCommentRepository::new(db);
+
+        let first = Comment::new_test_comment("post-1", "author-1");
+        let second = Comment::new_test_comment("post-1", "author-2");
+        repo.create_comment(first.clone()).await.unwrap();
+        repo.create_comment(second.clone()).await.unwrap();
+        for body in ["a", "b", "c"] {
+            let reply = Comment::new(
+                "post-1".into(),
+                "author-3".into(),
+                Some(&first),
+                body.into(),
+            )
+            .unwrap();
+            repo.create_comment(reply).await.unwrap();
+        }
+
+        let mut opts = GetCommentsOptions {
+            post_id: "post-1".into(),
+            parent_id: None,
+            first: 1,
+            after: None,
+        };
+        let page = repo.list_comments(&opts).await.unwrap();
+        assert_eq!(page.comments[0].id(), first.id());
+        assert!(page.has_next);
+
+        opts.after = Some(CommentPosition::from_cursor(&page.comments[0].cursor()).unwrap());
+        let next = repo.list_comments(&opts).await.unwrap();
+        assert_eq!(next.comments[0].id(), second.id());
+        assert!(!next.has_next);
+
+        let replies = repo
+            .list_replies(&[first.id().clone(), second.id().clone()], 2)
+            .await
+            .unwrap();
+        let level = &replies[first.id()];
+        let bodies: Vec<&str> = level.comments.iter().map(|c| c.body().as_str()).collect();
+        assert_eq!(bodies, vec!["a", "b"]);
+        assert!(level.has_next);
+        assert!(!replies.contains_key(second.id()));
+    }
+}
@@ -0,0 +1,182 @@
+use chrono::{DateTime, Utc};
+use mongodb::{
+    Collection, Database,
+    bson::{Document, doc},
+};
+use serde::{Deserialize, Serialize};
+use shared::types::AppResult;
+
+use crate::domain::post::{GetPostsOptions, Post, PostPage};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostDocument {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub author_id: String,
+    pub title: String,
+    pub body: String,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<PostDocument> for Post {
+    fn from(value: PostDocument) -> Self {
+        Post::new_with_all_fields(
+            value.id,
+            value.author_id,
+            value.title,
+            value.body,
+            value.created_at,
+            value.updated_at,
+        )
+    }
+}
+
+impl From<Post> for PostDocument {
+    fn from(value: Post) -> Self {
+        PostDocument {
+            id: value.id().clone(),
+            author_id: value.author_id().clone(),
+            title: value.title().clone(),
+            body: value.body().clone(),
+            created_at: *value.created_at(),
+            updated_at: *value.updated_at(),
+        }
+    }
+}
+
+pub struct MongoPostRepository {
+    collection: Collection<PostDocument>,
+}
+
+impl MongoPostRepository {
+    pub fn new(db: Database) -> Self {
+        let collection = db.collection::<PostDocument>("posts");
+        Self { collection }
+    }
+
+    pub async fn create_post(&self, post: Post) -> AppResult<()> {
+        self.collection.insert_one(PostDocument::from(post)).await?;
+        Ok(())
+    }
+
+    pub async fn get_post(&self, id: &str) -> AppResult<Option<Post>> {
+        let post = self
+            .collection
+            .find_one(doc! {"_id": id})
+            .await?
+            .map(Into::into);
+        Ok(post)
+    }
+
+    pub async fn update_post(&self, post: Post) -> AppResult<()> {
+        self.collection
+            .update_one(
+                doc! {"_id": post.id()},
+                doc! {"$set": {
+                    "title": post.title(),
+                    "body": post.body(),
+                    "updated_at": bson::DateTime::from_chrono(*post.updated_at()),
+                }},
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_post(&self, id: &str) -> AppResult<bool> {
+        let result = self.collection.delete_one(doc! {"_id": id}).await?;
+        Ok(result.deleted_count == 1)
+    }
+
+    pub async fn list_posts(&self, opts: &GetPostsOptions) -> AppResult<PostPage> {
+        let mut query = Document::new();
+        if let Some(author_id) = &opts.author_id {
+            query.insert("author_id", author_id);
+        }
+        if let Some(after) = &opts.after {
+            let after_at = bson::DateTime::from_chrono(after.created_at);
+            query.insert(
+                "$or",
+                vec![
+                    doc! {"created_at": {"$lt": after_at}},
+                    doc! {"created_at": after_at, "_id": {"$lt": &after.id}},
+                ],
+            );
+        }
+        let mut cursor = self
+            .collection
+            .find(query)
+            .sort(doc! {"created_at": -1, "_id": -1})
+            .limit(opts.first as i64 + 1)
+            .await?;
+        let mut posts: Vec<Post> = vec![];
+        while cursor.advance().await? {
+            posts.push(cursor.deserialize_current()?.into());
+        }
+        let has_next = posts.len() > opts.first as usize;
+        posts.truncate(opts.first as usize);
+        Ok(PostPage { posts, has_next })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::post::PostPosition;
+    use shared::test_utils;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_post_crud() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let repo = MongoPostRepository::new(db);
+
+        let mut post = Post::new_test_post("author-1");
+        repo.create_post(post.clone()).await.unwrap();
+
+        post.edit(Some("Edited".into()), None).unwrap();
+        repo.update_post(post.clone()).await.unwrap();
+        let found = repo.get_post(post.id()).await.unwrap().unwrap();
+        assert_eq!(found.title(), "Edited");
+        assert_eq!(
+            found.updated_at().timestamp_millis(),
+            post.updated_at().timestamp_millis()
+        );
+
+        assert!(repo.delete_post(post.id()).await.unwrap());
+        assert!(!repo.delete_post(post.id()).await.unwrap());
+        assert!(repo.get_post(post.id()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_posts_pages_newest_first() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let repo = MongoPostRepository::new(db);
+
+        for author_id in ["author-1", "author-2", "author-1"] {
+            repo.create_post(Post::new_test_post(author_id))
+                .await
+                .unwrap();
+        }
+
+        let mut opts = GetPostsOptions {
+            author_id: Some("author-1".into()),
+            first: 1,
+            after: None,
+        };
+        let page = repo.list_posts(&opts).await.unwrap();
+        assert_eq!(page.posts.len(), 1);
+        assert!(page.has_next);
+
+        opts.after = Some(PostPosition::from_cursor(&page.posts[0].cursor()).unwrap());
+        let next = repo.list_posts(&opts).await.unwrap();
+        assert_eq!(next.posts.len(), 1);
+        assert!(!next.has_next);
+        assert!(next.posts[0].created_at() <= page.posts[0].created_at());
+        assert_ne!(next.posts[0].id(), page.posts[0].id());
+    }
+}
@@ -0,0 +1,4 @@
+pub mod comment_repository;
+pub mod comment_repository_trait;
+pub mod post_repository;
+pub mod post_repository_trait;
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use shared::types::AppResult;
+
+use crate::domain::comment::{Comment, GetCommentsOptions, ListCommentsResult};
+use crate::infra::mongoimpl::comment_repository::MongoCommentRepository;
+
+#[cfg(test)]
+use super::comment_repository_trait::CommentRepositoryTrait;
+
+pub enum CommentRepository {
+    MongoDb(MongoCommentRepository),
+    #[cfg(test)]
+    Mock(super::comment_repository_trait::MockCommentRepositoryTrait),
+}
+
+impl CommentRepository {
+    #[tracing::instrument(name = "comment_repository.create_comment", skip_all)]
+    pub async fn create_comment(&self, comment: Comment) -> AppResult<()> {
+        match self {
+            CommentRepository::MongoDb(repo) => repo.create_comment(comment).await,
+            #[cfg(test)]
+            CommentRepository::Mock(mock) => mock.create_comment(comment).await,
+        }
+    }
+
+    #[tracing::instrument(name = "comment_repository.get_comment", skip_all)]
+    pub async fn get_comment(&self, id: &str) -> AppResult<Option<Comment>> {
+        match self {
+            CommentRepository::MongoDb(repo) => repo.get_comment(id).await,
+            #[cfg(test)]
+            CommentRepository::Mock(mock) => mock.get_comment(id).await,
+        }
+    }
+
+    #[tracing::instrument(name = "comment_repository.update_comment", skip_all)]
+    pub async fn update_comment(&self, comment: Comment) -> AppResult<()> {
+        match self {
+            CommentRepository::MongoDb(repo) => repo.update_comment(comment).await,
+            #[cfg(test)]
+            CommentRepository::Mock(mock) => mock.update_comment(comment).await,
+        }
+    }
+
+    #[tracing::instrument(name = "comment_repository.list_comments", skip_all)]
+    pub async fn list_comments(&self, opts: &GetCommentsOptions) -> AppResult<ListCommentsResult> {
+        match self {
+            CommentRepository::MongoDb(repo) => repo.list_comments(opts).await,
+            #[cfg(test)]
+            CommentRepository::Mock(mock) => mock.list_comments(opts).await,
+        }
+    }
+
+    #[tracing::instrument(name = "comment_repository.list_replies", skip_all)]
+    pub async fn list_replies(
+        &self,
+        parent_ids: &[String],
+        first: u32,
+    ) -> AppResult<HashMap<String, ListCommentsResult>> {
+        match self {
+            CommentRepository::MongoDb(repo) => repo.list_replies(parent_ids, first).await,
+            #[cfg(test)]
+            CommentRepository::Mock(mock) => mock.list_replies(parent_ids, first).await,
+        }
+    }
+
+    #[tracing::instrument(name = "comment_repository.count_comments", skip_all)]
+    pub async fn count_comments(&self, post_id: &str, parent_id: Option<String>) -> AppResult<u64> {
+        match self {
+            CommentRepository::MongoDb(repo) => repo.count_comments(post_id, parent_id).await,
+            #[cfg(test)]
+            CommentRepository::Mock(mock) => mock.count_comments(post_id, parent_id).await,
+        }
+    }
+
+    #[tracing::instrument(name = "comment_repository.delete_post_comments", skip_all)]
+    pub async fn delete_post_comments(&self, post_id: &str) -> AppResult<u64> {
+        match self {
+            CommentRepository::MongoDb(repo) => repo.delete_post_comments(post_id).await,
+            #[cfg(test)]
+            CommentRepository::Mock(mock) => mock.delete_post_comments(post_id).await,
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+
+use shared::types::AppResult;
+
+use crate::domain::comment::{Comment, GetCommentsOptions, ListCommentsResult};
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait CommentRepositoryTrait {
+    /// Also counts the comment as a reply of its parent.
+    async fn create_comment(&self, comment: Comment) -> AppResult<()>;
+    async fn get_comment(&self, id: &str) -> AppResult<Option<Comment>>;
+    /// Saves the body, `deleted` and `updated_at` of an existing comment.
+    async fn update_comment(&self, comment: Comment) -> AppResult<()>;
+    async fn list_comments(&self, opts: &GetCommentsOptions) -> AppResult<ListCommentsResult>;
+    /// The first `first` replies of each parent, oldest first, keyed by parent
+    /// id. Parents without replies are left out.
+    async fn list_replies(
+        &self,
+        parent_ids: &[String],
+        first: u32,
+    ) -> AppResult<HashMap<String, ListCommentsResult>>;
+    /// Comments under `parent_id`, or top level comments when it is `None`.
+    async fn count_comments(&self, post_id: &str, parent_id: Option<String>) -> AppResult<u64>;
+    /// Deletes every comment on a post, returning how many there were.
+    async fn delete_post_comments(&self, post_id: &str) -> AppResult<u64>;
+}
@@ -0,0 +1,60 @@
+use shared::types::AppResult;
+
+use crate::domain::post::{GetPostsOptions, Post, PostPage};
+use crate::infra::mongoimpl::post_repository::MongoPostRepository;
+
+#[cfg(test)]
+use super::post_repository_trait::PostRepositoryTrait;
+
+pub enum PostRepository {
+    MongoDb(MongoPostRepository),
+    #[cfg(test)]
+    Mock(super::post_repository_trait::MockPostRepositoryTrait),
+}
+
+impl PostRepository {
+    #[tracing::instrument(name = "post_repository.create_post", skip_all)]
+    pub async fn create_post(&self, post: Post) -> AppResult<()> {
+        match self {
+            PostRepository::MongoDb(repo) => repo.create_post(post).await,
+            #[cfg(test)]
+            PostRepository::Mock(mock) => mock.create_post(post).await,
+        }
+    }
+
+    #[tracing::instrument(name = "post_repository.get_post", skip_all)]
+    pub async fn get_post(&self, id: &str) -> AppResult<Option<Post>> {
+        match self {
+            PostRepository::MongoDb(repo) => repo.get_post(id).await,
+            #[cfg(test)]
+            PostRepository::Mock(mock) => mock.get_post(id).await,
+        }
+    }
+
+    #[tracing::instrument(name = "post_repository.update_post", skip_all)]
+    pub async fn update_post(&self, post: Post) -> AppResult<()> {
+        match self {
+            PostRepository::MongoDb(repo) => repo.update_post(post).await,
+            #[cfg(test)]
+            PostRepository::Mock(mock) => mock.update_post(post).await,
+        }
+    }
+
+    #[tracing::instrument(name = "post_repository.delete_post", skip_all)]
+    pub async fn delete_post(&self, id: &str) -> AppResult<bool> {
+        match self {
+            PostRepository::MongoDb(repo) => repo.delete_post(id).await,
+            #[cfg(test)]
+            PostRepository::Mock(mock) => mock.delete_post(id).await,
+        }
+    }
+
+    #[tracing::instrument(name = "post_repository.list_posts", skip_all)]
+    pub async fn list_posts(&self, opts: &GetPostsOptions) -> AppResult<PostPage> {
+        match self {
+            PostRepository::MongoDb(repo) => repo.list_posts(opts).await,
+            #[cfg(test)]
+            PostRepository::Mock(mock) => mock.list_posts(opts).await,
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use shared::types::AppResult;
+
+use crate::domain::post::{GetPostsOptions, Post, PostPage};
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait PostRepositoryTrait {
+    async fn create_post(&self, post: Post) -> AppResult<()>;
+    async fn get_post(&self, id: &str) -> AppResult<Option<Post>>;
+    /// Saves the title, body and `updated_at` of an existing post.
+    async fn update_post(&self, post: Post) -> AppResult<()>;
+    /// Returns `false` when there is no post with this id.
+    async fn delete_post(&self, id: &str) -> AppResult<bool>;
+    async fn list_posts(&self, opts: &GetPostsOptions) -> AppResult<PostPage>;
+}
@@ -0,0 +1,5 @@
+pub mod app;
+pub mod domain;
+pub mod guards;
+pub mod infra;
+pub mod ports;
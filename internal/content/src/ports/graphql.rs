@@ -0,0 +1,122 @@
+use crate::domain::comment::{CommentNode, CommentPage};
+use crate::domain::post::{Post, PostPage};
+use async_graphql::{Context, Object, Result};
+use shared::types::graphql_scalars::DateTimeScalar;
+use user::{domain::user_read_model::UserReadModel, ports::user_loader::load_user};
+
+#[Object(name = "Post")]
+impl Post {
+    #[graphql(name = "id")]
+    async fn resolve_id(&self) -> String {
+        self.id().to_owned()
+    }
+    #[graphql(name = "authorId")]
+    async fn resolve_author_id(&self) -> String {
+        self.author_id().to_owned()
+    }
+    #[graphql(name = "author", complexity = "5 + child_complexity")]
+    async fn resolve_author(&self, ctx: &Context<'_>) -> Result<Option<UserReadModel>> {
+        load_user(ctx, self.author_id()).await
+    }
+    #[graphql(name = "title")]
+    async fn resolve_title(&self) -> String {
+        self.title().to_owned()
+    }
+    #[graphql(name = "body")]
+    async fn resolve_body(&self) -> String {
+        self.body().to_owned()
+    }
+    #[graphql(name = "createdAt")]
+    async fn resolve_created_at(&self) -> DateTimeScalar {
+        (*self.created_at()).into()
+    }
+    #[graphql(name = "updatedAt")]
+    async fn resolve_updated_at(&self) -> DateTimeScalar {
+        (*self.updated_at()).into()
+    }
+    /// Pass as `after` to continue from this post.
+    #[graphql(name = "cursor")]
+    async fn resolve_cursor(&self) -> String {
+        self.cursor()
+    }
+}
+
+#[Object(name = "PostPage")]
+impl PostPage {
+    async fn posts(&self) -> Vec<Post> {
+        self.posts.to_owned()
+    }
+    async fn has_next(&self) -> bool {
+        self.has_next
+    }
+    async fn end_cursor(&self) -> Option<String> {
+        self.posts.last().map(Post::cursor)
+    }
+}
+
+/// Deleted comments keep their place in the thread, with `[deleted]` as body and no author.
+#[Object(name = "Comment")]
+impl CommentNode {
+    async fn id(&self) -> String {
+        self.comment.id().to_owned()
+    }
+    async fn post_id(&self) -> String {
+        self.comment.post_id().to_owned()
+    }
+    async fn parent_id(&self) -> Option<String> {
+        self.comment.parent_id().to_owned()
+    }
+    async fn author_id(&self) -> Option<String> {
+        if *self.comment.deleted() {
+            return None;
+        }
+        Some(self.comment.author_id().to_owned())
+    }
+    #[graphql(complexity = "5 + child_complexity")]
+    async fn author(&self, ctx: &Context<'_>) -> Result<Option<UserReadModel>> {
+        if *self.comment.deleted() {
+            return Ok(None);
+        }
+        load_user(ctx, self.comment.author_id()).await
+    }
+    async fn body(&self) -> String {
+        self.comment.body().to_owned()
+    }
+    /// 0 for top level comments.
+    async fn depth(&self) -> u32 {
+        *self.comment.depth()
+    }
+    async fn deleted(&self) -> bool {
+        *self.comment.deleted()
+    }
+    async fn created_at(&self) -> DateTimeScalar {
+        (*self.comment.created_at()).into()
+    }
+    async fn updated_at(&self) -> DateTimeScalar {
+        (*self.comment.updated_at()).into()
+    }
+    /// Pass as `after` to continue from this comment.
+    async fn cursor(&self) -> String {
+        self.comment.cursor()
+    }
+    /// The loaded replies. On the last level loaded only `totalCount` and `hasNext` are set.
+    async fn replies(&self) -> CommentPage {
+        self.replies.to_owned()
+    }
+}
+
+#[Object(name = "CommentPage")]
+impl CommentPage {
+    async fn comments(&self) -> Vec<CommentNode> {
+        self.comments.to_owned()
+    }
+    async fn total_count(&self) -> u64 {
+        self.total_count
+    }
+    async fn has_next(&self) -> bool {
+        self.has_next
+    }
+    async fn end_cursor(&self) -> Option<String> {
+        self.comments.last().map(|node| node.comment.cursor())
+    }
+}
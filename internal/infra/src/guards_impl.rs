@@ -2,10 +2,9 @@ use abac::AbacEngine;
 use rbac::RbacEngine;
 use shared::{
     auth::AuthUser,
-    guards::{
-        permissions::{Permission, UserPermission},
-        roles::UserRole,
-    },
+    errors::content::ContentDomainError,
+    guards::permissions::{ContentPermission, Permission, UserPermission},
+    types::AppResult,
 };
 use user::domain::{errors::UserDomainError, result::UserDomainResult};
 
@@ -31,11 +30,131 @@ impl user::guards::UserGuards for GuardsImpl {
     fn can_change_username(&self, user_id: &str, auth_user: &AuthUser) -> UserDomainResult<()> {
         self.abac.can_change_username(user_id, auth_user)
     }
-    fn authorize(&self, role: &UserRole, perm: &UserPermission) -> UserDomainResult<()> {
+    fn authorize(&self, auth_user: &AuthUser, perm: &UserPermission) -> UserDomainResult<()> {
         let internal = Permission::from(perm.clone());
-        match self.rbac.authorize(role, &internal) {
+        if !auth_user.has_scope_for(&internal) {
+            return Err(UserDomainError::Unauthorized);
+        }
+        if auth_user.impersonator().is_some() && !internal.allowed_when_impersonating() {
+            return Err(UserDomainError::Unauthorized);
+        }
+        match self.rbac.authorize(&auth_user.0.role, &internal) {
             Ok(_) => Ok(()),
             Err(..) => Err(UserDomainError::Unauthorized),
         }
     }
 }
+
+impl content::guards::ContentGuards for GuardsImpl {
+    fn authorize(&self, auth_user: &AuthUser, perm: &ContentPermission) -> AppResult<()> {
+        let internal = Permission::from(perm.clone());
+        if !auth_user.has_scope_for(&internal) {
+            return Err(ContentDomainError::Unauthorized.into());
+        }
+        if auth_user.impersonator().is_some() && !internal.allowed_when_impersonating() {
+            return Err(ContentDomainError::Unauthorized.into());
+        }
+        match self.rbac.authorize(&auth_user.0.role, &internal) {
+            Ok(_) => Ok(()),
+            Err(..) => Err(ContentDomainError::Unauthorized.into()),
+        }
+    }
+    fn can_update_post(&self, author_id: &str, auth_user: &AuthUser) -> AppResult<()> {
+        self.abac.can_update_post(author_id, auth_user)
+    }
+    fn can_delete_post(&self, author_id: &str, auth_user: &AuthUser) -> AppResult<()> {
+        self.abac.can_delete_post(author_id, auth_user)
+    }
+    fn can_update_comment(&self, author_id: &str, auth_user: &AuthUser) -> AppResult<()> {
+        self.abac.can_update_comment(author_id, auth_user)
+    }
+    fn can_delete_comment(&self, author_id: &str, auth_user: &AuthUser) -> AppResult<()> {
+        self.abac.can_delete_comment(author_id, auth_user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::{auth::jwt::Actor, guards::roles::UserRole};
+    use user::guards::UserGuards;
+
+    #[test]
+    fn scoped_credentials_are_limited_to_their_scopes() {
+        let mut auth_user = AuthUser::new_test_auth_user(UserRole::Moderator);
+        auth_user.0.scopes = vec![Permission::ViewUser.as_scope().to_string()];
+        let guards = GuardsImpl::new();
+
+        assert!(
+            guards
+                .authorize(&auth_user, &UserPermission::ViewUser)
+                .is_ok()
+        );
+        assert!(
+            guards
+                .authorize(&auth_user, &UserPermission::BanUser)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn impersonation_withholds_destructive_permissions() {
+        let mut auth_user = AuthUser::new_test_auth_user(UserRole::Moderator);
+        auth_user.0.act = Some(Actor {
+            sub: "admin@example.com".into(),
+            id: "admin-id".into(),
+        });
+        let guards = GuardsImpl::new();
+
+        assert!(
+            guards
+                .authorize(&auth_user, &UserPermission::ListUsers)
+                .is_ok()
+        );
+        assert!(
+            guards
+                .authorize(&auth_user, &UserPermission::BanUser)
+                .is_err()
+        );
+        assert!(
+            guards
+                .authorize(&auth_user, &UserPermission::ManageTwoFactor)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn scopes_do_not_extend_the_role() {
+        let mut auth_user = AuthUser::new_test_auth_user(UserRole::Regular);
+        auth_user.0.scopes = vec![Permission::BanUser.as_scope().to_string()];
+
+        let result = GuardsImpl::new().authorize(&auth_user, &UserPermission::BanUser);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn impersonation_cannot_change_content() {
+        let mut auth_user = AuthUser::new_test_auth_user(UserRole::Regular);
+        auth_user.0.act = Some(Actor {
+            sub: "admin@example.com".into(),
+            id: "admin-id".into(),
+        });
+        let guards: &dyn content::guards::ContentGuards = &GuardsImpl::new();
+
+        assert!(
+            guards
+                .authorize(&auth_user, &ContentPermission::UpdatePost)
+                .is_err()
+        );
+        assert!(
+            guards
+                .authorize(&auth_user, &ContentPermission::CreateComment)
+                .is_err()
+        );
+        assert!(
+            guards
+                .authorize(&auth_user, &ContentPermission::DeletePost)
+                .is_err()
+        );
+    }
+}
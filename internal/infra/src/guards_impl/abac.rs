@@ -1,5 +1,5 @@
 use ::user::domain::result::UserDomainResult;
-use shared::auth::AuthUser;
+use shared::{auth::AuthUser, types::AppResult};
 
 pub struct AbacEngine;
 
@@ -10,6 +10,18 @@ impl AbacEngine {
     pub fn can_change_username(&self, user_id: &str, auth_user: &AuthUser) -> UserDomainResult<()> {
         user::can_change_username(user_id, auth_user)
     }
+    pub fn can_update_post(&self, author_id: &str, auth_user: &AuthUser) -> AppResult<()> {
+        content::can_update_post(author_id, auth_user)
+    }
+    pub fn can_delete_post(&self, author_id: &str, auth_user: &AuthUser) -> AppResult<()> {
+        content::can_delete_post(author_id, auth_user)
+    }
+    pub fn can_update_comment(&self, author_id: &str, auth_user: &AuthUser) -> AppResult<()> {
+        content::can_update_comment(author_id, auth_user)
+    }
+    pub fn can_delete_comment(&self, author_id: &str, auth_user: &AuthUser) -> AppResult<()> {
+        content::can_delete_comment(author_id, auth_user)
+    }
 }
 
 pub mod user {
@@ -18,6 +30,10 @@ pub mod user {
     use user::domain::{errors::UserDomainError, result::UserDomainResult};
 
     pub fn can_change_username(user_id: &str, auth_user: &AuthUser) -> UserDomainResult<()> {
+        // No scope covers account changes, and impersonation is read-mostly.
+        if auth_user.is_scoped() || auth_user.impersonator().is_some() {
+            return Err(UserDomainError::Unauthorized);
+        }
         match auth_user.0.role {
             Admin => Ok(()),
             Regular | Moderator => {
@@ -71,6 +87,15 @@ pub mod user {
             assert_eq!(true, can_change.is_err());
         }
 
+        #[test]
+        fn scoped_token_cannot_change_username() {
+            let mut auth_user = AuthUser::new_test_auth_user(Regular);
+            auth_user.0.scopes = vec!["view_user".to_string()];
+            let user_id = auth_user.0.id.clone();
+            let can_change = can_change_username(user_id.as_str(), &auth_user);
+            assert!(can_change.is_err());
+        }
+
         #[test]
         fn guest_cannot_change_username() {
             let auth_user = AuthUser::new_test_auth_user(Guest);
@@ -80,3 +105,105 @@ pub mod user {
         }
     }
 }
+
+pub mod content {
+    use shared::auth::AuthUser;
+    use shared::errors::content::ContentDomainError;
+    use shared::guards::roles::UserRole::{Admin, Guest, Moderator, Regular};
+    use shared::types::AppResult;
+
+    /// Nobody edits someone else's words, admins included.
+    pub fn can_update_post(author_id: &str, auth_user: &AuthUser) -> AppResult<()> {
+        match auth_user.0.role {
+            Guest => Err(ContentDomainError::Unauthorized.into()),
+            Admin | Moderator | Regular => {
+                if author_id == auth_user.0.id {
+                    return Ok(());
+                }
+                Err(ContentDomainError::Unauthorized.into())
+            }
+        }
+    }
+
+    pub fn can_delete_post(author_id: &str, auth_user: &AuthUser) -> AppResult<()> {
+        match auth_user.0.role {
+            Admin | Moderator => Ok(()),
+            Regular => {
+                if author_id == auth_user.0.id {
+                    return Ok(());
+                }
+                Err(ContentDomainError::Unauthorized.into())
+            }
+            Guest => Err(ContentDomainError::Unauthorized.into()),
+        }
+    }
+
+    /// Same rule as for posts.
+    pub fn can_update_comment(author_id: &str, auth_user: &AuthUser) -> AppResult<()> {
+        can_update_post(author_id, auth_user)
+    }
+
+    /// Same rule as for posts: authors, and moderators for anyone's comment.
+    pub fn can_delete_comment(author_id: &str, auth_user: &AuthUser) -> AppResult<()> {
+        can_delete_post(author_id, auth_user)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn author_can_update_their_post() {
+            let auth_user = AuthUser::new_test_auth_user(Regular);
+            let author_id = auth_user.0.id.clone();
+            assert!(can_update_post(&author_id, &auth_user).is_ok());
+        }
+
+        #[test]
+        fn nobody_else_can_update_a_post() {
+            for role in [Regular, Moderator, Admin] {
+                let auth_user = AuthUser::new_test_auth_user(role);
+                assert!(can_update_post("author-id", &auth_user).is_err());
+            }
+        }
+
+        #[test]
+        fn author_can_delete_their_post() {
+            let auth_user = AuthUser::new_test_auth_user(Regular);
+            let author_id = auth_user.0.id.clone();
+            assert!(can_delete_post(&author_id, &auth_user).is_ok());
+        }
+
+        #[test]
+        fn moderator_and_admin_can_delete_any_post() {
+            for role in [Moderator, Admin] {
+                let auth_user = AuthUser::new_test_auth_user(role);
+                assert!(can_delete_post("author-id", &auth_user).is_ok());
+            }
+        }
+
+        #[test]
+        fn regular_user_and_guest_cannot_delete_others_posts() {
+            for role in [Regular, Guest] {
+                let auth_user = AuthUser::new_test_auth_user(role);
+                assert!(can_delete_post("author-id", &auth_user).is_err());
+            }
+        }
+
+        #[test]
+        fn only_the_author_can_update_a_comment() {
+            let auth_user = AuthUser::new_test_auth_user(Moderator);
+            let author_id = auth_user.0.id.clone();
+            assert!(can_update_comment(&author_id, &auth_user).is_ok());
+            assert!(can_update_comment("author-id", &auth_user).is_err());
+        }
+
+        #[test]
+        fn moderator_can_delete_any_comment() {
+            let moderator = AuthUser::new_test_auth_user(Moderator);
+            assert!(can_delete_comment("author-id", &moderator).is_ok());
+            let regular = AuthUser::new_test_auth_user(Regular);
+            assert!(can_delete_comment("author-id", &regular).is_err());
+        }
+    }
+}
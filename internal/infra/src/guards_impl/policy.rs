@@ -2,7 +2,9 @@ use std::collections::HashMap;
 
 use shared::guards::permissions::Permission;
 use shared::guards::permissions::Permission::{
-    BanUser, CreateAccount, ListUsers, UnbanUser, ViewUser,
+    BanUser, CreateAccount, CreateComment, CreatePost, DeleteComment, DeletePost, ListUsers,
+    ManageApiTokens, ManageTwoFactor, UnbanUser, UpdateComment, UpdatePost, ViewModerationQueue,
+    ViewUser,
 };
 use shared::guards::roles::UserRole;
 use shared::guards::roles::UserRole::{Admin, Guest, Moderator, Regular};
@@ -16,8 +18,38 @@ impl Policy {
     pub fn new() -> Self {
         let mut rules = HashMap::new();
         rules.insert(Admin, vec![ViewUser]);
-        rules.insert(Regular, vec![ViewUser]);
-        rules.insert(Moderator, vec![ViewUser, ListUsers, BanUser, UnbanUser]);
+        rules.insert(
+            Regular,
+            vec![
+                ViewUser,
+                ManageTwoFactor,
+                ManageApiTokens,
+                CreatePost,
+                UpdatePost,
+                DeletePost,
+                CreateComment,
+                UpdateComment,
+                DeleteComment,
+            ],
+        );
+        rules.insert(
+            Moderator,
+            vec![
+                ViewUser,
+                ListUsers,
+                BanUser,
+                UnbanUser,
+                ManageTwoFactor,
+                ManageApiTokens,
+                ViewModerationQueue,
+                CreatePost,
+                UpdatePost,
+                DeletePost,
+                CreateComment,
+                UpdateComment,
+                DeleteComment,
+            ],
+        );
         rules.insert(Guest, vec![CreateAccount]);
         Self { rules }
     }
@@ -20,8 +20,10 @@ impl RbacEngine {
 mod tests {
     use super::*;
     use shared::guards::permissions::Permission::{
-        AwardBadge, BanUser, CreateAccount, DeleteUser, ListUsers, MakeModerator, MakeRegular,
-        RevokeBadge, UnbanUser, ViewUser,
+        AwardBadge, BanUser, CreateAccount, CreateComment, CreatePost, DeleteComment, DeletePost,
+        DeleteUser, ListUsers, MakeModerator, MakeRegular, ManageApiTokens, ManageTwoFactor,
+        RevokeBadge, UnbanUser, UpdateComment, UpdatePost, ViewAuditLog, ViewModerationQueue,
+        ViewUser,
     };
     use shared::guards::roles::UserRole::{Admin, Guest, Moderator, Regular};
 
@@ -264,4 +266,81 @@ mod tests {
         let r = RbacEngine::new().authorize(&Guest, &CreateAccount);
         assert_eq!(r.is_ok(), true);
     }
+
+    #[test]
+    fn regular_user_has_manage_two_factor_permission() {
+        let r = RbacEngine::new().authorize(&Regular, &ManageTwoFactor);
+        assert_eq!(r.is_ok(), true);
+    }
+
+    #[test]
+    fn moderator_has_manage_two_factor_permission() {
+        let r = RbacEngine::new().authorize(&Moderator, &ManageTwoFactor);
+        assert_eq!(r.is_ok(), true);
+    }
+
+    #[test]
+    fn guest_has_no_manage_two_factor_permission() {
+        let r = RbacEngine::new().authorize(&Guest, &ManageTwoFactor);
+        assert_eq!(r.is_err(), true);
+    }
+
+    #[test]
+    fn regular_user_has_manage_api_tokens_permission() {
+        let r = RbacEngine::new().authorize(&Regular, &ManageApiTokens);
+        assert!(r.is_ok());
+    }
+
+    #[test]
+    fn guest_has_no_manage_api_tokens_permission() {
+        let r = RbacEngine::new().authorize(&Guest, &ManageApiTokens);
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn admin_has_view_audit_log_permission() {
+        let r = RbacEngine::new().authorize(&Admin, &ViewAuditLog);
+        assert!(r.is_ok());
+    }
+
+    #[test]
+    fn moderator_has_no_view_audit_log_permission() {
+        let r = RbacEngine::new().authorize(&Moderator, &ViewAuditLog);
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn moderator_has_view_moderation_queue_permission() {
+        let r = RbacEngine::new().authorize(&Moderator, &ViewModerationQueue);
+        assert!(r.is_ok());
+    }
+
+    #[test]
+    fn regular_user_has_no_view_moderation_queue_permission() {
+        let r = RbacEngine::new().authorize(&Regular, &ViewModerationQueue);
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn regular_user_and_moderator_have_post_permissions() {
+        for role in [Regular, Moderator] {
+            for perm in [
+                CreatePost,
+                UpdatePost,
+                DeletePost,
+                CreateComment,
+                UpdateComment,
+                DeleteComment,
+            ] {
+                assert!(RbacEngine::new().authorize(&role, &perm).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn guest_has_no_post_permissions() {
+        for perm in [CreatePost, UpdatePost, DeletePost] {
+            assert!(RbacEngine::new().authorize(&Guest, &perm).is_err());
+        }
+    }
 }
@@ -1,3 +1,3 @@
-pub mod config; //TODO: remove pub once pg config is consumed
 pub mod guards_impl;
+pub mod oidc_mock;
 pub mod storage;
@@ -0,0 +1,317 @@
+//! A minimal OpenID Connect provider for local development and tests.
+//!
+//! There is no login page: `/authorize` signs in whoever is named by the
+//! `login_hint` parameter and redirects straight back with a code. ID tokens
+//! are HS256 signed with the client secret, so no key material is needed.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Form, Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{EncodingKey, Header, encode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use url::Url;
+
+use user::domain::user_auth::oidc::utils::{code_challenge, random_token};
+
+const CODE_TTL_SECS: i64 = 60;
+const ID_TOKEN_TTL_SECS: i64 = 300;
+
+#[derive(Clone)]
+pub struct MockOidcProvider {
+    issuer: String,
+    client_id: String,
+    client_secret: String,
+    codes: Arc<Mutex<HashMap<String, IssuedCode>>>,
+}
+
+struct IssuedCode {
+    redirect_uri: String,
+    code_challenge: String,
+    nonce: Option<String>,
+    email: String,
+    email_verified: bool,
+    expires_at: chrono::DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthorizeParams {
+    response_type: String,
+    client_id: String,
+    redirect_uri: String,
+    state: String,
+    nonce: Option<String>,
+    code_challenge: String,
+    code_challenge_method: String,
+    /// Email of the user to sign in.
+    login_hint: Option<String>,
+    /// Set to `false` to simulate a provider that has not verified the email.
+    email_verified: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenParams {
+    grant_type: String,
+    code: String,
+    redirect_uri: String,
+    client_id: String,
+    client_secret: String,
+    code_verifier: String,
+}
+
+#[derive(Debug, Serialize)]
+struct IdTokenClaims {
+    iss: String,
+    sub: String,
+    aud: String,
+    exp: i64,
+    iat: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<String>,
+    email: String,
+    email_verified: bool,
+    preferred_username: String,
+}
+
+impl MockOidcProvider {
+    /// `issuer` is the public URL the router is served at.
+    pub fn new(issuer: String, client_id: String, client_secret: String) -> Self {
+        Self {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            client_id,
+            client_secret,
+            codes: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/authorize", get(authorize))
+            .route("/token", post(token))
+            .route("/jwks", get(jwks))
+            .with_state(self)
+    }
+
+    /// The subject the provider assigns to `email`. Stable across sign-ins.
+    pub fn subject_for(email: &str) -> String {
+        code_challenge(&email.to_lowercase())
+    }
+}
+
+fn oauth_error(error: &str) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response()
+}
+
+async fn discovery(State(provider): State<MockOidcProvider>) -> Json<serde_json::Value> {
+    let issuer = &provider.issuer;
+    Json(json!({
+        "issuer": issuer,
+        "authorization_endpoint": format!("{}/authorize", issuer),
+        "token_endpoint": format!("{}/token", issuer),
+        "jwks_uri": format!("{}/jwks", issuer),
+        "response_types_supported": ["code"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": ["HS256"],
+        "code_challenge_methods_supported": ["S256"],
+        "token_endpoint_auth_methods_supported": ["client_secret_post"],
+    }))
+}
+
+async fn authorize(
+    State(provider): State<MockOidcProvider>,
+    Query(params): Query<AuthorizeParams>,
+) -> Response {
+    if params.client_id != provider.client_id {
+        return oauth_error("unauthorized_client");
+    }
+    if params.response_type != "code" || params.code_challenge_method != "S256" {
+        return oauth_error("invalid_request");
+    }
+    let Some(email) = params.login_hint.filter(|email| email.contains('@')) else {
+        return oauth_error("login_required");
+    };
+    let Ok(mut location) = Url::parse(&params.redirect_uri) else {
+        return oauth_error("invalid_request");
+    };
+
+    let code = random_token();
+    provider.codes.lock().unwrap().insert(
+        code.clone(),
+        IssuedCode {
+            redirect_uri: params.redirect_uri.clone(),
+            code_challenge: params.code_challenge,
+            nonce: params.nonce,
+            email,
+            email_verified: params.email_verified.unwrap_or(true),
+            expires_at: Utc::now() + Duration::seconds(CODE_TTL_SECS),
+        },
+    );
+    location
+        .query_pairs_mut()
+        .append_pair("code", &code)
+        .append_pair("state", &params.state);
+    Redirect::to(location.as_str()).into_response()
+}
+
+async fn token(
+    State(provider): State<MockOidcProvider>,
+    Form(params): Form<TokenParams>,
+) -> Response {
+    if params.client_id != provider.client_id || params.client_secret != provider.client_secret {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "invalid_client" })),
+        )
+            .into_response();
+    }
+    if params.grant_type != "authorization_code" {
+        return oauth_error("unsupported_grant_type");
+    }
+    // Codes are single use, even when the exchange fails.
+    let Some(issued) = provider.codes.lock().unwrap().remove(&params.code) else {
+        return oauth_error("invalid_grant");
+    };
+    if issued.expires_at < Utc::now()
+        || issued.redirect_uri != params.redirect_uri
+        || issued.code_challenge != code_challenge(&params.code_verifier)
+    {
+        return oauth_error("invalid_grant");
+    }
+
+    let now = Utc::now();
+    let claims = IdTokenClaims {
+        iss: provider.issuer.clone(),
+        sub: MockOidcProvider::subject_for(&issued.email),
+        aud: provider.client_id.clone(),
+        exp: (now + Duration::seconds(ID_TOKEN_TTL_SECS)).timestamp(),
+        iat: now.timestamp(),
+        nonce: issued.nonce,
+        preferred_username: issued.email.split('@').next().unwrap_or_default().into(),
+        email: issued.email,
+        email_verified: issued.email_verified,
+    };
+    let id_token = match encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(provider.client_secret.as_bytes()),
+    ) {
+        Ok(id_token) => id_token,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    Json(json!({
+        "access_token": random_token(),
+        "token_type": "Bearer",
+        "expires_in": ID_TOKEN_TTL_SECS,
+        "id_token": id_token,
+    }))
+    .into_response()
+}
+
+async fn jwks() -> Json<serde_json::Value> {
+    Json(json!({ "keys": [] }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use user::domain::user_auth::oidc::{OidcAuthRequest, OidcProviderConfig};
+    use user::infra::oidc::http_oidc_client::HttpOidcClient;
+
+    /// Serves a mock provider on a random local port.
+    async fn start_provider() -> OidcProviderConfig {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let router =
+            MockOidcProvider::new(issuer.clone(), "client".into(), "secret".into()).router();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        OidcProviderConfig {
+            name: "mock".into(),
+            issuer,
+            client_id: "client".into(),
+            client_secret: "secret".into(),
+            scopes: vec!["openid".into(), "email".into()],
+            redirect_uri: "http://localhost/callback".into(),
+        }
+    }
+
+    /// Follows the authorization URL as the user's browser would and returns the code.
+    async fn authorize(url: &str, query: &str, expected_state: &str) -> String {
+        let http = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let response = http.get(format!("{}&{}", url, query)).send().await.unwrap();
+        let location = response.headers()["location"].to_str().unwrap();
+        let location = Url::parse(location).unwrap();
+        let params: HashMap<_, _> = location.query_pairs().into_owned().collect();
+        assert_eq!(params["state"], expected_state);
+        params["code"].clone()
+    }
+
+    #[tokio::test]
+    async fn sign_in_with_mock_provider() {
+        let provider = start_provider().await;
+        let client = HttpOidcClient::new();
+        let request = OidcAuthRequest::new("mock".into(), None, Utc::now());
+
+        let url = client.authorization_url(&provider, &request).await.unwrap();
+        let code = authorize(&url, "login_hint=jane@example.com", request.state()).await;
+        let identity = client
+            .exchange_code(&provider, &request, &code)
+            .await
+            .unwrap();
+
+        assert_eq!(identity.verified_email(), Some("jane@example.com"));
+        assert_eq!(
+            identity.subject,
+            MockOidcProvider::subject_for("jane@example.com")
+        );
+        assert_eq!(identity.preferred_username.as_deref(), Some("jane"));
+
+        let replayed = client.exchange_code(&provider, &request, &code).await;
+        assert!(replayed.is_err());
+    }
+
+    #[tokio::test]
+    async fn exchange_requires_matching_code_verifier() {
+        let provider = start_provider().await;
+        let client = HttpOidcClient::new();
+        let request = OidcAuthRequest::new("mock".into(), None, Utc::now());
+        let other_request = OidcAuthRequest::new("mock".into(), None, Utc::now());
+
+        let url = client.authorization_url(&provider, &request).await.unwrap();
+        let code = authorize(&url, "login_hint=jane@example.com", request.state()).await;
+        let result = client.exchange_code(&provider, &other_request, &code).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn unverified_email_is_reported() {
+        let provider = start_provider().await;
+        let client = HttpOidcClient::new();
+        let request = OidcAuthRequest::new("mock".into(), None, Utc::now());
+
+        let url = client.authorization_url(&provider, &request).await.unwrap();
+        let code = authorize(
+            &url,
+            "login_hint=jane@example.com&email_verified=false",
+            request.state(),
+        )
+        .await;
+        let identity = client
+            .exchange_code(&provider, &request, &code)
+            .await
+            .unwrap();
+        assert_eq!(identity.verified_email(), None);
+    }
+}
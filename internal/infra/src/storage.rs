@@ -1,18 +1,46 @@
 use std::sync::Arc;
 
+use content::infra::repository::{
+    comment_repository::CommentRepository, post_repository::PostRepository,
+};
 use user::infra::repository::{
-    otp_repository::OtpRepository, user_read_model_repository::UserReadModelRepository,
-    user_repository::UserRepository,
+    api_token_repository::ApiTokenRepository, audit_log_repository::AuditLogRepository,
+    oidc_auth_request_repository::OidcAuthRequestRepository,
+    otp_issuance_repository::OtpIssuanceRepository, otp_repository::OtpRepository,
+    user_read_model_repository::UserReadModelRepository, user_repository::UserRepository,
 };
 
+use migrations::MigrationStatus;
 use mongo_storage::MongoDBStorage;
+use serde::Serialize;
+use shared::{
+    background_jobs::BackgroundJobs, config::AppConfig, rate_limit::RateLimitStore,
+    types::AppResult,
+};
 
+pub mod migrations;
+mod mongo_metrics;
+mod mongo_rate_limit_store;
 mod mongo_storage;
 
 pub struct Repos {
     pub user_repo: Arc<UserRepository>,
     pub user_read_repo: Arc<UserReadModelRepository>,
     pub otp_repo: Arc<OtpRepository>,
+    pub otp_issuance_repo: Arc<OtpIssuanceRepository>,
+    pub oidc_auth_request_repo: Arc<OidcAuthRequestRepository>,
+    pub api_token_repo: Arc<ApiTokenRepository>,
+    pub audit_log_repo: Arc<AuditLogRepository>,
+    pub post_repo: Arc<PostRepository>,
+    pub comment_repo: Arc<CommentRepository>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageHealth {
+    pub engine: &'static str,
+    pub healthy: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
 }
 
 pub enum StorageEngine {
@@ -32,13 +60,48 @@ impl StorageSource {
             StorageSource::Mongo(mongo) => mongo.repos(),
         }
     }
+    pub async fn migrate(&self) -> AppResult<Vec<u32>> {
+        match self {
+            StorageSource::Mongo(mongo) => mongo.migrate().await,
+        }
+    }
+    pub async fn migration_status(&self) -> AppResult<Vec<MigrationStatus>> {
+        match self {
+            StorageSource::Mongo(mongo) => mongo.migration_status().await,
+        }
+    }
+    /// Rate limit buckets shared by every instance using this storage.
+    pub fn rate_limit_store(&self) -> Arc<dyn RateLimitStore> {
+        match self {
+            StorageSource::Mongo(mongo) => mongo.rate_limit_store(),
+        }
+    }
+    /// Checks that the storage answers, for readiness probes.
+    pub async fn health(&self) -> StorageHealth {
+        match self {
+            StorageSource::Mongo(mongo) => mongo.health().await,
+        }
+    }
 }
 
 pub struct AppStorage;
 impl AppStorage {
-    pub async fn build(engine: StorageEngine) -> StorageSource {
+    /// Pending migrations run as a background job in `jobs`; the app is not
+    /// ready until they finish.
+    pub async fn build(
+        engine: StorageEngine,
+        config: &AppConfig,
+        jobs: &BackgroundJobs,
+    ) -> StorageSource {
+        match engine {
+            StorageEngine::MongoDB => StorageSource::Mongo(MongoDBStorage::new(config, jobs).await),
+            _ => unimplemented!(),
+        }
+    }
+    /// Builds the storage without applying pending migrations, for the `migrate` subcommand.
+    pub async fn connect(engine: StorageEngine, config: &AppConfig) -> StorageSource {
         match engine {
-            StorageEngine::MongoDB => StorageSource::Mongo(MongoDBStorage::new().await),
+            StorageEngine::MongoDB => StorageSource::Mongo(MongoDBStorage::connect(config).await),
             _ => unimplemented!(),
         }
     }
@@ -0,0 +1,376 @@
+//! Versioned, forward-only schema migrations for MongoDB.
+//!
+//! Every migration has a unique, increasing version. Applied versions are
+//! recorded in the `_migrations` collection, so each migration runs once per
+//! database. Migrations must be idempotent: two instances starting at the same
+//! time may both run a pending migration before either has recorded it.
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::{Collection, Database, bson::doc};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use shared::types::AppResult;
+use user::domain::user_auth::otp_hasher::OtpHasher;
+
+mod v001_canonical_fields;
+mod v002_users_indexes;
+mod v003_otps_expiry;
+mod v004_otp_issuances;
+mod v005_keyed_otp_hashes;
+mod v006_oidc;
+mod v007_api_tokens;
+mod v008_audit_log;
+mod v009_rate_limits;
+mod v010_posts_indexes;
+mod v011_comments_indexes;
+
+pub const MIGRATIONS_COLLECTION: &str = "_migrations";
+
+#[async_trait]
+pub trait Migration: Send + Sync {
+    fn version(&self) -> u32;
+    fn name(&self) -> &'static str;
+    async fn up(&self, db: &Database) -> AppResult<()>;
+}
+
+/// All known migrations. Data migrations that backfill fields must come
+/// before the indexes that depend on them. `otp_hasher` rewraps legacy otp hashes.
+pub fn all(otp_hasher: OtpHasher) -> Vec<Box<dyn Migration>> {
+    vec![
+        Box::new(v001_canonical_fields::BackfillCanonicalFields),
+        Box::new(v002_users_indexes::CreateUsersIndexes),
+        Box::new(v003_otps_expiry::CreateOtpsExpiry),
+        Box::new(v004_otp_issuances::CreateOtpIssuancesIndexes),
+        Box::new(v005_keyed_otp_hashes::WrapLegacyOtpHashes::new(otp_hasher)),
+        Box::new(v006_oidc::CreateOidcIndexes),
+        Box::new(v007_api_tokens::CreateApiTokensIndexes),
+        Box::new(v008_audit_log::CreateAuditLogIndexes),
+        Box::new(v009_rate_limits::CreateRateLimitsExpiry),
+        Box::new(v010_posts_indexes::CreatePostsIndexes),
+        Box::new(v011_comments_indexes::CreateCommentsIndexes),
+    ]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MigrationRecord {
+    #[serde(rename = "_id")]
+    version: u32,
+    name: String,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    applied_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationStatus {
+    pub version: u32,
+    pub name: &'static str,
+    pub applied_at: Option<DateTime<Utc>>,
+}
+
+pub struct MigrationRunner {
+    db: Database,
+    records: Collection<MigrationRecord>,
+    migrations: Vec<Box<dyn Migration>>,
+}
+
+impl MigrationRunner {
+    pub fn new(db: Database, otp_hasher: OtpHasher) -> Self {
+        Self::with_migrations(db, all(otp_hasher))
+    }
+    pub fn with_migrations(db: Database, mut migrations: Vec<Box<dyn Migration>>) -> Self {
+        migrations.sort_by_key(|m| m.version());
+        assert!(
+            migrations
+                .windows(2)
+                .all(|w| w[0].version() != w[1].version()),
+            "Migration versions must be unique"
+        );
+        Self {
+            records: db.collection(MIGRATIONS_COLLECTION),
+            db,
+            migrations,
+        }
+    }
+
+    pub async fn status(&self) -> AppResult<Vec<MigrationStatus>> {
+        let applied = self.applied().await?;
+        Ok(self
+            .migrations
+            .iter()
+            .map(|m| MigrationStatus {
+                version: m.version(),
+                name: m.name(),
+                applied_at: applied
+                    .iter()
+                    .find(|r| r.version == m.version())
+                    .map(|r| r.applied_at),
+            })
+            .collect())
+    }
+
+    /// Applies all pending migrations in version order and returns the
+    /// versions that were applied. Stops at the first failing migration.
+    pub async fn run(&self) -> AppResult<Vec<u32>> {
+        let applied = self.applied().await?;
+        let mut newly_applied = vec![];
+        for migration in &self.migrations {
+            if applied.iter().any(|r| r.version == migration.version()) {
+                continue;
+            }
+            info!(
+                "Applying migration {} ({})",
+                migration.version(),
+                migration.name()
+            );
+            migration.up(&self.db).await?;
+            self.record(migration.as_ref()).await?;
+            newly_applied.push(migration.version());
+        }
+        Ok(newly_applied)
+    }
+
+    async fn applied(&self) -> AppResult<Vec<MigrationRecord>> {
+        let mut cursor = self.records.find(doc! {}).await?;
+        let mut records = vec![];
+        while cursor.advance().await? {
+            records.push(cursor.deserialize_current()?);
+        }
+        Ok(records)
+    }
+
+    async fn record(&self, migration: &dyn Migration) -> AppResult<()> {
+        let record = MigrationRecord {
+            version: migration.version(),
+            name: migration.name().into(),
+            applied_at: Utc::now(),
+        };
+        // An upsert keeps recording idempotent when another instance got there first.
+        self.records
+            .update_one(
+                doc! {"_id": record.version},
+                doc! {"$setOnInsert": {
+                    "name": &record.name,
+                    "applied_at": bson::DateTime::from_chrono(record.applied_at),
+                }},
+            )
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::IndexModel;
+    use shared::test_utils;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    };
+    use uuid::Uuid;
+
+    struct CountingMigration {
+        version: u32,
+        runs: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl Migration for CountingMigration {
+        fn version(&self) -> u32 {
+            self.version
+        }
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+        async fn up(&self, _db: &Database) -> AppResult<()> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    async fn index_names(db: &Database, collection: &str) -> Vec<String> {
+        db.collection::<bson::Document>(collection)
+            .list_index_names()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_run_applies_pending_migrations_once() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+
+        let runs = Arc::new(AtomicU32::new(0));
+        let build = || {
+            MigrationRunner::with_migrations(
+                db.clone(),
+                vec![
+                    Box::new(CountingMigration {
+                        version: 2,
+                        runs: runs.clone(),
+                    }),
+                    Box::new(CountingMigration {
+                        version: 1,
+                        runs: runs.clone(),
+                    }),
+                ],
+            )
+        };
+
+        assert_eq!(build().run().await.unwrap(), vec![1, 2]);
+        assert_eq!(build().run().await.unwrap(), Vec::<u32>::new());
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        let status = build().status().await.unwrap();
+        assert!(status.iter().all(|s| s.applied_at.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_all_migrations_create_indexes() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+
+        MigrationRunner::new(db.clone(), OtpHasher::new_test_hasher())
+            .run()
+            .await
+            .unwrap();
+
+        let users = index_names(&db, "users").await;
+        assert!(users.contains(&"username_canonical_1".to_string()));
+        assert!(users.contains(&"email_canonical_1".to_string()));
+        assert!(users.contains(&"created_at_1".to_string()));
+        assert!(users.contains(&"username_history.username_canonical_1".to_string()));
+        assert!(users.contains(&"identities.provider_1_identities.subject_1".to_string()));
+
+        let otps = db
+            .collection::<bson::Document>("otps")
+            .list_indexes()
+            .await
+            .unwrap();
+        let otps: Vec<IndexModel> = futures::TryStreamExt::try_collect(otps).await.unwrap();
+        let ttl = otps
+            .iter()
+            .find(|i| i.keys == doc! {"expire_at": 1})
+            .and_then(|i| i.options.as_ref())
+            .and_then(|o| o.expire_after);
+        assert_eq!(ttl, Some(std::time::Duration::ZERO));
+
+        let issuances = index_names(&db, "otp_issuances").await;
+        assert!(issuances.contains(&"key_1_issued_at_1".to_string()));
+        assert!(issuances.contains(&"expire_at_1".to_string()));
+
+        let oidc_auth_requests = index_names(&db, "oidc_auth_requests").await;
+        assert!(oidc_auth_requests.contains(&"expire_at_1".to_string()));
+
+        let api_tokens = index_names(&db, "api_tokens").await;
+        assert!(api_tokens.contains(&"user_id_1_created_at_-1".to_string()));
+
+        let audit_log = index_names(&db, "audit_log").await;
+        assert!(audit_log.contains(&"created_at_-1__id_-1".to_string()));
+        assert!(audit_log.contains(&"actor_id_1_created_at_-1".to_string()));
+        assert!(audit_log.contains(&"target_id_1_created_at_-1".to_string()));
+
+        let rate_limits = index_names(&db, "rate_limits").await;
+        assert!(rate_limits.contains(&"expire_at_1".to_string()));
+
+        let posts = index_names(&db, "posts").await;
+        assert!(posts.contains(&"created_at_-1__id_-1".to_string()));
+        assert!(posts.contains(&"author_id_1_created_at_-1__id_-1".to_string()));
+
+        let comments = index_names(&db, "comments").await;
+        assert!(comments.contains(&"post_id_1_parent_id_1_created_at_1__id_1".to_string()));
+        assert!(comments.contains(&"parent_id_1_created_at_1__id_1".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_wrap_legacy_otp_hashes() {
+        use user::domain::user_auth::otp::{ComparedOtps, utils::hash_otp};
+
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+
+        let otps = db.collection::<bson::Document>("otps");
+        otps.insert_one(doc! {
+            "otp_hash": hash_otp("123456"),
+            "expires_at": 0_i64,
+            "used": false,
+            "attempts": 0,
+            "email": "john@example.com",
+        })
+        .await
+        .unwrap();
+
+        let hasher = OtpHasher::new_test_hasher();
+        v005_keyed_otp_hashes::WrapLegacyOtpHashes::new(hasher.clone())
+            .up(&db)
+            .await
+            .unwrap();
+
+        let otp = otps.find_one(doc! {}).await.unwrap().unwrap();
+        let key_id = otp.get_str("key_id").unwrap();
+        assert_eq!(key_id, hasher.current_key_id());
+        assert_eq!(
+            hasher.compare("123456", Some(key_id), otp.get_str("otp_hash").unwrap()),
+            ComparedOtps::Equal
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backfill_canonical_fields_and_otp_expiry() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+
+        let now = bson::DateTime::now();
+        db.collection::<bson::Document>("users")
+            .insert_one(doc! {
+                "_id": "legacy-user",
+                "username": "JohnDoe",
+                "email": "John@Example.com",
+                "role": "Regular",
+                "badges": [],
+                "created_at": now,
+                "updated_at": now,
+                "email_status": "Verified",
+                "username_history": [{"username": "OldName", "changed_at": now}],
+            })
+            .await
+            .unwrap();
+        db.collection::<bson::Document>("otps")
+            .insert_one(doc! {
+                "otp_hash": "hash",
+                "expires_at": now.timestamp_millis(),
+                "used": false,
+                "attempts": 0,
+                "email": "john@example.com",
+            })
+            .await
+            .unwrap();
+
+        MigrationRunner::new(db.clone(), OtpHasher::new_test_hasher())
+            .run()
+            .await
+            .unwrap();
+
+        let user = db
+            .collection::<bson::Document>("users")
+            .find_one(doc! {"_id": "legacy-user"})
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.get_str("username_canonical").unwrap(), "johndoe");
+        assert_eq!(user.get_str("email_canonical").unwrap(), "john@example.com");
+        let history = user.get_array("username_history").unwrap();
+        let entry = history[0].as_document().unwrap();
+        assert_eq!(entry.get_str("username_canonical").unwrap(), "oldname");
+
+        let otp = db
+            .collection::<bson::Document>("otps")
+            .find_one(doc! {"email": "john@example.com"})
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(otp.get_datetime("expire_at").unwrap(), &now);
+    }
+}
@@ -0,0 +1,41 @@
+use async_trait::async_trait;
+use mongodb::{Database, bson::doc};
+
+use shared::types::AppResult;
+use user::{domain::user::User, infra::mongoimpl::user_document::UserDocument};
+
+use super::Migration;
+
+/// Fills in the canonical username and email, including those in the username
+/// history, for users created before they were stored. Two legacy users whose
+/// names differ only in casing will make the following unique index fail, and
+/// have to be resolved by hand.
+pub struct BackfillCanonicalFields;
+
+#[async_trait]
+impl Migration for BackfillCanonicalFields {
+    fn version(&self) -> u32 {
+        1
+    }
+    fn name(&self) -> &'static str {
+        "backfill_canonical_username_and_email"
+    }
+    async fn up(&self, db: &Database) -> AppResult<()> {
+        let collection = db.collection::<UserDocument>("users");
+        let missing = doc! {"$in": [null, ""]};
+        let filter = doc! {"$or": [
+            {"username_canonical": missing.clone()},
+            {"email_canonical": missing.clone()},
+            {"username_history": {"$elemMatch": {"username_canonical": missing}}},
+        ]};
+        let mut cursor = collection.find(filter).await?;
+        while cursor.advance().await? {
+            let legacy = cursor.deserialize_current()?;
+            let id = legacy.id.clone();
+            // The domain conversion recomputes every canonical field.
+            let user: UserDocument = User::from(legacy).into();
+            collection.replace_one(doc! {"_id": id}, user).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use async_trait::async_trait;
+use mongodb::{Database, IndexModel, bson::doc, options::IndexOptions};
+
+use shared::types::AppResult;
+
+use super::Migration;
+
+/// Unique indexes on the canonical username and email close the race window
+/// between the existence check and the upsert on sign up.
+pub struct CreateUsersIndexes;
+
+#[async_trait]
+impl Migration for CreateUsersIndexes {
+    fn version(&self) -> u32 {
+        2
+    }
+    fn name(&self) -> &'static str {
+        "create_users_indexes"
+    }
+    async fn up(&self, db: &Database) -> AppResult<()> {
+        let unique = IndexOptions::builder().unique(true).build();
+        db.collection::<bson::Document>("users")
+            .create_indexes([
+                IndexModel::builder()
+                    .keys(doc! {"username_canonical": 1})
+                    .options(unique.clone())
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! {"email_canonical": 1})
+                    .options(unique)
+                    .build(),
+                IndexModel::builder().keys(doc! {"created_at": 1}).build(),
+                IndexModel::builder()
+                    .keys(doc! {"username_history.username_canonical": 1})
+                    .build(),
+            ])
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use mongodb::{Database, IndexModel, bson::doc, options::IndexOptions};
+
+use shared::types::AppResult;
+
+use super::Migration;
+
+/// Backfills `expire_at` from the `expires_at` millis of existing OTPs, then
+/// indexes OTPs by email and lets a TTL index remove them once expired.
+pub struct CreateOtpsExpiry;
+
+#[async_trait]
+impl Migration for CreateOtpsExpiry {
+    fn version(&self) -> u32 {
+        3
+    }
+    fn name(&self) -> &'static str {
+        "create_otps_indexes_and_expiry"
+    }
+    async fn up(&self, db: &Database) -> AppResult<()> {
+        let collection = db.collection::<bson::Document>("otps");
+        collection
+            .update_many(
+                doc! {"expire_at": {"$exists": false}},
+                vec![doc! {"$set": {"expire_at": {"$toDate": "$expires_at"}}}],
+            )
+            .await?;
+        collection
+            .create_indexes([
+                IndexModel::builder()
+                    .keys(doc! {"email": 1})
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! {"expire_at": 1})
+                    .options(IndexOptions::builder().expire_after(Duration::ZERO).build())
+                    .build(),
+            ])
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use mongodb::{Database, IndexModel, bson::doc, options::IndexOptions};
+
+use shared::types::AppResult;
+
+use super::Migration;
+
+/// Indexes the issued-code log used to throttle otp requests. Each entry
+/// carries its own `expire_at`, so the TTL follows the configured window.
+pub struct CreateOtpIssuancesIndexes;
+
+#[async_trait]
+impl Migration for CreateOtpIssuancesIndexes {
+    fn version(&self) -> u32 {
+        4
+    }
+    fn name(&self) -> &'static str {
+        "create_otp_issuances_indexes"
+    }
+    async fn up(&self, db: &Database) -> AppResult<()> {
+        db.collection::<bson::Document>("otp_issuances")
+            .create_indexes([
+                IndexModel::builder()
+                    .keys(doc! {"key": 1, "issued_at": 1})
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! {"expire_at": 1})
+                    .options(IndexOptions::builder().expire_after(Duration::ZERO).build())
+                    .build(),
+            ])
+            .await?;
+        Ok(())
+    }
+}
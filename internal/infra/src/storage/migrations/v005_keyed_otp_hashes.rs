@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use mongodb::{Database, bson::doc};
+use tracing::warn;
+
+use shared::types::AppResult;
+use user::domain::user_auth::otp_hasher::OtpHasher;
+
+use super::Migration;
+
+/// Rewraps the unkeyed SHA-256 hashes of outstanding otps with the current
+/// pepper. Codes stay valid because the keyed hash is taken over the same digest.
+pub struct WrapLegacyOtpHashes {
+    hasher: OtpHasher,
+}
+
+impl WrapLegacyOtpHashes {
+    pub fn new(hasher: OtpHasher) -> Self {
+        Self { hasher }
+    }
+}
+
+#[async_trait]
+impl Migration for WrapLegacyOtpHashes {
+    fn version(&self) -> u32 {
+        5
+    }
+    fn name(&self) -> &'static str {
+        "wrap_legacy_otp_hashes"
+    }
+    async fn up(&self, db: &Database) -> AppResult<()> {
+        let collection = db.collection::<bson::Document>("otps");
+        let mut cursor = collection.find(doc! {"key_id": {"$exists": false}}).await?;
+        while cursor.advance().await? {
+            let otp = cursor.deserialize_current()?;
+            let (Some(id), Ok(legacy)) = (otp.get("_id"), otp.get_str("otp_hash")) else {
+                continue;
+            };
+            let Some(wrapped) = self.hasher.wrap_legacy(legacy) else {
+                warn!("Skipping otp {} with a malformed hash", id);
+                continue;
+            };
+            // Matching on the old hash leaves entries reissued in the meantime alone.
+            collection
+                .update_one(
+                    doc! {"_id": id, "otp_hash": legacy},
+                    doc! {"$set": {"otp_hash": wrapped.hash, "key_id": wrapped.key_id}},
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
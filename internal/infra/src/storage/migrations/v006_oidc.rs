@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use mongodb::{Database, IndexModel, bson::doc, options::IndexOptions};
+
+use shared::types::AppResult;
+
+use super::Migration;
+
+/// Expires abandoned OIDC sign-ins and makes each linked identity belong to
+/// at most one user.
+pub struct CreateOidcIndexes;
+
+#[async_trait]
+impl Migration for CreateOidcIndexes {
+    fn version(&self) -> u32 {
+        6
+    }
+    fn name(&self) -> &'static str {
+        "create_oidc_indexes"
+    }
+    async fn up(&self, db: &Database) -> AppResult<()> {
+        db.collection::<bson::Document>("oidc_auth_requests")
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"expire_at": 1})
+                    .options(IndexOptions::builder().expire_after(Duration::ZERO).build())
+                    .build(),
+            )
+            .await?;
+        db.collection::<bson::Document>("users")
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"identities.provider": 1, "identities.subject": 1})
+                    .options(
+                        IndexOptions::builder()
+                            .unique(true)
+                            .partial_filter_expression(
+                                doc! {"identities.subject": {"$exists": true}},
+                            )
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use async_trait::async_trait;
+use mongodb::{Database, IndexModel, bson::doc};
+
+use shared::types::AppResult;
+
+use super::Migration;
+
+/// Lists a user's API tokens without a collection scan.
+pub struct CreateApiTokensIndexes;
+
+#[async_trait]
+impl Migration for CreateApiTokensIndexes {
+    fn version(&self) -> u32 {
+        7
+    }
+    fn name(&self) -> &'static str {
+        "create_api_tokens_indexes"
+    }
+    async fn up(&self, db: &Database) -> AppResult<()> {
+        db.collection::<bson::Document>("api_tokens")
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"user_id": 1, "created_at": -1})
+                    .build(),
+            )
+            .await?;
+        Ok(())
+    }
+}
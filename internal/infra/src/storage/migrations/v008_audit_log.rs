@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use mongodb::{Database, IndexModel, bson::doc};
+
+use shared::types::AppResult;
+
+use super::Migration;
+
+/// Pages the audit log newest first, optionally by actor or target.
+pub struct CreateAuditLogIndexes;
+
+#[async_trait]
+impl Migration for CreateAuditLogIndexes {
+    fn version(&self) -> u32 {
+        8
+    }
+    fn name(&self) -> &'static str {
+        "create_audit_log_indexes"
+    }
+    async fn up(&self, db: &Database) -> AppResult<()> {
+        db.collection::<bson::Document>("audit_log")
+            .create_indexes(vec![
+                IndexModel::builder()
+                    .keys(doc! {"created_at": -1, "_id": -1})
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! {"actor_id": 1, "created_at": -1})
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! {"target_id": 1, "created_at": -1})
+                    .build(),
+            ])
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use mongodb::{Database, IndexModel, bson::doc, options::IndexOptions};
+
+use shared::types::AppResult;
+
+use super::Migration;
+
+/// Lets a TTL index remove rate limit buckets once they are full again.
+pub struct CreateRateLimitsExpiry;
+
+#[async_trait]
+impl Migration for CreateRateLimitsExpiry {
+    fn version(&self) -> u32 {
+        9
+    }
+    fn name(&self) -> &'static str {
+        "create_rate_limits_expiry"
+    }
+    async fn up(&self, db: &Database) -> AppResult<()> {
+        db.collection::<bson::Document>("rate_limits")
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"expire_at": 1})
+                    .options(IndexOptions::builder().expire_after(Duration::ZERO).build())
+                    .build(),
+            )
+            .await?;
+        Ok(())
+    }
+}
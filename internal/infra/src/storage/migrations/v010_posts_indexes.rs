@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+use mongodb::{Database, IndexModel, bson::doc};
+
+use shared::types::AppResult;
+
+use super::Migration;
+
+/// Pages posts newest first, optionally by author.
+pub struct CreatePostsIndexes;
+
+#[async_trait]
+impl Migration for CreatePostsIndexes {
+    fn version(&self) -> u32 {
+        10
+    }
+    fn name(&self) -> &'static str {
+        "create_posts_indexes"
+    }
+    async fn up(&self, db: &Database) -> AppResult<()> {
+        db.collection::<bson::Document>("posts")
+            .create_indexes(vec![
+                IndexModel::builder()
+                    .keys(doc! {"created_at": -1, "_id": -1})
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! {"author_id": 1, "created_at": -1, "_id": -1})
+                    .build(),
+            ])
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+use mongodb::{Database, IndexModel, bson::doc};
+
+use shared::types::AppResult;
+
+use super::Migration;
+
+/// Pages the comments of a post level by level, oldest first. The second
+/// index serves loading the replies of many parents at once.
+pub struct CreateCommentsIndexes;
+
+#[async_trait]
+impl Migration for CreateCommentsIndexes {
+    fn version(&self) -> u32 {
+        11
+    }
+    fn name(&self) -> &'static str {
+        "create_comments_indexes"
+    }
+    async fn up(&self, db: &Database) -> AppResult<()> {
+        db.collection::<bson::Document>("comments")
+            .create_indexes(vec![
+                IndexModel::builder()
+                    .keys(doc! {"post_id": 1, "parent_id": 1, "created_at": 1, "_id": 1})
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! {"parent_id": 1, "created_at": 1, "_id": 1})
+                    .build(),
+            ])
+            .await?;
+        Ok(())
+    }
+}
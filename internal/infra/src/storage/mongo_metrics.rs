@@ -0,0 +1,57 @@
+//! Command timings and connection pool activity reported by the MongoDB driver.
+use mongodb::{
+    event::{EventHandler, cmap::CmapEvent, command::CommandEvent},
+    options::ClientOptions,
+};
+use shared::metrics::{Counter, Gauge, Histogram, LATENCY_BUCKETS};
+
+static COMMAND_DURATION: Histogram = Histogram::new(
+    "mongodb_command_duration_seconds",
+    "MongoDB command latency, by command and outcome",
+    LATENCY_BUCKETS,
+);
+static POOL_CONNECTIONS: Gauge = Gauge::new(
+    "mongodb_pool_connections",
+    "Open connections in the MongoDB pool",
+);
+static POOL_CHECKED_OUT: Gauge = Gauge::new(
+    "mongodb_pool_checked_out_connections",
+    "MongoDB connections currently in use",
+);
+static POOL_CHECKOUT_DURATION: Histogram = Histogram::new(
+    "mongodb_pool_checkout_duration_seconds",
+    "Time spent waiting for a MongoDB connection",
+    LATENCY_BUCKETS,
+);
+static POOL_CHECKOUT_FAILURES: Counter = Counter::new(
+    "mongodb_pool_checkout_failures_total",
+    "Requests for a MongoDB connection that failed",
+);
+
+pub fn instrument(options: &mut ClientOptions) {
+    options.command_event_handler = Some(EventHandler::callback(|event| match event {
+        CommandEvent::Succeeded(event) => COMMAND_DURATION.observe_duration(
+            &[("command", &event.command_name), ("outcome", "success")],
+            event.duration,
+        ),
+        CommandEvent::Failed(event) => COMMAND_DURATION.observe_duration(
+            &[("command", &event.command_name), ("outcome", "failure")],
+            event.duration,
+        ),
+        _ => {}
+    }));
+    options.cmap_event_handler = Some(EventHandler::callback(|event| match event {
+        CmapEvent::ConnectionCreated(_) => POOL_CONNECTIONS.add(&[], 1),
+        CmapEvent::ConnectionClosed(_) => POOL_CONNECTIONS.add(&[], -1),
+        CmapEvent::ConnectionCheckedOut(event) => {
+            POOL_CHECKED_OUT.add(&[], 1);
+            POOL_CHECKOUT_DURATION.observe_duration(&[], event.duration);
+        }
+        CmapEvent::ConnectionCheckedIn(_) => POOL_CHECKED_OUT.add(&[], -1),
+        CmapEvent::ConnectionCheckoutFailed(event) => {
+            POOL_CHECKOUT_FAILURES.inc(&[]);
+            POOL_CHECKOUT_DURATION.observe_duration(&[], event.duration);
+        }
+        _ => {}
+    }));
+}
@@ -0,0 +1,151 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::{
+    Collection, Database,
+    bson::{self, doc},
+    options::ReturnDocument,
+};
+use serde::Deserialize;
+
+use shared::{
+    errors::app::AppError,
+    rate_limit::{RateLimit, RateLimitDecision, RateLimitStore},
+    types::AppResult,
+};
+
+/// Rate limit buckets shared by every instance using the database. Each take
+/// is a single atomic update of the bucket's document, which the TTL index on
+/// `expire_at` removes once the bucket would be full again.
+pub struct MongoRateLimitStore {
+    collection: Collection<BucketDocument>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BucketDocument {
+    tokens: f64,
+    allowed: bool,
+}
+
+impl MongoRateLimitStore {
+    pub fn new(db: Database) -> Self {
+        Self {
+            collection: db.collection("rate_limits"),
+        }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MongoRateLimitStore {
+    async fn take(
+        &self,
+        key: &str,
+        limit: RateLimit,
+        now: DateTime<Utc>,
+    ) -> AppResult<RateLimitDecision> {
+        let burst = f64::from(limit.burst);
+        let tokens_per_ms = f64::from(limit.per_minute.max(1)) / 60_000.0;
+        let now_bson = bson::DateTime::from_chrono(now);
+        let expire_at = bson::DateTime::from_chrono(now + limit.refill_time());
+        // Same refill as `Bucket::take`, run by the server so concurrent
+        // requests cannot both spend the last token.
+        let refill = doc! {
+            "$set": {
+                "tokens": {
+                    "$min": [
+                        burst,
+                        {
+                            "$add": [
+                                { "$ifNull": ["$tokens", burst] },
+                                {
+                                    "$multiply": [
+                                        {
+                                            "$max": [
+                                                0,
+                                                {
+                                                    "$subtract": [
+                                                        now_bson,
+                                                        { "$ifNull": ["$updated_at", now_bson] },
+                                                    ]
+                                                },
+                                            ]
+                                        },
+                                        tokens_per_ms,
+                                    ]
+                                },
+                            ]
+                        },
+                    ]
+                },
+                "updated_at": now_bson,
+            }
+        };
+        let take = doc! {
+            "$set": {
+                "allowed": { "$gte": ["$tokens", 1] },
+                "tokens": {
+                    "$cond": [
+                        { "$gte": ["$tokens", 1] },
+                        { "$subtract": ["$tokens", 1] },
+                        "$tokens",
+                    ]
+                },
+                "expire_at": expire_at,
+            }
+        };
+        let bucket = self
+            .collection
+            .find_one_and_update(doc! {"_id": key}, vec![refill, take])
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await?
+            .ok_or_else(|| AppError::Database("Rate limit bucket was not saved".into()))?;
+        Ok(if bucket.allowed {
+            RateLimitDecision::Allowed
+        } else {
+            RateLimitDecision::Limited {
+                retry_after: limit.retry_after(bucket.tokens),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use shared::test_utils;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_take_shares_buckets_through_the_database() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let first = MongoRateLimitStore::new(db.clone());
+        let second = MongoRateLimitStore::new(db);
+        let limit = RateLimit {
+            burst: 2,
+            per_minute: 60,
+        };
+        let now = Utc::now();
+
+        assert_eq!(
+            first.take("ip:10.0.0.1", limit, now).await.unwrap(),
+            RateLimitDecision::Allowed
+        );
+        assert_eq!(
+            second.take("ip:10.0.0.1", limit, now).await.unwrap(),
+            RateLimitDecision::Allowed
+        );
+        assert!(matches!(
+            first.take("ip:10.0.0.1", limit, now).await.unwrap(),
+            RateLimitDecision::Limited { .. }
+        ));
+        assert_eq!(
+            second
+                .take("ip:10.0.0.1", limit, now + TimeDelta::seconds(1))
+                .await
+                .unwrap(),
+            RateLimitDecision::Allowed
+        );
+    }
+}
@@ -1,43 +1,108 @@
 use mongodb::{Client, bson::doc, options::ClientOptions};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::timeout;
 use tracing::info;
 
-use crate::config::{Config, MongoDbConfig};
-use shared::types::AppResult;
+use shared::{
+    background_jobs::BackgroundJobs,
+    config::{AppConfig, MongoDbConfig},
+    errors::app::AppError,
+    types::AppResult,
+};
 use std::sync::Arc;
 
+use content::infra::{
+    mongoimpl::{comment_repository::MongoCommentRepository, post_repository::MongoPostRepository},
+    repository::{comment_repository::CommentRepository, post_repository::PostRepository},
+};
+use user::domain::user_auth::otp_hasher::OtpHasher;
 use user::infra::mongoimpl::{
-    otp_respository::MongoOtpRepository, user_read_model_repository::MongoUserReadModelRepository,
-    user_repository::MongoUserRepository,
+    api_token_repository::MongoApiTokenRepository, audit_log_repository::MongoAuditLogRepository,
+    oidc_auth_request_repository::MongoOidcAuthRequestRepository,
+    otp_issuance_repository::MongoOtpIssuanceRepository, otp_respository::MongoOtpRepository,
+    user_read_model_repository::MongoUserReadModelRepository, user_repository::MongoUserRepository,
 };
 use user::infra::repository::{
-    otp_repository::OtpRepository, user_read_model_repository::UserReadModelRepository,
-    user_repository::UserRepository,
+    api_token_repository::ApiTokenRepository, audit_log_repository::AuditLogRepository,
+    oidc_auth_request_repository::OidcAuthRequestRepository,
+    otp_issuance_repository::OtpIssuanceRepository, otp_repository::OtpRepository,
+    user_read_model_repository::UserReadModelRepository, user_repository::UserRepository,
 };
 
+use super::migrations::{MigrationRunner, MigrationStatus};
+use super::mongo_metrics;
+use super::mongo_rate_limit_store::MongoRateLimitStore;
 use super::*;
 
 pub struct MongoDBStorage {
     client: Client,
     cfg: MongoDbConfig,
+    otp_hasher: OtpHasher,
 }
 
 impl MongoDBStorage {
-    pub async fn new() -> Self {
-        let cfg = Config::build();
-        let mongo_cfg = cfg.build_mongodb_config();
+    pub async fn new(config: &AppConfig, jobs: &BackgroundJobs) -> Self {
+        let storage = Self::connect(config).await;
+        if storage.cfg.run_migrations {
+            let runner = storage.migration_runner();
+            jobs.spawn("mongodb_migrations", true, async move {
+                let applied = runner.run().await?;
+                info!(
+                    "✅ MongoDB migrations up to date ({} applied)",
+                    applied.len()
+                );
+                Ok::<_, AppError>(())
+            });
+        }
+        storage
+    }
+    /// Connects without applying pending migrations.
+    pub async fn connect(config: &AppConfig) -> Self {
         info!("Connecting to MongoDB...");
-        let client = connect(&mongo_cfg)
+        let client = connect(&config.mongodb)
             .await
             .expect("Unable to contect to MongoDB");
 
         info!("✅ Connected to MongoDB");
+
         Self {
             client,
-            cfg: mongo_cfg,
+            cfg: config.mongodb.clone(),
+            otp_hasher: OtpHasher::from_config(config),
+        }
+    }
+    pub async fn migrate(&self) -> AppResult<Vec<u32>> {
+        let applied = self.migration_runner().run().await?;
+        info!(
+            "✅ MongoDB migrations up to date ({} applied)",
+            applied.len()
+        );
+        Ok(applied)
+    }
+    pub async fn migration_status(&self) -> AppResult<Vec<MigrationStatus>> {
+        self.migration_runner().status().await
+    }
+    pub async fn health(&self) -> StorageHealth {
+        let started = Instant::now();
+        let error = ping(&self.client, self.cfg.timeout_secs).await.err();
+        StorageHealth {
+            engine: "mongodb",
+            healthy: error.is_none(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            error: error.map(|err| err.to_string()),
         }
     }
+    fn migration_runner(&self) -> MigrationRunner {
+        MigrationRunner::new(
+            self.client.database(&self.cfg.database_name),
+            self.otp_hasher.clone(),
+        )
+    }
+    pub fn rate_limit_store(&self) -> Arc<dyn RateLimitStore> {
+        Arc::new(MongoRateLimitStore::new(
+            self.client.database(&self.cfg.database_name),
+        ))
+    }
     pub fn repos(&self) -> Repos {
         let db = self.client.database(&self.cfg.database_name);
         Repos {
@@ -48,12 +113,28 @@ impl MongoDBStorage {
                 MongoUserReadModelRepository::new(db.clone()),
             )),
             otp_repo: Arc::new(OtpRepository::MongoDb(MongoOtpRepository::new(db.clone()))),
+            otp_issuance_repo: Arc::new(OtpIssuanceRepository::MongoDb(
+                MongoOtpIssuanceRepository::new(db.clone()),
+            )),
+            oidc_auth_request_repo: Arc::new(OidcAuthRequestRepository::MongoDb(
+                MongoOidcAuthRequestRepository::new(db.clone()),
+            )),
+            api_token_repo: Arc::new(ApiTokenRepository::MongoDb(MongoApiTokenRepository::new(
+                db.clone(),
+            ))),
+            audit_log_repo: Arc::new(AuditLogRepository::MongoDb(MongoAuditLogRepository::new(
+                db.clone(),
+            ))),
+            post_repo: Arc::new(PostRepository::MongoDb(MongoPostRepository::new(
+                db.clone(),
+            ))),
+            comment_repo: Arc::new(CommentRepository::MongoDb(MongoCommentRepository::new(db))),
         }
     }
 }
 
 async fn connect(cf: &MongoDbConfig) -> AppResult<Client> {
-    let mut options = ClientOptions::parse(&cf.uri).await?;
+    let mut options = ClientOptions::parse(cf.uri.expose()).await?;
     options.max_pool_size = Some(cf.max_pool_size);
     options.min_pool_size = Some(cf.min_pool_size);
     options.max_idle_time = Some(Duration::from_secs(cf.conn_idle_time_secs));
@@ -62,14 +143,19 @@ async fn connect(cf: &MongoDbConfig) -> AppResult<Client> {
     if let Some(rs) = &cf.replica_set {
         options.repl_set_name = Some(rs.clone());
     }
+    mongo_metrics::instrument(&mut options);
 
     let client = Client::with_options(options)?;
-    let ping_result = timeout(
-        Duration::from_secs(cf.timeout_secs),
+    ping(&client, cf.timeout_secs).await?;
+    Ok(client)
+}
+
+async fn ping(client: &Client, timeout_secs: u64) -> AppResult<()> {
+    timeout(
+        Duration::from_secs(timeout_secs),
         client.database("admin").run_command(doc! { "ping": 1 }),
     )
-    .await;
-
-    info!("Ping response: {:?}", ping_result);
-    Ok(client)
+    .await
+    .map_err(|_| AppError::Database("MongoDB ping timed out".into()))??;
+    Ok(())
 }
@@ -1,32 +1,125 @@
+use chrono::Duration;
+use content::app::content_service::ContentService;
 use infra::guards_impl::GuardsImpl;
+use shared::{
+    auth::token_service::TokenService,
+    background_jobs::BackgroundJobs,
+    config::{AppConfig, RateLimitStoreKind},
+    rate_limit::{InMemoryRateLimitStore, RateLimitPolicy, RateLimitStore, RateLimiter},
+};
 use std::sync::Arc;
 
-use user::app::user_service::UserService;
+use user::{
+    app::user_service::{UserService, UserServiceSettings},
+    domain::{
+        user::UsernamePolicy,
+        user_auth::{
+            oidc::OidcProviderConfig, otp::OtpIssuePolicy, otp_hasher::OtpHasher,
+            two_factor::TwoFactorPolicy,
+        },
+    },
+    infra::oidc::{http_oidc_client::HttpOidcClient, oidc_client::OidcClient},
+};
 
-use infra::storage::{AppStorage, StorageEngine};
+use infra::storage::{AppStorage, StorageEngine, StorageSource};
 
 pub struct Services {
     pub user_service: UserService,
+    pub content_service: ContentService,
 }
 
+/// Cheap to clone, so the GraphQL schema and the REST routes can share it.
+#[derive(Clone)]
 pub struct AppService {
-    pub services: Services,
+    pub services: Arc<Services>,
+    pub storage: Arc<StorageSource>,
+    /// Startup work such as migrations; see the readiness endpoint.
+    pub jobs: BackgroundJobs,
+    /// Shared by the GraphQL and REST APIs, so both count against the same buckets.
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Loaded and validated once at startup.
+    pub config: Arc<AppConfig>,
 }
 
 impl AppService {
-    pub async fn build(engine: StorageEngine) -> Self {
-        let storage = AppStorage::build(engine).await;
+    pub async fn build(
+        engine: StorageEngine,
+        config: Arc<AppConfig>,
+        token_service: Arc<dyn TokenService>,
+    ) -> Self {
+        let jobs = BackgroundJobs::new();
+        let storage = AppStorage::build(engine, &config, &jobs).await;
         let repos = storage.repos();
         let guard = Arc::new(GuardsImpl::new());
+        let username_policy = UsernamePolicy {
+            change_cooldown: Duration::days(config.username_change_cooldown_days),
+            reservation_period: Duration::days(config.username_reservation_days),
+        };
+        let otp_issue_policy = OtpIssuePolicy {
+            min_interval: Duration::seconds(config.otp_resend_interval_secs),
+            window: Duration::seconds(config.otp_issue_window_secs),
+            max_per_email: config.otp_max_per_email,
+            max_per_ip: config.otp_max_per_ip,
+        };
+        let otp_hasher = OtpHasher::from_config(&config);
+        let two_factor_policy = TwoFactorPolicy {
+            required_roles: config.two_factor_required_roles.clone(),
+            issuer: config.totp_issuer.clone(),
+        };
+        let oidc_providers = config
+            .oidc_providers
+            .iter()
+            .map(|provider| OidcProviderConfig {
+                name: provider.name.clone(),
+                issuer: provider.issuer.clone(),
+                client_id: provider.client_id.clone(),
+                client_secret: provider.client_secret.expose().to_string(),
+                scopes: provider.scopes.clone(),
+                redirect_uri: provider.redirect_uri.clone(),
+            })
+            .collect();
+        let rate_limit_store: Arc<dyn RateLimitStore> = match config.rate_limit_store {
+            RateLimitStoreKind::Memory => Arc::new(InMemoryRateLimitStore::new()),
+            RateLimitStoreKind::MongoDB => storage.rate_limit_store(),
+        };
+        let rate_limiter = RateLimiter::new(
+            rate_limit_store,
+            RateLimitPolicy {
+                api: config.rate_limit_api,
+                auth: config.rate_limit_auth,
+            },
+        )
+        .with_enabled(config.rate_limit_enabled);
         let services = Services {
             user_service: UserService::new(
                 repos.user_repo,
                 repos.user_read_repo,
-                guard,
+                guard.clone(),
                 repos.otp_repo,
+                repos.otp_issuance_repo,
+                repos.oidc_auth_request_repo,
+                repos.api_token_repo,
+                repos.audit_log_repo,
+                UserServiceSettings {
+                    username_policy,
+                    otp_issue_policy,
+                    otp_hasher: Arc::new(otp_hasher),
+                    two_factor_policy,
+                    oidc_providers,
+                    oidc_client: Arc::new(OidcClient::Http(HttpOidcClient::new())),
+                    token_service,
+                    impersonation_ttl: Duration::seconds(config.impersonation_ttl_secs),
+                },
             ),
+            content_service: ContentService::new(repos.post_repo, repos.comment_repo, guard),
             // Add more services for other app domains here
         };
-        Self { services }
+        Self {
+            services: Arc::new(services),
+            storage: Arc::new(storage),
+            jobs,
+            rate_limiter: Arc::new(rate_limiter),
+            config,
+        }
     }
 }
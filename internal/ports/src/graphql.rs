@@ -1,13 +1,39 @@
-use async_graphql::{EmptySubscription, MergedObject, Schema};
+use async_graphql::{Context, MergedObject, MergedSubscription, Schema};
+use shared::auth::{AppContext, AuthUser, RequestMetadata};
 
+mod content;
+pub mod errors;
+pub mod impersonation_log;
+pub mod loaders;
+pub mod metrics;
+pub mod persisted_queries;
+pub mod query_limits;
+pub mod rate_limit;
 mod user;
 
-use user::{user_mutation::UserMutation, user_query::UserQuery};
+use content::{content_mutation::ContentMutation, content_query::ContentQuery};
+use user::{
+    user_mutation::UserMutation, user_query::UserQuery, user_subscription::UserSubscription,
+};
 
 #[derive(Default, MergedObject)]
-pub struct Query(UserQuery);
+pub struct Query(UserQuery, ContentQuery);
 
 #[derive(Default, MergedObject)]
-pub struct Mutation(UserMutation);
+pub struct Mutation(UserMutation, ContentMutation);
 
-pub type AppSchema = Schema<Query, Mutation, EmptySubscription>;
+#[derive(Default, MergedSubscription)]
+pub struct Subscription(UserSubscription);
+
+pub type AppSchema = Schema<Query, Mutation, Subscription>;
+
+/// Context for the handlers an operation calls: the signed in user, plus the
+/// request id, client IP, user agent and locale of the HTTP request.
+fn app_context(ctx: &Context<'_>) -> AppContext {
+    let auth_user = ctx.data::<AuthUser>().unwrap();
+    let app_ctx = AppContext::new().with_user(auth_user.to_owned());
+    match ctx.data_opt::<RequestMetadata>() {
+        Some(request) => app_ctx.with_request(request),
+        None => app_ctx,
+    }
+}
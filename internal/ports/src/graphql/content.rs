@@ -0,0 +1,2 @@
+pub mod content_mutation;
+pub mod content_query;
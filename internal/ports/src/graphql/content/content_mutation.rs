@@ -0,0 +1,111 @@
+use crate::app_service::AppService;
+use crate::graphql::app_context;
+use async_graphql::{Context, Object};
+use content::{
+    app::command::{
+        create_comment::CreateComment, create_post::CreatePost, delete_comment::DeleteComment,
+        delete_post::DeletePost, update_comment::UpdateComment, update_post::UpdatePost,
+    },
+    domain::{comment::CommentNode, post::Post},
+};
+use shared::{command_handler::CommandHanlder, types::AppResult};
+
+#[derive(Debug, Default)]
+pub struct ContentMutation;
+
+#[Object]
+impl ContentMutation {
+    #[graphql(name = "createPost")]
+    async fn create_post(&self, ctx: &Context<'_>, cmd: CreatePost) -> AppResult<Post> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .content_service
+            .command_handler
+            .create_post
+            .handle(&app_ctx, cmd)
+            .await
+    }
+
+    /// Authors only.
+    #[graphql(name = "updatePost")]
+    async fn update_post(&self, ctx: &Context<'_>, cmd: UpdatePost) -> AppResult<Post> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .content_service
+            .command_handler
+            .update_post
+            .handle(&app_ctx, cmd)
+            .await
+    }
+
+    /// Authors may delete their own posts, moderators any post.
+    #[graphql(name = "deletePost")]
+    async fn delete_post(&self, ctx: &Context<'_>, cmd: DeletePost) -> AppResult<bool> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .content_service
+            .command_handler
+            .delete_post
+            .handle(&app_ctx, cmd)
+            .await?;
+        Ok(true)
+    }
+
+    /// Replies to `parentId` when given.
+    #[graphql(name = "createComment")]
+    async fn create_comment(
+        &self,
+        ctx: &Context<'_>,
+        cmd: CreateComment,
+    ) -> AppResult<CommentNode> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .content_service
+            .command_handler
+            .create_comment
+            .handle(&app_ctx, cmd)
+            .await
+    }
+
+    /// Authors only.
+    #[graphql(name = "updateComment")]
+    async fn update_comment(
+        &self,
+        ctx: &Context<'_>,
+        cmd: UpdateComment,
+    ) -> AppResult<CommentNode> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .content_service
+            .command_handler
+            .update_comment
+            .handle(&app_ctx, cmd)
+            .await
+    }
+
+    /// Leaves a `[deleted]` placeholder so replies keep their place. Authors
+    /// may delete their own comments, moderators any comment.
+    #[graphql(name = "deleteComment")]
+    async fn delete_comment(&self, ctx: &Context<'_>, cmd: DeleteComment) -> AppResult<bool> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .content_service
+            .command_handler
+            .delete_comment
+            .handle(&app_ctx, cmd)
+            .await?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,64 @@
+use crate::app_service::AppService;
+use crate::graphql::app_context;
+use async_graphql::{Context, Object};
+use content::{
+    app::query::{comments::GetComments, post_by_id::GetPostById, posts::GetPosts},
+    domain::{
+        comment::CommentPage,
+        post::{Post, PostPage},
+    },
+};
+use shared::{query_handler::QueryHandler, types::AppResult};
+
+#[derive(Default, Debug)]
+pub struct ContentQuery;
+
+#[Object]
+impl ContentQuery {
+    #[graphql(name = "getPostById", complexity = "5 + child_complexity")]
+    async fn get_post_by_id(&self, ctx: &Context<'_>, id: String) -> AppResult<Post> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .content_service
+            .query_handler
+            .get_post_by_id
+            .handle(&app_ctx, GetPostById { id })
+            .await
+    }
+
+    /// Newest first, optionally by one author.
+    #[graphql(
+        name = "posts",
+        complexity = "5 + filter.first as usize * child_complexity"
+    )]
+    async fn posts(&self, ctx: &Context<'_>, filter: GetPosts) -> AppResult<PostPage> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .content_service
+            .query_handler
+            .get_posts
+            .handle(&app_ctx, filter)
+            .await
+    }
+
+    /// A comment thread, oldest first, with replies loaded `filter.levels` deep.
+    #[graphql(
+        name = "comments",
+        complexity = "5 + filter.first as usize * child_complexity"
+    )]
+    async fn comments(&self, ctx: &Context<'_>, filter: GetComments) -> AppResult<CommentPage> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .content_service
+            .query_handler
+            .get_comments
+            .handle(&app_ctx, filter)
+            .await
+    }
+}
@@ -0,0 +1,244 @@
+use std::sync::Arc;
+
+use async_graphql::{
+    ErrorExtensionValues, Response, ServerError,
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute},
+};
+use chrono::{DateTime, Utc};
+use shared::{
+    auth::RequestMetadata,
+    errors::{app::AppError, validation::FieldError},
+};
+use tracing::error;
+use user::domain::errors::UserDomainError;
+use uuid::Uuid;
+
+const INTERNAL_SERVER_ERROR: &str = "INTERNAL_SERVER_ERROR";
+
+/// Adds `extensions { code }` to the errors resolvers return, with `fields`
+/// for invalid input and `retryAfter` in seconds for errors that pass with
+/// time. Internal errors are logged and replaced by a generic message with a
+/// `correlationId` to find them in the logs.
+pub struct ErrorCodes;
+
+impl ExtensionFactory for ErrorCodes {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(ErrorCodesExtension)
+    }
+}
+
+struct ErrorCodesExtension;
+
+#[async_trait::async_trait]
+impl Extension for ErrorCodesExtension {
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let mut response = next.run(ctx, operation_name).await;
+        let request_id = ctx
+            .data_opt::<RequestMetadata>()
+            .map(|request| request.request_id.as_str());
+        for err in &mut response.errors {
+            if let Some(details) = ErrorDetails::of(err) {
+                details.apply(err, request_id);
+            }
+        }
+        response
+    }
+}
+
+/// A `ServerError` with `code` set in its extensions.
+pub(crate) fn coded_error(message: impl Into<String>, code: &str) -> ServerError {
+    let mut error = ServerError::new(message, None);
+    let mut extensions = ErrorExtensionValues::default();
+    extensions.set("code", code);
+    error.extensions = Some(extensions);
+    error
+}
+
+struct ErrorDetails {
+    code: &'static str,
+    fields: Vec<FieldError>,
+    retry_at: Option<DateTime<Utc>>,
+}
+
+impl ErrorDetails {
+    fn of(err: &ServerError) -> Option<Self> {
+        if let Some(err) = err.source::<UserDomainError>() {
+            return Some(Self {
+                code: err.code(),
+                fields: err.field_errors().to_vec(),
+                retry_at: err.retry_at(),
+            });
+        }
+        err.source::<AppError>().map(|err| Self {
+            code: err.code(),
+            fields: match err {
+                AppError::Validation(fields) => fields.clone(),
+                _ => vec![],
+            },
+            retry_at: None,
+        })
+    }
+
+    fn apply(self, err: &mut ServerError, request_id: Option<&str>) {
+        let extensions = err.extensions.get_or_insert_with(Default::default);
+        extensions.set("code", self.code);
+        if self.code == INTERNAL_SERVER_ERROR {
+            // Every error of a request shares its id, which is also on its log lines.
+            let correlation_id =
+                request_id.map_or_else(|| Uuid::new_v4().to_string(), String::from);
+            error!(
+                correlation_id = %correlation_id,
+                path = ?err.path,
+                "GraphQL resolver failed: {}",
+                err.message
+            );
+            err.message = format!("Internal server error. Reference: {}", correlation_id);
+            extensions.set("correlationId", correlation_id);
+            return;
+        }
+        if !self.fields.is_empty() {
+            let fields = self
+                .fields
+                .into_iter()
+                .map(|field| FieldError {
+                    field: camel_case(&field.field),
+                    ..field
+                })
+                .collect::<Vec<_>>();
+            if let Ok(fields) = async_graphql::to_value(fields) {
+                extensions.set("fields", fields);
+            }
+        }
+        if let Some(retry_at) = self.retry_at {
+            extensions.set("retryAfter", (retry_at - Utc::now()).num_seconds().max(1));
+        }
+    }
+}
+
+/// Field paths as named in the schema: `expires_in_days` becomes `expiresInDays`.
+fn camel_case(path: &str) -> String {
+    let mut camel = String::with_capacity(path.len());
+    let mut upper = false;
+    for c in path.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            camel.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{Pos, Value, value};
+    use chrono::Duration;
+
+    use super::*;
+
+    fn server_error<E>(err: E) -> ServerError
+    where
+        E: std::fmt::Display + Send + Sync + 'static,
+    {
+        let mut err = async_graphql::Error::new_with_source(err).into_server_error(Pos::default());
+        if let Some(details) = ErrorDetails::of(&err) {
+            details.apply(&mut err, Some("req-1"));
+        }
+        err
+    }
+
+    fn extension(err: &ServerError, name: &str) -> Option<Value> {
+        err.extensions
+            .as_ref()
+            .and_then(|extensions| extensions.get(name))
+            .cloned()
+    }
+
+    #[test]
+    fn sets_the_code() {
+        let err = server_error(UserDomainError::UserNotFound);
+
+        assert_eq!(extension(&err, "code"), Some(Value::from("USER_NOT_FOUND")));
+        assert_eq!(err.message, UserDomainError::UserNotFound.to_string());
+        assert_eq!(extension(&err, "fields"), None);
+        assert_eq!(extension(&err, "retryAfter"), None);
+    }
+
+    #[test]
+    fn names_fields_as_in_the_schema() {
+        let err = server_error(AppError::Validation(vec![
+            FieldError {
+                field: "expires_in_days".to_string(),
+                code: "range".to_string(),
+                message: None,
+            },
+            FieldError {
+                field: "links[0].display_name".to_string(),
+                code: "length".to_string(),
+                message: Some("too long".to_string()),
+            },
+        ]));
+
+        assert_eq!(extension(&err, "code"), Some(Value::from("BAD_USER_INPUT")));
+        assert_eq!(
+            extension(&err, "fields"),
+            Some(value!([
+                { "field": "expiresInDays", "code": "range", "message": null },
+                { "field": "links[0].displayName", "code": "length", "message": "too long" },
+            ]))
+        );
+    }
+
+    #[test]
+    fn gives_seconds_until_retry() {
+        let err = server_error(UserDomainError::UsernameChangeTooSoon(
+            Utc::now() + Duration::seconds(90),
+        ));
+
+        let Some(Value::Number(retry_after)) = extension(&err, "retryAfter") else {
+            panic!("retryAfter is missing");
+        };
+        assert!((89..=90).contains(&retry_after.as_i64().unwrap()));
+    }
+
+    #[test]
+    fn masks_internal_errors() {
+        let err = server_error(UserDomainError::Database("connection reset".to_string()));
+
+        assert_eq!(err.message, "Internal server error. Reference: req-1");
+        assert_eq!(
+            extension(&err, "code"),
+            Some(Value::from(INTERNAL_SERVER_ERROR))
+        );
+        assert_eq!(extension(&err, "correlationId"), Some(Value::from("req-1")));
+        assert_eq!(extension(&err, "fields"), None);
+    }
+
+    #[test]
+    fn internal_errors_without_a_request_get_their_own_id() {
+        let mut err = async_graphql::Error::new_with_source(AppError::Internal("boom".to_string()))
+            .into_server_error(Pos::default());
+        ErrorDetails::of(&err).unwrap().apply(&mut err, None);
+
+        let Some(Value::String(correlation_id)) = extension(&err, "correlationId") else {
+            panic!("correlationId is missing");
+        };
+        assert!(Uuid::parse_str(&correlation_id).is_ok());
+        assert!(err.message.ends_with(&correlation_id));
+    }
+
+    #[test]
+    fn leaves_other_errors_alone() {
+        let err = server_error("unknown");
+
+        assert_eq!(err.extensions, None);
+    }
+}
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use async_graphql::{
+    ServerResult, Value,
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextResolve, ResolveInfo},
+};
+use shared::auth::AuthUser;
+use tracing::info;
+
+/// Logs every top-level query and mutation field resolved with an
+/// impersonation token, naming both the admin and the user acted as.
+pub struct ImpersonationLog;
+
+impl ExtensionFactory for ImpersonationLog {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(ImpersonationLogExtension)
+    }
+}
+
+struct ImpersonationLogExtension;
+
+#[async_trait::async_trait]
+impl Extension for ImpersonationLogExtension {
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        let is_root = info.path_node.parent.is_none() && !info.is_for_introspection;
+        let auth_user = ctx.data_opt::<AuthUser>();
+        let Some((auth_user, actor)) = auth_user
+            .filter(|_| is_root)
+            .and_then(|user| user.impersonator().map(|actor| (user, actor)))
+        else {
+            return next.run(ctx, info).await;
+        };
+
+        let field = format!("{}.{}", info.parent_type, info.name);
+        let result = next.run(ctx, info).await;
+        info!(
+            impersonator_id = %actor.id,
+            subject_id = %auth_user.0.id,
+            field = %field,
+            success = result.is_ok(),
+            "Action under impersonation"
+        );
+        result
+    }
+}
@@ -0,0 +1,64 @@
+use std::{any::TypeId, sync::Arc};
+
+use async_graphql::{
+    Request, ServerResult,
+    dataloader::DataLoader,
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextPrepareRequest},
+};
+use shared::auth::{AppContext, AuthUser, RequestMetadata};
+use user::ports::user_loader::UserLoader;
+
+use crate::app_service::AppService;
+
+/// Gives every request its own data loaders, so nested fields that need the
+/// same kind of record are fetched in one batch.
+pub struct Loaders;
+
+impl ExtensionFactory for Loaders {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(LoadersExtension)
+    }
+}
+
+struct LoadersExtension;
+
+#[async_trait::async_trait]
+impl Extension for LoadersExtension {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        mut request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        // HTTP requests carry the user in the request, WebSocket connections
+        // in the session.
+        let auth_user = request
+            .data
+            .get(&TypeId::of::<AuthUser>())
+            .and_then(|data| data.downcast_ref::<AuthUser>())
+            .or_else(|| ctx.data_opt::<AuthUser>())
+            .cloned()
+            .unwrap_or_else(AuthUser::guest);
+        let mut app_ctx = AppContext::new().with_user(auth_user);
+        if let Some(metadata) = request
+            .data
+            .get(&TypeId::of::<RequestMetadata>())
+            .and_then(|data| data.downcast_ref::<RequestMetadata>())
+            .or_else(|| ctx.data_opt::<RequestMetadata>())
+        {
+            app_ctx = app_ctx.with_request(metadata);
+        }
+        let app_service = ctx.data_unchecked::<AppService>();
+        let handler = app_service
+            .services
+            .user_service
+            .query_handler
+            .get_users_by_ids
+            .clone();
+        request.data.insert(DataLoader::new(
+            UserLoader::new(handler, app_ctx),
+            tokio::spawn,
+        ));
+        next.run(ctx, request).await
+    }
+}
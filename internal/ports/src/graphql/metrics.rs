@@ -0,0 +1,184 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use async_graphql::{
+    Response, ServerResult, Variables,
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery},
+    parser::types::{ExecutableDocument, OperationDefinition, Selection},
+};
+use shared::metrics::{Histogram, LATENCY_BUCKETS};
+use user::{app::metrics::record_error, domain::errors::UserDomainError};
+
+static OPERATION_DURATION: Histogram = Histogram::new(
+    "graphql_operation_duration_seconds",
+    "GraphQL operation latency, by root field and outcome",
+    LATENCY_BUCKETS,
+);
+
+/// Times each GraphQL operation and counts the domain errors it returns.
+/// Operations are labelled by the field they select at the root rather than by
+/// the name the client gave them. Only documents that pass validation are
+/// executed, so the labels are bounded by the root fields of the schema.
+pub struct Metrics;
+
+impl ExtensionFactory for Metrics {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(MetricsExtension {
+            labels: Mutex::new(vec![]),
+        })
+    }
+}
+
+struct MetricsExtension {
+    /// Label of each operation of the document, by operation name.
+    labels: Mutex<Vec<(Option<String>, String)>>,
+}
+
+#[async_trait::async_trait]
+impl Extension for MetricsExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        *self.labels.lock().unwrap() = document
+            .operations
+            .iter()
+            .map(|(name, operation)| {
+                (
+                    name.map(|n| n.to_string()),
+                    operation_label(&operation.node),
+                )
+            })
+            .collect();
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let started = Instant::now();
+        let response = next.run(ctx, operation_name).await;
+        let operation = self
+            .labels
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(name, _)| operation_name.is_none() || name.as_deref() == operation_name)
+            .map_or_else(|| "other".to_string(), |(_, label)| label.clone());
+        let outcome = if response.is_ok() { "success" } else { "error" };
+        OPERATION_DURATION.observe_duration(
+            &[("operation", &operation), ("outcome", outcome)],
+            started.elapsed(),
+        );
+        for err in &response.errors {
+            if let Some(err) = err.source::<UserDomainError>() {
+                record_error("graphql", &operation, err);
+            }
+        }
+        response
+    }
+}
+
+/// The root field an operation selects. Operations selecting several are
+/// labelled `multiple`, and those using fragments at the root `other`.
+fn operation_label(operation: &OperationDefinition) -> String {
+    let mut fields = vec![];
+    for selection in &operation.selection_set.node.items {
+        match &selection.node {
+            Selection::Field(field) => fields.push(field.node.name.node.as_str()),
+            _ => return "other".to_string(),
+        }
+    }
+    fields.retain(|name| !name.starts_with("__"));
+    fields.sort_unstable();
+    fields.dedup();
+    match fields.as_slice() {
+        [] => "introspection",
+        [field] => field,
+        _ => "multiple",
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, parser::parse_query};
+
+    use super::*;
+
+    fn label(query: &str) -> String {
+        let document = parse_query(query).unwrap();
+        let (_, operation) = document.operations.iter().next().unwrap();
+        operation_label(&operation.node)
+    }
+
+    #[test]
+    fn labels_by_root_field() {
+        assert_eq!(label("query Named { user { id } }"), "user");
+        assert_eq!(
+            label("{ first: user { id } second: user { name } }"),
+            "user"
+        );
+        assert_eq!(
+            label("{ user { ...UserFields } } fragment UserFields on User { id }"),
+            "user"
+        );
+    }
+
+    #[test]
+    fn several_root_fields_are_multiple() {
+        assert_eq!(label("{ user { id } posts { id } }"), "multiple");
+        assert_eq!(label("mutation { signIn signUp }"), "multiple");
+    }
+
+    #[test]
+    fn fragments_at_the_root_are_other() {
+        assert_eq!(
+            label("{ ...Root } fragment Root on Query { user { id } }"),
+            "other"
+        );
+        assert_eq!(label("{ ... on Query { user { id } } }"), "other");
+    }
+
+    #[test]
+    fn introspection_is_labelled_as_such() {
+        assert_eq!(label("{ __schema { types { name } } }"), "introspection");
+        assert_eq!(
+            label("{ __typename __type(name: \"User\") { name } }"),
+            "introspection"
+        );
+        assert_eq!(label("{ __typename user { id } }"), "user");
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn metrics_test_field(&self) -> i32 {
+            1
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_fields_do_not_become_labels() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(Metrics)
+            .finish();
+
+        assert!(schema.execute("{ metricsTestField }").await.is_ok());
+        assert!(schema.execute("{ metricsTestUnknownField }").await.is_err());
+
+        let rendered = shared::metrics::render();
+        assert!(rendered.contains("operation=\"metricsTestField\""));
+        assert!(!rendered.contains("metricsTestUnknownField"));
+    }
+}
@@ -0,0 +1,174 @@
+use std::{collections::HashSet, fs, io, sync::Arc};
+
+use async_graphql::{
+    Request, ServerResult, Value,
+    extensions::{
+        Extension, ExtensionContext, ExtensionFactory, NextPrepareRequest,
+        apollo_persisted_queries::{ApolloPersistedQueries, LruCacheStorage},
+    },
+};
+use sha2::{Digest, Sha256};
+
+use crate::graphql::errors::coded_error;
+
+/// Automatic persisted queries: clients send the SHA-256 hash of a query and
+/// only send the full text when the server has not seen it yet.
+pub fn automatic_persisted_queries(cache_size: usize) -> ApolloPersistedQueries<LruCacheStorage> {
+    ApolloPersistedQueries::new(LruCacheStorage::new(cache_size))
+}
+
+/// Accepts only operations whose SHA-256 hash is listed. Register it before
+/// the persisted queries extension, which strips the hash from the request.
+#[derive(Clone)]
+pub struct OperationAllowList {
+    hashes: Arc<HashSet<String>>,
+}
+
+impl OperationAllowList {
+    pub fn new(hashes: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            hashes: Arc::new(hashes.into_iter().collect()),
+        }
+    }
+
+    /// Reads one hash per line. Blank lines and lines starting with `#` are skipped.
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(Self::new(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_lowercase),
+        ))
+    }
+}
+
+impl ExtensionFactory for OperationAllowList {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(OperationAllowListExtension {
+            hashes: self.hashes.clone(),
+        })
+    }
+}
+
+struct OperationAllowListExtension {
+    hashes: Arc<HashSet<String>>,
+}
+
+#[async_trait::async_trait]
+impl Extension for OperationAllowListExtension {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        // The text wins over a client supplied hash, so a listed hash cannot
+        // smuggle in another query.
+        let hash = if request.query.is_empty() {
+            persisted_query_hash(&request)
+        } else {
+            Some(format!("{:x}", Sha256::digest(request.query.as_bytes())))
+        };
+        match hash {
+            Some(hash) if self.hashes.contains(&hash) => next.run(ctx, request).await,
+            _ => Err(coded_error(
+                "Operation is not allow-listed",
+                "OPERATION_NOT_ALLOWED",
+            )),
+        }
+    }
+}
+
+fn persisted_query_hash(request: &Request) -> Option<String> {
+    match request.extensions.get("persistedQuery")? {
+        Value::Object(persisted_query) => match persisted_query.get("sha256Hash")? {
+            Value::String(hash) => Some(hash.to_lowercase()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, value};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn value(&self) -> i32 {
+            1
+        }
+    }
+
+    const LISTED: &str = "{ value }";
+
+    fn hash(query: &str) -> String {
+        format!("{:x}", Sha256::digest(query.as_bytes()))
+    }
+
+    fn schema() -> Schema<Query, EmptyMutation, EmptySubscription> {
+        Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(OperationAllowList::new([hash(LISTED)]))
+            .extension(automatic_persisted_queries(16))
+            .finish()
+    }
+
+    fn with_hash(mut request: Request, hash: String) -> Request {
+        request.extensions.insert(
+            "persistedQuery".into(),
+            value!({ "version": 1, "sha256Hash": hash }),
+        );
+        request
+    }
+
+    fn not_allowed(response: &async_graphql::Response) -> bool {
+        response.errors.iter().any(|error| {
+            error.extensions.as_ref().and_then(|ext| ext.get("code"))
+                == Some(&Value::from("OPERATION_NOT_ALLOWED"))
+        })
+    }
+
+    #[tokio::test]
+    async fn listed_operations_run() {
+        let response = schema().execute(Request::new(LISTED)).await;
+        assert!(response.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unlisted_operations_are_rejected() {
+        let response = schema().execute(Request::new("{ __typename }")).await;
+        assert!(not_allowed(&response));
+    }
+
+    #[tokio::test]
+    async fn listed_hash_cannot_carry_other_text() {
+        let request = with_hash(Request::new("{ __typename }"), hash(LISTED));
+        let response = schema().execute(request).await;
+        assert!(not_allowed(&response));
+    }
+
+    #[tokio::test]
+    async fn hash_only_operations_must_be_listed() {
+        let request = with_hash(Request::new(""), hash("{ __typename }"));
+        let response = schema().execute(request).await;
+        assert!(not_allowed(&response));
+    }
+
+    #[tokio::test]
+    async fn hash_only_listed_operation_runs_once_registered() {
+        let schema = schema();
+        let register = with_hash(Request::new(LISTED), hash(LISTED));
+        assert!(schema.execute(register).await.errors.is_empty());
+
+        let response = schema
+            .execute(with_hash(Request::new(""), hash(LISTED)))
+            .await;
+        assert!(response.errors.is_empty());
+        assert_eq!(response.data, value!({ "value": 1 }));
+    }
+}
@@ -0,0 +1,240 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use async_graphql::{
+    ServerError, ServerResult, ValidationResult, Variables,
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextValidation},
+    parser::types::{ExecutableDocument, Selection},
+};
+use shared::{auth::AuthUser, config::AppConfig, guards::roles::UserRole};
+
+use crate::graphql::errors::coded_error;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_depth: usize,
+    pub max_complexity: usize,
+}
+
+/// Limits of schema introspection, whoever sends it. The usual tooling query
+/// is deep but fits well within them.
+const INTROSPECTION_LIMITS: Limits = Limits {
+    max_depth: 20,
+    max_complexity: 500,
+};
+
+/// Rejects operations that are nested too deeply or cost too much, before
+/// any resolver runs. Guests get the stricter limits. Field costs come from
+/// the `complexity` annotations on the schema; unannotated fields cost 1.
+/// Introspection has fixed limits of its own, so tooling works for guests.
+pub struct QueryLimits {
+    user: Limits,
+    guest: Limits,
+}
+
+impl QueryLimits {
+    pub fn new(user: Limits, guest: Limits) -> Self {
+        Self { user, guest }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::new(
+            Limits {
+                max_depth: config.graphql_max_depth,
+                max_complexity: config.graphql_max_complexity,
+            },
+            Limits {
+                max_depth: config.graphql_guest_max_depth,
+                max_complexity: config.graphql_guest_max_complexity,
+            },
+        )
+    }
+}
+
+impl ExtensionFactory for QueryLimits {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(QueryLimitsExtension {
+            user: self.user,
+            guest: self.guest,
+            introspection_only: AtomicBool::new(false),
+        })
+    }
+}
+
+struct QueryLimitsExtension {
+    user: Limits,
+    guest: Limits,
+    introspection_only: AtomicBool,
+}
+
+#[async_trait::async_trait]
+impl Extension for QueryLimitsExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        self.introspection_only
+            .store(is_introspection_only(&document), Ordering::Relaxed);
+        Ok(document)
+    }
+
+    async fn validation(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        next: NextValidation<'_>,
+    ) -> Result<ValidationResult, Vec<ServerError>> {
+        let result = next.run(ctx).await?;
+        let is_guest = ctx
+            .data_opt::<AuthUser>()
+            .is_none_or(|user| user.0.role == UserRole::Guest);
+        let limits = if self.introspection_only.load(Ordering::Relaxed) {
+            INTROSPECTION_LIMITS
+        } else if is_guest {
+            self.guest
+        } else {
+            self.user
+        };
+        if result.depth > limits.max_depth {
+            return Err(vec![coded_error(
+                format!(
+                    "Query is nested too deeply: {} levels, at most {} allowed",
+                    result.depth, limits.max_depth
+                ),
+                "QUERY_TOO_DEEP",
+            )]);
+        }
+        if result.complexity > limits.max_complexity {
+            return Err(vec![coded_error(
+                format!(
+                    "Query is too complex: cost {}, at most {} allowed",
+                    result.complexity, limits.max_complexity
+                ),
+                "QUERY_TOO_COMPLEX",
+            )]);
+        }
+        Ok(result)
+    }
+}
+
+fn is_introspection_only(document: &ExecutableDocument) -> bool {
+    document.operations.iter().all(|(_, operation)| {
+        operation
+            .node
+            .selection_set
+            .node
+            .items
+            .iter()
+            .all(|selection| match &selection.node {
+                Selection::Field(field) => field.node.name.node.starts_with("__"),
+                _ => false,
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn node(&self) -> Node {
+            Node
+        }
+    }
+
+    struct Node;
+
+    #[Object]
+    impl Node {
+        async fn value(&self) -> i32 {
+            1
+        }
+        async fn child(&self) -> Node {
+            Node
+        }
+    }
+
+    const NESTED: &str = "{ node { child { child { value } } } }";
+    const INTROSPECTION: &str = "{ __schema { types { name fields { name type { name ofType { name ofType { name } } } } } } }";
+
+    fn schema() -> Schema<Query, EmptyMutation, EmptySubscription> {
+        Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(QueryLimits::new(
+                Limits {
+                    max_depth: 4,
+                    max_complexity: 100,
+                },
+                Limits {
+                    max_depth: 2,
+                    max_complexity: 100,
+                },
+            ))
+            .finish()
+    }
+
+    async fn error_code(request: Request) -> Option<String> {
+        let response = schema().execute(request).await;
+        let error = response.errors.first()?;
+        match error.extensions.as_ref()?.get("code")? {
+            async_graphql::Value::String(code) => Some(code.clone()),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn guests_get_the_stricter_limits() {
+        assert_eq!(
+            error_code(Request::new(NESTED)).await.as_deref(),
+            Some("QUERY_TOO_DEEP")
+        );
+        assert_eq!(
+            error_code(Request::new(NESTED).data(AuthUser::guest()))
+                .await
+                .as_deref(),
+            Some("QUERY_TOO_DEEP")
+        );
+    }
+
+    #[tokio::test]
+    async fn signed_in_users_get_the_user_limits() {
+        let request = Request::new(NESTED).data(AuthUser::new_test_auth_user(UserRole::Regular));
+        assert_eq!(error_code(request).await, None);
+    }
+
+    #[tokio::test]
+    async fn introspection_gets_its_own_limits() {
+        assert_eq!(error_code(Request::new(INTROSPECTION)).await, None);
+    }
+
+    #[tokio::test]
+    async fn introspection_mixed_with_fields_gets_the_caller_limits() {
+        let query = INTROSPECTION.replacen("{ __schema", "{ node { value } __schema", 1);
+        assert_eq!(
+            error_code(Request::new(query)).await.as_deref(),
+            Some("QUERY_TOO_DEEP")
+        );
+        let document = async_graphql::parser::parse_query(
+            "query Tooling { __schema { types { name } } } query Other { node { value } }",
+        )
+        .unwrap();
+        assert!(!is_introspection_only(&document));
+    }
+
+    #[test]
+    fn fragments_are_not_treated_as_introspection() {
+        let document = async_graphql::parser::parse_query(
+            "{ ...F } fragment F on Query { __typename node { value } }",
+        )
+        .unwrap();
+        assert!(!is_introspection_only(&document));
+    }
+}
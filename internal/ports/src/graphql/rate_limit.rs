@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use async_graphql::{
+    Name, ServerError, ServerResult, Variables,
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextParseQuery},
+    parser::types::{ExecutableDocument, OperationType, Selection, SelectionSet},
+};
+use shared::{
+    auth::{AuthUser, RequestMetadata},
+    rate_limit::RateLimited,
+};
+
+use crate::{app_service::AppService, graphql::errors::coded_error};
+
+/// Mutations that can be used to guess codes or flood inboxes. Each has its
+/// own bucket per caller on top of the general one.
+pub const AUTH_MUTATIONS: &[&str] = &[
+    "signUp",
+    "signIn",
+    "resendOtp",
+    "verifyOtp",
+    "verifyEmail",
+    "verifyTwoFactor",
+    "startOidcSignIn",
+    "completeOidcSignIn",
+];
+
+/// Counts each operation against the caller's general bucket, and each auth
+/// mutation it selects against that mutation's bucket, once it is parsed and
+/// before it is validated or run. Refused operations get a `RATE_LIMITED`
+/// error with `retryAfter` in seconds.
+pub struct RateLimit;
+
+impl ExtensionFactory for RateLimit {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(RateLimitExtension)
+    }
+}
+
+struct RateLimitExtension;
+
+#[async_trait::async_trait]
+impl Extension for RateLimitExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        let limiter = &ctx.data_unchecked::<AppService>().rate_limiter;
+        let user = ctx.data_opt::<AuthUser>();
+        let client_ip = ctx
+            .data_opt::<RequestMetadata>()
+            .and_then(|request| request.client_ip.as_deref());
+
+        limiter
+            .check_api(user, client_ip)
+            .await
+            .map_err(rate_limited_error)?;
+        for operation in auth_mutations(&document) {
+            limiter
+                .check_auth(operation, user, client_ip)
+                .await
+                .map_err(rate_limited_error)?;
+        }
+        Ok(document)
+    }
+}
+
+fn rate_limited_error(limited: RateLimited) -> ServerError {
+    let mut error = coded_error(limited.to_string(), "RATE_LIMITED");
+    if let Some(extensions) = &mut error.extensions {
+        extensions.set("retryAfter", limited.retry_after_secs());
+    }
+    error
+}
+
+/// Auth mutations selected at the root of any mutation in the document,
+/// including through fragments. Each occurrence is listed, so aliases of the
+/// same mutation are counted one by one.
+fn auth_mutations(document: &ExecutableDocument) -> Vec<&'static str> {
+    let mut found = vec![];
+    let mut visited = vec![];
+    for (_, operation) in document.operations.iter() {
+        if operation.node.ty == OperationType::Mutation {
+            collect_auth_mutations(
+                document,
+                &operation.node.selection_set.node,
+                &mut visited,
+                &mut found,
+            );
+        }
+    }
+    found
+}
+
+/// `visited` holds the fragments already searched. Documents are not
+/// validated yet, so fragments may spread each other in a cycle.
+fn collect_auth_mutations<'a>(
+    document: &'a ExecutableDocument,
+    selection_set: &'a SelectionSet,
+    visited: &mut Vec<&'a Name>,
+    found: &mut Vec<&'static str>,
+) {
+    for selection in &selection_set.items {
+        match &selection.node {
+            Selection::Field(field) => {
+                let name = field.node.name.node.as_str();
+                if let Some(operation) = AUTH_MUTATIONS.iter().find(|op| **op == name) {
+                    found.push(operation);
+                }
+            }
+            Selection::InlineFragment(fragment) => {
+                collect_auth_mutations(document, &fragment.node.selection_set.node, visited, found);
+            }
+            Selection::FragmentSpread(spread) => {
+                let name = &spread.node.fragment_name.node;
+                if visited.contains(&name) {
+                    continue;
+                }
+                visited.push(name);
+                if let Some(fragment) = document.fragments.get(name) {
+                    collect_auth_mutations(
+                        document,
+                        &fragment.node.selection_set.node,
+                        visited,
+                        found,
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::parser::parse_query;
+
+    fn auth_mutations_of(query: &str) -> Vec<&'static str> {
+        auth_mutations(&parse_query(query).unwrap())
+    }
+
+    #[test]
+    fn each_alias_is_counted() {
+        let found = auth_mutations_of(
+            r#"mutation {
+                a: verifyOtp(cmd: { email: "a@b.c", otp: "000000" }) { token }
+                b: verifyOtp(cmd: { email: "a@b.c", otp: "000001" }) { token }
+                signIn(cmd: { email: "a@b.c" }) { message }
+                me { id }
+            }"#,
+        );
+        assert_eq!(found, vec!["verifyOtp", "verifyOtp", "signIn"]);
+    }
+
+    #[test]
+    fn queries_are_not_counted() {
+        assert!(auth_mutations_of("query { verifyOtp { token } }").is_empty());
+    }
+
+    #[test]
+    fn fragments_are_searched() {
+        let found = auth_mutations_of(
+            r#"mutation {
+                ...Codes
+                ... on UserMutation { c: verifyOtp(cmd: { email: "a@b.c", otp: "2" }) { token } }
+            }
+            fragment Codes on UserMutation {
+                a: verifyOtp(cmd: { email: "a@b.c", otp: "0" }) { token }
+                b: verifyOtp(cmd: { email: "a@b.c", otp: "1" }) { token }
+            }"#,
+        );
+        assert_eq!(found, vec!["verifyOtp", "verifyOtp", "verifyOtp"]);
+    }
+
+    #[test]
+    fn fragment_cycles_end() {
+        let found = auth_mutations_of(
+            r#"mutation { ...A }
+            fragment A on UserMutation { signIn(cmd: { email: "a@b.c" }) { message } ...B }
+            fragment B on UserMutation { ...A }"#,
+        );
+        assert_eq!(found, vec!["signIn"]);
+    }
+}
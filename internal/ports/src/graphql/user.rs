@@ -1,2 +1,3 @@
 pub mod user_mutation;
 pub mod user_query;
+pub mod user_subscription;
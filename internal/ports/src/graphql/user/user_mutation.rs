@@ -1,16 +1,28 @@
 use crate::app_service::AppService;
+use crate::graphql::app_context;
 use async_graphql::{Context, Object, SimpleObject};
-use shared::{
-    auth::{AppContext, AuthUser},
-    command_handler::CommandHanlder,
-};
+use shared::command_handler::CommandHanlder;
 
 use user::{
     app::command::{
-        sign_in::SignIn, sign_up::SignUp, verify_email_with_otp::VerifyEmailWithOtp,
+        complete_oidc_sign_in::CompleteOidcSignIn,
+        confirm_totp::ConfirmTotp,
+        create_api_token::{CreateApiToken, CreatedApiToken},
+        delete_api_token::DeleteApiToken,
+        disable_totp::DisableTotp,
+        enroll_totp::{EnrollTotp, TotpEnrollment},
+        impersonate::{Impersonate, ImpersonationToken},
+        resend_otp::ResendOtp,
+        sign_in::SignIn,
+        sign_up::SignUp,
+        start_oidc_sign_in::{OidcAuthorization, StartOidcSignIn},
+        update_api_token::UpdateApiToken,
+        verify_email_with_otp::VerifyEmailWithOtp,
         verify_otp::VerifyOtp,
+        verify_two_factor::VerifyTwoFactor,
     },
-    domain::{errors::UserDomainError, result::UserDomainResult},
+    app::session_issuer::SignInOutcome,
+    domain::{errors::UserDomainError, result::UserDomainResult, user_auth::api_token::ApiToken},
 };
 
 #[derive(SimpleObject, Debug, Default)]
@@ -23,6 +35,40 @@ pub struct VerificationResponse {
     pub token: String,
 }
 
+/// Either `token` is set, or `two_factor_challenge` must be completed with `verifyTwoFactor`.
+#[derive(SimpleObject, Debug, Default)]
+pub struct SignInResponse {
+    pub token: Option<String>,
+    pub two_factor_challenge: Option<String>,
+    /// The user's role requires 2FA. Until it is enabled the token only grants a regular user's access.
+    pub two_factor_enrollment_required: bool,
+}
+
+impl From<SignInOutcome> for SignInResponse {
+    fn from(outcome: SignInOutcome) -> Self {
+        match outcome {
+            SignInOutcome::Token {
+                token,
+                two_factor_enrollment_required,
+            } => SignInResponse {
+                token: Some(token),
+                two_factor_enrollment_required,
+                ..Default::default()
+            },
+            SignInOutcome::TwoFactorChallenge(challenge) => SignInResponse {
+                two_factor_challenge: Some(challenge),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(SimpleObject, Debug, Default)]
+pub struct RecoveryCodesResponse {
+    /// Shown only once. Each code can be used in place of a TOTP code one time.
+    pub recovery_codes: Vec<String>,
+}
+
 #[derive(Debug, Default)]
 pub struct UserMutation;
 
@@ -31,8 +77,7 @@ impl UserMutation {
     #[graphql(name = "signUp")]
     async fn sign_up(&self, ctx: &Context<'_>, cmd: SignUp) -> UserDomainResult<AuthResponse> {
         let app_service = ctx.data::<AppService>().unwrap();
-        let auth_user = ctx.data::<AuthUser>().unwrap();
-        let app_ctx = AppContext::new().with_user(auth_user.to_owned());
+        let app_ctx = app_context(ctx);
         app_service
             .services
             .user_service
@@ -48,8 +93,7 @@ impl UserMutation {
     #[graphql(name = "signIn")]
     async fn sign_in(&self, ctx: &Context<'_>, cmd: SignIn) -> UserDomainResult<AuthResponse> {
         let app_service = ctx.data::<AppService>().unwrap();
-        let auth_user = ctx.data::<AuthUser>().unwrap();
-        let app_ctx = AppContext::new().with_user(auth_user.to_owned());
+        let app_ctx = app_context(ctx);
         app_service
             .services
             .user_service
@@ -64,36 +108,166 @@ impl UserMutation {
         })
     }
 
+    #[graphql(name = "resendOtp")]
+    async fn resend_otp(
+        &self,
+        ctx: &Context<'_>,
+        cmd: ResendOtp,
+    ) -> UserDomainResult<AuthResponse> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .user_service
+            .command_handler
+            .resend_otp
+            .handle(&app_ctx, cmd)
+            .await?;
+
+        Ok(AuthResponse {
+            message: "If an account exists for this email, a new OTP has been sent to it."
+                .to_string(),
+        })
+    }
+
     #[graphql(name = "verifyOtp")]
     async fn verify_otp(
         &self,
         ctx: &Context<'_>,
         cmd: VerifyOtp,
+    ) -> UserDomainResult<SignInResponse> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        let outcome = app_service
+            .services
+            .user_service
+            .command_handler
+            .verify_otp
+            .handle(&app_ctx, cmd)
+            .await?;
+
+        Ok(outcome.into())
+    }
+
+    /// Returns the provider URL to send the user to. When signed in, the
+    /// identity is linked to the current account.
+    #[graphql(name = "startOidcSignIn")]
+    async fn start_oidc_sign_in(
+        &self,
+        ctx: &Context<'_>,
+        cmd: StartOidcSignIn,
+    ) -> UserDomainResult<OidcAuthorization> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .user_service
+            .command_handler
+            .start_oidc_sign_in
+            .handle(&app_ctx, cmd)
+            .await
+    }
+
+    #[graphql(name = "completeOidcSignIn")]
+    async fn complete_oidc_sign_in(
+        &self,
+        ctx: &Context<'_>,
+        cmd: CompleteOidcSignIn,
+    ) -> UserDomainResult<SignInResponse> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        let outcome = app_service
+            .services
+            .user_service
+            .command_handler
+            .complete_oidc_sign_in
+            .handle(&app_ctx, cmd)
+            .await?;
+
+        Ok(outcome.into())
+    }
+
+    #[graphql(name = "verifyTwoFactor")]
+    async fn verify_two_factor(
+        &self,
+        ctx: &Context<'_>,
+        cmd: VerifyTwoFactor,
     ) -> UserDomainResult<VerificationResponse> {
         let app_service = ctx.data::<AppService>().unwrap();
-        let auth_user = ctx.data::<AuthUser>().unwrap();
-        let app_ctx = AppContext::new().with_user(auth_user.to_owned());
+        let app_ctx = app_context(ctx);
         let token = app_service
             .services
             .user_service
             .command_handler
-            .verify_otp
+            .verify_two_factor
             .handle(&app_ctx, cmd)
             .await?;
 
         Ok(VerificationResponse { token })
     }
 
+    #[graphql(name = "enrollTotp")]
+    async fn enroll_totp(&self, ctx: &Context<'_>) -> UserDomainResult<TotpEnrollment> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .user_service
+            .command_handler
+            .enroll_totp
+            .handle(&app_ctx, EnrollTotp)
+            .await
+    }
+
+    #[graphql(name = "confirmTotp")]
+    async fn confirm_totp(
+        &self,
+        ctx: &Context<'_>,
+        cmd: ConfirmTotp,
+    ) -> UserDomainResult<RecoveryCodesResponse> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        let recovery_codes = app_service
+            .services
+            .user_service
+            .command_handler
+            .confirm_totp
+            .handle(&app_ctx, cmd)
+            .await?;
+
+        Ok(RecoveryCodesResponse { recovery_codes })
+    }
+
+    #[graphql(name = "disableTotp")]
+    async fn disable_totp(
+        &self,
+        ctx: &Context<'_>,
+        cmd: DisableTotp,
+    ) -> UserDomainResult<AuthResponse> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .user_service
+            .command_handler
+            .disable_totp
+            .handle(&app_ctx, cmd)
+            .await?;
+
+        Ok(AuthResponse {
+            message: "Two-factor authentication has been disabled.".to_string(),
+        })
+    }
+
     #[graphql(name = "verifyEmail")]
     async fn verify_email(
         &self,
         ctx: &Context<'_>,
         cmd: VerifyEmailWithOtp,
-    ) -> UserDomainResult<VerificationResponse> {
+    ) -> UserDomainResult<SignInResponse> {
         let app_service = ctx.data::<AppService>().unwrap();
-        let auth_user = ctx.data::<AuthUser>().unwrap();
-        let app_ctx = AppContext::new().with_user(auth_user.to_owned());
-        let token = app_service
+        let app_ctx = app_context(ctx);
+        let outcome = app_service
             .services
             .user_service
             .command_handler
@@ -102,6 +276,80 @@ impl UserMutation {
             .await?
             .ok_or(UserDomainError::UnableToVerifyEmail)?;
 
-        Ok(VerificationResponse { token })
+        Ok(outcome.into())
+    }
+
+    /// Admins only. The token acts as `user_id` for a short time and is
+    /// read-only.
+    #[graphql(name = "impersonate")]
+    async fn impersonate(
+        &self,
+        ctx: &Context<'_>,
+        user_id: String,
+    ) -> UserDomainResult<ImpersonationToken> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .user_service
+            .command_handler
+            .impersonate
+            .handle(&app_ctx, Impersonate { user_id })
+            .await
+    }
+
+    #[graphql(name = "createApiToken")]
+    async fn create_api_token(
+        &self,
+        ctx: &Context<'_>,
+        cmd: CreateApiToken,
+    ) -> UserDomainResult<CreatedApiToken> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .user_service
+            .command_handler
+            .create_api_token
+            .handle(&app_ctx, cmd)
+            .await
+    }
+
+    #[graphql(name = "updateApiToken")]
+    async fn update_api_token(
+        &self,
+        ctx: &Context<'_>,
+        cmd: UpdateApiToken,
+    ) -> UserDomainResult<ApiToken> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .user_service
+            .command_handler
+            .update_api_token
+            .handle(&app_ctx, cmd)
+            .await
+    }
+
+    #[graphql(name = "deleteApiToken")]
+    async fn delete_api_token(
+        &self,
+        ctx: &Context<'_>,
+        cmd: DeleteApiToken,
+    ) -> UserDomainResult<AuthResponse> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .user_service
+            .command_handler
+            .delete_api_token
+            .handle(&app_ctx, cmd)
+            .await?;
+
+        Ok(AuthResponse {
+            message: "The API token has been deleted.".to_string(),
+        })
     }
 }
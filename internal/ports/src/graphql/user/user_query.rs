@@ -1,12 +1,15 @@
 use crate::app_service::AppService;
+use crate::graphql::app_context;
 use async_graphql::{Context, Object};
-use shared::{
-    auth::{AppContext, AuthUser},
-    query_handler::QueryHandler,
+use shared::query_handler::QueryHandler;
+use user::domain::{
+    audit_log::AuditLogPage, user_auth::api_token::ApiToken, user_read_model::UserReadModel,
 };
-use user::domain::user_read_model::UserReadModel;
 use user::{
-    app::query::{user_by_email::GetUserByEmail, user_by_id::GetUserById},
+    app::query::{
+        api_tokens::GetApiTokens, audit_log::GetAuditLog, user_by_email::GetUserByEmail,
+        user_by_id::GetUserById, user_by_username::GetUserByUsername,
+    },
     domain::result::UserDomainResult,
 };
 
@@ -15,15 +18,14 @@ pub struct UserQuery;
 
 #[Object]
 impl UserQuery {
-    #[graphql(name = "getUserById")]
+    #[graphql(name = "getUserById", complexity = "5 + child_complexity")]
     async fn get_user_by_id(
         &self,
         ctx: &Context<'_>,
         id: String,
     ) -> UserDomainResult<UserReadModel> {
         let app_service = ctx.data::<AppService>().unwrap();
-        let auth_user = ctx.data::<AuthUser>().unwrap();
-        let app_ctx = AppContext::new().with_user(auth_user.to_owned());
+        let app_ctx = app_context(ctx);
         app_service
             .services
             .user_service
@@ -33,15 +35,14 @@ impl UserQuery {
             .await
     }
 
-    #[graphql(name = "getUserByEmail")]
+    #[graphql(name = "getUserByEmail", complexity = "5 + child_complexity")]
     async fn get_user_by_email(
         &self,
         ctx: &Context<'_>,
         email: String,
     ) -> UserDomainResult<UserReadModel> {
         let app_service = ctx.data::<AppService>().unwrap();
-        let auth_user = ctx.data::<AuthUser>().unwrap();
-        let app_ctx = AppContext::new().with_user(auth_user.to_owned());
+        let app_ctx = app_context(ctx);
         app_service
             .services
             .user_service
@@ -50,4 +51,57 @@ impl UserQuery {
             .handle(&app_ctx, GetUserByEmail { email })
             .await
     }
+
+    /// Resolves current and previous usernames, so old mention links keep working.
+    #[graphql(name = "getUserByUsername", complexity = "5 + child_complexity")]
+    async fn get_user_by_username(
+        &self,
+        ctx: &Context<'_>,
+        username: String,
+    ) -> UserDomainResult<UserReadModel> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .user_service
+            .query_handler
+            .get_user_by_username
+            .handle(&app_ctx, GetUserByUsername { username })
+            .await
+    }
+
+    /// The signed in user's API tokens. Secrets are never returned.
+    #[graphql(name = "apiTokens", complexity = "5 + 10 * child_complexity")]
+    async fn api_tokens(&self, ctx: &Context<'_>) -> UserDomainResult<Vec<ApiToken>> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .user_service
+            .query_handler
+            .get_api_tokens
+            .handle(&app_ctx, GetApiTokens)
+            .await
+    }
+
+    /// Privileged operations, newest first. Admins only.
+    #[graphql(
+        name = "auditLog",
+        complexity = "5 + filter.first as usize * child_complexity"
+    )]
+    async fn audit_log(
+        &self,
+        ctx: &Context<'_>,
+        filter: GetAuditLog,
+    ) -> UserDomainResult<AuditLogPage> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        app_service
+            .services
+            .user_service
+            .query_handler
+            .get_audit_log
+            .handle(&app_ctx, filter)
+            .await
+    }
 }
@@ -0,0 +1,50 @@
+use crate::app_service::AppService;
+use crate::graphql::app_context;
+use async_graphql::{Context, Result, Subscription, futures_util::stream::BoxStream};
+use shared::query_handler::QueryHandler;
+use user::{
+    app::query::{account_changes::WatchMyAccount, moderation_queue::WatchModerationQueue},
+    domain::events::{AccountChange, ModerationQueueUpdate},
+};
+
+#[derive(Default, Debug)]
+pub struct UserSubscription;
+
+#[Subscription]
+impl UserSubscription {
+    /// Bans, role changes and badges on the signed in user's account.
+    #[graphql(name = "myAccountChanged")]
+    async fn my_account_changed(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<BoxStream<'static, AccountChange>> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        let changes = app_service
+            .services
+            .user_service
+            .query_handler
+            .watch_my_account
+            .handle(&app_ctx, WatchMyAccount)
+            .await?;
+        Ok(changes)
+    }
+
+    /// Bans and unbans as moderators make them. Moderators and admins only.
+    #[graphql(name = "moderationQueueUpdated")]
+    async fn moderation_queue_updated(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<BoxStream<'static, ModerationQueueUpdate>> {
+        let app_service = ctx.data::<AppService>().unwrap();
+        let app_ctx = app_context(ctx);
+        let updates = app_service
+            .services
+            .user_service
+            .query_handler
+            .watch_moderation_queue
+            .handle(&app_ctx, WatchModerationQueue)
+            .await?;
+        Ok(updates)
+    }
+}
@@ -1,22 +1,74 @@
-use jwt::Claims;
+use jwt::{Actor, Claims};
 
-use crate::guards::roles::UserRole;
+use crate::guards::{permissions::Permission, roles::UserRole};
+
+pub mod jwt;
+pub mod keys;
+pub mod token_service;
 
 #[derive(Debug, Clone)]
 pub struct AppContext {
     pub user: Option<AuthUser>,
+    pub client_ip: Option<String>,
+    pub request_id: Option<String>,
+    pub user_agent: Option<String>,
+    /// Preferred language tag of the caller, such as `en-GB`.
+    pub locale: Option<String>,
 }
 
 impl AppContext {
     pub fn new() -> Self {
-        Self { user: None }
+        Self {
+            user: None,
+            client_ip: None,
+            request_id: None,
+            user_agent: None,
+            locale: None,
+        }
     }
     pub fn with_user(mut self, user: AuthUser) -> Self {
         self.user = Some(user);
         self
     }
+    pub fn with_client_ip(mut self, client_ip: Option<String>) -> Self {
+        self.client_ip = client_ip;
+        self
+    }
+    /// Copies the client IP, request id, user agent and locale of the request.
+    pub fn with_request(mut self, request: &RequestMetadata) -> Self {
+        self.client_ip = request.client_ip.clone();
+        self.request_id = Some(request.request_id.clone());
+        self.user_agent = request.user_agent.clone();
+        self.locale = request.locale.clone();
+        self
+    }
+    /// The user really making the request: the admin when impersonating,
+    /// otherwise the signed in user.
+    pub fn actor_id(&self) -> Option<&str> {
+        let user = self.user.as_ref()?;
+        match user.impersonator() {
+            Some(actor) => Some(&actor.id),
+            None => Some(&user.0.id),
+        }
+    }
+}
+
+/// Describes the HTTP request an operation runs for. The server attaches it
+/// to each request, REST or GraphQL, and handlers copy it into [`AppContext`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestMetadata {
+    /// The caller's `X-Request-Id` when valid, otherwise generated.
+    pub request_id: String,
+    /// Trace the request belongs to, from `traceparent` or newly started.
+    pub trace_id: Option<String>,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub locale: Option<String>,
 }
 
+/// Claim holding the id of the API token a request was authenticated with.
+pub const API_TOKEN_ID_CLAIM: &str = "api_token_id";
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct AuthUser(pub Claims);
 
@@ -27,99 +79,34 @@ impl AuthUser {
     pub fn guest() -> Self {
         Self(Claims::guest_claims())
     }
+    /// Whether the credentials are limited to a set of scopes.
+    pub fn is_scoped(&self) -> bool {
+        !self.0.scopes.is_empty()
+    }
+    /// Unscoped credentials carry every permission of the role.
+    pub fn has_scope_for(&self, perm: &Permission) -> bool {
+        !self.is_scoped() || self.0.has_scope(perm.as_scope())
+    }
+    /// Set when the request was authenticated with an API token rather than a session.
+    pub fn api_token_id(&self) -> Option<&str> {
+        self.0
+            .custom
+            .get(API_TOKEN_ID_CLAIM)
+            .and_then(|id| id.as_str())
+    }
+    /// The admin acting as this user, when the token is an impersonation token.
+    pub fn impersonator(&self) -> Option<&Actor> {
+        self.0.act.as_ref()
+    }
     pub fn new_test_auth_user(role: UserRole) -> Self {
-        Self(Claims {
-            exp: 0,
-            sub: "johndoe@example.com".to_string(),
+        Self(Claims::new(
+            "johndoe@example.com".to_string(),
             role,
-            id: "test-user-id".to_string(),
-        })
+            "test-user-id".to_string(),
+        ))
     }
 }
 
 pub fn get_auth_user_from_ctx(ctx: &AppContext) -> &AuthUser {
     ctx.user.as_ref().unwrap()
 }
-
-pub mod jwt {
-    use crate::{config::Config, guards::roles::UserRole};
-    use chrono::{Duration, Utc};
-    use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
-
-    use serde::{Deserialize, Serialize};
-
-    pub type JWTError = jsonwebtoken::errors::Error;
-    pub type JWTResult<T> = Result<T, JWTError>;
-
-    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-    pub struct Claims {
-        /// Subject (usually the user's email)
-        pub sub: String,
-        pub exp: usize,
-        pub role: UserRole,
-        pub id: String,
-    }
-
-    impl Claims {
-        pub fn guest_claims() -> Self {
-            Self {
-                sub: "".to_string(),
-                exp: 0,
-                role: UserRole::Guest,
-                id: "".to_string(),
-            }
-        }
-    }
-
-    pub fn create_jwt(email: String, role: UserRole, id: String) -> JWTResult<String> {
-        const JWT_EXPIRATION_IN_HOURS: i64 = 24;
-        let expiration = Utc::now() + Duration::hours(JWT_EXPIRATION_IN_HOURS);
-        let claims = Claims {
-            sub: email,
-            exp: expiration.timestamp() as usize,
-            role,
-            id,
-        };
-
-        let config = Config::build();
-        let secret = config.auth_secret.as_bytes();
-
-        let code = encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(secret),
-        )?;
-        Ok(code)
-    }
-
-    pub fn verify_jwt(token: &str) -> JWTResult<Claims> {
-        let config = Config::build();
-        let secret = config.auth_secret.as_bytes();
-        let claims = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(secret),
-            &Validation::new(Algorithm::HS256),
-        )
-        .map(|d| d.claims)?;
-        Ok(claims)
-    }
-
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-
-        #[test]
-        fn test_create_jwt_and_verify() {
-            let code = create_jwt(
-                "user@example.com".to_string(),
-                UserRole::Admin,
-                "user-id".to_string(),
-            )
-            .unwrap();
-
-            let claims = verify_jwt(&code).unwrap();
-            assert_eq!(claims.sub, "user@example.com");
-            assert_eq!(claims.role, UserRole::Admin);
-        }
-    }
-}
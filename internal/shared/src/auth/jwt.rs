@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::guards::roles::UserRole;
+
+pub type JWTError = jsonwebtoken::errors::Error;
+pub type JWTResult<T> = Result<T, JWTError>;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Claims {
+    /// Subject (usually the user's email)
+    pub sub: String,
+    pub exp: usize,
+    pub role: UserRole,
+    pub id: String,
+    #[serde(default)]
+    pub iat: usize,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub iss: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub aud: String,
+    /// Set when an admin acts as the subject.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
+    /// Limits what the token may be used for. Empty means the role's full access.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
+    /// Any other claims, kept as they were issued.
+    #[serde(flatten)]
+    pub custom: BTreeMap<String, serde_json::Value>,
+}
+
+/// The user behind an impersonated token, as in the RFC 8693 `act` claim.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Actor {
+    pub sub: String,
+    pub id: String,
+}
+
+impl Claims {
+    pub fn new(sub: String, role: UserRole, id: String) -> Self {
+        Self {
+            sub,
+            exp: 0,
+            role,
+            id,
+            iat: 0,
+            iss: String::new(),
+            aud: String::new(),
+            act: None,
+            scopes: vec![],
+            custom: BTreeMap::new(),
+        }
+    }
+    pub fn guest_claims() -> Self {
+        Self::new("".to_string(), UserRole::Guest, "".to_string())
+    }
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
@@ -0,0 +1,304 @@
+//! Keys that sign and verify access tokens.
+//!
+//! Tokens are signed with a single current key and carry its `kid`. Keys that
+//! signed earlier tokens are kept for verification until those tokens expire,
+//! so a key can be rotated without signing everyone out. RSA keys sign with
+//! RS256 and Ed25519 keys with EdDSA; their public halves are published as a
+//! JWKS. Without a configured key, tokens fall back to HS256 with `AUTH_SECRET`.
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode,
+    errors::ErrorKind,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+};
+use rsa::{
+    RsaPrivateKey, RsaPublicKey,
+    pkcs1::DecodeRsaPrivateKey,
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+    traits::PublicKeyParts,
+};
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::jwt::JWTResult;
+use crate::config::AppConfig;
+
+/// `kid` of the HS256 key derived from `AUTH_SECRET`.
+pub const HMAC_KEY_ID: &str = "auth-secret";
+
+struct SigningKey {
+    kid: String,
+    algorithm: Algorithm,
+    key: EncodingKey,
+}
+
+struct VerificationKey {
+    kid: String,
+    algorithm: Algorithm,
+    key: DecodingKey,
+    /// Public keys only; the HMAC secret is never published.
+    jwk: Option<Jwk>,
+}
+
+pub struct KeyManager {
+    signing: SigningKey,
+    verification: Vec<VerificationKey>,
+}
+
+impl KeyManager {
+    /// Loads the keys named by `JWT_SIGNING_KEY` and `JWT_VERIFICATION_KEYS`.
+    pub fn from_config(config: &AppConfig) -> JWTResult<Self> {
+        let Some((kid, path)) = &config.jwt_signing_key else {
+            return Ok(Self::hmac(config.auth_secret.expose().as_bytes()));
+        };
+        let mut manager = Self::from_private_pem(kid, &read_pem(path))?;
+        for (kid, path) in &config.jwt_verification_keys {
+            manager = manager.with_public_pem(kid, &read_pem(path))?;
+        }
+        Ok(manager)
+    }
+
+    pub fn hmac(secret: &[u8]) -> Self {
+        Self {
+            signing: SigningKey {
+                kid: HMAC_KEY_ID.into(),
+                algorithm: Algorithm::HS256,
+                key: EncodingKey::from_secret(secret),
+            },
+            verification: vec![VerificationKey {
+                kid: HMAC_KEY_ID.into(),
+                algorithm: Algorithm::HS256,
+                key: DecodingKey::from_secret(secret),
+                jwk: None,
+            }],
+        }
+    }
+
+    /// Signs with an RSA (PKCS#1 or PKCS#8) or Ed25519 (PKCS#8) private key.
+    pub fn from_private_pem(kid: &str, pem: &str) -> JWTResult<Self> {
+        let (signing, verification) = if let Some(private_key) = rsa_private_key(pem) {
+            (
+                SigningKey {
+                    kid: kid.into(),
+                    algorithm: Algorithm::RS256,
+                    key: EncodingKey::from_rsa_pem(pem.as_bytes())?,
+                },
+                rsa_verification_key(kid, &private_key.to_public_key())?,
+            )
+        } else if let Ok(private_key) = ed25519_dalek::SigningKey::from_pkcs8_pem(pem) {
+            (
+                SigningKey {
+                    kid: kid.into(),
+                    algorithm: Algorithm::EdDSA,
+                    key: EncodingKey::from_ed_pem(pem.as_bytes())?,
+                },
+                ed25519_verification_key(kid, &private_key.verifying_key())?,
+            )
+        } else {
+            return Err(ErrorKind::InvalidKeyFormat.into());
+        };
+        Ok(Self {
+            signing,
+            verification: vec![verification],
+        })
+    }
+
+    /// Keeps accepting tokens signed by a previous key.
+    pub fn with_public_pem(mut self, kid: &str, pem: &str) -> JWTResult<Self> {
+        if self.verification.iter().any(|key| key.kid == kid) {
+            return Err(ErrorKind::InvalidKeyFormat.into());
+        }
+        let key = if let Ok(public_key) = RsaPublicKey::from_public_key_pem(pem) {
+            rsa_verification_key(kid, &public_key)?
+        } else if let Ok(public_key) = ed25519_dalek::VerifyingKey::from_public_key_pem(pem) {
+            ed25519_verification_key(kid, &public_key)?
+        } else {
+            return Err(ErrorKind::InvalidKeyFormat.into());
+        };
+        self.verification.push(key);
+        Ok(self)
+    }
+
+    pub fn current_key_id(&self) -> &str {
+        &self.signing.kid
+    }
+
+    pub fn sign<T: Serialize>(&self, claims: &T) -> JWTResult<String> {
+        let mut header = Header::new(self.signing.algorithm);
+        header.kid = Some(self.signing.kid.clone());
+        encode(&header, claims, &self.signing.key)
+    }
+
+    /// Verifies with the key named by the token's `kid`. Tokens without one
+    /// were issued before keys had ids and are checked against the current key.
+    /// The algorithm always comes from the key, never from the token.
+    pub fn verify<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        validation: &Validation,
+    ) -> JWTResult<T> {
+        let header = decode_header(token)?;
+        let kid = header.kid.as_deref().unwrap_or(&self.signing.kid);
+        let key = self
+            .verification
+            .iter()
+            .find(|key| key.kid == kid)
+            .ok_or(ErrorKind::InvalidToken)?;
+        if key.algorithm != header.alg {
+            return Err(ErrorKind::InvalidAlgorithm.into());
+        }
+        let mut validation = validation.clone();
+        validation.algorithms = vec![key.algorithm];
+        decode::<T>(token, &key.key, &validation).map(|data| data.claims)
+    }
+
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self
+                .verification
+                .iter()
+                .filter_map(|key| key.jwk.clone())
+                .collect(),
+        }
+    }
+}
+
+fn read_pem(path: &str) -> String {
+    std::fs::read_to_string(path).unwrap_or_else(|err| panic!("Unable to read {}: {}", path, err))
+}
+
+fn rsa_private_key(pem: &str) -> Option<RsaPrivateKey> {
+    RsaPrivateKey::from_pkcs8_pem(pem)
+        .ok()
+        .or_else(|| RsaPrivateKey::from_pkcs1_pem(pem).ok())
+}
+
+fn rsa_verification_key(kid: &str, public_key: &RsaPublicKey) -> JWTResult<VerificationKey> {
+    let n = URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be());
+    let e = URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be());
+    Ok(VerificationKey {
+        kid: kid.into(),
+        algorithm: Algorithm::RS256,
+        key: DecodingKey::from_rsa_components(&n, &e)?,
+        jwk: Some(Jwk {
+            common: common_parameters(kid, KeyAlgorithm::RS256),
+            algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+                key_type: RSAKeyType::RSA,
+                n,
+                e,
+            }),
+        }),
+    })
+}
+
+fn ed25519_verification_key(
+    kid: &str,
+    public_key: &ed25519_dalek::VerifyingKey,
+) -> JWTResult<VerificationKey> {
+    let x = URL_SAFE_NO_PAD.encode(public_key.to_bytes());
+    Ok(VerificationKey {
+        kid: kid.into(),
+        algorithm: Algorithm::EdDSA,
+        key: DecodingKey::from_ed_components(&x)?,
+        jwk: Some(Jwk {
+            common: common_parameters(kid, KeyAlgorithm::EdDSA),
+            algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x,
+            }),
+        }),
+    })
+}
+
+fn common_parameters(kid: &str, algorithm: KeyAlgorithm) -> CommonParameters {
+    CommonParameters {
+        public_key_use: Some(PublicKeyUse::Signature),
+        key_algorithm: Some(algorithm),
+        key_id: Some(kid.into()),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::jwt::Claims;
+    use crate::guards::roles::UserRole;
+
+    const RSA_PRIVATE: &str = include_str!("testdata/rsa_private.pem");
+    const ED25519_PRIVATE: &str = include_str!("testdata/ed25519_private.pem");
+    const ED25519_PUBLIC: &str = include_str!("testdata/ed25519_public.pem");
+
+    fn claims() -> Claims {
+        Claims {
+            exp: (chrono::Utc::now().timestamp() + 60) as usize,
+            ..Claims::new(
+                "user@example.com".into(),
+                UserRole::Regular,
+                "user-id".into(),
+            )
+        }
+    }
+
+    fn verify(keys: &KeyManager, token: &str) -> JWTResult<Claims> {
+        keys.verify(token, &Validation::default())
+    }
+
+    #[test]
+    fn rsa_tokens_carry_kid() {
+        let keys = KeyManager::from_private_pem("rsa-1", RSA_PRIVATE).unwrap();
+        let token = keys.sign(&claims()).unwrap();
+
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.alg, Algorithm::RS256);
+        assert_eq!(header.kid.as_deref(), Some("rsa-1"));
+        assert_eq!(verify(&keys, &token).unwrap(), claims());
+    }
+
+    #[test]
+    fn rotated_key_still_verifies() {
+        let old_keys = KeyManager::from_private_pem("ed-1", ED25519_PRIVATE).unwrap();
+        let token = old_keys.sign(&claims()).unwrap();
+
+        let keys = KeyManager::from_private_pem("rsa-2", RSA_PRIVATE)
+            .unwrap()
+            .with_public_pem("ed-1", ED25519_PUBLIC)
+            .unwrap();
+        assert_eq!(keys.current_key_id(), "rsa-2");
+        assert_eq!(verify(&keys, &token).unwrap(), claims());
+
+        let without_old_key = KeyManager::from_private_pem("rsa-2", RSA_PRIVATE).unwrap();
+        assert!(verify(&without_old_key, &token).is_err());
+    }
+
+    #[test]
+    fn jwks_publishes_public_keys_only() {
+        let keys = KeyManager::from_private_pem("rsa-2", RSA_PRIVATE)
+            .unwrap()
+            .with_public_pem("ed-1", ED25519_PUBLIC)
+            .unwrap();
+        let jwks = keys.jwks();
+        assert!(jwks.find("rsa-2").is_some());
+        assert!(jwks.find("ed-1").is_some());
+
+        // A verifier holding only the JWKS accepts our tokens.
+        let token = keys.sign(&claims()).unwrap();
+        let jwk = jwks.find("rsa-2").unwrap();
+        let key = DecodingKey::from_jwk(jwk).unwrap();
+        assert!(decode::<Claims>(&token, &key, &Validation::new(Algorithm::RS256)).is_ok());
+
+        assert!(KeyManager::hmac(b"secret").jwks().keys.is_empty());
+    }
+
+    #[test]
+    fn rejects_token_with_swapped_algorithm() {
+        let keys = KeyManager::from_private_pem("ed-1", ED25519_PRIVATE).unwrap();
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("ed-1".into());
+        let forged = encode(&header, &claims(), &EncodingKey::from_secret(b"secret")).unwrap();
+        assert!(verify(&keys, &forged).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! Issuing and verifying access tokens.
+//!
+//! Handlers receive an `Arc<dyn TokenService>` instead of calling free
+//! functions, so tests can swap in [`FakeTokenService`] and the keys are
+//! loaded once, when the service is built.
+use std::collections::BTreeMap;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{Validation, errors::ErrorKind, jwk::JwkSet};
+
+use super::{
+    jwt::{Actor, Claims, JWTResult},
+    keys::KeyManager,
+};
+use crate::{config::AppConfig, guards::roles::UserRole};
+
+#[derive(Debug, Clone)]
+pub struct TokenSettings {
+    pub issuer: String,
+    pub audience: String,
+    pub access_token_ttl: Duration,
+    /// Clock skew allowed when checking `exp`.
+    pub leeway_secs: u64,
+}
+
+impl Default for TokenSettings {
+    fn default() -> Self {
+        Self {
+            issuer: "social-app".into(),
+            audience: "social-app".into(),
+            access_token_ttl: Duration::hours(24),
+            leeway_secs: 60,
+        }
+    }
+}
+
+impl TokenSettings {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            issuer: config.jwt_issuer.clone(),
+            audience: config.jwt_audience.clone(),
+            access_token_ttl: Duration::seconds(config.access_token_ttl_secs),
+            leeway_secs: config.jwt_leeway_secs,
+        }
+    }
+}
+
+/// What to put in a token. Issuer, audience and timestamps are added by the service.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenRequest {
+    pub email: String,
+    pub role: UserRole,
+    pub id: String,
+    pub scopes: Vec<String>,
+    pub actor: Option<Actor>,
+    /// Overrides the configured access token TTL.
+    pub ttl: Option<Duration>,
+    pub custom: BTreeMap<String, serde_json::Value>,
+}
+
+impl TokenRequest {
+    pub fn new(email: String, role: UserRole, id: String) -> Self {
+        Self {
+            email,
+            role,
+            id,
+            scopes: vec![],
+            actor: None,
+            ttl: None,
+            custom: BTreeMap::new(),
+        }
+    }
+    pub fn with_scopes(mut self, scopes: Vec<String>) -> Self {
+        self.scopes = scopes;
+        self
+    }
+    pub fn with_actor(mut self, actor: Actor) -> Self {
+        self.actor = Some(actor);
+        self
+    }
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+    pub fn with_claim(mut self, name: &str, value: serde_json::Value) -> Self {
+        self.custom.insert(name.to_string(), value);
+        self
+    }
+}
+
+pub trait TokenService: Send + Sync {
+    fn issue(&self, request: TokenRequest) -> JWTResult<String>;
+    fn verify(&self, token: &str) -> JWTResult<Claims>;
+    /// Public keys that verify the issued tokens.
+    fn jwks(&self) -> JwkSet;
+}
+
+fn claims_for(request: TokenRequest, settings: &TokenSettings) -> Claims {
+    let now = Utc::now();
+    let ttl = request.ttl.unwrap_or(settings.access_token_ttl);
+    Claims {
+        sub: request.email,
+        exp: (now + ttl).timestamp() as usize,
+        role: request.role,
+        id: request.id,
+        iat: now.timestamp() as usize,
+        iss: settings.issuer.clone(),
+        aud: settings.audience.clone(),
+        act: request.actor,
+        scopes: request.scopes,
+        custom: request.custom,
+    }
+}
+
+pub struct JwtTokenService {
+    keys: KeyManager,
+    settings: TokenSettings,
+}
+
+impl JwtTokenService {
+    pub fn new(keys: KeyManager, settings: TokenSettings) -> Self {
+        Self { keys, settings }
+    }
+    pub fn from_config(config: &AppConfig) -> JWTResult<Self> {
+        Ok(Self::new(
+            KeyManager::from_config(config)?,
+            TokenSettings::from_config(config),
+        ))
+    }
+    pub fn current_key_id(&self) -> &str {
+        self.keys.current_key_id()
+    }
+}
+
+impl TokenService for JwtTokenService {
+    fn issue(&self, request: TokenRequest) -> JWTResult<String> {
+        self.keys.sign(&claims_for(request, &self.settings))
+    }
+    fn verify(&self, token: &str) -> JWTResult<Claims> {
+        let mut validation = Validation::default();
+        validation.set_issuer(&[&self.settings.issuer]);
+        validation.set_audience(&[&self.settings.audience]);
+        validation.leeway = self.settings.leeway_secs;
+        self.keys.verify(token, &validation)
+    }
+    fn jwks(&self) -> JwkSet {
+        self.keys.jwks()
+    }
+}
+
+/// Issues unsigned tokens that only it accepts, for tests.
+#[derive(Default)]
+pub struct FakeTokenService {
+    settings: TokenSettings,
+}
+
+const FAKE_TOKEN_PREFIX: &str = "fake.";
+
+impl FakeTokenService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TokenService for FakeTokenService {
+    fn issue(&self, request: TokenRequest) -> JWTResult<String> {
+        let claims = serde_json::to_vec(&claims_for(request, &self.settings))?;
+        Ok(format!(
+            "{}{}",
+            FAKE_TOKEN_PREFIX,
+            URL_SAFE_NO_PAD.encode(claims)
+        ))
+    }
+    fn verify(&self, token: &str) -> JWTResult<Claims> {
+        let payload = token
+            .strip_prefix(FAKE_TOKEN_PREFIX)
+            .ok_or(ErrorKind::InvalidToken)?;
+        let claims: Claims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload)?)?;
+        if claims.exp < Utc::now().timestamp() as usize {
+            return Err(ErrorKind::ExpiredSignature.into());
+        }
+        Ok(claims)
+    }
+    fn jwks(&self) -> JwkSet {
+        JwkSet { keys: vec![] }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(settings: TokenSettings) -> JwtTokenService {
+        JwtTokenService::new(KeyManager::hmac(b"secret"), settings)
+    }
+
+    #[test]
+    fn issue_and_verify_with_scopes() {
+        let service = service(TokenSettings::default());
+        let token = service
+            .issue(
+                TokenRequest::new("user@example.com".into(), UserRole::Admin, "user-id".into())
+                    .with_scopes(vec!["users:read".into()])
+                    .with_claim("tenant", "acme".into()),
+            )
+            .unwrap();
+
+        let claims = service.verify(&token).unwrap();
+        assert_eq!(claims.sub, "user@example.com");
+        assert_eq!(claims.role, UserRole::Admin);
+        assert!(claims.has_scope("users:read"));
+        assert_eq!(claims.custom["tenant"], "acme");
+        assert_eq!(claims.act, None);
+    }
+
+    #[test]
+    fn impersonation_token_carries_actor() {
+        let service = service(TokenSettings::default());
+        let actor = Actor {
+            sub: "admin@example.com".into(),
+            id: "admin-id".into(),
+        };
+        let token = service
+            .issue(
+                TokenRequest::new(
+                    "user@example.com".into(),
+                    UserRole::Regular,
+                    "user-id".into(),
+                )
+                .with_actor(actor.clone()),
+            )
+            .unwrap();
+
+        let claims = service.verify(&token).unwrap();
+        assert_eq!(claims.id, "user-id");
+        assert_eq!(claims.act, Some(actor));
+    }
+
+    #[test]
+    fn rejects_other_audience_and_expired_tokens() {
+        let issuer = service(TokenSettings {
+            audience: "other-service".into(),
+            ..TokenSettings::default()
+        });
+        let request = TokenRequest::new("user@example.com".into(), UserRole::Regular, "id".into());
+        let token = issuer.issue(request.clone()).unwrap();
+        assert!(service(TokenSettings::default()).verify(&token).is_err());
+
+        let verifier = service(TokenSettings {
+            leeway_secs: 0,
+            ..TokenSettings::default()
+        });
+        let expired = verifier
+            .issue(request.with_ttl(Duration::seconds(-10)))
+            .unwrap();
+        assert!(verifier.verify(&expired).is_err());
+    }
+
+    #[test]
+    fn fake_service_round_trips() {
+        let fake = FakeTokenService::new();
+        let token = fake
+            .issue(TokenRequest::new(
+                "user@example.com".into(),
+                UserRole::Moderator,
+                "id".into(),
+            ))
+            .unwrap();
+        assert_eq!(fake.verify(&token).unwrap().role, UserRole::Moderator);
+        assert!(fake.verify("not-a-token").is_err());
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    fmt::Display,
+    future::Future,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::Notify;
+use tracing::{error, info};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Succeeded,
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub name: String,
+    pub state: JobState,
+    /// The app does not report ready until this job has succeeded.
+    pub required_for_readiness: bool,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Work started in the background, such as migrations at startup, so that
+/// readiness checks can report it and wait for the jobs the app needs.
+#[derive(Debug, Clone, Default)]
+pub struct BackgroundJobs {
+    jobs: Arc<RwLock<Vec<JobStatus>>>,
+    finished: Arc<Notify>,
+}
+
+impl BackgroundJobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `job` on the runtime and records how it ends.
+    pub fn spawn<F, E>(&self, name: &str, required_for_readiness: bool, job: F)
+    where
+        F: Future<Output = Result<(), E>> + Send + 'static,
+        E: Display,
+    {
+        self.jobs.write().unwrap().push(JobStatus {
+            name: name.to_string(),
+            state: JobState::Running,
+            required_for_readiness,
+            started_at: Utc::now(),
+            finished_at: None,
+        });
+        let jobs = self.clone();
+        let name = name.to_string();
+        tokio::spawn(async move {
+            let state = match job.await {
+                Ok(()) => {
+                    info!(job = %name, "Background job finished");
+                    JobState::Succeeded
+                }
+                Err(err) => {
+                    error!(job = %name, error = %err, "Background job failed");
+                    JobState::Failed {
+                        error: err.to_string(),
+                    }
+                }
+            };
+            jobs.finish(&name, state);
+        });
+    }
+
+    pub fn statuses(&self) -> Vec<JobStatus> {
+        self.jobs.read().unwrap().clone()
+    }
+
+    /// Whether every job required for readiness has succeeded.
+    pub fn is_ready(&self) -> bool {
+        self.jobs
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|job| job.required_for_readiness)
+            .all(|job| job.state == JobState::Succeeded)
+    }
+
+    /// Waits up to `timeout` for running jobs to finish, as on shutdown.
+    /// Returns whether they all did.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, async {
+            loop {
+                // Registered before checking so a job finishing in between is not missed.
+                let finished = self.finished.notified();
+                tokio::pin!(finished);
+                finished.as_mut().enable();
+                if !self.is_running() {
+                    return;
+                }
+                finished.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+
+    fn is_running(&self) -> bool {
+        self.jobs
+            .read()
+            .unwrap()
+            .iter()
+            .any(|job| job.state == JobState::Running)
+    }
+
+    fn finish(&self, name: &str, state: JobState) {
+        {
+            let mut jobs = self.jobs.write().unwrap();
+            if let Some(job) = jobs.iter_mut().rev().find(|job| job.name == name) {
+                job.state = state;
+                job.finished_at = Some(Utc::now());
+            }
+        }
+        self.finished.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    async fn settle(jobs: &BackgroundJobs) {
+        while jobs
+            .statuses()
+            .iter()
+            .any(|job| job.state == JobState::Running)
+        {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn not_ready_until_required_jobs_succeed() {
+        let jobs = BackgroundJobs::new();
+        let (done, wait) = oneshot::channel::<()>();
+        jobs.spawn("migrations", true, async move {
+            wait.await.map_err(|_| "cancelled")
+        });
+        jobs.spawn("warm cache", false, async { Err("cache unavailable") });
+
+        assert!(!jobs.is_ready());
+        done.send(()).unwrap();
+        settle(&jobs).await;
+
+        assert!(jobs.is_ready());
+        let statuses = jobs.statuses();
+        assert_eq!(statuses[0].state, JobState::Succeeded);
+        assert!(statuses[0].finished_at.is_some());
+        assert_eq!(
+            statuses[1].state,
+            JobState::Failed {
+                error: "cache unavailable".into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_required_job_keeps_app_not_ready() {
+        let jobs = BackgroundJobs::new();
+        jobs.spawn("migrations", true, async { Err("index build failed") });
+        settle(&jobs).await;
+        assert!(!jobs.is_ready());
+    }
+
+    #[tokio::test]
+    async fn drain_waits_for_running_jobs() {
+        let jobs = BackgroundJobs::new();
+        let (done, wait) = oneshot::channel::<()>();
+        jobs.spawn("migrations", true, async move {
+            wait.await.map_err(|_| "cancelled")
+        });
+
+        assert!(!jobs.drain(Duration::from_millis(10)).await);
+        done.send(()).unwrap();
+        assert!(jobs.drain(Duration::from_secs(1)).await);
+        assert_eq!(jobs.statuses()[0].state, JobState::Succeeded);
+    }
+}
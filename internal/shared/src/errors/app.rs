@@ -1,4 +1,7 @@
-use crate::errors::content::ContentDomainError;
+use crate::errors::{
+    content::ContentDomainError,
+    validation::{FieldError, field_errors, field_names},
+};
 use std::fmt;
 use tracing::error;
 
@@ -9,18 +12,29 @@ pub enum AppError {
     Internal(String),
     NonEmptyString,
     Base64(String),
-    Validation(String),
+    Validation(Vec<FieldError>),
 }
 
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Content(err) => write!(f, "Content error: {}", err),
+            Self::Content(err) => write!(f, "{}", err),
             Self::Database(msg) => write!(f, "Database error: {}", msg),
             Self::Internal(msg) => write!(f, "Internal error: {}", msg),
             Self::NonEmptyString => write!(f, "Empty string is not allowed"),
             Self::Base64(msg) => write!(f, "Base64 error: {}", msg),
-            Self::Validation(msg) => write!(f, "Validation error: {}", msg),
+            Self::Validation(fields) => write!(f, "Invalid input: {}", field_names(fields)),
+        }
+    }
+}
+
+impl AppError {
+    /// Stable code sent to clients with the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Content(err) => err.code(),
+            Self::NonEmptyString | Self::Base64(_) | Self::Validation(_) => "BAD_USER_INPUT",
+            Self::Database(_) | Self::Internal(_) => "INTERNAL_SERVER_ERROR",
         }
     }
 }
@@ -48,8 +62,6 @@ impl From<base64::DecodeError> for AppError {
 
 impl From<validator::ValidationErrors> for AppError {
     fn from(err: validator::ValidationErrors) -> Self {
-        let json =
-            serde_json::to_string(&err).unwrap_or_else(|_| format!("{{\"error\": \"{}\"}}", err));
-        Self::Validation(json)
+        Self::Validation(field_errors(&err))
     }
 }
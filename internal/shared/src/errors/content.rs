@@ -4,6 +4,11 @@ use std::fmt;
 pub enum ContentDomainError {
     PostNotFound,
     InvalidTitle,
+    InvalidBody,
+    Unauthorized,
+    CommentNotFound,
+    CommentTooDeep,
+    CommentDeleted,
 }
 
 impl fmt::Display for ContentDomainError {
@@ -11,6 +16,26 @@ impl fmt::Display for ContentDomainError {
         match self {
             Self::PostNotFound => write!(f, "Post not found"),
             Self::InvalidTitle => write!(f, "Title is invalid"),
+            Self::InvalidBody => write!(f, "Body is invalid"),
+            Self::Unauthorized => write!(f, "Unauthorized access"),
+            Self::CommentNotFound => write!(f, "Comment not found"),
+            Self::CommentTooDeep => write!(f, "Replies cannot be nested any deeper"),
+            Self::CommentDeleted => write!(f, "Comment was deleted"),
+        }
+    }
+}
+
+impl ContentDomainError {
+    /// Stable code sent to clients with the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::PostNotFound => "POST_NOT_FOUND",
+            Self::InvalidTitle => "INVALID_TITLE",
+            Self::InvalidBody => "INVALID_BODY",
+            Self::Unauthorized => "FORBIDDEN",
+            Self::CommentNotFound => "COMMENT_NOT_FOUND",
+            Self::CommentTooDeep => "COMMENT_TOO_DEEP",
+            Self::CommentDeleted => "COMMENT_DELETED",
         }
     }
 }
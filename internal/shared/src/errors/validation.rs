@@ -0,0 +1,111 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+/// One failed rule on one input field, as sent to clients. Nested fields are
+/// named by path, such as `profile.links[0].url`.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    /// The rule that failed, such as `email` or `length`.
+    pub code: String,
+    pub message: Option<String>,
+}
+
+/// Flattens `errors` into one entry per failed rule, ordered by field.
+pub fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut fields = vec![];
+    collect_field_errors(errors, "", &mut fields);
+    fields.sort_by(|a, b| a.field.cmp(&b.field));
+    fields
+}
+
+fn collect_field_errors(errors: &ValidationErrors, prefix: &str, fields: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{}", prefix, field)
+        };
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                fields.extend(errors.iter().map(|error| FieldError {
+                    field: path.clone(),
+                    code: error.code.to_string(),
+                    message: error.message.as_ref().map(|message| message.to_string()),
+                }));
+            }
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(errors, &path, fields),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect_field_errors(errors, &format!("{}[{}]", path, index), fields);
+                }
+            }
+        }
+    }
+}
+
+/// `field_a, field_b` for error messages.
+pub fn field_names(fields: &[FieldError]) -> String {
+    let mut names: Vec<&str> = fields.iter().map(|field| field.field.as_str()).collect();
+    names.dedup();
+    names.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use validator::Validate;
+
+    #[derive(Validate)]
+    struct Link {
+        #[validate(url)]
+        url: String,
+    }
+
+    #[derive(Validate)]
+    struct Profile {
+        #[validate(email)]
+        email: String,
+        #[validate(length(min = 3, message = "Too short"))]
+        username: String,
+        #[validate(nested)]
+        links: Vec<Link>,
+    }
+
+    #[test]
+    fn field_errors_lists_nested_fields_by_path() {
+        let profile = Profile {
+            email: "not-an-email".into(),
+            username: "ab".into(),
+            links: vec![
+                Link {
+                    url: "https://example.com".into(),
+                },
+                Link { url: "nope".into() },
+            ],
+        };
+        let fields = field_errors(&profile.validate().unwrap_err());
+        assert_eq!(
+            fields,
+            vec![
+                FieldError {
+                    field: "email".into(),
+                    code: "email".into(),
+                    message: None,
+                },
+                FieldError {
+                    field: "links[1].url".into(),
+                    code: "url".into(),
+                    message: None,
+                },
+                FieldError {
+                    field: "username".into(),
+                    code: "length".into(),
+                    message: Some("Too short".into()),
+                },
+            ]
+        );
+        assert_eq!(field_names(&fields), "email, links[1].url, username");
+    }
+}
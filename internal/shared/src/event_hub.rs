@@ -0,0 +1,68 @@
+use futures::stream::{self, BoxStream, StreamExt};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+/// In-process fan-out of events to live subscribers, such as GraphQL
+/// subscriptions. Events published while nobody listens are dropped, and a
+/// subscriber that falls more than `capacity` events behind skips the rest.
+pub struct EventHub<E> {
+    sender: broadcast::Sender<E>,
+}
+
+impl<E: Clone + Send + 'static> EventHub<E> {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: E) {
+        // Only fails when there are no subscribers.
+        let _ = self.sender.send(event);
+    }
+
+    /// Events published from now on. The stream ends when the hub is dropped.
+    pub fn subscribe(&self) -> BoxStream<'static, E> {
+        stream::unfold(self.sender.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "Event subscriber fell behind");
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn subscribers_receive_events_published_after_subscribing() {
+        let hub = EventHub::new(8);
+        hub.publish(0);
+        let mut first = hub.subscribe();
+        let mut second = hub.subscribe();
+        hub.publish(1);
+        hub.publish(2);
+
+        assert_eq!(first.next().await, Some(1));
+        assert_eq!(first.next().await, Some(2));
+        assert_eq!(second.next().await, Some(1));
+    }
+
+    #[tokio::test]
+    async fn lagging_subscriber_skips_missed_events() {
+        let hub = EventHub::new(2);
+        let mut subscriber = hub.subscribe();
+        for event in 1..=5 {
+            hub.publish(event);
+        }
+        assert_eq!(subscriber.next().await, Some(4));
+        assert_eq!(subscriber.next().await, Some(5));
+    }
+}
@@ -1,5 +1,8 @@
 pub mod permissions {
-    #[derive(Debug, PartialEq, Clone)]
+    use async_graphql::Enum;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Enum)]
     pub enum Permission {
         BanUser,
         UnbanUser,
@@ -14,6 +17,77 @@ pub mod permissions {
         ViewUser,
         ListUsers,
         MakeRegular,
+        ManageTwoFactor,
+        ManageApiTokens,
+        ImpersonateUser,
+        ViewAuditLog,
+        ViewModerationQueue,
+        CreateComment,
+        UpdateComment,
+        DeleteComment,
+    }
+
+    impl Permission {
+        pub const ALL: [Permission; 21] = [
+            Permission::BanUser,
+            Permission::UnbanUser,
+            Permission::CreatePost,
+            Permission::DeletePost,
+            Permission::UpdatePost,
+            Permission::DeleteUser,
+            Permission::CreateAccount,
+            Permission::AwardBadge,
+            Permission::RevokeBadge,
+            Permission::MakeModerator,
+            Permission::ViewUser,
+            Permission::ListUsers,
+            Permission::MakeRegular,
+            Permission::ManageTwoFactor,
+            Permission::ManageApiTokens,
+            Permission::ImpersonateUser,
+            Permission::ViewAuditLog,
+            Permission::ViewModerationQueue,
+            Permission::CreateComment,
+            Permission::UpdateComment,
+            Permission::DeleteComment,
+        ];
+
+        /// Name of the permission in token scopes.
+        pub fn as_scope(&self) -> &'static str {
+            match self {
+                Permission::BanUser => "ban_user",
+                Permission::UnbanUser => "unban_user",
+                Permission::CreatePost => "create_post",
+                Permission::DeletePost => "delete_post",
+                Permission::UpdatePost => "update_post",
+                Permission::DeleteUser => "delete_user",
+                Permission::CreateAccount => "create_account",
+                Permission::AwardBadge => "award_badge",
+                Permission::RevokeBadge => "revoke_badge",
+                Permission::MakeModerator => "make_moderator",
+                Permission::ViewUser => "view_user",
+                Permission::ListUsers => "list_users",
+                Permission::MakeRegular => "make_regular",
+                Permission::ManageTwoFactor => "manage_two_factor",
+                Permission::ManageApiTokens => "manage_api_tokens",
+                Permission::ImpersonateUser => "impersonate_user",
+                Permission::ViewAuditLog => "view_audit_log",
+                Permission::ViewModerationQueue => "view_moderation_queue",
+                Permission::CreateComment => "create_comment",
+                Permission::UpdateComment => "update_comment",
+                Permission::DeleteComment => "delete_comment",
+            }
+        }
+
+        pub fn from_scope(scope: &str) -> Option<Permission> {
+            Self::ALL.into_iter().find(|p| p.as_scope() == scope)
+        }
+
+        /// Impersonation is read-only: admins acting as another user may
+        /// only look around.
+        pub fn allowed_when_impersonating(&self) -> bool {
+            matches!(self, Permission::ViewUser | Permission::ListUsers)
+        }
     }
 
     #[derive(Debug, PartialEq, Clone)]
@@ -27,6 +101,11 @@ pub mod permissions {
         ViewUser,
         ListUsers,
         MakeRegular,
+        ManageTwoFactor,
+        ManageApiTokens,
+        ImpersonateUser,
+        ViewAuditLog,
+        ViewModerationQueue,
     }
 
     impl From<UserPermission> for Permission {
@@ -40,15 +119,60 @@ pub mod permissions {
                 UserPermission::ViewUser => Permission::ViewUser,
                 UserPermission::ListUsers => Permission::ListUsers,
                 UserPermission::MakeRegular => Permission::MakeRegular,
+                UserPermission::ManageTwoFactor => Permission::ManageTwoFactor,
                 UserPermission::CreateAccount => Permission::CreateAccount,
+                UserPermission::ManageApiTokens => Permission::ManageApiTokens,
+                UserPermission::ImpersonateUser => Permission::ImpersonateUser,
+                UserPermission::ViewAuditLog => Permission::ViewAuditLog,
+                UserPermission::ViewModerationQueue => Permission::ViewModerationQueue,
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub enum ContentPermission {
+        CreatePost,
+        UpdatePost,
+        DeletePost,
+        CreateComment,
+        UpdateComment,
+        DeleteComment,
+    }
+
+    impl From<ContentPermission> for Permission {
+        fn from(p: ContentPermission) -> Self {
+            match p {
+                ContentPermission::CreatePost => Permission::CreatePost,
+                ContentPermission::UpdatePost => Permission::UpdatePost,
+                ContentPermission::DeletePost => Permission::DeletePost,
+                ContentPermission::CreateComment => Permission::CreateComment,
+                ContentPermission::UpdateComment => Permission::UpdateComment,
+                ContentPermission::DeleteComment => Permission::DeleteComment,
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn scopes_round_trip() {
+            for permission in Permission::ALL {
+                assert_eq!(
+                    Permission::from_scope(permission.as_scope()),
+                    Some(permission)
+                );
             }
+            assert_eq!(Permission::from_scope("unknown"), None);
         }
     }
 }
 pub mod roles {
     use serde::{Deserialize, Serialize};
+    use utoipa::ToSchema;
 
-    #[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Clone)]
+    #[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Clone, ToSchema)]
     pub enum UserRole {
         Admin,
         Regular,
@@ -1,10 +1,14 @@
 pub mod auth;
+pub mod background_jobs;
 pub mod command_handler;
 pub mod config;
 pub mod db_transactions;
 pub mod errors;
+pub mod event_hub;
 pub mod guards;
+pub mod metrics;
 pub mod pagination;
 pub mod query_handler;
+pub mod rate_limit;
 pub mod test_utils;
 pub mod types;
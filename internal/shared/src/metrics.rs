@@ -0,0 +1,282 @@
+//! Process wide metrics, rendered in the Prometheus text format.
+//!
+//! Metrics are declared as statics next to the code that records them and
+//! recorded through those statics; [`render`] lists every series recorded so
+//! far. Keep label values to small, known sets such as routes or error kinds.
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+/// `(name, value)` pairs identifying one series of a metric.
+pub type Labels<'a> = &'a [(&'static str, &'a str)];
+
+/// Seconds, from a millisecond up to ten seconds.
+pub const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+static REGISTRY: LazyLock<Mutex<BTreeMap<&'static str, Family>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// A value that only goes up, such as requests served.
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+}
+
+impl Counter {
+    pub const fn new(name: &'static str, help: &'static str) -> Self {
+        Self { name, help }
+    }
+
+    pub fn inc(&self, labels: Labels) {
+        self.inc_by(labels, 1);
+    }
+
+    pub fn inc_by(&self, labels: Labels, value: u64) {
+        record(self.name, self.help, labels, Series::Counter(0), |series| {
+            if let Series::Counter(total) = series {
+                *total += value;
+            }
+        });
+    }
+}
+
+/// A value that goes up and down, such as open connections.
+pub struct Gauge {
+    name: &'static str,
+    help: &'static str,
+}
+
+impl Gauge {
+    pub const fn new(name: &'static str, help: &'static str) -> Self {
+        Self { name, help }
+    }
+
+    pub fn add(&self, labels: Labels, delta: i64) {
+        record(self.name, self.help, labels, Series::Gauge(0), |series| {
+            if let Series::Gauge(value) = series {
+                *value += delta;
+            }
+        });
+    }
+
+    pub fn set(&self, labels: Labels, value: i64) {
+        record(self.name, self.help, labels, Series::Gauge(0), |series| {
+            if let Series::Gauge(current) = series {
+                *current = value;
+            }
+        });
+    }
+}
+
+/// Counts observations into buckets, such as request latencies.
+pub struct Histogram {
+    name: &'static str,
+    help: &'static str,
+    buckets: &'static [f64],
+}
+
+impl Histogram {
+    /// `buckets` are upper bounds in ascending order.
+    pub const fn new(name: &'static str, help: &'static str, buckets: &'static [f64]) -> Self {
+        Self {
+            name,
+            help,
+            buckets,
+        }
+    }
+
+    pub fn observe(&self, labels: Labels, value: f64) {
+        let empty = Series::Histogram {
+            buckets: self.buckets,
+            counts: vec![0; self.buckets.len()],
+            sum: 0.0,
+            count: 0,
+        };
+        record(self.name, self.help, labels, empty, |series| {
+            if let Series::Histogram {
+                buckets,
+                counts,
+                sum,
+                count,
+            } = series
+            {
+                if let Some(i) = buckets.iter().position(|bound| value <= *bound) {
+                    counts[i] += 1;
+                }
+                *sum += value;
+                *count += 1;
+            }
+        });
+    }
+
+    pub fn observe_duration(&self, labels: Labels, duration: Duration) {
+        self.observe(labels, duration.as_secs_f64());
+    }
+}
+
+struct Family {
+    help: &'static str,
+    series: BTreeMap<Vec<(&'static str, String)>, Series>,
+}
+
+enum Series {
+    Counter(u64),
+    Gauge(i64),
+    Histogram {
+        buckets: &'static [f64],
+        /// Per bucket, not cumulative.
+        counts: Vec<u64>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+fn record(
+    name: &'static str,
+    help: &'static str,
+    labels: Labels,
+    empty: Series,
+    update: impl FnOnce(&mut Series),
+) {
+    let key = labels
+        .iter()
+        .map(|(label, value)| (*label, value.to_string()))
+        .collect();
+    let mut registry = REGISTRY.lock().unwrap();
+    let family = registry.entry(name).or_insert_with(|| Family {
+        help,
+        series: BTreeMap::new(),
+    });
+    update(family.series.entry(key).or_insert(empty));
+}
+
+/// Every recorded series in the Prometheus text exposition format.
+pub fn render() -> String {
+    let registry = REGISTRY.lock().unwrap();
+    let mut out = String::new();
+    for (name, family) in registry.iter() {
+        let Some(first) = family.series.values().next() else {
+            continue;
+        };
+        let kind = match first {
+            Series::Counter(_) => "counter",
+            Series::Gauge(_) => "gauge",
+            Series::Histogram { .. } => "histogram",
+        };
+        let _ = writeln!(out, "# HELP {} {}", name, family.help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        for (labels, series) in &family.series {
+            match series {
+                Series::Counter(value) => {
+                    let _ = writeln!(out, "{}{} {}", name, format_labels(labels, None), value);
+                }
+                Series::Gauge(value) => {
+                    let _ = writeln!(out, "{}{} {}", name, format_labels(labels, None), value);
+                }
+                Series::Histogram {
+                    buckets,
+                    counts,
+                    sum,
+                    count,
+                } => {
+                    let mut cumulative = 0;
+                    for (bound, bucket_count) in buckets.iter().zip(counts) {
+                        cumulative += bucket_count;
+                        let le = bound.to_string();
+                        let _ = writeln!(
+                            out,
+                            "{}_bucket{} {}",
+                            name,
+                            format_labels(labels, Some(&le)),
+                            cumulative
+                        );
+                    }
+                    let _ = writeln!(
+                        out,
+                        "{}_bucket{} {}",
+                        name,
+                        format_labels(labels, Some("+Inf")),
+                        count
+                    );
+                    let _ = writeln!(out, "{}_sum{} {}", name, format_labels(labels, None), sum);
+                    let _ = writeln!(
+                        out,
+                        "{}_count{} {}",
+                        name,
+                        format_labels(labels, None),
+                        count
+                    );
+                }
+            }
+        }
+    }
+    out
+}
+
+fn format_labels(labels: &[(&'static str, String)], le: Option<&str>) -> String {
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(label, value)| (*label, value.as_str()))
+        .chain(le.map(|le| ("le", le)))
+        .map(|(label, value)| format!("{}=\"{}\"", label, escape(value)))
+        .collect();
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_counters_by_label() {
+        static REQUESTS: Counter = Counter::new("test_requests_total", "Requests served");
+        REQUESTS.inc(&[("route", "/graphql")]);
+        REQUESTS.inc_by(&[("route", "/graphql")], 2);
+        REQUESTS.inc(&[("route", "say \"hi\"")]);
+
+        let out = render();
+        assert!(out.contains("# TYPE test_requests_total counter"));
+        assert!(out.contains("test_requests_total{route=\"/graphql\"} 3"));
+        assert!(out.contains("test_requests_total{route=\"say \\\"hi\\\"\"} 1"));
+    }
+
+    #[test]
+    fn renders_cumulative_histogram_buckets() {
+        static LATENCY: Histogram = Histogram::new("test_latency_seconds", "Latency", &[0.1, 1.0]);
+        LATENCY.observe(&[], 0.0625);
+        LATENCY.observe(&[], 0.5);
+        LATENCY.observe(&[], 5.0);
+
+        let out = render();
+        assert!(out.contains("# TYPE test_latency_seconds histogram"));
+        assert!(out.contains("test_latency_seconds_bucket{le=\"0.1\"} 1"));
+        assert!(out.contains("test_latency_seconds_bucket{le=\"1\"} 2"));
+        assert!(out.contains("test_latency_seconds_bucket{le=\"+Inf\"} 3"));
+        assert!(out.contains("test_latency_seconds_sum 5.5625"));
+        assert!(out.contains("test_latency_seconds_count 3"));
+    }
+
+    #[test]
+    fn gauges_move_both_ways() {
+        static OPEN: Gauge = Gauge::new("test_open_connections", "Open connections");
+        OPEN.add(&[], 3);
+        OPEN.add(&[], -1);
+        assert!(render().contains("test_open_connections 2"));
+    }
+}
@@ -0,0 +1,418 @@
+//! Token bucket rate limiting. Every caller gets a bucket of `burst` tokens
+//! that refills at `per_minute` tokens a minute, and each request takes one.
+//! Requests are counted per user when signed in, otherwise per IP, or per /64
+//! network for IPv6 since a single host usually holds a whole one. Auth
+//! operations such as `signIn` also take from a stricter bucket of their own.
+use std::{
+    fmt,
+    net::IpAddr,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use lru::LruCache;
+use tracing::warn;
+
+use crate::{auth::AuthUser, guards::roles::UserRole, metrics::Counter, types::AppResult};
+
+/// Buckets kept in memory. Past it the least recently used one is dropped,
+/// which by then has usually refilled.
+const MAX_IN_MEMORY_BUCKETS: usize = 10_000;
+
+static RATE_LIMITED: Counter = Counter::new(
+    "rate_limited_requests_total",
+    "Requests refused by the rate limiter, by bucket",
+);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Requests allowed at once after being idle.
+    pub burst: u32,
+    /// Sustained requests allowed per minute. At least 1.
+    pub per_minute: u32,
+}
+
+impl RateLimit {
+    fn tokens_per_sec(&self) -> f64 {
+        f64::from(self.per_minute.max(1)) / 60.0
+    }
+
+    /// How long until a bucket holding `tokens` has a whole token again.
+    pub fn retry_after(&self, tokens: f64) -> Duration {
+        Duration::from_secs_f64(((1.0 - tokens) / self.tokens_per_sec()).max(0.0))
+    }
+
+    /// How long an empty bucket takes to fill up, after which its state can be forgotten.
+    pub fn refill_time(&self) -> Duration {
+        Duration::from_secs_f64(f64::from(self.burst) / self.tokens_per_sec())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitDecision {
+    Allowed,
+    Limited { retry_after: Duration },
+}
+
+/// State of one bucket. Stores keep it however suits them but refill it the same way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    pub tokens: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Bucket {
+    pub fn full(limit: RateLimit, now: DateTime<Utc>) -> Self {
+        Self {
+            tokens: f64::from(limit.burst),
+            updated_at: now,
+        }
+    }
+
+    /// Adds the tokens earned since the bucket was last used, then takes one if there is one.
+    pub fn take(&mut self, limit: RateLimit, now: DateTime<Utc>) -> RateLimitDecision {
+        let elapsed_secs = (now - self.updated_at).num_milliseconds().max(0) as f64 / 1000.0;
+        self.tokens =
+            (self.tokens + elapsed_secs * limit.tokens_per_sec()).min(f64::from(limit.burst));
+        self.updated_at = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            RateLimitDecision::Allowed
+        } else {
+            RateLimitDecision::Limited {
+                retry_after: limit.retry_after(self.tokens),
+            }
+        }
+    }
+}
+
+/// Where buckets are kept. The in-memory store limits each instance on its
+/// own; a shared store, such as MongoDB, applies the limits across instances.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Takes a token from the bucket at `key`, creating it full when missing.
+    async fn take(
+        &self,
+        key: &str,
+        limit: RateLimit,
+        now: DateTime<Utc>,
+    ) -> AppResult<RateLimitDecision>;
+}
+
+pub struct InMemoryRateLimitStore {
+    buckets: Mutex<LruCache<String, Bucket>>,
+}
+
+impl InMemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::with_capacity(MAX_IN_MEMORY_BUCKETS)
+    }
+
+    /// A store keeping at most `capacity` buckets.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            buckets: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+}
+
+impl Default for InMemoryRateLimitStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryRateLimitStore {
+    async fn take(
+        &self,
+        key: &str,
+        limit: RateLimit,
+        now: DateTime<Utc>,
+    ) -> AppResult<RateLimitDecision> {
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.get_or_insert_mut(key.to_string(), || Bucket::full(limit, now));
+        Ok(bucket.take(limit, now))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitPolicy {
+    /// Every request.
+    pub api: RateLimit,
+    /// Each auth operation, such as signing up, signing in or verifying an OTP.
+    pub auth: RateLimit,
+}
+
+/// A request refused by the rate limiter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimited {
+    pub retry_after: Duration,
+}
+
+impl RateLimited {
+    /// Whole seconds to wait, as sent in `Retry-After`.
+    pub fn retry_after_secs(&self) -> u64 {
+        self.retry_after.as_secs_f64().ceil().max(1.0) as u64
+    }
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Too many requests. Retry in {} seconds",
+            self.retry_after_secs()
+        )
+    }
+}
+
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    policy: RateLimitPolicy,
+    enabled: bool,
+}
+
+impl RateLimiter {
+    pub fn new(store: Arc<dyn RateLimitStore>, policy: RateLimitPolicy) -> Self {
+        Self {
+            store,
+            policy,
+            enabled: true,
+        }
+    }
+
+    /// A disabled limiter lets every request through.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Counts a request against the caller's general bucket.
+    pub async fn check_api(
+        &self,
+        user: Option<&AuthUser>,
+        client_ip: Option<&str>,
+    ) -> Result<(), RateLimited> {
+        let Some(subject) = subject(user, client_ip) else {
+            return Ok(());
+        };
+        self.check("api", &format!("api:{}", subject), self.policy.api)
+            .await
+    }
+
+    /// Counts a request against the caller's bucket for `operation`, such as `signIn`.
+    pub async fn check_auth(
+        &self,
+        operation: &str,
+        user: Option<&AuthUser>,
+        client_ip: Option<&str>,
+    ) -> Result<(), RateLimited> {
+        let Some(subject) = subject(user, client_ip) else {
+            return Ok(());
+        };
+        self.check(
+            "auth",
+            &format!("auth:{}:{}", operation, subject),
+            self.policy.auth,
+        )
+        .await
+    }
+
+    async fn check(&self, bucket: &str, key: &str, limit: RateLimit) -> Result<(), RateLimited> {
+        if !self.enabled {
+            return Ok(());
+        }
+        match self.store.take(key, limit, Utc::now()).await {
+            Ok(RateLimitDecision::Allowed) => Ok(()),
+            Ok(RateLimitDecision::Limited { retry_after }) => {
+                RATE_LIMITED.inc(&[("bucket", bucket)]);
+                Err(RateLimited { retry_after })
+            }
+            // An unavailable store should not take the API down with it.
+            Err(err) => {
+                warn!(
+                    "Rate limit store failed, letting the request through: {}",
+                    err
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Signed in users are limited by id, guests by IP. Requests with neither are not limited.
+fn subject(user: Option<&AuthUser>, client_ip: Option<&str>) -> Option<String> {
+    match user {
+        Some(user) if user.0.role != UserRole::Guest && !user.0.id.is_empty() => {
+            Some(format!("user:{}", user.0.id))
+        }
+        _ => client_ip.map(|ip| format!("ip:{}", ip_subject(ip))),
+    }
+}
+
+/// An IPv6 address is counted by its /64 network, so a host cannot get a new
+/// bucket by moving to another address of its own.
+fn ip_subject(ip: &str) -> String {
+    let Ok(IpAddr::V6(ip_v6)) = ip.parse::<IpAddr>() else {
+        return ip.to_string();
+    };
+    if let Some(ip_v4) = ip_v6.to_ipv4_mapped() {
+        return ip_v4.to_string();
+    }
+    let [a, b, c, d, ..] = ip_v6.segments();
+    format!("{:x}:{:x}:{:x}:{:x}::/64", a, b, c, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    const LIMIT: RateLimit = RateLimit {
+        burst: 2,
+        per_minute: 60,
+    };
+
+    #[tokio::test]
+    async fn bucket_allows_burst_then_refills() {
+        let store = InMemoryRateLimitStore::new();
+        let now = Utc::now();
+
+        assert_eq!(
+            store.take("key", LIMIT, now).await.unwrap(),
+            RateLimitDecision::Allowed
+        );
+        assert_eq!(
+            store.take("key", LIMIT, now).await.unwrap(),
+            RateLimitDecision::Allowed
+        );
+        assert_eq!(
+            store.take("key", LIMIT, now).await.unwrap(),
+            RateLimitDecision::Limited {
+                retry_after: Duration::from_secs(1)
+            }
+        );
+        // Other keys have their own bucket.
+        assert_eq!(
+            store.take("other", LIMIT, now).await.unwrap(),
+            RateLimitDecision::Allowed
+        );
+
+        let later = now + TimeDelta::milliseconds(1500);
+        assert_eq!(
+            store.take("key", LIMIT, later).await.unwrap(),
+            RateLimitDecision::Allowed
+        );
+        assert!(matches!(
+            store.take("key", LIMIT, later).await.unwrap(),
+            RateLimitDecision::Limited { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn store_drops_least_recently_used_bucket() {
+        let store = InMemoryRateLimitStore::with_capacity(2);
+        let now = Utc::now();
+
+        for key in ["a", "a", "b"] {
+            store.take(key, LIMIT, now).await.unwrap();
+        }
+        assert!(matches!(
+            store.take("a", LIMIT, now).await.unwrap(),
+            RateLimitDecision::Limited { .. }
+        ));
+        // "b" is now the least recently used and makes room for "c".
+        store.take("c", LIMIT, now).await.unwrap();
+        assert_eq!(store.buckets.lock().unwrap().len(), 2);
+        assert!(!store.buckets.lock().unwrap().contains("b"));
+    }
+
+    #[test]
+    fn ipv6_addresses_share_their_network_bucket() {
+        assert_eq!(
+            subject(None, Some("2001:db8:1:2:aaaa::1")),
+            subject(None, Some("2001:db8:1:2:bbbb::2"))
+        );
+        assert_ne!(
+            subject(None, Some("2001:db8:1:2::1")),
+            subject(None, Some("2001:db8:1:3::1"))
+        );
+        assert_eq!(
+            subject(None, Some("::ffff:10.0.0.1")).as_deref(),
+            Some("ip:10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn bucket_never_holds_more_than_burst() {
+        let now = Utc::now();
+        let mut bucket = Bucket {
+            tokens: 0.0,
+            updated_at: now,
+        };
+        bucket.take(LIMIT, now + TimeDelta::hours(1));
+        assert_eq!(bucket.tokens, 1.0);
+    }
+
+    #[tokio::test]
+    async fn limiter_counts_users_by_id_and_guests_by_ip() {
+        let limiter = RateLimiter::new(
+            Arc::new(InMemoryRateLimitStore::new()),
+            RateLimitPolicy {
+                api: LIMIT,
+                auth: RateLimit {
+                    burst: 1,
+                    per_minute: 1,
+                },
+            },
+        );
+        let user = AuthUser::new_test_auth_user(UserRole::Regular);
+        let guest = AuthUser::guest();
+
+        for _ in 0..2 {
+            assert!(
+                limiter
+                    .check_api(Some(&user), Some("10.0.0.1"))
+                    .await
+                    .is_ok()
+            );
+        }
+        assert!(
+            limiter
+                .check_api(Some(&user), Some("10.0.0.2"))
+                .await
+                .is_err()
+        );
+        assert!(
+            limiter
+                .check_api(Some(&guest), Some("10.0.0.1"))
+                .await
+                .is_ok()
+        );
+
+        assert!(
+            limiter
+                .check_auth("signIn", None, Some("10.0.0.1"))
+                .await
+                .is_ok()
+        );
+        let limited = limiter
+            .check_auth("signIn", None, Some("10.0.0.1"))
+            .await
+            .unwrap_err();
+        assert_eq!(limited.retry_after_secs(), 60);
+        assert!(
+            limiter
+                .check_auth("signUp", None, Some("10.0.0.1"))
+                .await
+                .is_ok()
+        );
+        // Nothing to count by.
+        assert!(limiter.check_auth("signIn", None, None).await.is_ok());
+    }
+}
@@ -1,3 +1,8 @@
+pub mod api_token_authenticator;
+pub mod audit_trail;
 pub mod command;
+pub mod metrics;
+pub mod otp_issuer;
 pub mod query;
+pub mod session_issuer;
 pub mod user_service;
@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde_json::json;
+use shared::auth::{API_TOKEN_ID_CLAIM, AuthUser, jwt::Claims};
+
+use crate::domain::{
+    errors::UserDomainError,
+    result::UserDomainResult,
+    user_auth::{api_token::utils, errors::UserAuthError, otp_hasher::OtpHasher},
+};
+use crate::infra::repository::{
+    api_token_repository::ApiTokenRepository, user_repository::UserRepository,
+};
+
+/// Resolves an API token to the user it belongs to, restricted to the
+/// token's scopes. The role is read from the user on every request, so
+/// demotions apply to existing tokens.
+pub struct ApiTokenAuthenticator {
+    api_token_repo: Arc<ApiTokenRepository>,
+    user_repo: Arc<UserRepository>,
+    otp_hasher: Arc<OtpHasher>,
+}
+
+impl ApiTokenAuthenticator {
+    pub fn new(
+        api_token_repo: Arc<ApiTokenRepository>,
+        user_repo: Arc<UserRepository>,
+        otp_hasher: Arc<OtpHasher>,
+    ) -> Self {
+        Self {
+            api_token_repo,
+            user_repo,
+            otp_hasher,
+        }
+    }
+
+    pub async fn authenticate(&self, token: &str) -> UserDomainResult<AuthUser> {
+        let (id, secret) = utils::parse_token(token).ok_or(UserAuthError::InvalidToken)?;
+        let now = Utc::now();
+        let api_token = self
+            .api_token_repo
+            .get_api_token(id)
+            .await?
+            .filter(|api_token| api_token.matches_secret(secret, &self.otp_hasher))
+            .filter(|api_token| !api_token.is_expired(now))
+            .ok_or(UserAuthError::InvalidToken)?;
+        let user = self
+            .user_repo
+            .get_user_by_id(api_token.user_id())
+            .await?
+            .ok_or(UserAuthError::InvalidToken)?;
+        if user.ban_status().is_some_and(|ban| ban.is_banned()) {
+            return Err(UserDomainError::Unauthorized);
+        }
+        if api_token.needs_last_used_update(now) {
+            self.api_token_repo
+                .touch_api_token(api_token.id(), now)
+                .await?;
+        }
+
+        let mut claims = Claims::new(
+            user.email().to_string(),
+            user.role().to_owned(),
+            user.id().to_string(),
+        );
+        claims.exp = api_token.expires_at().timestamp() as usize;
+        claims.iat = now.timestamp() as usize;
+        claims.scopes = api_token.scope_names();
+        claims
+            .custom
+            .insert(API_TOKEN_ID_CLAIM.to_string(), json!(api_token.id()));
+        Ok(AuthUser::new(claims))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::User;
+    use crate::domain::user_auth::api_token::ApiToken;
+    use crate::infra::repository::{
+        api_token_repository_trait::MockApiTokenRepositoryTrait,
+        user_repository_trait::MockUserRepositoryTrait,
+    };
+    use chrono::Duration;
+    use shared::guards::{permissions::Permission, roles::UserRole};
+
+    #[tokio::test]
+    async fn authenticate_returns_scoped_user() {
+        let (api_token, token) = ApiToken::issue(
+            User::test_user_id(),
+            "ci bot".into(),
+            vec![Permission::ViewUser],
+            Duration::days(30),
+            &OtpHasher::new_test_hasher(),
+            Utc::now(),
+        )
+        .unwrap();
+        let token_id = api_token.id().clone();
+        let mut mock_repo = MockApiTokenRepositoryTrait::new();
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+
+        mock_repo
+            .expect_get_api_token()
+            .returning(move |_| Ok(Some(api_token.clone())));
+        mock_repo
+            .expect_touch_api_token()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock_user_repo
+            .expect_get_user_by_id()
+            .returning(|_| Ok(Some(User::new_test_user(Some(UserRole::Moderator)))));
+
+        let authenticator = ApiTokenAuthenticator::new(
+            Arc::new(ApiTokenRepository::Mock(mock_repo)),
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+        );
+        let auth_user = authenticator.authenticate(&token).await.unwrap();
+        assert_eq!(auth_user.0.id, User::test_user_id());
+        assert_eq!(auth_user.0.role, UserRole::Moderator);
+        assert_eq!(auth_user.0.scopes, vec!["view_user".to_string()]);
+        assert_eq!(auth_user.api_token_id(), Some(token_id.as_str()));
+        assert!(auth_user.has_scope_for(&Permission::ViewUser));
+        assert!(!auth_user.has_scope_for(&Permission::BanUser));
+    }
+
+    #[tokio::test]
+    async fn authenticate_rejects_wrong_secret() {
+        let (api_token, token) = ApiToken::issue(
+            User::test_user_id(),
+            "ci bot".into(),
+            vec![Permission::ViewUser],
+            Duration::days(30),
+            &OtpHasher::new_test_hasher(),
+            Utc::now(),
+        )
+        .unwrap();
+        let mut mock_repo = MockApiTokenRepositoryTrait::new();
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+
+        mock_repo
+            .expect_get_api_token()
+            .returning(move |_| Ok(Some(api_token.clone())));
+        mock_user_repo.expect_get_user_by_id().never();
+
+        let authenticator = ApiTokenAuthenticator::new(
+            Arc::new(ApiTokenRepository::Mock(mock_repo)),
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+        );
+        let (id, _) = utils::parse_token(&token).unwrap();
+        let forged = utils::format_token(id, "forged-secret");
+        let result = authenticator.authenticate(&forged).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(UserAuthError::InvalidToken))
+        ));
+    }
+
+    #[tokio::test]
+    async fn authenticate_rejects_expired_token() {
+        let (api_token, token) = ApiToken::issue(
+            User::test_user_id(),
+            "ci bot".into(),
+            vec![Permission::ViewUser],
+            Duration::days(30),
+            &OtpHasher::new_test_hasher(),
+            Utc::now() - Duration::days(31),
+        )
+        .unwrap();
+        let mut mock_repo = MockApiTokenRepositoryTrait::new();
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+
+        mock_repo
+            .expect_get_api_token()
+            .returning(move |_| Ok(Some(api_token.clone())));
+        mock_user_repo.expect_get_user_by_id().never();
+
+        let authenticator = ApiTokenAuthenticator::new(
+            Arc::new(ApiTokenRepository::Mock(mock_repo)),
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+        );
+        let result = authenticator.authenticate(&token).await;
+        assert!(result.is_err());
+    }
+}
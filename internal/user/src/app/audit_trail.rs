@@ -0,0 +1,82 @@
+use std::sync::{Arc, Mutex};
+
+use shared::auth::AppContext;
+
+use crate::domain::{
+    audit_log::{AuditAction, AuditChange, AuditEntry, AuditMetadata, UserSnapshot},
+    errors::UserDomainError,
+    result::UserDomainResult,
+    user::User,
+};
+use crate::infra::repository::audit_log_repository::AuditLogRepository;
+
+/// Writes the audit log entries of privileged commands.
+pub struct AuditTrail {
+    audit_log_repo: Arc<AuditLogRepository>,
+}
+
+impl AuditTrail {
+    pub fn new(audit_log_repo: Arc<AuditLogRepository>) -> Self {
+        Self { audit_log_repo }
+    }
+
+    /// Records `action` on `target_id` by the real actor of the request.
+    pub async fn record(
+        &self,
+        ctx: &AppContext,
+        action: AuditAction,
+        target_id: &str,
+        changes: Vec<AuditChange>,
+    ) -> UserDomainResult<()> {
+        let actor_id = ctx.actor_id().ok_or(UserDomainError::Unauthorized)?;
+        let entry = AuditEntry::new(
+            actor_id.to_string(),
+            action,
+            target_id.to_string(),
+            changes,
+            AuditMetadata::from_ctx(ctx),
+        );
+        self.audit_log_repo.append(entry).await
+    }
+
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        use crate::infra::memoryimpl::audit_log_repository::InMemoryAuditLogRepository;
+        Self::new(Arc::new(AuditLogRepository::InMemory(
+            InMemoryAuditLogRepository::new(),
+        )))
+    }
+
+    #[cfg(test)]
+    pub async fn recorded(&self) -> Vec<AuditEntry> {
+        use crate::domain::audit_log::{AuditLogFilter, MAX_AUDIT_LOG_PAGE_SIZE};
+        self.audit_log_repo
+            .list(&AuditLogFilter::new(MAX_AUDIT_LOG_PAGE_SIZE))
+            .await
+            .unwrap()
+            .entries
+    }
+}
+
+/// Collects what an update closure changed on a user. The repositories only
+/// lend the user to the closure, so the diff is taken inside it.
+#[derive(Clone, Default)]
+pub struct UserChanges(Arc<Mutex<Vec<AuditChange>>>);
+
+impl UserChanges {
+    pub fn track(&self, user: &mut User, update: impl FnOnce(&mut User)) {
+        let before = UserSnapshot::of(user);
+        update(user);
+        let changes = before.diff(&UserSnapshot::of(user));
+        if let Ok(mut recorded) = self.0.lock() {
+            *recorded = changes;
+        }
+    }
+
+    pub fn take(&self) -> Vec<AuditChange> {
+        self.0
+            .lock()
+            .map(|mut recorded| std::mem::take(&mut *recorded))
+            .unwrap_or_default()
+    }
+}
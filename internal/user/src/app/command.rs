@@ -1,10 +1,21 @@
 pub mod award_badge;
 pub mod ban_user;
 pub mod change_username;
+pub mod complete_oidc_sign_in;
+pub mod confirm_totp;
+pub mod create_api_token;
+pub mod delete_api_token;
+pub mod disable_totp;
+pub mod enroll_totp;
+pub mod impersonate;
 pub mod make_moderator;
+pub mod resend_otp;
 pub mod revoke_badge;
 pub mod sign_in;
 pub mod sign_up;
+pub mod start_oidc_sign_in;
 pub mod unban_user;
+pub mod update_api_token;
 pub mod verify_email_with_otp;
 pub mod verify_otp;
+pub mod verify_two_factor;
@@ -5,10 +5,15 @@ use async_trait::async_trait;
 use shared::{
     auth::{AppContext, get_auth_user_from_ctx},
     command_handler::CommandHanlder,
+    event_hub::EventHub,
     guards::permissions::UserPermission,
 };
 
+use crate::app::audit_trail::{AuditTrail, UserChanges};
+use crate::app::metrics;
+use crate::domain::audit_log::AuditAction;
 use crate::domain::errors::UserDomainError;
+use crate::domain::events::{AccountChangeKind, UserEvent};
 use crate::domain::result::UserDomainResult;
 use crate::guards::UserGuards;
 use crate::infra::repository::user_repository::UserRepository;
@@ -21,25 +26,52 @@ pub struct AwardBadge {
 pub struct AwardBadgeHandler {
     user_repo: Arc<UserRepository>,
     guard: Arc<dyn UserGuards>,
+    audit_trail: Arc<AuditTrail>,
+    events: Arc<EventHub<UserEvent>>,
 }
 
 impl AwardBadgeHandler {
-    pub fn new(user_repo: Arc<UserRepository>, guard: Arc<dyn UserGuards>) -> Self {
-        Self { user_repo, guard }
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        guard: Arc<dyn UserGuards>,
+        audit_trail: Arc<AuditTrail>,
+        events: Arc<EventHub<UserEvent>>,
+    ) -> Self {
+        Self {
+            user_repo,
+            guard,
+            audit_trail,
+            events,
+        }
     }
 }
 
 #[async_trait]
 impl CommandHanlder<AwardBadge, UserDomainError> for AwardBadgeHandler {
+    #[tracing::instrument(name = "command.award_badge", skip_all)]
     async fn handle(&self, ctx: &AppContext, cmd: AwardBadge) -> UserDomainResult<()> {
         let auth_user = get_auth_user_from_ctx(&ctx);
         self.guard
-            .authorize(&auth_user.0.role, &UserPermission::AwardBadge)?;
+            .authorize(auth_user, &UserPermission::AwardBadge)?;
+        let badge = cmd.badge.clone();
+        let changes = UserChanges::default();
+        let tracker = changes.clone();
         self.user_repo
-            .award_badge(&cmd.user_id, |user| {
-                user.award_badge(cmd.badge);
+            .award_badge(&cmd.user_id, move |user| {
+                tracker.track(user, |user| {
+                    user.award_badge(cmd.badge);
+                });
             })
             .await?;
+        self.audit_trail
+            .record(ctx, AuditAction::AwardBadge, &cmd.user_id, changes.take())
+            .await?;
+        self.events.publish(UserEvent::account_changed(
+            &cmd.user_id,
+            AccountChangeKind::BadgeAwarded,
+            Some(badge),
+        ));
+        metrics::BADGE_AWARDS.inc(&[]);
         Ok(())
     }
 }
@@ -50,6 +82,7 @@ mod tests {
     use crate::domain::user::User;
     use crate::guards::MockUserGuards;
     use crate::infra::repository::user_repository_trait::MockUserRepositoryTrait;
+    use futures::StreamExt;
     use mockall::predicate::eq;
     use shared::{
         auth::{AppContext, AuthUser},
@@ -59,13 +92,18 @@ mod tests {
 
     #[tokio::test]
     async fn award_badge_success() {
+        let audit_trail = Arc::new(AuditTrail::in_memory());
+        let events = Arc::new(EventHub::new(8));
         let mut mock_user_repo = MockUserRepositoryTrait::new();
         let mut mock_guard = MockUserGuards::new();
 
         let badge = "Helpful".to_string();
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Admin), eq(UserPermission::AwardBadge))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Admin)),
+                eq(UserPermission::AwardBadge),
+            )
             .returning(|_, _| Ok(()));
 
         mock_user_repo
@@ -86,6 +124,8 @@ mod tests {
         let handler = AwardBadgeHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
             Arc::new(mock_guard),
+            audit_trail.clone(),
+            events.clone(),
         );
         let cmd = AwardBadge {
             user_id: User::test_user_id(),
@@ -94,18 +134,37 @@ mod tests {
 
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Admin));
 
+        let mut published = events.subscribe();
         let result = handler.handle(&ctx, cmd).await;
-        assert!(result.is_ok())
+        assert!(result.is_ok());
+        let entries = audit_trail.recorded().await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::AwardBadge);
+        assert_eq!(entries[0].actor_id, "test-user-id");
+        assert_eq!(entries[0].target_id, User::test_user_id());
+        assert_eq!(entries[0].changes[0].field, "badges");
+        match published.next().await {
+            Some(UserEvent::AccountChanged(change)) => {
+                assert_eq!(change.user_id, User::test_user_id());
+                assert_eq!(change.kind, AccountChangeKind::BadgeAwarded);
+            }
+            other => panic!("expected an account change, got {:?}", other),
+        }
     }
 
     #[tokio::test]
     async fn award_badge_unauthorized() {
+        let audit_trail = Arc::new(AuditTrail::in_memory());
+        let events = Arc::new(EventHub::new(8));
         let mut mock_user_repo = MockUserRepositoryTrait::new();
         let mut mock_guard = MockUserGuards::new();
         let badge = "Helpful".to_string();
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Regular), eq(UserPermission::AwardBadge))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::AwardBadge),
+            )
             .returning(|_, _| Err(UserDomainError::Unauthorized));
 
         mock_user_repo.expect_award_badge().never();
@@ -113,6 +172,8 @@ mod tests {
         let handler = AwardBadgeHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
             Arc::new(mock_guard),
+            audit_trail.clone(),
+            events.clone(),
         );
         let cmd = AwardBadge {
             user_id: User::test_user_id(),
@@ -121,5 +182,6 @@ mod tests {
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
         let result = handler.handle(&ctx, cmd).await;
         assert!(result.is_err());
+        assert!(audit_trail.recorded().await.is_empty());
     }
 }
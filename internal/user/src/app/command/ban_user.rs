@@ -5,12 +5,17 @@ use async_trait::async_trait;
 use shared::{
     auth::{AppContext, get_auth_user_from_ctx},
     command_handler::CommandHanlder,
+    event_hub::EventHub,
     guards::permissions::UserPermission,
 };
 
 use crate::domain::{errors::UserDomainError, result::UserDomainResult, user::BanType};
 use crate::infra::repository::user_repository::UserRepository;
 
+use crate::app::audit_trail::{AuditTrail, UserChanges};
+use crate::app::metrics;
+use crate::domain::audit_log::AuditAction;
+use crate::domain::events::{AccountChangeKind, ModerationQueueUpdateKind, UserEvent};
 use crate::guards::UserGuards;
 
 pub struct BanUser {
@@ -22,25 +27,56 @@ pub struct BanUser {
 pub struct BanUserHandler {
     user_repo: Arc<UserRepository>,
     guard: Arc<dyn UserGuards>,
+    audit_trail: Arc<AuditTrail>,
+    events: Arc<EventHub<UserEvent>>,
 }
 
 impl BanUserHandler {
-    pub fn new(user_repo: Arc<UserRepository>, guard: Arc<dyn UserGuards>) -> Self {
-        Self { user_repo, guard }
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        guard: Arc<dyn UserGuards>,
+        audit_trail: Arc<AuditTrail>,
+        events: Arc<EventHub<UserEvent>>,
+    ) -> Self {
+        Self {
+            user_repo,
+            guard,
+            audit_trail,
+            events,
+        }
     }
 }
 
 #[async_trait]
 impl CommandHanlder<BanUser, UserDomainError> for BanUserHandler {
+    #[tracing::instrument(name = "command.ban_user", skip_all)]
     async fn handle(&self, ctx: &AppContext, cmd: BanUser) -> UserDomainResult<()> {
         let auth_user = get_auth_user_from_ctx(&ctx);
-        self.guard
-            .authorize(&auth_user.0.role, &UserPermission::BanUser)?;
+        self.guard.authorize(auth_user, &UserPermission::BanUser)?;
+        let reason = cmd.reason.clone();
+        let changes = UserChanges::default();
+        let tracker = changes.clone();
         self.user_repo
-            .ban_user(&cmd.user_id, |user| {
-                user.ban(cmd.reason, cmd.ban_type);
+            .ban_user(&cmd.user_id, move |user| {
+                tracker.track(user, |user| {
+                    user.ban(cmd.reason, cmd.ban_type);
+                });
             })
             .await?;
+        self.audit_trail
+            .record(ctx, AuditAction::BanUser, &cmd.user_id, changes.take())
+            .await?;
+        self.events.publish(UserEvent::account_changed(
+            &cmd.user_id,
+            AccountChangeKind::Banned,
+            Some(reason),
+        ));
+        self.events.publish(UserEvent::moderation_queue_updated(
+            &cmd.user_id,
+            ModerationQueueUpdateKind::UserBanned,
+            &auth_user.0.id,
+        ));
+        metrics::BANS.inc(&[]);
         Ok(())
     }
 }
@@ -51,6 +87,7 @@ mod tests {
     use crate::domain::user::User;
     use crate::guards::MockUserGuards;
     use crate::infra::repository::user_repository_trait::MockUserRepositoryTrait;
+    use futures::StreamExt;
     use mockall::predicate::eq;
     use shared::{
         auth::{AppContext, AuthUser},
@@ -60,12 +97,17 @@ mod tests {
 
     #[tokio::test]
     async fn ban_user_success() {
+        let audit_trail = Arc::new(AuditTrail::in_memory());
+        let events = Arc::new(EventHub::new(8));
         let mut mock_user_repo = MockUserRepositoryTrait::new();
         let mut mock_guard = MockUserGuards::new();
 
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Admin), eq(UserPermission::BanUser))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Admin)),
+                eq(UserPermission::BanUser),
+            )
             .returning(|_, _| Ok(()));
 
         mock_user_repo
@@ -81,6 +123,8 @@ mod tests {
         let handler = BanUserHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
             Arc::new(mock_guard),
+            audit_trail.clone(),
+            events.clone(),
         );
         let cmd = BanUser {
             user_id: User::test_user_id(),
@@ -90,18 +134,37 @@ mod tests {
 
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Admin));
 
+        let mut published = events.subscribe();
         let result = handler.handle(&ctx, cmd).await;
-        assert!(result.is_ok())
+        assert!(result.is_ok());
+        let entries = audit_trail.recorded().await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::BanUser);
+        assert_eq!(entries[0].actor_id, "test-user-id");
+        assert_eq!(entries[0].target_id, User::test_user_id());
+        assert_eq!(entries[0].changes[0].field, "ban");
+        match published.next().await {
+            Some(UserEvent::AccountChanged(change)) => {
+                assert_eq!(change.user_id, User::test_user_id());
+                assert_eq!(change.kind, AccountChangeKind::Banned);
+            }
+            other => panic!("expected an account change, got {:?}", other),
+        }
     }
 
     #[tokio::test]
     async fn ban_user_unauthorized() {
+        let audit_trail = Arc::new(AuditTrail::in_memory());
+        let events = Arc::new(EventHub::new(8));
         let mut mock_user_repo = MockUserRepositoryTrait::new();
         let mut mock_guard = MockUserGuards::new();
 
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Regular), eq(UserPermission::BanUser))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::BanUser),
+            )
             .returning(|_, _| Err(UserDomainError::Unauthorized));
 
         mock_user_repo.expect_ban_user().never();
@@ -109,6 +172,8 @@ mod tests {
         let handler = BanUserHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
             Arc::new(mock_guard),
+            audit_trail.clone(),
+            events.clone(),
         );
         let cmd = BanUser {
             user_id: User::test_user_id(),
@@ -118,5 +183,6 @@ mod tests {
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
         let result = handler.handle(&ctx, cmd).await;
         assert!(result.is_err());
+        assert!(audit_trail.recorded().await.is_empty());
     }
 }
@@ -1,13 +1,22 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::Utc;
 
 use shared::{
     auth::{AppContext, get_auth_user_from_ctx},
     command_handler::CommandHanlder,
+    guards::roles::UserRole,
 };
 
-use crate::domain::{errors::UserDomainError, result::UserDomainResult, user::EmailStatus};
+use crate::app::audit_trail::{AuditTrail, UserChanges};
+use crate::domain::{
+    audit_log::AuditAction,
+    canonical::canonical_username,
+    errors::UserDomainError,
+    result::UserDomainResult,
+    user::{EmailStatus, UsernamePolicy},
+};
 use crate::guards::UserGuards;
 use crate::infra::repository::user_repository::UserRepository;
 
@@ -20,34 +29,100 @@ pub struct ChangeUsername {
 pub struct ChangeUsernameHandler {
     user_repo: Arc<UserRepository>,
     guard: Arc<dyn UserGuards>,
+    policy: UsernamePolicy,
+    audit_trail: Arc<AuditTrail>,
 }
 
 impl ChangeUsernameHandler {
-    pub fn new(user_repo: Arc<UserRepository>, guard: Arc<dyn UserGuards>) -> Self {
-        Self { user_repo, guard }
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        guard: Arc<dyn UserGuards>,
+        policy: UsernamePolicy,
+        audit_trail: Arc<AuditTrail>,
+    ) -> Self {
+        Self {
+            user_repo,
+            guard,
+            policy,
+            audit_trail,
+        }
     }
 }
 
 #[async_trait]
 impl CommandHanlder<ChangeUsername, UserDomainError> for ChangeUsernameHandler {
+    #[tracing::instrument(name = "command.change_username", skip_all)]
     async fn handle(&self, ctx: &AppContext, cmd: ChangeUsername) -> UserDomainResult<()> {
         let auth_user = get_auth_user_from_ctx(&ctx);
 
         self.guard.can_change_username(&cmd.user_id, &auth_user)?;
 
+        let user = self
+            .user_repo
+            .get_user_by_id(&cmd.user_id)
+            .await?
+            .ok_or(UserDomainError::UserNotFound)?;
+
+        // Admins may rename users (e.g. offensive usernames) regardless of the cooldown.
+        if auth_user.0.role != UserRole::Admin
+            && let Some(allowed_at) =
+                user.username_change_blocked_until(self.policy.change_cooldown)
+        {
+            return Err(UserDomainError::UsernameChangeTooSoon(allowed_at));
+        }
+
+        // A user may change the casing of their own username without it counting as taken.
+        if canonical_username(&cmd.username) != user.canonical_username() {
+            self.ensure_username_available(&cmd).await?;
+        }
+
+        let changes = UserChanges::default();
+        let tracker = changes.clone();
+        self.user_repo
+            .change_username(&cmd.user_id, move |user| {
+                tracker.track(user, |user| {
+                    user.change_username(cmd.username);
+                });
+            })
+            .await?;
+        // Renames by admins are audited; users renaming themselves are not.
+        if ctx.actor_id() != Some(cmd.user_id.as_str()) {
+            self.audit_trail
+                .record(
+                    ctx,
+                    AuditAction::ChangeUsername,
+                    &cmd.user_id,
+                    changes.take(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+impl ChangeUsernameHandler {
+    async fn ensure_username_available(&self, cmd: &ChangeUsername) -> UserDomainResult<()> {
         let exists = self
             .user_repo
             .user_exists(&cmd.username, "", Some(EmailStatus::Verified))
             .await?;
 
         if exists {
-            return Err(UserDomainError::UsernameTaken.into());
+            return Err(UserDomainError::UsernameTaken);
         }
-        self.user_repo
-            .change_username(&cmd.user_id, |user| {
-                user.change_username(cmd.username);
-            })
+
+        let reserved = self
+            .user_repo
+            .username_reserved(
+                &cmd.username,
+                Utc::now() - self.policy.reservation_period,
+                &cmd.user_id,
+            )
             .await?;
+
+        if reserved {
+            return Err(UserDomainError::UsernameTaken);
+        }
         Ok(())
     }
 }
@@ -87,6 +162,11 @@ mod tests {
             .with(eq(cmd.user_id.clone()), eq(expected_auth_user))
             .returning(|_, _| Ok(()));
 
+        mock_user_repo
+            .expect_get_user_by_id()
+            .with(eq(cmd.user_id.clone()))
+            .returning(|_| Ok(Some(User::new_test_user(None))));
+
         mock_user_repo
             .expect_user_exists()
             .with(
@@ -96,6 +176,11 @@ mod tests {
             )
             .returning(|_, _, _| Ok(false));
 
+        mock_user_repo
+            .expect_username_reserved()
+            .withf(|username, _, user_id| username == "newUsername" && user_id == "test-user-id")
+            .returning(|_, _, _| Ok(false));
+
         mock_user_repo
             .expect_change_username()
             .withf(move |uuid, _| uuid == expected_auth_user_id.clone())
@@ -111,15 +196,68 @@ mod tests {
                 );
                 Ok(())
             });
+        let audit_trail = Arc::new(AuditTrail::in_memory());
         let handler = ChangeUsernameHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
             Arc::new(mock_guard),
+            UsernamePolicy::default(),
+            audit_trail.clone(),
         );
 
         let ctx = AppContext::new().with_user(auth_user);
 
         let result = handler.handle(&ctx, cmd).await;
-        assert!(result.is_ok())
+        assert!(result.is_ok());
+        assert!(audit_trail.recorded().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn change_username_by_admin_is_audited() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_can_change_username()
+            .returning(|_, _| Ok(()));
+        mock_user_repo
+            .expect_get_user_by_id()
+            .returning(|_| Ok(Some(User::new_test_user(None))));
+        mock_user_repo
+            .expect_user_exists()
+            .returning(|_, _, _| Ok(false));
+        mock_user_repo
+            .expect_username_reserved()
+            .returning(|_, _, _| Ok(false));
+        mock_user_repo
+            .expect_change_username()
+            .returning(|_, update_fn| {
+                update_fn(&mut User::new_test_user(None));
+                Ok(())
+            });
+
+        let audit_trail = Arc::new(AuditTrail::in_memory());
+        let handler = ChangeUsernameHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            UsernamePolicy::default(),
+            audit_trail.clone(),
+        );
+
+        let cmd = ChangeUsername {
+            user_id: "other-user-id".into(),
+            username: "cleanName".into(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Admin));
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(result.is_ok());
+
+        let entries = audit_trail.recorded().await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::ChangeUsername);
+        assert_eq!(entries[0].target_id, "other-user-id");
+        assert_eq!(entries[0].changes.len(), 1);
+        assert_eq!(entries[0].changes[0].field, "username");
+        assert_eq!(entries[0].changes[0].after.as_deref(), Some("cleanName"));
     }
 
     #[tokio::test]
@@ -142,6 +280,10 @@ mod tests {
             .with(eq(expected_user_id), eq(expected_auth_user))
             .returning(|_, _| Ok(()));
 
+        mock_user_repo
+            .expect_get_user_by_id()
+            .returning(|_| Ok(Some(User::new_test_user(None))));
+
         mock_user_repo
             .expect_user_exists()
             .with(
@@ -156,10 +298,124 @@ mod tests {
         let handler = ChangeUsernameHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
             Arc::new(mock_guard),
+            UsernamePolicy::default(),
+            Arc::new(AuditTrail::in_memory()),
         );
 
         let ctx = AppContext::new().with_user(auth_user);
         let result = handler.handle(&ctx, cmd).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn change_username_failed_within_cooldown() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        let auth_user = AuthUser::new_test_auth_user(UserRole::Regular);
+
+        mock_guard
+            .expect_can_change_username()
+            .returning(|_, _| Ok(()));
+
+        mock_user_repo.expect_get_user_by_id().returning(|_| {
+            let mut user = User::new_test_user(None);
+            user.change_username("recentlyChanged".into());
+            Ok(Some(user))
+        });
+        mock_user_repo.expect_user_exists().never();
+        mock_user_repo.expect_change_username().never();
+
+        let handler = ChangeUsernameHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            UsernamePolicy::default(),
+            Arc::new(AuditTrail::in_memory()),
+        );
+
+        let cmd = ChangeUsername {
+            user_id: auth_user.0.id.clone(),
+            username: "newUsername".into(),
+        };
+        let ctx = AppContext::new().with_user(auth_user);
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::UsernameChangeTooSoon(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn change_username_failed_username_reserved() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        let auth_user = AuthUser::new_test_auth_user(UserRole::Regular);
+
+        mock_guard
+            .expect_can_change_username()
+            .returning(|_, _| Ok(()));
+
+        mock_user_repo
+            .expect_get_user_by_id()
+            .returning(|_| Ok(Some(User::new_test_user(None))));
+        mock_user_repo
+            .expect_user_exists()
+            .returning(|_, _, _| Ok(false));
+        mock_user_repo
+            .expect_username_reserved()
+            .returning(|_, _, _| Ok(true));
+        mock_user_repo.expect_change_username().never();
+
+        let handler = ChangeUsernameHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            UsernamePolicy::default(),
+            Arc::new(AuditTrail::in_memory()),
+        );
+
+        let cmd = ChangeUsername {
+            user_id: auth_user.0.id.clone(),
+            username: "someonesOldName".into(),
+        };
+        let ctx = AppContext::new().with_user(auth_user);
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(result, Err(UserDomainError::UsernameTaken)));
+    }
+
+    #[tokio::test]
+    async fn change_username_casing_of_own_username() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        let auth_user = AuthUser::new_test_auth_user(UserRole::Regular);
+
+        mock_guard
+            .expect_can_change_username()
+            .returning(|_, _| Ok(()));
+
+        mock_user_repo
+            .expect_get_user_by_id()
+            .returning(|_| Ok(Some(User::new_test_user(None))));
+        mock_user_repo.expect_user_exists().never();
+        mock_user_repo.expect_username_reserved().never();
+        mock_user_repo
+            .expect_change_username()
+            .returning(|_, _| Ok(()));
+
+        let handler = ChangeUsernameHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            UsernamePolicy::default(),
+            Arc::new(AuditTrail::in_memory()),
+        );
+
+        let cmd = ChangeUsername {
+            user_id: auth_user.0.id.clone(),
+            username: "JohnDoe".into(),
+        };
+        let ctx = AppContext::new().with_user(auth_user);
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(result.is_ok());
+    }
 }
@@ -0,0 +1,555 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use chrono::Utc;
+use rand::Rng;
+use serde::Deserialize;
+use validator::Validate;
+
+use shared::{auth::AppContext, command_handler::CommandHanlder, guards::roles::UserRole};
+
+use crate::app::session_issuer::{SessionIssuer, SignInOutcome};
+use crate::domain::{
+    errors::UserDomainError,
+    result::UserDomainResult,
+    user::{EmailStatus, LinkedIdentity, User},
+    user_auth::{
+        errors::UserAuthError,
+        oidc::{OidcIdentity, OidcProviderConfig, find_provider},
+    },
+};
+use crate::infra::{
+    oidc::oidc_client::OidcClient,
+    repository::{
+        oidc_auth_request_repository::OidcAuthRequestRepository, user_repository::UserRepository,
+    },
+};
+
+const USERNAME_MIN_LEN: usize = 3;
+const USERNAME_MAX_LEN: usize = 30;
+const USERNAME_ATTEMPTS: usize = 5;
+
+/// Handles the provider's redirect back with `state` and `code`. Signs in the
+/// user the identity is linked to; otherwise links it to the account with the
+/// same verified email, or creates one.
+#[derive(Debug, Clone, Validate, Deserialize, InputObject)]
+pub struct CompleteOidcSignIn {
+    #[validate(length(min = 1))]
+    pub state: String,
+    #[validate(length(min = 1))]
+    pub code: String,
+}
+
+pub struct CompleteOidcSignInHandler {
+    user_repo: Arc<UserRepository>,
+    auth_request_repo: Arc<OidcAuthRequestRepository>,
+    oidc_client: Arc<OidcClient>,
+    providers: Vec<OidcProviderConfig>,
+    session_issuer: Arc<SessionIssuer>,
+}
+
+impl CompleteOidcSignInHandler {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        auth_request_repo: Arc<OidcAuthRequestRepository>,
+        oidc_client: Arc<OidcClient>,
+        providers: Vec<OidcProviderConfig>,
+        session_issuer: Arc<SessionIssuer>,
+    ) -> Self {
+        Self {
+            user_repo,
+            auth_request_repo,
+            oidc_client,
+            providers,
+            session_issuer,
+        }
+    }
+
+    async fn resolve_user(
+        &self,
+        identity: &OidcIdentity,
+        link_user_id: Option<&str>,
+    ) -> UserDomainResult<User> {
+        let linked_user = self
+            .user_repo
+            .get_user_by_identity(&identity.provider, &identity.subject)
+            .await?;
+
+        if let Some(user_id) = link_user_id {
+            if linked_user
+                .as_ref()
+                .is_some_and(|user| user.id() != user_id)
+            {
+                return Err(UserAuthError::OidcIdentityAlreadyLinked.into());
+            }
+            return self.link(user_id, identity, false).await;
+        }
+        if let Some(user) = linked_user {
+            return Ok(user);
+        }
+
+        let email = identity
+            .verified_email()
+            .ok_or(UserAuthError::OidcEmailNotVerified)?;
+        match self
+            .user_repo
+            .get_user_by_username_or_email("", email)
+            .await?
+        {
+            Some(user) => self.link(user.id(), identity, true).await,
+            None => self.create_user(identity, email).await,
+        }
+    }
+
+    /// Links `identity` to the user. `verify_email` marks the account's email
+    /// as verified, since the provider vouched for it.
+    async fn link(
+        &self,
+        user_id: &str,
+        identity: &OidcIdentity,
+        verify_email: bool,
+    ) -> UserDomainResult<User> {
+        let linked_identity = linked_identity(identity);
+        self.user_repo
+            .link_identity(user_id, move |user| {
+                user.link_identity(linked_identity);
+                if verify_email {
+                    user.set_email_status(EmailStatus::Verified);
+                }
+            })
+            .await?;
+        self.user_repo
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or(UserDomainError::UserNotFound)
+    }
+
+    async fn create_user(&self, identity: &OidcIdentity, email: &str) -> UserDomainResult<User> {
+        let base = utils::username_from(identity.preferred_username.as_deref(), email);
+        let mut username = base.clone();
+        let mut attempts = 0;
+        while self.user_repo.user_exists(&username, "", None).await? {
+            attempts += 1;
+            if attempts == USERNAME_ATTEMPTS {
+                return Err(UserDomainError::UsernameTaken);
+            }
+            username = utils::with_random_suffix(&base);
+        }
+
+        let mut user = User::new(email.to_string(), username, UserRole::Regular);
+        user.set_email_status(EmailStatus::Verified);
+        user.link_identity(linked_identity(identity));
+        self.user_repo.create_account(user.clone()).await?;
+        tracing::info!(
+            "Created user {} from {} identity",
+            user.email(),
+            identity.provider
+        );
+        Ok(user)
+    }
+}
+
+fn linked_identity(identity: &OidcIdentity) -> LinkedIdentity {
+    LinkedIdentity::new(
+        identity.provider.clone(),
+        identity.subject.clone(),
+        identity.email.clone(),
+        Utc::now(),
+    )
+}
+
+#[async_trait]
+impl CommandHanlder<CompleteOidcSignIn, UserDomainError, SignInOutcome>
+    for CompleteOidcSignInHandler
+{
+    #[tracing::instrument(name = "command.complete_oidc_sign_in", skip_all)]
+    async fn handle(
+        &self,
+        _ctx: &AppContext,
+        cmd: CompleteOidcSignIn,
+    ) -> UserDomainResult<SignInOutcome> {
+        cmd.validate()?;
+        let request = self
+            .auth_request_repo
+            .take_auth_request(&cmd.state)
+            .await?
+            .filter(|request| !request.is_expired(Utc::now()))
+            .ok_or(UserAuthError::InvalidOidcState)?;
+        let provider = find_provider(&self.providers, request.provider())?;
+
+        let identity = self
+            .oidc_client
+            .exchange_code(provider, &request, &cmd.code)
+            .await?;
+        let user = self
+            .resolve_user(&identity, request.link_user_id().as_deref())
+            .await?;
+        tracing::info!(
+            "OIDC sign-in with {} for user: {}",
+            provider.name,
+            user.email()
+        );
+        self.session_issuer.start_session(&user).await
+    }
+}
+
+mod utils {
+    use super::*;
+
+    /// A username from the provider's preferred username or the email's local
+    /// part, keeping only ASCII letters, digits, `_` and `.`.
+    pub fn username_from(preferred_username: Option<&str>, email: &str) -> String {
+        let source = preferred_username
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| email.split('@').next().unwrap_or_default());
+        let mut username: String = source
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '.')
+            .take(USERNAME_MAX_LEN)
+            .collect();
+        if username.len() < USERNAME_MIN_LEN {
+            username = format!("user{}", username);
+        }
+        username
+    }
+
+    pub fn with_random_suffix(base: &str) -> String {
+        let suffix: u32 = rand::rng().random_range(1000..10000);
+        let base: String = base.chars().take(USERNAME_MAX_LEN - 5).collect();
+        format!("{}_{}", base, suffix)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn username_from_sanitizes() {
+            assert_eq!(username_from(Some("John Doe!"), "x@y.com"), "JohnDoe");
+            assert_eq!(username_from(None, "jo@example.com"), "userjo");
+            assert_eq!(with_random_suffix("john").len(), "john".len() + 5);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user_auth::{oidc::OidcAuthRequest, two_factor::TwoFactorPolicy};
+    use crate::infra::oidc::oidc_client_trait::MockOidcClientTrait;
+    use crate::infra::repository::{
+        oidc_auth_request_repository_trait::MockOidcAuthRequestRepositoryTrait,
+        user_repository_trait::MockUserRepositoryTrait,
+    };
+    use shared::auth::{AuthUser, token_service::FakeTokenService};
+
+    #[tokio::test]
+    async fn complete_oidc_sign_in_with_linked_identity() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        mock_user_repo
+            .expect_get_user_by_identity()
+            .returning(|_, _| Ok(Some(User::new_test_user(None))));
+        mock_user_repo.expect_link_identity().never();
+        mock_user_repo.expect_create_account().never();
+
+        let mut mock_auth_request_repo = MockOidcAuthRequestRepositoryTrait::new();
+        mock_auth_request_repo
+            .expect_take_auth_request()
+            .returning(|_| Ok(Some(OidcAuthRequest::new("mock".into(), None, Utc::now()))));
+        let identity = OidcIdentity {
+            provider: "mock".into(),
+            subject: "subject-1".into(),
+            email: Some("johndoe@gmail.com".into()),
+            email_verified: true,
+            preferred_username: Some("johndoe".into()),
+        };
+        let mut mock_client = MockOidcClientTrait::new();
+        mock_client
+            .expect_exchange_code()
+            .returning(move |_, _, _| Ok(identity.clone()));
+
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
+        let handler = CompleteOidcSignInHandler::new(
+            user_repo.clone(),
+            Arc::new(OidcAuthRequestRepository::Mock(mock_auth_request_repo)),
+            Arc::new(OidcClient::Mock(mock_client)),
+            vec![OidcProviderConfig::new_test_provider()],
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
+        );
+        let cmd = CompleteOidcSignIn {
+            state: "state".into(),
+            code: "code".into(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(result, Ok(SignInOutcome::Token { .. })));
+    }
+
+    #[tokio::test]
+    async fn complete_oidc_sign_in_links_by_verified_email() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        mock_user_repo
+            .expect_get_user_by_identity()
+            .returning(|_, _| Ok(None));
+        mock_user_repo
+            .expect_get_user_by_username_or_email()
+            .returning(|_, _| Ok(Some(User::new_test_user(None))));
+        mock_user_repo
+            .expect_link_identity()
+            .withf(|user_id, _| user_id == User::test_user_id())
+            .times(1)
+            .returning(|_, update_fn| {
+                let mut user = User::new_test_user(None);
+                update_fn(&mut user);
+                assert!(user.has_identity("mock", "subject-1"));
+                assert_eq!(&EmailStatus::Verified, user.email_status());
+                Ok(())
+            });
+        mock_user_repo
+            .expect_get_user_by_id()
+            .returning(|_| Ok(Some(User::new_test_user(None))));
+        mock_user_repo.expect_create_account().never();
+
+        let mut mock_auth_request_repo = MockOidcAuthRequestRepositoryTrait::new();
+        mock_auth_request_repo
+            .expect_take_auth_request()
+            .returning(|_| Ok(Some(OidcAuthRequest::new("mock".into(), None, Utc::now()))));
+        let identity = OidcIdentity {
+            provider: "mock".into(),
+            subject: "subject-1".into(),
+            email: Some("johndoe@gmail.com".into()),
+            email_verified: true,
+            preferred_username: Some("johndoe".into()),
+        };
+        let mut mock_client = MockOidcClientTrait::new();
+        mock_client
+            .expect_exchange_code()
+            .returning(move |_, _, _| Ok(identity.clone()));
+
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
+        let handler = CompleteOidcSignInHandler::new(
+            user_repo.clone(),
+            Arc::new(OidcAuthRequestRepository::Mock(mock_auth_request_repo)),
+            Arc::new(OidcClient::Mock(mock_client)),
+            vec![OidcProviderConfig::new_test_provider()],
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
+        );
+        let cmd = CompleteOidcSignIn {
+            state: "state".into(),
+            code: "code".into(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn complete_oidc_sign_in_creates_user() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        mock_user_repo
+            .expect_get_user_by_identity()
+            .returning(|_, _| Ok(None));
+        mock_user_repo
+            .expect_get_user_by_username_or_email()
+            .returning(|_, _| Ok(None));
+        mock_user_repo
+            .expect_user_exists()
+            .returning(|username, _, _| Ok(username == "johndoe"));
+        mock_user_repo
+            .expect_create_account()
+            .withf(|user| {
+                user.username().starts_with("johndoe_")
+                    && user.email_status() == &EmailStatus::Verified
+                    && user.has_identity("mock", "subject-1")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut mock_auth_request_repo = MockOidcAuthRequestRepositoryTrait::new();
+        mock_auth_request_repo
+            .expect_take_auth_request()
+            .returning(|_| Ok(Some(OidcAuthRequest::new("mock".into(), None, Utc::now()))));
+        let identity = OidcIdentity {
+            provider: "mock".into(),
+            subject: "subject-1".into(),
+            email: Some("johndoe@gmail.com".into()),
+            email_verified: true,
+            preferred_username: Some("johndoe".into()),
+        };
+        let mut mock_client = MockOidcClientTrait::new();
+        mock_client
+            .expect_exchange_code()
+            .returning(move |_, _, _| Ok(identity.clone()));
+
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
+        let handler = CompleteOidcSignInHandler::new(
+            user_repo.clone(),
+            Arc::new(OidcAuthRequestRepository::Mock(mock_auth_request_repo)),
+            Arc::new(OidcClient::Mock(mock_client)),
+            vec![OidcProviderConfig::new_test_provider()],
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
+        );
+        let cmd = CompleteOidcSignIn {
+            state: "state".into(),
+            code: "code".into(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn complete_oidc_sign_in_unverified_email() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        mock_user_repo
+            .expect_get_user_by_identity()
+            .returning(|_, _| Ok(None));
+        mock_user_repo
+            .expect_get_user_by_username_or_email()
+            .never();
+        mock_user_repo.expect_create_account().never();
+
+        let mut mock_auth_request_repo = MockOidcAuthRequestRepositoryTrait::new();
+        mock_auth_request_repo
+            .expect_take_auth_request()
+            .returning(|_| Ok(Some(OidcAuthRequest::new("mock".into(), None, Utc::now()))));
+        let identity = OidcIdentity {
+            provider: "mock".into(),
+            subject: "subject-1".into(),
+            email: Some("johndoe@gmail.com".into()),
+            email_verified: false,
+            preferred_username: Some("johndoe".into()),
+        };
+        let mut mock_client = MockOidcClientTrait::new();
+        mock_client
+            .expect_exchange_code()
+            .returning(move |_, _, _| Ok(identity.clone()));
+
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
+        let handler = CompleteOidcSignInHandler::new(
+            user_repo.clone(),
+            Arc::new(OidcAuthRequestRepository::Mock(mock_auth_request_repo)),
+            Arc::new(OidcClient::Mock(mock_client)),
+            vec![OidcProviderConfig::new_test_provider()],
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
+        );
+        let cmd = CompleteOidcSignIn {
+            state: "state".into(),
+            code: "code".into(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::OidcEmailNotVerified
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn complete_oidc_sign_in_identity_linked_to_other_user() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        mock_user_repo
+            .expect_get_user_by_identity()
+            .returning(|_, _| Ok(Some(User::new_test_user(None))));
+        mock_user_repo.expect_link_identity().never();
+
+        let mut mock_auth_request_repo = MockOidcAuthRequestRepositoryTrait::new();
+        mock_auth_request_repo
+            .expect_take_auth_request()
+            .returning(|_| {
+                Ok(Some(OidcAuthRequest::new(
+                    "mock".into(),
+                    Some("other-user".into()),
+                    Utc::now(),
+                )))
+            });
+        let identity = OidcIdentity {
+            provider: "mock".into(),
+            subject: "subject-1".into(),
+            email: Some("johndoe@gmail.com".into()),
+            email_verified: true,
+            preferred_username: Some("johndoe".into()),
+        };
+        let mut mock_client = MockOidcClientTrait::new();
+        mock_client
+            .expect_exchange_code()
+            .returning(move |_, _, _| Ok(identity.clone()));
+
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
+        let handler = CompleteOidcSignInHandler::new(
+            user_repo.clone(),
+            Arc::new(OidcAuthRequestRepository::Mock(mock_auth_request_repo)),
+            Arc::new(OidcClient::Mock(mock_client)),
+            vec![OidcProviderConfig::new_test_provider()],
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
+        );
+        let cmd = CompleteOidcSignIn {
+            state: "state".into(),
+            code: "code".into(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::OidcIdentityAlreadyLinked
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn complete_oidc_sign_in_unknown_state() {
+        let mut mock_auth_request_repo = MockOidcAuthRequestRepositoryTrait::new();
+        mock_auth_request_repo
+            .expect_take_auth_request()
+            .returning(|_| Ok(None));
+        let user_repo = Arc::new(UserRepository::Mock(MockUserRepositoryTrait::new()));
+        let handler = CompleteOidcSignInHandler::new(
+            user_repo.clone(),
+            Arc::new(OidcAuthRequestRepository::Mock(mock_auth_request_repo)),
+            Arc::new(OidcClient::Mock(MockOidcClientTrait::new())),
+            vec![OidcProviderConfig::new_test_provider()],
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
+        );
+
+        let cmd = CompleteOidcSignIn {
+            state: "state".into(),
+            code: "code".into(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::InvalidOidcState
+            ))
+        ));
+    }
+}
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use validator::Validate;
+
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    command_handler::CommandHanlder,
+    guards::permissions::UserPermission,
+};
+
+use crate::domain::{
+    errors::UserDomainError,
+    result::UserDomainResult,
+    user_auth::{errors::UserAuthError, otp_hasher::OtpHasher, two_factor::utils},
+};
+use crate::guards::UserGuards;
+use crate::infra::repository::user_repository::UserRepository;
+
+/// Enables the pending TOTP enrollment once the user proves their authenticator
+/// works. Returns the plain recovery codes, which are not shown again.
+#[derive(Debug, Clone, Validate, Deserialize, InputObject)]
+pub struct ConfirmTotp {
+    #[validate(length(equal = 6))]
+    pub code: String,
+}
+
+pub struct ConfirmTotpHandler {
+    user_repo: Arc<UserRepository>,
+    guard: Arc<dyn UserGuards>,
+    otp_hasher: Arc<OtpHasher>,
+}
+
+impl ConfirmTotpHandler {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        guard: Arc<dyn UserGuards>,
+        otp_hasher: Arc<OtpHasher>,
+    ) -> Self {
+        Self {
+            user_repo,
+            guard,
+            otp_hasher,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<ConfirmTotp, UserDomainError, Vec<String>> for ConfirmTotpHandler {
+    #[tracing::instrument(name = "command.confirm_totp", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: ConfirmTotp) -> UserDomainResult<Vec<String>> {
+        cmd.validate()?;
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &UserPermission::ManageTwoFactor)?;
+
+        let user = self
+            .user_repo
+            .get_user_by_id(&auth_user.0.id)
+            .await?
+            .ok_or(UserDomainError::UserNotFound)?;
+        let mut two_factor = user
+            .two_factor()
+            .cloned()
+            .ok_or(UserAuthError::TwoFactorNotEnrolled)?;
+        if two_factor.is_enabled() {
+            return Err(UserAuthError::TwoFactorAlreadyEnabled.into());
+        }
+
+        let now = Utc::now();
+        if !two_factor.verify_totp(&cmd.code, now) {
+            return Err(UserAuthError::InvalidTwoFactorCode.into());
+        }
+        let recovery_codes = utils::generate_recovery_codes();
+        two_factor.enable(
+            utils::hash_recovery_codes(&recovery_codes, &self.otp_hasher),
+            now,
+        );
+        self.user_repo
+            .update_two_factor(user.id(), |user| {
+                user.set_two_factor(Some(two_factor));
+            })
+            .await?;
+        Ok(recovery_codes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::User;
+    use crate::domain::user_auth::two_factor::{RECOVERY_CODE_COUNT, TwoFactor};
+    use crate::guards::MockUserGuards;
+    use crate::infra::repository::user_repository_trait::MockUserRepositoryTrait;
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn confirm_totp_enables_two_factor() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+        let two_factor = TwoFactor::enroll();
+        let code = two_factor.generate_code(Utc::now());
+
+        mock_user_repo.expect_get_user_by_id().returning(move |_| {
+            Ok(Some(
+                User::new_test_user(None).with_two_factor(Some(two_factor.clone())),
+            ))
+        });
+        mock_user_repo
+            .expect_update_two_factor()
+            .returning(|_, update_fn| {
+                let mut user = User::new_test_user(None);
+                update_fn(&mut user);
+                let two_factor = user.two_factor().unwrap();
+                assert!(two_factor.is_enabled());
+                assert_eq!(RECOVERY_CODE_COUNT, two_factor.recovery_codes().len());
+                Ok(())
+            });
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+
+        let handler = ConfirmTotpHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            Arc::new(OtpHasher::new_test_hasher()),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let codes = handler.handle(&ctx, ConfirmTotp { code }).await.unwrap();
+        assert_eq!(RECOVERY_CODE_COUNT, codes.len());
+    }
+
+    #[tokio::test]
+    async fn confirm_totp_wrong_code() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+        let two_factor = TwoFactor::enroll();
+        let code = two_factor.generate_code(Utc::now() - chrono::Duration::minutes(10));
+
+        mock_user_repo.expect_get_user_by_id().returning(move |_| {
+            Ok(Some(
+                User::new_test_user(None).with_two_factor(Some(two_factor.clone())),
+            ))
+        });
+        mock_user_repo.expect_update_two_factor().never();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+
+        let handler = ConfirmTotpHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            Arc::new(OtpHasher::new_test_hasher()),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let result = handler.handle(&ctx, ConfirmTotp { code }).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::InvalidTwoFactorCode
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn confirm_totp_without_enrollment() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+        mock_user_repo
+            .expect_get_user_by_id()
+            .returning(|_| Ok(Some(User::new_test_user(None))));
+        mock_user_repo.expect_update_two_factor().never();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+
+        let handler = ConfirmTotpHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            Arc::new(OtpHasher::new_test_hasher()),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let result = handler
+            .handle(
+                &ctx,
+                ConfirmTotp {
+                    code: "123456".into(),
+                },
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::TwoFactorNotEnrolled
+            ))
+        ));
+    }
+}
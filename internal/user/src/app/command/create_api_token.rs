@@ -0,0 +1,200 @@
+use std::sync::Arc;
+
+use async_graphql::{InputObject, SimpleObject};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use validator::Validate;
+
+use shared::{
+    auth::{AppContext, AuthUser, get_auth_user_from_ctx},
+    command_handler::CommandHanlder,
+    guards::permissions::{Permission, UserPermission},
+};
+
+use crate::domain::{
+    errors::UserDomainError,
+    result::UserDomainResult,
+    user_auth::{api_token::ApiToken, otp_hasher::OtpHasher},
+};
+use crate::guards::UserGuards;
+use crate::infra::repository::api_token_repository::ApiTokenRepository;
+
+/// Creates a token for bots and scripts. Requests made with it act as the
+/// signed in user, limited to `scopes`.
+#[derive(Debug, Clone, Validate, Deserialize, InputObject)]
+pub struct CreateApiToken {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub scopes: Vec<Permission>,
+    #[validate(range(min = 1, max = 365))]
+    pub expires_in_days: i64,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct CreatedApiToken {
+    /// Shown only once.
+    pub token: String,
+    pub api_token: ApiToken,
+}
+
+pub struct CreateApiTokenHandler {
+    api_token_repo: Arc<ApiTokenRepository>,
+    guard: Arc<dyn UserGuards>,
+    otp_hasher: Arc<OtpHasher>,
+}
+
+impl CreateApiTokenHandler {
+    pub fn new(
+        api_token_repo: Arc<ApiTokenRepository>,
+        guard: Arc<dyn UserGuards>,
+        otp_hasher: Arc<OtpHasher>,
+    ) -> Self {
+        Self {
+            api_token_repo,
+            guard,
+            otp_hasher,
+        }
+    }
+}
+
+/// API tokens are managed from a session, so a leaked token cannot mint more.
+pub(crate) fn ensure_session_user(auth_user: &AuthUser) -> UserDomainResult<()> {
+    if auth_user.api_token_id().is_some() {
+        return Err(UserDomainError::Unauthorized);
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl CommandHanlder<CreateApiToken, UserDomainError, CreatedApiToken> for CreateApiTokenHandler {
+    #[tracing::instrument(name = "command.create_api_token", skip_all)]
+    async fn handle(
+        &self,
+        ctx: &AppContext,
+        cmd: CreateApiToken,
+    ) -> UserDomainResult<CreatedApiToken> {
+        cmd.validate()?;
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &UserPermission::ManageApiTokens)?;
+        ensure_session_user(auth_user)?;
+
+        let (api_token, token) = ApiToken::issue(
+            auth_user.0.id.clone(),
+            cmd.name,
+            cmd.scopes,
+            Duration::days(cmd.expires_in_days),
+            &self.otp_hasher,
+            Utc::now(),
+        )?;
+        self.api_token_repo
+            .create_api_token(api_token.clone())
+            .await?;
+        Ok(CreatedApiToken { token, api_token })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user_auth::api_token::utils;
+    use crate::guards::MockUserGuards;
+    use crate::infra::repository::api_token_repository_trait::MockApiTokenRepositoryTrait;
+    use mockall::predicate::eq;
+    use serde_json::json;
+    use shared::auth::API_TOKEN_ID_CLAIM;
+    use shared::guards::roles::UserRole;
+
+    #[tokio::test]
+    async fn create_api_token_stores_hash_only() {
+        let mut mock_repo = MockApiTokenRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_authorize()
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::ManageApiTokens),
+            )
+            .returning(|_, _| Ok(()));
+        mock_repo
+            .expect_create_api_token()
+            .withf(|token| token.user_id() == "test-user-id" && token.name() == "ci bot")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let handler = CreateApiTokenHandler::new(
+            Arc::new(ApiTokenRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+            Arc::new(OtpHasher::new_test_hasher()),
+        );
+        let cmd = CreateApiToken {
+            name: "ci bot".into(),
+            scopes: vec![Permission::ViewUser],
+            expires_in_days: 30,
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let created = handler.handle(&ctx, cmd).await.unwrap();
+        let (id, secret) = utils::parse_token(&created.token).unwrap();
+        assert_eq!(id, created.api_token.id());
+        assert!(
+            created
+                .api_token
+                .matches_secret(secret, &OtpHasher::new_test_hasher())
+        );
+    }
+
+    #[tokio::test]
+    async fn api_token_cannot_create_tokens() {
+        let mut mock_repo = MockApiTokenRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_repo.expect_create_api_token().never();
+
+        let handler = CreateApiTokenHandler::new(
+            Arc::new(ApiTokenRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+            Arc::new(OtpHasher::new_test_hasher()),
+        );
+        let cmd = CreateApiToken {
+            name: "ci bot".into(),
+            scopes: vec![Permission::ViewUser],
+            expires_in_days: 30,
+        };
+        let mut auth_user = AuthUser::new_test_auth_user(UserRole::Regular);
+        auth_user
+            .0
+            .custom
+            .insert(API_TOKEN_ID_CLAIM.into(), json!("token-id"));
+        let ctx = AppContext::new().with_user(auth_user);
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(result, Err(UserDomainError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn create_api_token_unauthorized() {
+        let mut mock_repo = MockApiTokenRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_authorize()
+            .returning(|_, _| Err(UserDomainError::Unauthorized));
+        mock_repo.expect_create_api_token().never();
+
+        let handler = CreateApiTokenHandler::new(
+            Arc::new(ApiTokenRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+            Arc::new(OtpHasher::new_test_hasher()),
+        );
+        let cmd = CreateApiToken {
+            name: "ci bot".into(),
+            scopes: vec![Permission::ViewUser],
+            expires_in_days: 30,
+        };
+        let ctx = AppContext::new().with_user(AuthUser::guest());
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(result.is_err());
+    }
+}
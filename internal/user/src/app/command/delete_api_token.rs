@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    command_handler::CommandHanlder,
+    guards::permissions::UserPermission,
+};
+
+use crate::domain::{
+    errors::UserDomainError, result::UserDomainResult, user_auth::errors::UserAuthError,
+};
+use crate::guards::UserGuards;
+use crate::infra::repository::api_token_repository::ApiTokenRepository;
+
+use super::create_api_token::ensure_session_user;
+
+/// Revokes a token. Requests made with it are rejected from then on.
+#[derive(Debug, Clone, Deserialize, InputObject)]
+pub struct DeleteApiToken {
+    pub id: String,
+}
+
+pub struct DeleteApiTokenHandler {
+    api_token_repo: Arc<ApiTokenRepository>,
+    guard: Arc<dyn UserGuards>,
+}
+
+impl DeleteApiTokenHandler {
+    pub fn new(api_token_repo: Arc<ApiTokenRepository>, guard: Arc<dyn UserGuards>) -> Self {
+        Self {
+            api_token_repo,
+            guard,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<DeleteApiToken, UserDomainError> for DeleteApiTokenHandler {
+    #[tracing::instrument(name = "command.delete_api_token", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: DeleteApiToken) -> UserDomainResult<()> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &UserPermission::ManageApiTokens)?;
+        ensure_session_user(auth_user)?;
+
+        let deleted = self
+            .api_token_repo
+            .delete_api_token(&auth_user.0.id, &cmd.id)
+            .await?;
+        if !deleted {
+            return Err(UserAuthError::ApiTokenNotFound.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guards::MockUserGuards;
+    use crate::infra::repository::api_token_repository_trait::MockApiTokenRepositoryTrait;
+    use mockall::predicate::eq;
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn delete_api_token_scoped_to_owner() {
+        let mut mock_repo = MockApiTokenRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_repo
+            .expect_delete_api_token()
+            .with(eq("test-user-id"), eq("token-id"))
+            .returning(|_, _| Ok(true));
+
+        let handler = DeleteApiTokenHandler::new(
+            Arc::new(ApiTokenRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = DeleteApiToken {
+            id: "token-id".into(),
+        };
+        assert!(handler.handle(&ctx, cmd).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn delete_missing_api_token() {
+        let mut mock_repo = MockApiTokenRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_repo
+            .expect_delete_api_token()
+            .returning(|_, _| Ok(false));
+
+        let handler = DeleteApiTokenHandler::new(
+            Arc::new(ApiTokenRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = DeleteApiToken {
+            id: "token-id".into(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::ApiTokenNotFound
+            ))
+        ));
+    }
+}
@@ -0,0 +1,277 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use validator::Validate;
+
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    command_handler::CommandHanlder,
+    guards::permissions::UserPermission,
+};
+
+use crate::app::metrics;
+use crate::domain::{
+    errors::UserDomainError,
+    result::UserDomainResult,
+    user_auth::{errors::UserAuthError, otp_hasher::OtpHasher, two_factor::TwoFactorPolicy},
+};
+use crate::guards::UserGuards;
+use crate::infra::repository::user_repository::UserRepository;
+
+/// Turns 2FA off after checking a TOTP or recovery code. Not allowed for roles
+/// the [`TwoFactorPolicy`] requires it for.
+#[derive(Debug, Clone, Validate, Deserialize, InputObject)]
+pub struct DisableTotp {
+    #[validate(length(min = 1))]
+    pub code: String,
+}
+
+pub struct DisableTotpHandler {
+    user_repo: Arc<UserRepository>,
+    guard: Arc<dyn UserGuards>,
+    otp_hasher: Arc<OtpHasher>,
+    policy: TwoFactorPolicy,
+}
+
+impl DisableTotpHandler {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        guard: Arc<dyn UserGuards>,
+        otp_hasher: Arc<OtpHasher>,
+        policy: TwoFactorPolicy,
+    ) -> Self {
+        Self {
+            user_repo,
+            guard,
+            otp_hasher,
+            policy,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<DisableTotp, UserDomainError> for DisableTotpHandler {
+    #[tracing::instrument(name = "command.disable_totp", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: DisableTotp) -> UserDomainResult<()> {
+        cmd.validate()?;
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &UserPermission::ManageTwoFactor)?;
+
+        let user = self
+            .user_repo
+            .get_user_by_id(&auth_user.0.id)
+            .await?
+            .ok_or(UserDomainError::UserNotFound)?;
+        if self.policy.is_required_for(user.role()) {
+            return Err(UserAuthError::TwoFactorRequired.into());
+        }
+        let mut two_factor = user
+            .two_factor()
+            .filter(|two_factor| two_factor.is_enabled())
+            .cloned()
+            .ok_or(UserAuthError::TwoFactorNotEnabled)?;
+
+        let now = Utc::now();
+        two_factor
+            .validate_code_attempts(now)
+            .inspect_err(|_| metrics::LOCKOUTS.inc(&[("check", "disable_totp")]))?;
+        if !two_factor.verify(&cmd.code, &self.otp_hasher, now) {
+            two_factor.record_failed_code(now);
+            self.user_repo
+                .update_two_factor(user.id(), |user| {
+                    user.set_two_factor(Some(two_factor));
+                })
+                .await?;
+            return Err(UserAuthError::InvalidTwoFactorCode.into());
+        }
+        self.user_repo
+            .update_two_factor(user.id(), |user| {
+                user.set_two_factor(None);
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::User;
+    use crate::domain::user_auth::two_factor::{MAX_CODE_ATTEMPTS, TwoFactor, utils};
+    use crate::guards::MockUserGuards;
+    use crate::infra::repository::user_repository_trait::MockUserRepositoryTrait;
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn disable_totp_with_recovery_code() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+        let codes = utils::generate_recovery_codes();
+        let mut two_factor = TwoFactor::enroll();
+        two_factor.enable(
+            utils::hash_recovery_codes(&codes, &OtpHasher::new_test_hasher()),
+            Utc::now(),
+        );
+        let user = User::new_test_user(Some(UserRole::Regular)).with_two_factor(Some(two_factor));
+
+        mock_user_repo
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(Some(user.clone())));
+        mock_user_repo
+            .expect_update_two_factor()
+            .returning(|_, update_fn| {
+                let mut user = User::new_test_user(None);
+                update_fn(&mut user);
+                assert!(user.two_factor().is_none());
+                Ok(())
+            });
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+
+        let handler = DisableTotpHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            Arc::new(OtpHasher::new_test_hasher()),
+            TwoFactorPolicy::default(),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = DisableTotp {
+            code: codes[3].clone(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn disable_totp_required_for_role() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+        let codes = utils::generate_recovery_codes();
+        let mut two_factor = TwoFactor::enroll();
+        two_factor.enable(
+            utils::hash_recovery_codes(&codes, &OtpHasher::new_test_hasher()),
+            Utc::now(),
+        );
+        let user = User::new_test_user(Some(UserRole::Moderator)).with_two_factor(Some(two_factor));
+
+        mock_user_repo
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(Some(user.clone())));
+        mock_user_repo.expect_update_two_factor().never();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+
+        let policy = TwoFactorPolicy {
+            required_roles: vec![UserRole::Admin, UserRole::Moderator],
+            ..TwoFactorPolicy::default()
+        };
+        let handler = DisableTotpHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            Arc::new(OtpHasher::new_test_hasher()),
+            policy,
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Moderator));
+        let cmd = DisableTotp {
+            code: codes[0].clone(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::TwoFactorRequired
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn disable_totp_counts_wrong_codes() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+        let mut two_factor = TwoFactor::enroll();
+        two_factor.enable(
+            utils::hash_recovery_codes(
+                &utils::generate_recovery_codes(),
+                &OtpHasher::new_test_hasher(),
+            ),
+            Utc::now(),
+        );
+        let user = User::new_test_user(Some(UserRole::Regular)).with_two_factor(Some(two_factor));
+
+        mock_user_repo
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(Some(user.clone())));
+        mock_user_repo
+            .expect_update_two_factor()
+            .times(1)
+            .returning(|_, update_fn| {
+                let mut user = User::new_test_user(None);
+                update_fn(&mut user);
+                assert_eq!(*user.two_factor().unwrap().failed_attempts(), 1);
+                Ok(())
+            });
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+
+        let handler = DisableTotpHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            Arc::new(OtpHasher::new_test_hasher()),
+            TwoFactorPolicy::default(),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = DisableTotp {
+            code: "not-a-code".into(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::InvalidTwoFactorCode
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn disable_totp_locked_out_after_too_many_wrong_codes() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+        let codes = utils::generate_recovery_codes();
+        let mut two_factor = TwoFactor::enroll();
+        two_factor.enable(
+            utils::hash_recovery_codes(&codes, &OtpHasher::new_test_hasher()),
+            Utc::now(),
+        );
+        (0..MAX_CODE_ATTEMPTS).for_each(|_| two_factor.record_failed_code(Utc::now()));
+        let user = User::new_test_user(Some(UserRole::Regular)).with_two_factor(Some(two_factor));
+
+        mock_user_repo
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(Some(user.clone())));
+        mock_user_repo.expect_update_two_factor().never();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+
+        let handler = DisableTotpHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            Arc::new(OtpHasher::new_test_hasher()),
+            TwoFactorPolicy::default(),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = DisableTotp {
+            code: codes[0].clone(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::TooManyAttempts
+            ))
+        ));
+    }
+}
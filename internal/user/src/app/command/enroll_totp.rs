@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use async_graphql::SimpleObject;
+use async_trait::async_trait;
+
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    command_handler::CommandHanlder,
+    guards::permissions::UserPermission,
+};
+
+use crate::domain::{
+    errors::UserDomainError,
+    result::UserDomainResult,
+    user_auth::{
+        errors::UserAuthError,
+        two_factor::{TwoFactor, TwoFactorPolicy},
+    },
+};
+use crate::guards::UserGuards;
+use crate::infra::repository::user_repository::UserRepository;
+
+/// Starts TOTP enrollment for the signed in user. Enrolling again before
+/// confirming replaces the pending secret.
+pub struct EnrollTotp;
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    /// `otpauth://` URI to render as a QR code.
+    pub provisioning_uri: String,
+}
+
+pub struct EnrollTotpHandler {
+    user_repo: Arc<UserRepository>,
+    guard: Arc<dyn UserGuards>,
+    policy: TwoFactorPolicy,
+}
+
+impl EnrollTotpHandler {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        guard: Arc<dyn UserGuards>,
+        policy: TwoFactorPolicy,
+    ) -> Self {
+        Self {
+            user_repo,
+            guard,
+            policy,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<EnrollTotp, UserDomainError, TotpEnrollment> for EnrollTotpHandler {
+    #[tracing::instrument(name = "command.enroll_totp", skip_all)]
+    async fn handle(&self, ctx: &AppContext, _cmd: EnrollTotp) -> UserDomainResult<TotpEnrollment> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &UserPermission::ManageTwoFactor)?;
+
+        let user = self
+            .user_repo
+            .get_user_by_id(&auth_user.0.id)
+            .await?
+            .ok_or(UserDomainError::UserNotFound)?;
+        if user.has_two_factor_enabled() {
+            return Err(UserAuthError::TwoFactorAlreadyEnabled.into());
+        }
+
+        let two_factor = TwoFactor::enroll();
+        let enrollment = TotpEnrollment {
+            secret: two_factor.secret().clone(),
+            provisioning_uri: two_factor.provisioning_uri(&self.policy.issuer, user.email()),
+        };
+        self.user_repo
+            .update_two_factor(user.id(), |user| {
+                user.set_two_factor(Some(two_factor));
+            })
+            .await?;
+        Ok(enrollment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::User;
+    use crate::guards::MockUserGuards;
+    use crate::infra::repository::user_repository_trait::MockUserRepositoryTrait;
+    use chrono::Utc;
+    use mockall::predicate::eq;
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn enroll_totp_stores_pending_secret() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_authorize()
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::ManageTwoFactor),
+            )
+            .returning(|_, _| Ok(()));
+        mock_user_repo
+            .expect_get_user_by_id()
+            .returning(|_| Ok(Some(User::new_test_user(None))));
+        mock_user_repo
+            .expect_update_two_factor()
+            .withf(|uid, _| uid == User::test_user_id())
+            .returning(|_, update_fn| {
+                let mut user = User::new_test_user(None);
+                update_fn(&mut user);
+                let two_factor = user.two_factor().unwrap();
+                assert!(!two_factor.is_enabled());
+                Ok(())
+            });
+
+        let handler = EnrollTotpHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            TwoFactorPolicy::default(),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let enrollment = handler.handle(&ctx, EnrollTotp).await.unwrap();
+        assert!(
+            enrollment
+                .provisioning_uri
+                .contains(&format!("secret={}", enrollment.secret))
+        );
+    }
+
+    #[tokio::test]
+    async fn enroll_totp_already_enabled() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_user_repo.expect_get_user_by_id().returning(|_| {
+            let mut two_factor = TwoFactor::enroll();
+            two_factor.enable(vec![], Utc::now());
+            Ok(Some(
+                User::new_test_user(None).with_two_factor(Some(two_factor)),
+            ))
+        });
+        mock_user_repo.expect_update_two_factor().never();
+
+        let handler = EnrollTotpHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            TwoFactorPolicy::default(),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let result = handler.handle(&ctx, EnrollTotp).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::TwoFactorAlreadyEnabled
+            ))
+        ));
+    }
+}
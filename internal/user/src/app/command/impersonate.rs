@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use async_graphql::SimpleObject;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use tracing::info;
+use validator::Validate;
+
+use shared::{
+    auth::{
+        AppContext, get_auth_user_from_ctx,
+        jwt::Actor,
+        token_service::{TokenRequest, TokenService},
+    },
+    command_handler::CommandHanlder,
+    guards::{permissions::UserPermission, roles::UserRole},
+    types::graphql_scalars::DateTimeScalar,
+};
+
+use crate::app::audit_trail::AuditTrail;
+use crate::domain::{audit_log::AuditAction, errors::UserDomainError, result::UserDomainResult};
+use crate::guards::UserGuards;
+use crate::infra::repository::user_repository::UserRepository;
+
+use super::create_api_token::ensure_session_user;
+
+/// Lets an admin see the API as another user. The token names the admin in
+/// its `act` claim and is read-only.
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct Impersonate {
+    #[validate(length(min = 1))]
+    pub user_id: String,
+}
+
+#[derive(SimpleObject)]
+pub struct ImpersonationToken {
+    pub token: String,
+    pub expires_at: DateTimeScalar,
+}
+
+pub struct ImpersonateHandler {
+    user_repo: Arc<UserRepository>,
+    guard: Arc<dyn UserGuards>,
+    token_service: Arc<dyn TokenService>,
+    audit_trail: Arc<AuditTrail>,
+    ttl: Duration,
+}
+
+impl ImpersonateHandler {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        guard: Arc<dyn UserGuards>,
+        token_service: Arc<dyn TokenService>,
+        audit_trail: Arc<AuditTrail>,
+        ttl: Duration,
+    ) -> Self {
+        Self {
+            user_repo,
+            guard,
+            token_service,
+            audit_trail,
+            ttl,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<Impersonate, UserDomainError, ImpersonationToken> for ImpersonateHandler {
+    #[tracing::instrument(name = "command.impersonate", skip_all)]
+    async fn handle(
+        &self,
+        ctx: &AppContext,
+        cmd: Impersonate,
+    ) -> UserDomainResult<ImpersonationToken> {
+        cmd.validate()?;
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &UserPermission::ImpersonateUser)?;
+        if auth_user.0.role != UserRole::Admin || auth_user.impersonator().is_some() {
+            return Err(UserDomainError::Unauthorized);
+        }
+        ensure_session_user(auth_user)?;
+
+        let user = self
+            .user_repo
+            .get_user_by_id(&cmd.user_id)
+            .await?
+            .ok_or(UserDomainError::UserNotFound)?;
+        // Acting as another admin would hide who did what among admins.
+        if user.role() == &UserRole::Admin {
+            return Err(UserDomainError::Unauthorized);
+        }
+
+        let expires_at = Utc::now() + self.ttl;
+        let token = self.token_service.issue(
+            TokenRequest::new(
+                user.email().to_string(),
+                user.role().to_owned(),
+                user.id().to_string(),
+            )
+            .with_actor(Actor {
+                sub: auth_user.0.sub.clone(),
+                id: auth_user.0.id.clone(),
+            })
+            .with_ttl(self.ttl),
+        )?;
+        info!(
+            impersonator_id = %auth_user.0.id,
+            subject_id = %user.id(),
+            "Issued impersonation token"
+        );
+        self.audit_trail
+            .record(ctx, AuditAction::Impersonate, user.id(), vec![])
+            .await?;
+        Ok(ImpersonationToken {
+            token,
+            expires_at: expires_at.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::User;
+    use crate::guards::MockUserGuards;
+    use crate::infra::repository::user_repository_trait::MockUserRepositoryTrait;
+    use mockall::predicate::eq;
+    use shared::auth::{AuthUser, token_service::FakeTokenService};
+
+    #[tokio::test]
+    async fn admin_gets_token_for_subject_with_actor() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+        let token_service = Arc::new(FakeTokenService::new());
+
+        mock_guard
+            .expect_authorize()
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Admin)),
+                eq(UserPermission::ImpersonateUser),
+            )
+            .returning(|_, _| Ok(()));
+        mock_user_repo
+            .expect_get_user_by_id()
+            .with(eq(User::test_user_id()))
+            .returning(|_| Ok(Some(User::new_test_user(None))));
+
+        let audit_trail = Arc::new(AuditTrail::in_memory());
+        let handler = ImpersonateHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            token_service.clone(),
+            audit_trail.clone(),
+            Duration::minutes(15),
+        );
+        let cmd = Impersonate {
+            user_id: User::test_user_id(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Admin));
+        let result = handler.handle(&ctx, cmd).await.unwrap();
+
+        let claims = token_service.verify(&result.token).unwrap();
+        assert_eq!(claims.id, User::test_user_id());
+        assert_eq!(claims.role, UserRole::Regular);
+        let actor = claims.act.unwrap();
+        assert_eq!(actor.id, "test-user-id");
+        assert_eq!(actor.sub, "johndoe@example.com");
+        let entries = audit_trail.recorded().await;
+        assert_eq!(entries[0].action, AuditAction::Impersonate);
+        assert_eq!(entries[0].target_id, User::test_user_id());
+    }
+
+    #[tokio::test]
+    async fn admin_cannot_impersonate_another_admin() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_user_repo
+            .expect_get_user_by_id()
+            .returning(|_| Ok(Some(User::new_test_user(Some(UserRole::Admin)))));
+
+        let handler = ImpersonateHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            Arc::new(FakeTokenService::new()),
+            Arc::new(AuditTrail::in_memory()),
+            Duration::minutes(15),
+        );
+        let cmd = Impersonate {
+            user_id: User::test_user_id(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Admin));
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(result, Err(UserDomainError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn only_admins_can_impersonate() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_user_repo.expect_get_user_by_id().never();
+
+        let handler = ImpersonateHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(mock_guard),
+            Arc::new(FakeTokenService::new()),
+            Arc::new(AuditTrail::in_memory()),
+            Duration::minutes(15),
+        );
+        let cmd = Impersonate {
+            user_id: User::test_user_id(),
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Moderator));
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(result, Err(UserDomainError::Unauthorized)));
+    }
+}
@@ -5,10 +5,14 @@ use async_trait::async_trait;
 use shared::{
     auth::{AppContext, get_auth_user_from_ctx},
     command_handler::CommandHanlder,
-    guards::permissions::UserPermission,
+    event_hub::EventHub,
+    guards::{permissions::UserPermission, roles::UserRole},
 };
 
+use crate::app::audit_trail::{AuditTrail, UserChanges};
+use crate::domain::audit_log::AuditAction;
 use crate::domain::errors::UserDomainError;
+use crate::domain::events::{AccountChangeKind, UserEvent};
 use crate::domain::result::UserDomainResult;
 use crate::guards::UserGuards;
 use crate::infra::repository::user_repository::UserRepository;
@@ -20,25 +24,55 @@ pub struct MakeModerator {
 pub struct MakeModeratorHandler {
     user_repo: Arc<UserRepository>,
     guard: Arc<dyn UserGuards>,
+    audit_trail: Arc<AuditTrail>,
+    events: Arc<EventHub<UserEvent>>,
 }
 
 impl MakeModeratorHandler {
-    pub fn new(user_repo: Arc<UserRepository>, guard: Arc<dyn UserGuards>) -> Self {
-        Self { user_repo, guard }
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        guard: Arc<dyn UserGuards>,
+        audit_trail: Arc<AuditTrail>,
+        events: Arc<EventHub<UserEvent>>,
+    ) -> Self {
+        Self {
+            user_repo,
+            guard,
+            audit_trail,
+            events,
+        }
     }
 }
 
 #[async_trait]
 impl CommandHanlder<MakeModerator, UserDomainError> for MakeModeratorHandler {
+    #[tracing::instrument(name = "command.make_moderator", skip_all)]
     async fn handle(&self, ctx: &AppContext, cmd: MakeModerator) -> UserDomainResult<()> {
         let auth_user = get_auth_user_from_ctx(&ctx);
         self.guard
-            .authorize(&auth_user.0.role, &UserPermission::MakeModerator)?;
+            .authorize(auth_user, &UserPermission::MakeModerator)?;
+        let changes = UserChanges::default();
+        let tracker = changes.clone();
         self.user_repo
-            .make_moderator(&cmd.user_id, |user| {
-                user.make_moderator();
+            .make_moderator(&cmd.user_id, move |user| {
+                tracker.track(user, |user| {
+                    user.make_moderator();
+                });
             })
             .await?;
+        self.audit_trail
+            .record(
+                ctx,
+                AuditAction::MakeModerator,
+                &cmd.user_id,
+                changes.take(),
+            )
+            .await?;
+        self.events.publish(UserEvent::account_changed(
+            &cmd.user_id,
+            AccountChangeKind::RoleChanged,
+            Some(format!("{:?}", UserRole::Moderator)),
+        ));
         Ok(())
     }
 }
@@ -49,6 +83,7 @@ mod tests {
     use crate::domain::user::User;
     use crate::guards::MockUserGuards;
     use crate::infra::repository::user_repository_trait::MockUserRepositoryTrait;
+    use futures::StreamExt;
     use mockall::predicate::eq;
     use shared::{
         auth::{AppContext, AuthUser},
@@ -58,12 +93,17 @@ mod tests {
 
     #[tokio::test]
     async fn make_moderator_success() {
+        let audit_trail = Arc::new(AuditTrail::in_memory());
+        let events = Arc::new(EventHub::new(8));
         let mut mock_user_repo = MockUserRepositoryTrait::new();
         let mut mock_guard = MockUserGuards::new();
 
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Admin), eq(UserPermission::MakeModerator))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Admin)),
+                eq(UserPermission::MakeModerator),
+            )
             .returning(|_, _| Ok(()));
 
         mock_user_repo
@@ -84,6 +124,8 @@ mod tests {
         let handler = MakeModeratorHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
             Arc::new(mock_guard),
+            audit_trail.clone(),
+            events.clone(),
         );
 
         let cmd = MakeModerator {
@@ -92,18 +134,37 @@ mod tests {
 
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Admin));
 
+        let mut published = events.subscribe();
         let result = handler.handle(&ctx, cmd).await;
-        assert!(result.is_ok())
+        assert!(result.is_ok());
+        let entries = audit_trail.recorded().await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::MakeModerator);
+        assert_eq!(entries[0].actor_id, "test-user-id");
+        assert_eq!(entries[0].target_id, User::test_user_id());
+        assert_eq!(entries[0].changes[0].field, "role");
+        match published.next().await {
+            Some(UserEvent::AccountChanged(change)) => {
+                assert_eq!(change.user_id, User::test_user_id());
+                assert_eq!(change.kind, AccountChangeKind::RoleChanged);
+            }
+            other => panic!("expected an account change, got {:?}", other),
+        }
     }
 
     #[tokio::test]
     async fn make_moderator_unauthorized() {
+        let audit_trail = Arc::new(AuditTrail::in_memory());
+        let events = Arc::new(EventHub::new(8));
         let mut mock_user_repo = MockUserRepositoryTrait::new();
         let mut mock_guard = MockUserGuards::new();
 
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Regular), eq(UserPermission::MakeModerator))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::MakeModerator),
+            )
             .returning(|_, _| Err(UserDomainError::Unauthorized));
 
         mock_user_repo.expect_ban_user().never();
@@ -111,6 +172,8 @@ mod tests {
         let handler = MakeModeratorHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
             Arc::new(mock_guard),
+            audit_trail.clone(),
+            events.clone(),
         );
         let cmd = MakeModerator {
             user_id: User::test_user_id(),
@@ -118,5 +181,6 @@ mod tests {
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
         let result = handler.handle(&ctx, cmd).await;
         assert!(result.is_err());
+        assert!(audit_trail.recorded().await.is_empty());
     }
 }
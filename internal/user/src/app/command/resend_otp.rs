@@ -0,0 +1,194 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+
+use crate::app::otp_issuer::{IssuedOtp, OtpIssuer};
+use serde::Deserialize;
+use validator::Validate;
+
+use shared::{auth::AppContext, command_handler::CommandHanlder};
+
+use crate::domain::{errors::UserDomainError, result::UserDomainResult};
+use crate::infra::repository::{otp_repository::OtpRepository, user_repository::UserRepository};
+
+/// Sends a new code to a user who signed up or signed in, replacing the previous one.
+/// The issue limits apply and the answer is the same whether or not the email
+/// has an account, so it cannot be used to find out which emails do.
+#[derive(Debug, Clone, Validate, Deserialize, InputObject)]
+pub struct ResendOtp {
+    #[validate(email)]
+    pub email: String,
+}
+
+pub struct ResendOtpHandler {
+    user_repo: Arc<UserRepository>,
+    otp_repo: Arc<OtpRepository>,
+    otp_issuer: Arc<OtpIssuer>,
+}
+
+impl ResendOtpHandler {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        otp_repo: Arc<OtpRepository>,
+        otp_issuer: Arc<OtpIssuer>,
+    ) -> Self {
+        Self {
+            user_repo,
+            otp_repo,
+            otp_issuer,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<ResendOtp, UserDomainError> for ResendOtpHandler {
+    #[tracing::instrument(name = "command.resend_otp", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: ResendOtp) -> UserDomainResult<()> {
+        cmd.validate()?;
+        let IssuedOtp { entry, code } = self.otp_issuer.prepare(ctx, &cmd.email).await?;
+        let Some(user) = self
+            .user_repo
+            .get_user_by_username_or_email("", &cmd.email)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        self.otp_repo.upsert_otp(entry, None).await?;
+        //TODO: send otp to user via email or sms
+        tracing::info!("OTP for user {} is {}", user.email(), code);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::User;
+    use crate::domain::user_auth::{
+        errors::UserAuthError,
+        otp::{MAX_ALLOWED_ATTEMPTS, OtpEntry, OtpIssuePolicy, utils as otp_utils},
+        otp_hasher::OtpHasher,
+    };
+    use crate::infra::repository::otp_issuance_repository::OtpIssuanceRepository;
+    use crate::infra::repository::otp_issuance_repository_trait::MockOtpIssuanceRepositoryTrait;
+    use crate::infra::repository::otp_repository_trait::MockOtpRepositoryTrait;
+    use crate::infra::repository::user_repository_trait::MockUserRepositoryTrait;
+    use mockall::predicate::eq;
+    use shared::auth::{AppContext, AuthUser};
+    use shared::guards::roles::UserRole;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn resend_otp_keeps_attempts() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_otp_repo = MockOtpRepositoryTrait::new();
+
+        let email = "test@example.com".to_string();
+        let user = User::new_test_user(None);
+        let previous = OtpEntry::new(
+            email.clone(),
+            false,
+            MAX_ALLOWED_ATTEMPTS - 1,
+            otp_utils::hash_otp("123456"),
+            otp_utils::get_otp_expiration(),
+        );
+        let previous_hash = previous.otp_hash().clone();
+
+        mock_user_repo
+            .expect_get_user_by_username_or_email()
+            .with(eq("".to_string()), eq(email.clone()))
+            .returning(move |_, _| Ok(Some(user.clone())));
+        mock_otp_repo
+            .expect_get_otp_by_user_email()
+            .with(eq(email.clone()))
+            .returning(move |_| Ok(Some(previous.clone())));
+        mock_otp_repo
+            .expect_upsert_otp()
+            .withf(move |otp, _| {
+                *otp.attempts() == MAX_ALLOWED_ATTEMPTS - 1 && otp.otp_hash() != &previous_hash
+            })
+            .returning(|_, _| Ok(()));
+
+        let otp_repo = Arc::new(OtpRepository::Mock(mock_otp_repo));
+        let handler = ResendOtpHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            otp_repo.clone(),
+            Arc::new(OtpIssuer::new_test_issuer(otp_repo)),
+        );
+
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let result = handler.handle(&ctx, ResendOtp { email }).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn resend_otp_unknown_email_looks_the_same() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_otp_repo = MockOtpRepositoryTrait::new();
+
+        mock_user_repo
+            .expect_get_user_by_username_or_email()
+            .returning(|_, _| Ok(None));
+        mock_otp_repo
+            .expect_get_otp_by_user_email()
+            .returning(|_| Ok(None));
+        mock_otp_repo.expect_upsert_otp().never();
+
+        let otp_repo = Arc::new(OtpRepository::Mock(mock_otp_repo));
+        let handler = ResendOtpHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            otp_repo.clone(),
+            Arc::new(OtpIssuer::new_test_issuer(otp_repo)),
+        );
+
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let cmd = ResendOtp {
+            email: "test@example.com".into(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn resend_otp_limited_before_looking_up_user() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_otp_repo = MockOtpRepositoryTrait::new();
+        let mut mock_issuance_repo = MockOtpIssuanceRepositoryTrait::new();
+
+        let last = chrono::Utc::now() - chrono::Duration::seconds(5);
+        mock_issuance_repo
+            .expect_get_issuances_since()
+            .returning(move |_, _| Ok(vec![last]));
+        mock_issuance_repo.expect_record_issuance().never();
+        mock_user_repo
+            .expect_get_user_by_username_or_email()
+            .never();
+        mock_otp_repo.expect_upsert_otp().never();
+
+        let otp_repo = Arc::new(OtpRepository::Mock(mock_otp_repo));
+        let handler = ResendOtpHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            otp_repo.clone(),
+            Arc::new(OtpIssuer::new(
+                otp_repo,
+                Arc::new(OtpIssuanceRepository::Mock(mock_issuance_repo)),
+                OtpIssuePolicy::default(),
+                Arc::new(OtpHasher::new_test_hasher()),
+            )),
+        );
+
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let cmd = ResendOtp {
+            email: "test@example.com".into(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::OtpResendTooSoon(_)
+            ))
+        ));
+    }
+}
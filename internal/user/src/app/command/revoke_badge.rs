@@ -5,6 +5,7 @@ use async_trait::async_trait;
 use shared::{
     auth::{AppContext, get_auth_user_from_ctx},
     command_handler::CommandHanlder,
+    event_hub::EventHub,
     guards::permissions::UserPermission,
 };
 
@@ -12,6 +13,9 @@ use crate::domain::errors::UserDomainError;
 use crate::domain::result::UserDomainResult;
 use crate::infra::repository::user_repository::UserRepository;
 
+use crate::app::audit_trail::{AuditTrail, UserChanges};
+use crate::domain::audit_log::AuditAction;
+use crate::domain::events::{AccountChangeKind, UserEvent};
 use crate::guards::UserGuards;
 
 pub struct RevokeBadge {
@@ -22,24 +26,50 @@ pub struct RevokeBadge {
 pub struct RevokeBadgeHandler {
     user_repo: Arc<UserRepository>,
     guard: Arc<dyn UserGuards>,
+    audit_trail: Arc<AuditTrail>,
+    events: Arc<EventHub<UserEvent>>,
 }
 
 impl RevokeBadgeHandler {
-    pub fn new(user_repo: Arc<UserRepository>, guard: Arc<dyn UserGuards>) -> Self {
-        Self { user_repo, guard }
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        guard: Arc<dyn UserGuards>,
+        audit_trail: Arc<AuditTrail>,
+        events: Arc<EventHub<UserEvent>>,
+    ) -> Self {
+        Self {
+            user_repo,
+            guard,
+            audit_trail,
+            events,
+        }
     }
 }
 #[async_trait]
 impl CommandHanlder<RevokeBadge, UserDomainError> for RevokeBadgeHandler {
+    #[tracing::instrument(name = "command.revoke_badge", skip_all)]
     async fn handle(&self, ctx: &AppContext, cmd: RevokeBadge) -> UserDomainResult<()> {
         let auth_user = get_auth_user_from_ctx(&ctx);
         self.guard
-            .authorize(&auth_user.0.role, &UserPermission::RevokeBadge)?;
+            .authorize(auth_user, &UserPermission::RevokeBadge)?;
+        let badge = cmd.badge.clone();
+        let changes = UserChanges::default();
+        let tracker = changes.clone();
         self.user_repo
-            .revoke_badge(&cmd.user_id, |user| {
-                user.revoke_badge(cmd.badge);
+            .revoke_badge(&cmd.user_id, move |user| {
+                tracker.track(user, |user| {
+                    user.revoke_badge(cmd.badge);
+                });
             })
             .await?;
+        self.audit_trail
+            .record(ctx, AuditAction::RevokeBadge, &cmd.user_id, changes.take())
+            .await?;
+        self.events.publish(UserEvent::account_changed(
+            &cmd.user_id,
+            AccountChangeKind::BadgeRevoked,
+            Some(badge),
+        ));
         Ok(())
     }
 }
@@ -50,6 +80,7 @@ mod tests {
     use crate::domain::user::User;
     use crate::guards::MockUserGuards;
     use crate::infra::repository::user_repository_trait::MockUserRepositoryTrait;
+    use futures::StreamExt;
     use mockall::predicate::eq;
     use shared::{
         auth::{AppContext, AuthUser},
@@ -59,6 +90,8 @@ mod tests {
 
     #[tokio::test]
     async fn revoke_badge_success() {
+        let audit_trail = Arc::new(AuditTrail::in_memory());
+        let events = Arc::new(EventHub::new(8));
         let mut mock_user_repo = MockUserRepositoryTrait::new();
         let mut mock_guard = MockUserGuards::new();
 
@@ -67,7 +100,10 @@ mod tests {
 
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Admin), eq(UserPermission::RevokeBadge))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Admin)),
+                eq(UserPermission::RevokeBadge),
+            )
             .returning(|_, _| Ok(()));
 
         mock_user_repo
@@ -89,6 +125,8 @@ mod tests {
         let handler = RevokeBadgeHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
             Arc::new(mock_guard),
+            audit_trail.clone(),
+            events.clone(),
         );
         let cmd = RevokeBadge {
             user_id: User::test_user_id(),
@@ -97,18 +135,37 @@ mod tests {
 
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Admin));
 
+        let mut published = events.subscribe();
         let result = handler.handle(&ctx, cmd).await;
-        assert!(result.is_ok())
+        assert!(result.is_ok());
+        let entries = audit_trail.recorded().await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::RevokeBadge);
+        assert_eq!(entries[0].actor_id, "test-user-id");
+        assert_eq!(entries[0].target_id, User::test_user_id());
+        assert_eq!(entries[0].changes[0].field, "badges");
+        match published.next().await {
+            Some(UserEvent::AccountChanged(change)) => {
+                assert_eq!(change.user_id, User::test_user_id());
+                assert_eq!(change.kind, AccountChangeKind::BadgeRevoked);
+            }
+            other => panic!("expected an account change, got {:?}", other),
+        }
     }
 
     #[tokio::test]
     async fn revoke_badge_unauthorized() {
+        let audit_trail = Arc::new(AuditTrail::in_memory());
+        let events = Arc::new(EventHub::new(8));
         let mut mock_user_repo = MockUserRepositoryTrait::new();
         let mut mock_guard = MockUserGuards::new();
         let badge = "Helpful".to_string();
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Regular), eq(UserPermission::RevokeBadge))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::RevokeBadge),
+            )
             .returning(|_, _| Err(UserDomainError::Unauthorized));
 
         mock_user_repo.expect_award_badge().never();
@@ -116,6 +173,8 @@ mod tests {
         let handler = RevokeBadgeHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
             Arc::new(mock_guard),
+            audit_trail.clone(),
+            events.clone(),
         );
         let cmd = RevokeBadge {
             user_id: User::test_user_id(),
@@ -124,5 +183,6 @@ mod tests {
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
         let result = handler.handle(&ctx, cmd).await;
         assert!(result.is_err());
+        assert!(audit_trail.recorded().await.is_empty());
     }
 }
@@ -3,43 +3,41 @@ use std::sync::Arc;
 use async_graphql::InputObject;
 use async_trait::async_trait;
 
-use crate::infra::repository::otp_repository::OtpRepository;
+use crate::app::otp_issuer::OtpIssuer;
 use serde::Deserialize;
+use utoipa::ToSchema;
 use validator::Validate;
 
 use shared::{auth::AppContext, command_handler::CommandHanlder};
 
-use crate::domain::{
-    errors::UserDomainError,
-    result::UserDomainResult,
-    user::EmailStatus,
-    user_auth::otp::{OtpEntry, utils as otp_utils},
-};
+use crate::domain::{errors::UserDomainError, result::UserDomainResult, user::EmailStatus};
 use crate::infra::repository::user_repository::UserRepository;
 
-#[derive(Debug, Clone, Validate, Deserialize, InputObject)]
+#[derive(Debug, Clone, Validate, Deserialize, InputObject, ToSchema)]
 pub struct SignIn {
     #[validate(email)]
+    #[schema(format = Email)]
     pub email: String,
 }
 
 pub struct SignInHandler {
     user_repo: Arc<UserRepository>,
-    otp_repo: Arc<OtpRepository>,
+    otp_issuer: Arc<OtpIssuer>,
 }
 
 impl SignInHandler {
-    pub fn new(user_repo: Arc<UserRepository>, otp_repo: Arc<OtpRepository>) -> Self {
+    pub fn new(user_repo: Arc<UserRepository>, otp_issuer: Arc<OtpIssuer>) -> Self {
         Self {
             user_repo,
-            otp_repo,
+            otp_issuer,
         }
     }
 }
 
 #[async_trait]
 impl CommandHanlder<SignIn, UserDomainError> for SignInHandler {
-    async fn handle(&self, _ctx: &AppContext, cmd: SignIn) -> UserDomainResult<()> {
+    #[tracing::instrument(name = "command.sign_in", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: SignIn) -> UserDomainResult<()> {
         cmd.validate()?;
         let user = self
             .user_repo
@@ -51,12 +49,7 @@ impl CommandHanlder<SignIn, UserDomainError> for SignInHandler {
             return Err(UserDomainError::UnverifiedEmail);
         }
 
-        let otp_val = otp_utils::generate_otp();
-        let otp_hash = otp_utils::hash_otp(&otp_val);
-        let expires_at = otp_utils::get_otp_expiration();
-        let otp_entry = OtpEntry::new(cmd.email.clone(), false, 0, otp_hash, expires_at);
-
-        self.otp_repo.upsert_otp(otp_entry, None).await?;
+        let otp_val = self.otp_issuer.issue(ctx, &cmd.email).await?;
         //TODO: send otp to user via email or sms
         tracing::info!("OTP for user {} is {}", user.email(), otp_val);
         Ok(())
@@ -66,6 +59,7 @@ impl CommandHanlder<SignIn, UserDomainError> for SignInHandler {
 mod tests {
     use super::*;
     use crate::domain::user::{EmailStatus, User};
+    use crate::infra::repository::otp_repository::OtpRepository;
     use crate::infra::repository::otp_repository_trait::MockOtpRepositoryTrait;
     use crate::infra::repository::user_repository_trait::MockUserRepositoryTrait;
     use mockall::predicate::eq;
@@ -87,11 +81,16 @@ mod tests {
             .with(eq("".to_string()), eq(email.clone()))
             .returning(move |_, _| Ok(Some(user.clone())));
 
+        mock_otp_repo
+            .expect_get_otp_by_user_email()
+            .returning(|_| Ok(None));
         mock_otp_repo.expect_upsert_otp().returning(|_, _| Ok(()));
 
         let handler = SignInHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
-            Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpIssuer::new_test_issuer(Arc::new(OtpRepository::Mock(
+                mock_otp_repo,
+            )))),
         );
 
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
@@ -117,7 +116,9 @@ mod tests {
 
         let handler = SignInHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
-            Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpIssuer::new_test_issuer(Arc::new(OtpRepository::Mock(
+                mock_otp_repo,
+            )))),
         );
 
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
@@ -141,7 +142,9 @@ mod tests {
 
         let handler = SignInHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
-            Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpIssuer::new_test_issuer(Arc::new(OtpRepository::Mock(
+                mock_otp_repo,
+            )))),
         );
 
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
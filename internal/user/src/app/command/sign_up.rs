@@ -3,8 +3,11 @@ use std::sync::Arc;
 use async_graphql::InputObject;
 use async_trait::async_trait;
 
+use crate::app::metrics;
+use crate::app::otp_issuer::{IssuedOtp, OtpIssuer};
 use crate::infra::repository::otp_repository::OtpRepository;
 use serde::Deserialize;
+use utoipa::ToSchema;
 use validator::Validate;
 
 use shared::{
@@ -16,36 +19,44 @@ use crate::domain::{
     errors::UserDomainError,
     result::UserDomainResult,
     user::{EmailStatus, User},
-    user_auth::otp::{OtpEntry, utils as otp_utils},
 };
 use crate::infra::repository::user_repository::UserRepository;
 use shared::db_transactions::{DBTransaction, RepoDB};
 
-#[derive(Debug, Clone, Validate, Deserialize, InputObject)]
+#[derive(Debug, Clone, Validate, Deserialize, InputObject, ToSchema)]
 pub struct SignUp {
     #[validate(email)]
+    #[schema(format = Email)]
     pub email: String,
     #[validate(length(min = 3, max = 40))]
+    #[schema(min_length = 3, max_length = 40)]
     pub username: String,
 }
 
 pub struct SignUpHandler {
     user_repo: Arc<UserRepository>,
     otp_repo: Arc<OtpRepository>,
+    otp_issuer: Arc<OtpIssuer>,
 }
 
 impl SignUpHandler {
-    pub fn new(user_repo: Arc<UserRepository>, otp_repo: Arc<OtpRepository>) -> Self {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        otp_repo: Arc<OtpRepository>,
+        otp_issuer: Arc<OtpIssuer>,
+    ) -> Self {
         Self {
             user_repo,
             otp_repo,
+            otp_issuer,
         }
     }
 }
 
 #[async_trait]
 impl CommandHanlder<SignUp, UserDomainError> for SignUpHandler {
-    async fn handle(&self, _ctx: &AppContext, cmd: SignUp) -> UserDomainResult<()> {
+    #[tracing::instrument(name = "command.sign_up", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: SignUp) -> UserDomainResult<()> {
         cmd.validate()?;
         let user = self
             .user_repo
@@ -61,10 +72,10 @@ impl CommandHanlder<SignUp, UserDomainError> for SignUpHandler {
 
         let user = user.map_or(new_user, |existing_user| existing_user);
 
-        let otp_val = otp_utils::generate_otp();
-        let otp_hash = otp_utils::hash_otp(&otp_val);
-        let expires_at = otp_utils::get_otp_expiration();
-        let otp_entry = OtpEntry::new(user_email.clone(), false, 0, otp_hash, expires_at);
+        let IssuedOtp {
+            entry: otp_entry,
+            code: otp_val,
+        } = self.otp_issuer.prepare(ctx, &user_email).await?;
 
         let repo_db = self.user_repo.get_repo_db();
         match repo_db {
@@ -86,6 +97,7 @@ impl CommandHanlder<SignUp, UserDomainError> for SignUpHandler {
 
                 if let Ok(..) = result {
                     session.commit_transaction().await?;
+                    metrics::SIGN_UPS.inc(&[]);
                     //TODO: send otp to user via email or sms
                     tracing::info!("OTP for user {} is {}", user_email, otp_val);
                     Ok(())
@@ -101,6 +113,7 @@ impl CommandHanlder<SignUp, UserDomainError> for SignUpHandler {
                 self.otp_repo
                     .upsert_otp(otp_entry, Some(DBTransaction::Mock(&mut MockTransaction)))
                     .await?;
+                metrics::SIGN_UPS.inc(&[]);
                 Ok(())
             }
         }
@@ -159,10 +172,15 @@ mod tests {
                 }
             })
             .returning(|_, _| Ok(()));
+        mock_otp_repo
+            .expect_get_otp_by_user_email()
+            .returning(|_| Ok(None));
 
+        let otp_repo = Arc::new(OtpRepository::Mock(mock_otp_repo));
         let handler = SignUpHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
-            Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            otp_repo.clone(),
+            Arc::new(OtpIssuer::new_test_issuer(otp_repo)),
         );
         let ctx = AppContext::new().with_user(auth_user);
         let result = handler.handle(&ctx, cmd).await;
@@ -194,9 +212,11 @@ mod tests {
         mock_user_repo.expect_upsert_user().never();
         mock_otp_repo.expect_upsert_otp().never();
 
+        let otp_repo = Arc::new(OtpRepository::Mock(mock_otp_repo));
         let handler = SignUpHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
-            Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            otp_repo.clone(),
+            Arc::new(OtpIssuer::new_test_issuer(otp_repo)),
         );
         let ctx = AppContext::new().with_user(auth_user);
         let result = handler.handle(&ctx, cmd).await;
@@ -212,9 +232,11 @@ mod tests {
             email: "invalid_email".into(),
             username: "us".into(),
         };
+        let otp_repo = Arc::new(OtpRepository::Mock(mock_otp_repo));
         let handler = SignUpHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
-            Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            otp_repo.clone(),
+            Arc::new(OtpIssuer::new_test_issuer(otp_repo)),
         );
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Admin));
         let result = handler.handle(&ctx, cmd).await;
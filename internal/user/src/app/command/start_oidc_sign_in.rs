@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use async_graphql::{InputObject, SimpleObject};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use validator::Validate;
+
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    command_handler::CommandHanlder,
+    guards::roles::UserRole,
+};
+
+use crate::domain::{
+    errors::UserDomainError,
+    result::UserDomainResult,
+    user_auth::oidc::{OidcAuthRequest, OidcProviderConfig, find_provider},
+};
+use crate::infra::{
+    oidc::oidc_client::OidcClient,
+    repository::oidc_auth_request_repository::OidcAuthRequestRepository,
+};
+
+/// Starts an OIDC sign-in with `provider`. When called by a signed in user,
+/// the identity is linked to their account instead.
+#[derive(Debug, Clone, Validate, Deserialize, InputObject)]
+pub struct StartOidcSignIn {
+    #[validate(length(min = 1))]
+    pub provider: String,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct OidcAuthorization {
+    /// Where to send the user to sign in with the provider.
+    pub authorization_url: String,
+    pub state: String,
+}
+
+pub struct StartOidcSignInHandler {
+    auth_request_repo: Arc<OidcAuthRequestRepository>,
+    oidc_client: Arc<OidcClient>,
+    providers: Vec<OidcProviderConfig>,
+}
+
+impl StartOidcSignInHandler {
+    pub fn new(
+        auth_request_repo: Arc<OidcAuthRequestRepository>,
+        oidc_client: Arc<OidcClient>,
+        providers: Vec<OidcProviderConfig>,
+    ) -> Self {
+        Self {
+            auth_request_repo,
+            oidc_client,
+            providers,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<StartOidcSignIn, UserDomainError, OidcAuthorization>
+    for StartOidcSignInHandler
+{
+    #[tracing::instrument(name = "command.start_oidc_sign_in", skip_all)]
+    async fn handle(
+        &self,
+        ctx: &AppContext,
+        cmd: StartOidcSignIn,
+    ) -> UserDomainResult<OidcAuthorization> {
+        cmd.validate()?;
+        let provider = find_provider(&self.providers, &cmd.provider)?;
+        let auth_user = get_auth_user_from_ctx(ctx);
+        // Linking changes the account, which neither token scopes nor
+        // impersonation allow.
+        if auth_user.is_scoped() || auth_user.impersonator().is_some() {
+            return Err(UserDomainError::Unauthorized);
+        }
+        let link_user_id = (auth_user.0.role != UserRole::Guest).then(|| auth_user.0.id.clone());
+
+        let request = OidcAuthRequest::new(provider.name.clone(), link_user_id, Utc::now());
+        let authorization_url = self
+            .oidc_client
+            .authorization_url(provider, &request)
+            .await?;
+        let state = request.state().clone();
+        self.auth_request_repo.save_auth_request(request).await?;
+        Ok(OidcAuthorization {
+            authorization_url,
+            state,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user_auth::errors::UserAuthError;
+    use crate::infra::oidc::oidc_client_trait::MockOidcClientTrait;
+    use crate::infra::repository::oidc_auth_request_repository_trait::MockOidcAuthRequestRepositoryTrait;
+    use shared::auth::AuthUser;
+
+    #[tokio::test]
+    async fn start_oidc_sign_in_saves_request() {
+        let mut mock_auth_request_repo = MockOidcAuthRequestRepositoryTrait::new();
+        let mut mock_client = MockOidcClientTrait::new();
+        mock_auth_request_repo
+            .expect_save_auth_request()
+            .withf(|request| request.provider() == "mock" && request.link_user_id().is_none())
+            .times(1)
+            .returning(|_| Ok(()));
+
+        mock_client
+            .expect_authorization_url()
+            .returning(|_, request| Ok(format!("http://idp/authorize?state={}", request.state())));
+
+        let handler = StartOidcSignInHandler::new(
+            Arc::new(OidcAuthRequestRepository::Mock(mock_auth_request_repo)),
+            Arc::new(OidcClient::Mock(mock_client)),
+            vec![OidcProviderConfig::new_test_provider()],
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let cmd = StartOidcSignIn {
+            provider: "mock".into(),
+        };
+        let authorization = handler.handle(&ctx, cmd).await.unwrap();
+        assert!(
+            authorization
+                .authorization_url
+                .ends_with(&authorization.state)
+        );
+    }
+
+    #[tokio::test]
+    async fn start_oidc_sign_in_links_for_signed_in_user() {
+        let mut mock_auth_request_repo = MockOidcAuthRequestRepositoryTrait::new();
+        let mut mock_client = MockOidcClientTrait::new();
+        mock_auth_request_repo
+            .expect_save_auth_request()
+            .withf(|request| request.link_user_id().as_deref() == Some("test-user-id"))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        mock_client
+            .expect_authorization_url()
+            .returning(|_, request| Ok(format!("http://idp/authorize?state={}", request.state())));
+
+        let handler = StartOidcSignInHandler::new(
+            Arc::new(OidcAuthRequestRepository::Mock(mock_auth_request_repo)),
+            Arc::new(OidcClient::Mock(mock_client)),
+            vec![OidcProviderConfig::new_test_provider()],
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = StartOidcSignIn {
+            provider: "mock".into(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn start_oidc_sign_in_unknown_provider() {
+        let mut mock_auth_request_repo = MockOidcAuthRequestRepositoryTrait::new();
+        let mut mock_client = MockOidcClientTrait::new();
+        mock_auth_request_repo.expect_save_auth_request().never();
+
+        mock_client
+            .expect_authorization_url()
+            .returning(|_, request| Ok(format!("http://idp/authorize?state={}", request.state())));
+
+        let handler = StartOidcSignInHandler::new(
+            Arc::new(OidcAuthRequestRepository::Mock(mock_auth_request_repo)),
+            Arc::new(OidcClient::Mock(mock_client)),
+            vec![OidcProviderConfig::new_test_provider()],
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let cmd = StartOidcSignIn {
+            provider: "other".into(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::UnknownOidcProvider(_)
+            ))
+        ));
+    }
+}
@@ -5,10 +5,14 @@ use async_trait::async_trait;
 use shared::{
     auth::{AppContext, get_auth_user_from_ctx},
     command_handler::CommandHanlder,
+    event_hub::EventHub,
     guards::permissions::UserPermission,
 };
 
+use crate::app::audit_trail::{AuditTrail, UserChanges};
+use crate::domain::audit_log::AuditAction;
 use crate::domain::errors::UserDomainError;
+use crate::domain::events::{AccountChangeKind, ModerationQueueUpdateKind, UserEvent};
 use crate::domain::result::UserDomainResult;
 use crate::guards::UserGuards;
 use crate::infra::repository::user_repository::UserRepository;
@@ -20,25 +24,55 @@ pub struct UnbanUser {
 pub struct UnbanUserHandler {
     user_repo: Arc<UserRepository>,
     guard: Arc<dyn UserGuards>,
+    audit_trail: Arc<AuditTrail>,
+    events: Arc<EventHub<UserEvent>>,
 }
 
 impl UnbanUserHandler {
-    pub fn new(user_repo: Arc<UserRepository>, guard: Arc<dyn UserGuards>) -> Self {
-        Self { user_repo, guard }
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        guard: Arc<dyn UserGuards>,
+        audit_trail: Arc<AuditTrail>,
+        events: Arc<EventHub<UserEvent>>,
+    ) -> Self {
+        Self {
+            user_repo,
+            guard,
+            audit_trail,
+            events,
+        }
     }
 }
 
 #[async_trait]
 impl CommandHanlder<UnbanUser, UserDomainError> for UnbanUserHandler {
+    #[tracing::instrument(name = "command.unban_user", skip_all)]
     async fn handle(&self, ctx: &AppContext, cmd: UnbanUser) -> UserDomainResult<()> {
         let auth_user = get_auth_user_from_ctx(&ctx);
         self.guard
-            .authorize(&auth_user.0.role, &UserPermission::UnbanUser)?;
+            .authorize(auth_user, &UserPermission::UnbanUser)?;
+        let changes = UserChanges::default();
+        let tracker = changes.clone();
         self.user_repo
-            .unban_user(&cmd.user_id, |user| {
-                user.unban();
+            .unban_user(&cmd.user_id, move |user| {
+                tracker.track(user, |user| {
+                    user.unban();
+                });
             })
             .await?;
+        self.audit_trail
+            .record(ctx, AuditAction::UnbanUser, &cmd.user_id, changes.take())
+            .await?;
+        self.events.publish(UserEvent::account_changed(
+            &cmd.user_id,
+            AccountChangeKind::Unbanned,
+            None,
+        ));
+        self.events.publish(UserEvent::moderation_queue_updated(
+            &cmd.user_id,
+            ModerationQueueUpdateKind::UserUnbanned,
+            &auth_user.0.id,
+        ));
         Ok(())
     }
 }
@@ -46,6 +80,7 @@ impl CommandHanlder<UnbanUser, UserDomainError> for UnbanUserHandler {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use futures::StreamExt;
 
     use crate::domain::user::BanType;
     use crate::domain::user::User;
@@ -61,12 +96,17 @@ mod tests {
 
     #[tokio::test]
     async fn unban_user_success() {
+        let audit_trail = Arc::new(AuditTrail::in_memory());
+        let events = Arc::new(EventHub::new(8));
         let mut mock_user_repo = MockUserRepositoryTrait::new();
         let mut mock_guard = MockUserGuards::new();
 
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Admin), eq(UserPermission::UnbanUser))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Admin)),
+                eq(UserPermission::UnbanUser),
+            )
             .returning(|_, _| Ok(()));
 
         mock_user_repo
@@ -83,6 +123,8 @@ mod tests {
         let handler = UnbanUserHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
             Arc::new(mock_guard),
+            audit_trail.clone(),
+            events.clone(),
         );
         let cmd = UnbanUser {
             user_id: User::test_user_id(),
@@ -90,18 +132,37 @@ mod tests {
 
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Admin));
 
+        let mut published = events.subscribe();
         let result = handler.handle(&ctx, cmd).await;
-        assert!(result.is_ok())
+        assert!(result.is_ok());
+        let entries = audit_trail.recorded().await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::UnbanUser);
+        assert_eq!(entries[0].actor_id, "test-user-id");
+        assert_eq!(entries[0].target_id, User::test_user_id());
+        assert_eq!(entries[0].changes[0].field, "ban");
+        match published.next().await {
+            Some(UserEvent::AccountChanged(change)) => {
+                assert_eq!(change.user_id, User::test_user_id());
+                assert_eq!(change.kind, AccountChangeKind::Unbanned);
+            }
+            other => panic!("expected an account change, got {:?}", other),
+        }
     }
 
     #[tokio::test]
     async fn unban_user_unauthorized() {
+        let audit_trail = Arc::new(AuditTrail::in_memory());
+        let events = Arc::new(EventHub::new(8));
         let mut mock_user_repo = MockUserRepositoryTrait::new();
         let mut mock_guard = MockUserGuards::new();
 
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Regular), eq(UserPermission::UnbanUser))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::UnbanUser),
+            )
             .returning(|_, _| Err(UserDomainError::Unauthorized));
 
         mock_user_repo.expect_unban_user().never();
@@ -109,6 +170,8 @@ mod tests {
         let handler = UnbanUserHandler::new(
             Arc::new(UserRepository::Mock(mock_user_repo)),
             Arc::new(mock_guard),
+            audit_trail.clone(),
+            events.clone(),
         );
         let cmd = UnbanUser {
             user_id: User::test_user_id(),
@@ -116,5 +179,6 @@ mod tests {
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
         let result = handler.handle(&ctx, cmd).await;
         assert!(result.is_err());
+        assert!(audit_trail.recorded().await.is_empty());
     }
 }
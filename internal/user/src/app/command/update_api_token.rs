@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use serde::Deserialize;
+use validator::Validate;
+
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    command_handler::CommandHanlder,
+    guards::permissions::{Permission, UserPermission},
+};
+
+use crate::domain::{
+    errors::UserDomainError,
+    result::UserDomainResult,
+    user_auth::{api_token::ApiToken, errors::UserAuthError},
+};
+use crate::guards::UserGuards;
+use crate::infra::repository::api_token_repository::ApiTokenRepository;
+
+use super::create_api_token::ensure_session_user;
+
+/// Renames a token or replaces its scopes. The expiry cannot be extended.
+#[derive(Debug, Clone, Validate, Deserialize, InputObject)]
+pub struct UpdateApiToken {
+    pub id: String,
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    pub scopes: Option<Vec<Permission>>,
+}
+
+pub struct UpdateApiTokenHandler {
+    api_token_repo: Arc<ApiTokenRepository>,
+    guard: Arc<dyn UserGuards>,
+}
+
+impl UpdateApiTokenHandler {
+    pub fn new(api_token_repo: Arc<ApiTokenRepository>, guard: Arc<dyn UserGuards>) -> Self {
+        Self {
+            api_token_repo,
+            guard,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<UpdateApiToken, UserDomainError, ApiToken> for UpdateApiTokenHandler {
+    #[tracing::instrument(name = "command.update_api_token", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: UpdateApiToken) -> UserDomainResult<ApiToken> {
+        cmd.validate()?;
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &UserPermission::ManageApiTokens)?;
+        ensure_session_user(auth_user)?;
+
+        let mut api_token = self
+            .api_token_repo
+            .get_api_token(&cmd.id)
+            .await?
+            .filter(|token| token.user_id() == &auth_user.0.id)
+            .ok_or(UserAuthError::ApiTokenNotFound)?;
+        if let Some(name) = cmd.name {
+            api_token.rename(name);
+        }
+        if let Some(scopes) = cmd.scopes {
+            api_token.set_scopes(scopes)?;
+        }
+        self.api_token_repo
+            .update_api_token(api_token.clone())
+            .await?;
+        Ok(api_token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user_auth::otp_hasher::OtpHasher;
+    use crate::guards::MockUserGuards;
+    use crate::infra::repository::api_token_repository_trait::MockApiTokenRepositoryTrait;
+    use chrono::{Duration, Utc};
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn update_api_token_replaces_name_and_scopes() {
+        let mut mock_repo = MockApiTokenRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        let (token, _) = ApiToken::issue(
+            "test-user-id".into(),
+            "ci bot".into(),
+            vec![Permission::ViewUser],
+            Duration::days(30),
+            &OtpHasher::new_test_hasher(),
+            Utc::now(),
+        )
+        .unwrap();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_repo
+            .expect_get_api_token()
+            .returning(move |_| Ok(Some(token.clone())));
+        mock_repo
+            .expect_update_api_token()
+            .withf(|token| {
+                token.name() == "deploy bot" && token.scopes() == &vec![Permission::ListUsers]
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let handler = UpdateApiTokenHandler::new(
+            Arc::new(ApiTokenRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Moderator));
+        let cmd = UpdateApiToken {
+            id: "token-id".into(),
+            name: Some("deploy bot".into()),
+            scopes: Some(vec![Permission::ListUsers]),
+        };
+        assert!(handler.handle(&ctx, cmd).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn update_api_token_of_another_user_is_not_found() {
+        let mut mock_repo = MockApiTokenRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        let (token, _) = ApiToken::issue(
+            "someone-else".into(),
+            "ci bot".into(),
+            vec![Permission::ViewUser],
+            Duration::days(30),
+            &OtpHasher::new_test_hasher(),
+            Utc::now(),
+        )
+        .unwrap();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_repo
+            .expect_get_api_token()
+            .returning(move |_| Ok(Some(token.clone())));
+        mock_repo.expect_update_api_token().never();
+
+        let handler = UpdateApiTokenHandler::new(
+            Arc::new(ApiTokenRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = UpdateApiToken {
+            id: "token-id".into(),
+            name: Some("mine now".into()),
+            scopes: None,
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::ApiTokenNotFound
+            ))
+        ));
+    }
+}
@@ -4,22 +4,19 @@ use async_graphql::InputObject;
 use async_trait::async_trait;
 
 use crate::{
-    domain::user_auth::{errors::UserAuthError, otp::ComparedOtps},
+    app::{
+        metrics,
+        session_issuer::{SessionIssuer, SignInOutcome},
+    },
+    domain::user_auth::{errors::UserAuthError, otp::ComparedOtps, otp_hasher::OtpHasher},
     infra::repository::otp_repository::OtpRepository,
 };
 use serde::Deserialize;
 use validator::Validate;
 
-use shared::{
-    auth::{AppContext, jwt},
-    command_handler::CommandHanlder,
-    db_transactions::MockTransaction,
-};
+use shared::{auth::AppContext, command_handler::CommandHanlder, db_transactions::MockTransaction};
 
-use crate::domain::{
-    errors::UserDomainError, result::UserDomainResult, user::EmailStatus,
-    user_auth::otp::utils as otp_utils,
-};
+use crate::domain::{errors::UserDomainError, result::UserDomainResult, user::EmailStatus};
 use crate::infra::repository::user_repository::UserRepository;
 use shared::db_transactions::{DBTransaction, RepoDB};
 
@@ -32,26 +29,36 @@ pub struct VerifyEmailWithOtp {
 pub struct VerifyEmailWithOtpHandler {
     user_repo: Arc<UserRepository>,
     otp_repo: Arc<OtpRepository>,
+    otp_hasher: Arc<OtpHasher>,
+    session_issuer: Arc<SessionIssuer>,
 }
 
 impl VerifyEmailWithOtpHandler {
-    pub fn new(user_repo: Arc<UserRepository>, otp_repo: Arc<OtpRepository>) -> Self {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        otp_repo: Arc<OtpRepository>,
+        otp_hasher: Arc<OtpHasher>,
+        session_issuer: Arc<SessionIssuer>,
+    ) -> Self {
         Self {
             user_repo,
             otp_repo,
+            otp_hasher,
+            session_issuer,
         }
     }
 }
 
 #[async_trait]
-impl CommandHanlder<VerifyEmailWithOtp, UserDomainError, Option<String>>
+impl CommandHanlder<VerifyEmailWithOtp, UserDomainError, Option<SignInOutcome>>
     for VerifyEmailWithOtpHandler
 {
+    #[tracing::instrument(name = "command.verify_email_with_otp", skip_all)]
     async fn handle(
         &self,
         _ctx: &AppContext,
         cmd: VerifyEmailWithOtp,
-    ) -> UserDomainResult<Option<String>> {
+    ) -> UserDomainResult<Option<SignInOutcome>> {
         let mut otp_entry = self
             .otp_repo
             .get_otp_by_user_email(&cmd.email)
@@ -64,20 +71,29 @@ impl CommandHanlder<VerifyEmailWithOtp, UserDomainError, Option<String>>
             .await?
             .ok_or(UserDomainError::UserNotFound)?;
 
+        if *user.email_status() != EmailStatus::Unverified {
+            return Err(UserDomainError::UnableToVerifyEmail);
+        }
+
         if let Err(err) = otp_entry.validate_otp() {
-            if err == UserAuthError::TooManyAttempts {
-                //TODO: set up eventing system to delete OTP after too many attempts
-                self.otp_repo.delete_otp(&cmd.email).await?;
-                return Err(err.into());
+            metrics::record_otp_failure("email_verification", &err);
+            // The entry is kept, so its attempts carry over to the next code issued.
+            if err != UserAuthError::TooManyAttempts {
+                otp_entry.increment_attempts();
+                self.otp_repo.upsert_otp(otp_entry, None).await?;
             }
-            otp_entry.increment_attempts();
-            self.otp_repo.upsert_otp(otp_entry, None).await?;
             return Err(err.into());
         }
 
-        if otp_utils::compare_otps(&cmd.otp, otp_entry.otp_hash()) == ComparedOtps::NotEqual {
+        if self.otp_hasher.compare(
+            &cmd.otp,
+            otp_entry.key_id().as_deref(),
+            otp_entry.otp_hash(),
+        ) == ComparedOtps::NotEqual
+        {
             otp_entry.increment_attempts();
             self.otp_repo.upsert_otp(otp_entry, None).await?;
+            metrics::record_otp_failure("email_verification", &UserAuthError::InvalidOtp);
             return Err(UserAuthError::InvalidOtp.into());
         }
 
@@ -103,31 +119,27 @@ impl CommandHanlder<VerifyEmailWithOtp, UserDomainError, Option<String>>
 
                 if let Ok(..) = result {
                     session.commit_transaction().await?;
-                    let token = jwt::create_jwt(
-                        user.email().to_string(),
-                        user.role().to_owned(),
-                        user.id().to_string(),
-                    )?;
-                    tracing::info!(
-                        "OTP verified successfully for user: {}, token: {}",
-                        user.email(),
-                        token
-                    );
+                    let outcome = self.session_issuer.start_session(&user).await?;
+                    tracing::info!("Email verified successfully for user: {}", user.email());
                     //TODO: set up eventing system to delete OTP after successful  email verification
-                    return Ok(Some(token));
+                    return Ok(Some(outcome));
                 } else {
                     session.abort_transaction().await?;
                 }
                 Ok(None)
             }
             RepoDB::Mock => {
+                user.set_email_status(EmailStatus::Verified);
                 self.user_repo
-                    .upsert_user(user, Some(DBTransaction::Mock(&mut MockTransaction)))
+                    .upsert_user(
+                        user.clone(),
+                        Some(DBTransaction::Mock(&mut MockTransaction)),
+                    )
                     .await?;
                 self.otp_repo
                     .upsert_otp(otp_entry, Some(DBTransaction::Mock(&mut MockTransaction)))
                     .await?;
-                Ok(Some("token".to_string()))
+                Ok(Some(self.session_issuer.start_session(&user).await?))
             }
         }
     }
@@ -137,11 +149,12 @@ impl CommandHanlder<VerifyEmailWithOtp, UserDomainError, Option<String>>
 mod tests {
     use super::*;
     use crate::domain::user::{EmailStatus, User};
-    use crate::domain::user_auth::otp::{OtpEntry, utils as otp_utils};
+    use crate::domain::user_auth::otp::{MAX_ALLOWED_ATTEMPTS, OtpEntry, utils as otp_utils};
+    use crate::domain::user_auth::two_factor::TwoFactorPolicy;
     use crate::infra::repository::otp_repository_trait::MockOtpRepositoryTrait;
     use crate::infra::repository::user_repository_trait::MockUserRepositoryTrait;
     use mockall::predicate::eq;
-    use shared::auth::{AppContext, AuthUser};
+    use shared::auth::{AppContext, AuthUser, token_service::FakeTokenService};
     use shared::guards::roles::UserRole;
     use std::sync::Arc;
 
@@ -152,13 +165,15 @@ mod tests {
 
         let email = "test@example.com".to_string();
         let otp = "123456".to_string();
+        let otp_hash = OtpHasher::new_test_hasher().hash(&otp);
         let otp_entry = OtpEntry::new(
             email.clone(),
             false,
             0,
-            otp_utils::hash_otp(&otp),
+            otp_hash.hash,
             otp_utils::get_otp_expiration(),
-        );
+        )
+        .with_key_id(Some(otp_hash.key_id));
         let mut user = User::new_test_user(None);
         user.set_email_status(EmailStatus::Unverified);
 
@@ -176,17 +191,76 @@ mod tests {
 
         mock_user_repo.expect_upsert_user().returning(|_, _| Ok(()));
 
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
         let handler = VerifyEmailWithOtpHandler::new(
-            Arc::new(UserRepository::Mock(mock_user_repo)),
+            user_repo.clone(),
             Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
         );
 
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
         let cmd = VerifyEmailWithOtp { email, otp };
 
         let result = handler.handle(&ctx, cmd).await;
-        assert!(result.is_ok());
-        assert!(result.unwrap().is_some());
+        assert!(matches!(
+            result,
+            Ok(Some(SignInOutcome::Token {
+                two_factor_enrollment_required: false,
+                ..
+            }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn verify_email_with_otp_rejects_verified_user() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_otp_repo = MockOtpRepositoryTrait::new();
+
+        let email = "test@example.com".to_string();
+        let otp = "123456".to_string();
+        let otp_hash = OtpHasher::new_test_hasher().hash(&otp);
+        let otp_entry = OtpEntry::new(
+            email.clone(),
+            false,
+            0,
+            otp_hash.hash,
+            otp_utils::get_otp_expiration(),
+        )
+        .with_key_id(Some(otp_hash.key_id));
+        let mut user = User::new_test_user(None);
+        user.set_email_status(EmailStatus::Verified);
+
+        mock_otp_repo
+            .expect_get_otp_by_user_email()
+            .returning(move |_| Ok(Some(otp_entry.clone())));
+        mock_user_repo
+            .expect_get_user_by_username_or_email()
+            .returning(move |_, _| Ok(Some(user.clone())));
+        mock_otp_repo.expect_upsert_otp().never();
+        mock_user_repo.expect_upsert_user().never();
+
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
+        let handler = VerifyEmailWithOtpHandler::new(
+            user_repo.clone(),
+            Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
+        );
+
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = VerifyEmailWithOtp { email, otp };
+
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(result, Err(UserDomainError::UnableToVerifyEmail)));
     }
 
     #[tokio::test]
@@ -218,9 +292,16 @@ mod tests {
 
         mock_otp_repo.expect_upsert_otp().returning(|_, _| Ok(()));
 
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
         let handler = VerifyEmailWithOtpHandler::new(
-            Arc::new(UserRepository::Mock(mock_user_repo)),
+            user_repo.clone(),
             Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
         );
 
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
@@ -229,4 +310,57 @@ mod tests {
         let result = handler.handle(&ctx, cmd).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn verify_email_with_otp_too_many_attempts_keeps_entry() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_otp_repo = MockOtpRepositoryTrait::new();
+
+        let email = "test@example.com".to_string();
+        let otp = "123456".to_string();
+        let otp_entry = OtpEntry::new(
+            email.clone(),
+            false,
+            MAX_ALLOWED_ATTEMPTS,
+            otp_utils::hash_otp(&otp),
+            otp_utils::get_otp_expiration(),
+        );
+        let mut user = User::new_test_user(None);
+        user.set_email_status(EmailStatus::Unverified);
+
+        mock_otp_repo
+            .expect_get_otp_by_user_email()
+            .with(eq(email.clone()))
+            .returning(move |_| Ok(Some(otp_entry.clone())));
+
+        mock_user_repo
+            .expect_get_user_by_username_or_email()
+            .returning(move |_, _| Ok(Some(user.clone())));
+
+        mock_otp_repo.expect_delete_otp().never();
+        mock_otp_repo.expect_upsert_otp().never();
+
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
+        let handler = VerifyEmailWithOtpHandler::new(
+            user_repo.clone(),
+            Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
+        );
+
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = VerifyEmailWithOtp { email, otp };
+
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::TooManyAttempts
+            ))
+        ));
+    }
 }
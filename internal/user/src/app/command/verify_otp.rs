@@ -4,24 +4,25 @@ use async_graphql::InputObject;
 use async_trait::async_trait;
 
 use crate::{
-    domain::user_auth::{errors::UserAuthError, otp::ComparedOtps},
+    app::{
+        metrics,
+        session_issuer::{SessionIssuer, SignInOutcome},
+    },
+    domain::user_auth::{errors::UserAuthError, otp::ComparedOtps, otp_hasher::OtpHasher},
     infra::repository::otp_repository::OtpRepository,
 };
 use serde::Deserialize;
+use utoipa::ToSchema;
 use validator::Validate;
 
-use shared::{
-    auth::{AppContext, jwt},
-    command_handler::CommandHanlder,
-};
+use shared::{auth::AppContext, command_handler::CommandHanlder};
 
-use crate::domain::{
-    errors::UserDomainError, result::UserDomainResult, user_auth::otp::utils as otp_utils,
-};
+use crate::domain::{errors::UserDomainError, result::UserDomainResult};
 use crate::infra::repository::user_repository::UserRepository;
 
-#[derive(Debug, Clone, Validate, Deserialize, InputObject)]
+#[derive(Debug, Clone, Validate, Deserialize, InputObject, ToSchema)]
 pub struct VerifyOtp {
+    #[schema(format = Email)]
     pub email: String,
     pub otp: String,
 }
@@ -29,20 +30,30 @@ pub struct VerifyOtp {
 pub struct VerifyOtpHandler {
     user_repo: Arc<UserRepository>,
     otp_repo: Arc<OtpRepository>,
+    otp_hasher: Arc<OtpHasher>,
+    session_issuer: Arc<SessionIssuer>,
 }
 
 impl VerifyOtpHandler {
-    pub fn new(user_repo: Arc<UserRepository>, otp_repo: Arc<OtpRepository>) -> Self {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        otp_repo: Arc<OtpRepository>,
+        otp_hasher: Arc<OtpHasher>,
+        session_issuer: Arc<SessionIssuer>,
+    ) -> Self {
         Self {
             user_repo,
             otp_repo,
+            otp_hasher,
+            session_issuer,
         }
     }
 }
 
 #[async_trait]
-impl CommandHanlder<VerifyOtp, UserDomainError, String> for VerifyOtpHandler {
-    async fn handle(&self, _ctx: &AppContext, cmd: VerifyOtp) -> UserDomainResult<String> {
+impl CommandHanlder<VerifyOtp, UserDomainError, SignInOutcome> for VerifyOtpHandler {
+    #[tracing::instrument(name = "command.verify_otp", skip_all)]
+    async fn handle(&self, _ctx: &AppContext, cmd: VerifyOtp) -> UserDomainResult<SignInOutcome> {
         let mut otp_entry = self
             .otp_repo
             .get_otp_by_user_email(&cmd.email)
@@ -55,33 +66,31 @@ impl CommandHanlder<VerifyOtp, UserDomainError, String> for VerifyOtpHandler {
             .await?
             .ok_or(UserDomainError::UserNotFound)?;
 
-        if let Err(err) = otp_entry.validate_otp() {
-            //TODO: set up eventing system to delete OTP after too many attempts
-            self.otp_repo.delete_otp(&cmd.email).await?;
-            return Err(err.into());
-        }
-
-        if otp_utils::compare_otps(&cmd.otp, otp_entry.otp_hash()) == ComparedOtps::NotEqual {
+        // The entry is kept when rejected, so its attempts carry over to the next code issued.
+        otp_entry
+            .validate_otp()
+            .inspect_err(|err| metrics::record_otp_failure("sign_in", err))?;
+
+        if self.otp_hasher.compare(
+            &cmd.otp,
+            otp_entry.key_id().as_deref(),
+            otp_entry.otp_hash(),
+        ) == ComparedOtps::NotEqual
+        {
             otp_entry.increment_attempts();
             self.otp_repo.upsert_otp(otp_entry, None).await?; // TODO: Perhaps, handle this update via eventing system
+            metrics::record_otp_failure("sign_in", &UserAuthError::MissMatchOtp);
             return Err(UserAuthError::MissMatchOtp.into());
         }
 
         otp_entry.mark_as_used();
         otp_entry.increment_attempts();
         self.otp_repo.upsert_otp(otp_entry, None).await?;
-        let token = jwt::create_jwt(
-            user.email().to_string(),
-            user.role().to_owned(),
-            user.id().to_string(),
-        )?;
+
+        let outcome = self.session_issuer.start_session(&user).await?;
         //TODO: Set up eventing system to delete OTP after successful otp verification
-        tracing::info!(
-            "OTP verified successfully for user: {}, token: {}",
-            user.email(),
-            token
-        );
-        Ok(token)
+        tracing::info!("OTP verified successfully for user: {}", user.email());
+        Ok(outcome)
     }
 }
 
@@ -90,9 +99,11 @@ mod tests {
     use super::*;
     use crate::domain::user::User;
     use crate::domain::user_auth::otp::{MAX_ALLOWED_ATTEMPTS, OtpEntry, utils as otp_utils};
+    use crate::domain::user_auth::two_factor::TwoFactorPolicy;
     use crate::infra::repository::otp_repository_trait::MockOtpRepositoryTrait;
     use crate::infra::repository::user_repository_trait::MockUserRepositoryTrait;
     use mockall::predicate::eq;
+    use shared::auth::token_service::{FakeTokenService, TokenService};
     use shared::auth::{AppContext, AuthUser};
     use shared::guards::roles::UserRole;
     use std::sync::Arc;
@@ -104,13 +115,15 @@ mod tests {
 
         let email = "test@example.com".to_string();
         let otp = "123456".to_string();
+        let otp_hash = OtpHasher::new_test_hasher().hash(&otp);
         let otp_entry = OtpEntry::new(
             email.clone(),
             false,
             0,
-            otp_utils::hash_otp(&otp),
+            otp_hash.hash,
             otp_utils::get_otp_expiration(),
-        );
+        )
+        .with_key_id(Some(otp_hash.key_id));
         let user = User::new_test_user(None);
 
         let mut expected_otp_entry = otp_entry.clone();
@@ -132,17 +145,29 @@ mod tests {
             .withf(move |otp, trx| otp == &expected_otp_entry && trx.is_none())
             .returning(|_, _| Ok(()));
 
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
         let handler = VerifyOtpHandler::new(
-            Arc::new(UserRepository::Mock(mock_user_repo)),
+            user_repo.clone(),
             Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
         );
 
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
         let cmd = VerifyOtp { email, otp };
 
         let result = handler.handle(&ctx, cmd).await;
-        assert!(result.is_ok());
-        assert!(!result.unwrap().is_empty());
+        assert!(matches!(
+            result,
+            Ok(SignInOutcome::Token {
+                two_factor_enrollment_required: false,
+                ..
+            })
+        ));
     }
 
     #[tokio::test]
@@ -179,9 +204,16 @@ mod tests {
             .withf(move |otp, trx| otp == &expected_otp_entry && trx.is_none())
             .returning(|_, _| Ok(()));
 
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
         let handler = VerifyOtpHandler::new(
-            Arc::new(UserRepository::Mock(mock_user_repo)),
+            user_repo.clone(),
             Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
         );
 
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
@@ -217,16 +249,20 @@ mod tests {
             .with(eq("".to_string()), eq(email.clone()))
             .returning(move |_, _| Ok(Some(user.clone())));
 
-        mock_otp_repo
-            .expect_delete_otp()
-            .with(eq(email.clone()))
-            .returning(move |_| Ok(()));
+        mock_otp_repo.expect_delete_otp().never();
 
         mock_otp_repo.expect_upsert_otp().never();
 
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
         let handler = VerifyOtpHandler::new(
-            Arc::new(UserRepository::Mock(mock_user_repo)),
+            user_repo.clone(),
             Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
         );
 
         let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
@@ -235,4 +271,120 @@ mod tests {
         let result = handler.handle(&ctx, cmd).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn verify_otp_starts_two_factor_challenge() {
+        use crate::domain::user_auth::two_factor::TwoFactor;
+
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_otp_repo = MockOtpRepositoryTrait::new();
+
+        let email = "test@example.com".to_string();
+        let otp_hash = OtpHasher::new_test_hasher().hash("123456");
+        let otp_entry = OtpEntry::new(
+            email.clone(),
+            false,
+            0,
+            otp_hash.hash,
+            otp_utils::get_otp_expiration(),
+        )
+        .with_key_id(Some(otp_hash.key_id));
+        let mut two_factor = TwoFactor::enroll();
+        two_factor.enable(vec![], chrono::Utc::now());
+        let user = User::new_test_user(None).with_two_factor(Some(two_factor));
+
+        mock_otp_repo
+            .expect_get_otp_by_user_email()
+            .returning(move |_| Ok(Some(otp_entry.clone())));
+        mock_otp_repo.expect_upsert_otp().returning(|_, _| Ok(()));
+        mock_user_repo
+            .expect_get_user_by_username_or_email()
+            .returning(move |_, _| Ok(Some(user.clone())));
+        mock_user_repo
+            .expect_set_two_factor_challenge()
+            .withf(|uid, challenge| uid == User::test_user_id() && *challenge.attempts() == 0)
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock_user_repo.expect_update_two_factor().never();
+
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
+        let handler = VerifyOtpHandler::new(
+            user_repo.clone(),
+            Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy::default(),
+                Arc::new(FakeTokenService::new()),
+            )),
+        );
+
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let cmd = VerifyOtp {
+            email,
+            otp: "123456".into(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(result, Ok(SignInOutcome::TwoFactorChallenge(_))));
+    }
+
+    #[tokio::test]
+    async fn verify_otp_requires_enrollment_for_admin() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut mock_otp_repo = MockOtpRepositoryTrait::new();
+
+        let email = "test@example.com".to_string();
+        let otp_hash = OtpHasher::new_test_hasher().hash("123456");
+        let otp_entry = OtpEntry::new(
+            email.clone(),
+            false,
+            0,
+            otp_hash.hash,
+            otp_utils::get_otp_expiration(),
+        )
+        .with_key_id(Some(otp_hash.key_id));
+        let user = User::new_test_user(Some(UserRole::Admin));
+
+        mock_otp_repo
+            .expect_get_otp_by_user_email()
+            .returning(move |_| Ok(Some(otp_entry.clone())));
+        mock_otp_repo.expect_upsert_otp().returning(|_, _| Ok(()));
+        mock_user_repo
+            .expect_get_user_by_username_or_email()
+            .returning(move |_, _| Ok(Some(user.clone())));
+
+        let user_repo = Arc::new(UserRepository::Mock(mock_user_repo));
+        let handler = VerifyOtpHandler::new(
+            user_repo.clone(),
+            Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+            Arc::new(SessionIssuer::new(
+                user_repo,
+                TwoFactorPolicy {
+                    required_roles: vec![UserRole::Admin, UserRole::Moderator],
+                    ..TwoFactorPolicy::default()
+                },
+                Arc::new(FakeTokenService::new()),
+            )),
+        );
+
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let cmd = VerifyOtp {
+            email,
+            otp: "123456".into(),
+        };
+        let token_service = FakeTokenService::new();
+        let Ok(SignInOutcome::Token {
+            token,
+            two_factor_enrollment_required,
+        }) = handler.handle(&ctx, cmd).await
+        else {
+            panic!("expected a token");
+        };
+        assert!(two_factor_enrollment_required);
+        assert_eq!(
+            UserRole::Regular,
+            token_service.verify(&token).unwrap().role
+        );
+    }
 }
@@ -0,0 +1,239 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use shared::{
+    auth::{
+        AppContext,
+        token_service::{TokenRequest, TokenService},
+    },
+    command_handler::CommandHanlder,
+};
+
+use crate::app::metrics;
+use crate::domain::{
+    errors::UserDomainError,
+    result::UserDomainResult,
+    user_auth::{errors::UserAuthError, otp_hasher::OtpHasher},
+};
+use crate::infra::repository::user_repository::UserRepository;
+
+/// Completes a sign-in started by `VerifyOtp` for a user with 2FA enabled.
+/// `code` is a TOTP code or one of the user's recovery codes.
+#[derive(Debug, Clone, Validate, Deserialize, InputObject, ToSchema)]
+pub struct VerifyTwoFactor {
+    #[validate(email)]
+    #[schema(format = Email)]
+    pub email: String,
+    /// The challenge returned when the sign in OTP was verified.
+    pub challenge: String,
+    #[validate(length(min = 1))]
+    #[schema(min_length = 1)]
+    pub code: String,
+}
+
+pub struct VerifyTwoFactorHandler {
+    user_repo: Arc<UserRepository>,
+    otp_hasher: Arc<OtpHasher>,
+    token_service: Arc<dyn TokenService>,
+}
+
+impl VerifyTwoFactorHandler {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        otp_hasher: Arc<OtpHasher>,
+        token_service: Arc<dyn TokenService>,
+    ) -> Self {
+        Self {
+            user_repo,
+            otp_hasher,
+            token_service,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHanlder<VerifyTwoFactor, UserDomainError, String> for VerifyTwoFactorHandler {
+    #[tracing::instrument(name = "command.verify_two_factor", skip_all)]
+    async fn handle(&self, _ctx: &AppContext, cmd: VerifyTwoFactor) -> UserDomainResult<String> {
+        cmd.validate()?;
+        let user = self
+            .user_repo
+            .get_user_by_username_or_email("", &cmd.email)
+            .await?
+            .ok_or(UserDomainError::UserNotFound)?;
+        let mut two_factor = user
+            .two_factor()
+            .filter(|two_factor| two_factor.is_enabled())
+            .cloned()
+            .ok_or(UserAuthError::InvalidTwoFactorChallenge)?;
+
+        let now = Utc::now();
+        two_factor
+            .validate_challenge(&cmd.challenge, now)
+            .inspect_err(|err| {
+                if *err == UserAuthError::TooManyAttempts {
+                    metrics::LOCKOUTS.inc(&[("check", "two_factor")]);
+                }
+            })?;
+
+        let verified = two_factor.verify(&cmd.code, &self.otp_hasher, now);
+        if verified {
+            two_factor.clear_challenge();
+        } else {
+            two_factor.increment_challenge_attempts();
+        }
+        // Persisted either way: the used step, consumed recovery code or failed attempt.
+        self.user_repo
+            .update_two_factor(user.id(), |user| {
+                user.set_two_factor(Some(two_factor));
+            })
+            .await?;
+        if !verified {
+            return Err(UserAuthError::InvalidTwoFactorCode.into());
+        }
+
+        let token = self.token_service.issue(TokenRequest::new(
+            user.email().to_string(),
+            user.role().to_owned(),
+            user.id().to_string(),
+        ))?;
+        tracing::info!(
+            "Two-factor verified successfully for user: {}",
+            user.email()
+        );
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::User;
+    use crate::domain::user_auth::two_factor::{MAX_CHALLENGE_ATTEMPTS, TwoFactor};
+    use crate::infra::repository::user_repository_trait::MockUserRepositoryTrait;
+    use shared::{
+        auth::{AuthUser, token_service::FakeTokenService},
+        guards::roles::UserRole,
+    };
+
+    #[tokio::test]
+    async fn verify_two_factor_success() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut two_factor = TwoFactor::enroll();
+        two_factor.enable(vec![], Utc::now());
+        let challenge = two_factor.start_challenge(Utc::now());
+        let code = two_factor.generate_code(Utc::now());
+        let user = User::new_test_user(Some(UserRole::Admin)).with_two_factor(Some(two_factor));
+
+        mock_user_repo
+            .expect_get_user_by_username_or_email()
+            .returning(move |_, _| Ok(Some(user.clone())));
+        mock_user_repo
+            .expect_update_two_factor()
+            .returning(|_, update_fn| {
+                let mut user = User::new_test_user(None);
+                update_fn(&mut user);
+                let two_factor = user.two_factor().unwrap();
+                assert!(two_factor.challenge().is_none());
+                assert!(two_factor.last_used_step().is_some());
+                Ok(())
+            });
+
+        let handler = VerifyTwoFactorHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+            Arc::new(FakeTokenService::new()),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let cmd = VerifyTwoFactor {
+            email: "johndoe@gmail.com".into(),
+            challenge,
+            code,
+        };
+        let token = handler.handle(&ctx, cmd).await.unwrap();
+        let claims = FakeTokenService::new().verify(&token).unwrap();
+        assert_eq!(UserRole::Admin, claims.role);
+    }
+
+    #[tokio::test]
+    async fn verify_two_factor_wrong_code_counts_attempt() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut two_factor = TwoFactor::enroll();
+        two_factor.enable(vec![], Utc::now());
+        let challenge = two_factor.start_challenge(Utc::now());
+        let user = User::new_test_user(None).with_two_factor(Some(two_factor));
+
+        mock_user_repo
+            .expect_get_user_by_username_or_email()
+            .returning(move |_, _| Ok(Some(user.clone())));
+        mock_user_repo
+            .expect_update_two_factor()
+            .returning(|_, update_fn| {
+                let mut user = User::new_test_user(None);
+                update_fn(&mut user);
+                let challenge = user.two_factor().unwrap().challenge().clone().unwrap();
+                assert_eq!(1, *challenge.attempts());
+                Ok(())
+            });
+
+        let handler = VerifyTwoFactorHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+            Arc::new(FakeTokenService::new()),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let cmd = VerifyTwoFactor {
+            email: "johndoe@gmail.com".into(),
+            challenge,
+            code: "not-a-recovery-code".into(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::InvalidTwoFactorCode
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn verify_two_factor_exhausted_challenge() {
+        let mut mock_user_repo = MockUserRepositoryTrait::new();
+        let mut two_factor = TwoFactor::enroll();
+        two_factor.enable(vec![], Utc::now());
+        let challenge = two_factor.start_challenge(Utc::now());
+        (0..MAX_CHALLENGE_ATTEMPTS).for_each(|_| two_factor.increment_challenge_attempts());
+        let code = two_factor.generate_code(Utc::now());
+        let user = User::new_test_user(None).with_two_factor(Some(two_factor));
+
+        mock_user_repo
+            .expect_get_user_by_username_or_email()
+            .returning(move |_, _| Ok(Some(user.clone())));
+        mock_user_repo.expect_update_two_factor().never();
+
+        let handler = VerifyTwoFactorHandler::new(
+            Arc::new(UserRepository::Mock(mock_user_repo)),
+            Arc::new(OtpHasher::new_test_hasher()),
+            Arc::new(FakeTokenService::new()),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Guest));
+        let cmd = VerifyTwoFactor {
+            email: "johndoe@gmail.com".into(),
+            challenge,
+            code,
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::TooManyAttempts
+            ))
+        ));
+    }
+}
@@ -0,0 +1,64 @@
+//! Counters for user domain activity, exposed on `/metrics`.
+use shared::metrics::Counter;
+
+use crate::domain::{errors::UserDomainError, user_auth::errors::UserAuthError};
+
+pub static SIGN_UPS: Counter = Counter::new("user_sign_ups_total", "Accounts created");
+pub static OTP_FAILURES: Counter = Counter::new(
+    "user_otp_failures_total",
+    "One-time passwords rejected, by purpose and reason",
+);
+pub static LOCKOUTS: Counter = Counter::new(
+    "user_lockouts_total",
+    "Codes refused because of too many failed attempts, by check",
+);
+pub static BANS: Counter = Counter::new("user_bans_total", "Users banned");
+pub static BADGE_AWARDS: Counter = Counter::new("user_badge_awards_total", "Badges awarded");
+pub static DOMAIN_ERRORS: Counter = Counter::new(
+    "user_domain_errors_total",
+    "User domain errors returned by the APIs, by API, operation and variant",
+);
+
+/// `purpose` is what the code was for, such as `sign_in`.
+pub fn record_otp_failure(purpose: &str, err: &UserAuthError) {
+    let reason = match err {
+        UserAuthError::TooManyAttempts => {
+            LOCKOUTS.inc(&[("check", "otp")]);
+            "too_many_attempts"
+        }
+        UserAuthError::OtpExpired => "expired",
+        UserAuthError::OtpAlreadyUsed => "already_used",
+        UserAuthError::InvalidOtp | UserAuthError::MissMatchOtp => "mismatch",
+        _ => "other",
+    };
+    OTP_FAILURES.inc(&[("purpose", purpose), ("reason", reason)]);
+}
+
+pub fn record_error(api: &str, operation: &str, err: &UserDomainError) {
+    DOMAIN_ERRORS.inc(&[
+        ("api", api),
+        ("operation", operation),
+        ("error", err.kind()),
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::metrics::render;
+
+    #[test]
+    fn too_many_attempts_counts_as_lockout() {
+        record_otp_failure("metrics_test", &UserAuthError::TooManyAttempts);
+        record_otp_failure("metrics_test", &UserAuthError::MissMatchOtp);
+
+        let out = render();
+        assert!(out.contains(
+            "user_otp_failures_total{purpose=\"metrics_test\",reason=\"too_many_attempts\"} 1"
+        ));
+        assert!(
+            out.contains("user_otp_failures_total{purpose=\"metrics_test\",reason=\"mismatch\"} 1")
+        );
+        assert!(out.contains("user_lockouts_total{check=\"otp\"}"));
+    }
+}
@@ -0,0 +1,220 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use shared::auth::AppContext;
+
+use crate::domain::{
+    canonical::canonical_email,
+    result::UserDomainResult,
+    user_auth::{
+        otp::{OtpEntry, OtpIssuePolicy, utils as otp_utils},
+        otp_hasher::OtpHasher,
+    },
+};
+use crate::infra::repository::{
+    otp_issuance_repository::OtpIssuanceRepository, otp_repository::OtpRepository,
+};
+
+pub struct IssuedOtp {
+    pub entry: OtpEntry,
+    /// The plain code to deliver to the user. Only its hash is stored.
+    pub code: String,
+}
+
+/// Issues codes for every flow that sends one, enforcing the [`OtpIssuePolicy`]
+/// per email and, when known, per client IP.
+pub struct OtpIssuer {
+    otp_repo: Arc<OtpRepository>,
+    issuance_repo: Arc<OtpIssuanceRepository>,
+    policy: OtpIssuePolicy,
+    hasher: Arc<OtpHasher>,
+}
+
+impl OtpIssuer {
+    pub fn new(
+        otp_repo: Arc<OtpRepository>,
+        issuance_repo: Arc<OtpIssuanceRepository>,
+        policy: OtpIssuePolicy,
+        hasher: Arc<OtpHasher>,
+    ) -> Self {
+        Self {
+            otp_repo,
+            issuance_repo,
+            policy,
+            hasher,
+        }
+    }
+
+    /// Checks the issue limits, records the issue and builds the next entry for
+    /// `email`. Storing the entry is left to the caller.
+    pub async fn prepare(&self, ctx: &AppContext, email: &str) -> UserDomainResult<IssuedOtp> {
+        let now = Utc::now();
+        let since = now - self.policy.window;
+        let email_key = format!("email:{}", canonical_email(email));
+        let ip_key = ctx.client_ip.as_ref().map(|ip| format!("ip:{}", ip));
+
+        let email_issued = self
+            .issuance_repo
+            .get_issuances_since(&email_key, since)
+            .await?;
+        let ip_issued = match &ip_key {
+            Some(key) => self.issuance_repo.get_issuances_since(key, since).await?,
+            None => vec![],
+        };
+        self.policy.check(now, &email_issued, &ip_issued)?;
+
+        let expire_at = now + self.policy.window;
+        self.issuance_repo
+            .record_issuance(&email_key, now, expire_at)
+            .await?;
+        if let Some(key) = &ip_key {
+            self.issuance_repo
+                .record_issuance(key, now, expire_at)
+                .await?;
+        }
+
+        let previous = self.otp_repo.get_otp_by_user_email(email).await?;
+        let code = otp_utils::generate_otp();
+        let entry = OtpEntry::reissue(
+            previous.as_ref(),
+            email.to_string(),
+            self.hasher.hash(&code),
+            otp_utils::get_otp_expiration(),
+        );
+        Ok(IssuedOtp { entry, code })
+    }
+
+    /// Prepares and stores a new code for `email`, returning the plain code.
+    pub async fn issue(&self, ctx: &AppContext, email: &str) -> UserDomainResult<String> {
+        let IssuedOtp { entry, code } = self.prepare(ctx, email).await?;
+        self.otp_repo.upsert_otp(entry, None).await?;
+        Ok(code)
+    }
+
+    /// An issuer whose limits are never reached, for handler tests.
+    #[cfg(test)]
+    pub fn new_test_issuer(otp_repo: Arc<OtpRepository>) -> Self {
+        use crate::infra::repository::otp_issuance_repository_trait::MockOtpIssuanceRepositoryTrait;
+
+        let mut issuance_repo = MockOtpIssuanceRepositoryTrait::new();
+        issuance_repo
+            .expect_get_issuances_since()
+            .returning(|_, _| Ok(vec![]));
+        issuance_repo
+            .expect_record_issuance()
+            .returning(|_, _, _| Ok(()));
+        Self::new(
+            otp_repo,
+            Arc::new(OtpIssuanceRepository::Mock(issuance_repo)),
+            OtpIssuePolicy::default(),
+            Arc::new(OtpHasher::new_test_hasher()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use mockall::predicate::eq;
+
+    use crate::domain::{
+        errors::UserDomainError,
+        user_auth::{errors::UserAuthError, otp::MAX_ALLOWED_ATTEMPTS},
+    };
+    use crate::infra::repository::{
+        otp_issuance_repository_trait::MockOtpIssuanceRepositoryTrait,
+        otp_repository_trait::MockOtpRepositoryTrait,
+    };
+
+    #[tokio::test]
+    async fn issue_keeps_attempts_of_live_code() {
+        let mut mock_otp_repo = MockOtpRepositoryTrait::new();
+        let email = "test@example.com".to_string();
+        let previous = OtpEntry::new(
+            email.clone(),
+            false,
+            MAX_ALLOWED_ATTEMPTS,
+            otp_utils::hash_otp("123456"),
+            otp_utils::get_otp_expiration(),
+        );
+
+        mock_otp_repo
+            .expect_get_otp_by_user_email()
+            .with(eq(email.clone()))
+            .returning(move |_| Ok(Some(previous.clone())));
+        mock_otp_repo
+            .expect_upsert_otp()
+            .withf(|otp, _| *otp.attempts() == MAX_ALLOWED_ATTEMPTS && !otp.is_used())
+            .returning(|_, _| Ok(()));
+
+        let issuer = OtpIssuer::new_test_issuer(Arc::new(OtpRepository::Mock(mock_otp_repo)));
+        let result = issuer.issue(&AppContext::new(), &email).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn issue_records_email_and_ip() {
+        let mut mock_otp_repo = MockOtpRepositoryTrait::new();
+        let mut mock_issuance_repo = MockOtpIssuanceRepositoryTrait::new();
+
+        mock_issuance_repo
+            .expect_get_issuances_since()
+            .times(2)
+            .returning(|_, _| Ok(vec![]));
+        mock_issuance_repo
+            .expect_record_issuance()
+            .withf(|key, _, _| key == "email:test@example.com")
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        mock_issuance_repo
+            .expect_record_issuance()
+            .withf(|key, _, _| key == "ip:10.0.0.1")
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        mock_otp_repo
+            .expect_get_otp_by_user_email()
+            .returning(|_| Ok(None));
+        mock_otp_repo
+            .expect_upsert_otp()
+            .withf(|otp, _| *otp.attempts() == 0)
+            .returning(|_, _| Ok(()));
+
+        let issuer = OtpIssuer::new(
+            Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpIssuanceRepository::Mock(mock_issuance_repo)),
+            OtpIssuePolicy::default(),
+            Arc::new(OtpHasher::new_test_hasher()),
+        );
+        let ctx = AppContext::new().with_client_ip(Some("10.0.0.1".into()));
+        let result = issuer.issue(&ctx, "Test@Example.com").await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn issue_rejects_resend_within_min_interval() {
+        let mut mock_otp_repo = MockOtpRepositoryTrait::new();
+        let mut mock_issuance_repo = MockOtpIssuanceRepositoryTrait::new();
+
+        let last = Utc::now() - Duration::seconds(5);
+        mock_issuance_repo
+            .expect_get_issuances_since()
+            .returning(move |_, _| Ok(vec![last]));
+        mock_issuance_repo.expect_record_issuance().never();
+        mock_otp_repo.expect_upsert_otp().never();
+
+        let issuer = OtpIssuer::new(
+            Arc::new(OtpRepository::Mock(mock_otp_repo)),
+            Arc::new(OtpIssuanceRepository::Mock(mock_issuance_repo)),
+            OtpIssuePolicy::default(),
+            Arc::new(OtpHasher::new_test_hasher()),
+        );
+        let result = issuer.issue(&AppContext::new(), "test@example.com").await;
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::OtpResendTooSoon(_)
+            ))
+        ));
+    }
+}
@@ -1,3 +1,9 @@
+pub mod account_changes;
+pub mod api_tokens;
+pub mod audit_log;
+pub mod moderation_queue;
 pub mod user_by_email;
 pub mod user_by_id;
+pub mod user_by_username;
 pub mod users;
+pub mod users_by_ids;
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::{StreamExt, future, stream::BoxStream};
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    event_hub::EventHub,
+    guards::permissions::UserPermission,
+    query_handler::QueryHandler,
+};
+
+use crate::domain::{
+    errors::UserDomainError,
+    events::{AccountChange, UserEvent},
+    result::UserDomainResult,
+};
+use crate::guards::UserGuards;
+
+/// Changes made to the signed in user's account from now on.
+pub struct WatchMyAccount;
+
+pub struct WatchMyAccountHandler {
+    events: Arc<EventHub<UserEvent>>,
+    guard: Arc<dyn UserGuards>,
+}
+
+impl WatchMyAccountHandler {
+    pub fn new(events: Arc<EventHub<UserEvent>>, guard: Arc<dyn UserGuards>) -> Self {
+        Self { events, guard }
+    }
+}
+
+#[async_trait]
+impl QueryHandler<WatchMyAccount, BoxStream<'static, AccountChange>, UserDomainError>
+    for WatchMyAccountHandler
+{
+    #[tracing::instrument(name = "query.account_changes", skip_all)]
+    async fn handle(
+        &self,
+        ctx: &AppContext,
+        _cmd: WatchMyAccount,
+    ) -> UserDomainResult<BoxStream<'static, AccountChange>> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard.authorize(auth_user, &UserPermission::ViewUser)?;
+        let user_id = auth_user.0.id.clone();
+        let changes = self.events.subscribe().filter_map(move |event| {
+            future::ready(match event {
+                UserEvent::AccountChanged(change) if change.user_id == user_id => Some(change),
+                _ => None,
+            })
+        });
+        Ok(changes.boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::events::AccountChangeKind;
+    use crate::guards::MockUserGuards;
+    use mockall::predicate::eq;
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn watch_my_account_only_sees_own_changes() {
+        let events = Arc::new(EventHub::new(8));
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_authorize()
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::ViewUser),
+            )
+            .returning(|_, _| Ok(()));
+
+        let handler = WatchMyAccountHandler::new(events.clone(), Arc::new(mock_guard));
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let mut changes = handler.handle(&ctx, WatchMyAccount).await.unwrap();
+
+        events.publish(UserEvent::account_changed(
+            "someone-else",
+            AccountChangeKind::Banned,
+            None,
+        ));
+        events.publish(UserEvent::account_changed(
+            "test-user-id",
+            AccountChangeKind::BadgeAwarded,
+            Some("early".into()),
+        ));
+
+        let change = changes.next().await.unwrap();
+        assert_eq!(change.kind, AccountChangeKind::BadgeAwarded);
+        assert_eq!(change.detail.as_deref(), Some("early"));
+    }
+
+    #[tokio::test]
+    async fn watch_my_account_unauthorized() {
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_authorize()
+            .returning(|_, _| Err(UserDomainError::Unauthorized));
+
+        let handler = WatchMyAccountHandler::new(Arc::new(EventHub::new(8)), Arc::new(mock_guard));
+        let ctx = AppContext::new().with_user(AuthUser::guest());
+        assert!(handler.handle(&ctx, WatchMyAccount).await.is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    guards::permissions::UserPermission,
+    query_handler::QueryHandler,
+};
+
+use crate::app::command::create_api_token::ensure_session_user;
+use crate::domain::{
+    errors::UserDomainError, result::UserDomainResult, user_auth::api_token::ApiToken,
+};
+use crate::guards::UserGuards;
+use crate::infra::repository::api_token_repository::ApiTokenRepository;
+
+/// The signed in user's API tokens, newest first.
+pub struct GetApiTokens;
+
+pub struct GetApiTokensHandler {
+    api_token_repo: Arc<ApiTokenRepository>,
+    guard: Arc<dyn UserGuards>,
+}
+
+impl GetApiTokensHandler {
+    pub fn new(api_token_repo: Arc<ApiTokenRepository>, guard: Arc<dyn UserGuards>) -> Self {
+        Self {
+            api_token_repo,
+            guard,
+        }
+    }
+}
+
+#[async_trait]
+impl QueryHandler<GetApiTokens, Vec<ApiToken>, UserDomainError> for GetApiTokensHandler {
+    #[tracing::instrument(name = "query.api_tokens", skip_all)]
+    async fn handle(
+        &self,
+        ctx: &AppContext,
+        _cmd: GetApiTokens,
+    ) -> UserDomainResult<Vec<ApiToken>> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &UserPermission::ManageApiTokens)?;
+        ensure_session_user(auth_user)?;
+        Ok(self.api_token_repo.list_api_tokens(&auth_user.0.id).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guards::MockUserGuards;
+    use crate::infra::repository::api_token_repository_trait::MockApiTokenRepositoryTrait;
+    use mockall::predicate::eq;
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn get_api_tokens_lists_own_tokens() {
+        let mut mock_repo = MockApiTokenRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_authorize()
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::ManageApiTokens),
+            )
+            .returning(|_, _| Ok(()));
+        mock_repo
+            .expect_list_api_tokens()
+            .with(eq("test-user-id"))
+            .returning(|_| Ok(vec![]));
+
+        let handler = GetApiTokensHandler::new(
+            Arc::new(ApiTokenRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        assert!(handler.handle(&ctx, GetApiTokens).await.unwrap().is_empty());
+    }
+}
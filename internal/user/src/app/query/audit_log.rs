@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use validator::Validate;
+
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    guards::permissions::UserPermission,
+    query_handler::QueryHandler,
+    types::graphql_scalars::DateTimeScalar,
+};
+
+use crate::domain::{
+    audit_log::{
+        AuditAction, AuditLogFilter, AuditLogPage, AuditLogPosition, MAX_AUDIT_LOG_PAGE_SIZE,
+    },
+    errors::UserDomainError,
+    result::UserDomainResult,
+};
+use crate::guards::UserGuards;
+use crate::infra::repository::audit_log_repository::AuditLogRepository;
+
+/// A page of the audit log, newest first. `from` is inclusive and `to` exclusive.
+#[derive(Validate, InputObject)]
+pub struct GetAuditLog {
+    pub actor_id: Option<String>,
+    pub target_id: Option<String>,
+    pub action: Option<AuditAction>,
+    pub from: Option<DateTimeScalar>,
+    pub to: Option<DateTimeScalar>,
+    #[graphql(default = 20)]
+    #[validate(range(min = 1, max = MAX_AUDIT_LOG_PAGE_SIZE))]
+    pub first: u32,
+    pub after: Option<String>,
+}
+
+pub struct GetAuditLogHandler {
+    audit_log_repo: Arc<AuditLogRepository>,
+    guard: Arc<dyn UserGuards>,
+}
+
+impl GetAuditLogHandler {
+    pub fn new(audit_log_repo: Arc<AuditLogRepository>, guard: Arc<dyn UserGuards>) -> Self {
+        Self {
+            audit_log_repo,
+            guard,
+        }
+    }
+}
+
+#[async_trait]
+impl QueryHandler<GetAuditLog, AuditLogPage, UserDomainError> for GetAuditLogHandler {
+    #[tracing::instrument(name = "query.audit_log", skip_all)]
+    async fn handle(&self, ctx: &AppContext, cmd: GetAuditLog) -> UserDomainResult<AuditLogPage> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &UserPermission::ViewAuditLog)?;
+        cmd.validate()?;
+        let filter = AuditLogFilter {
+            actor_id: cmd.actor_id,
+            target_id: cmd.target_id,
+            action: cmd.action,
+            from: cmd.from.map(Into::into),
+            to: cmd.to.map(Into::into),
+            first: cmd.first,
+            after: cmd
+                .after
+                .as_deref()
+                .map(AuditLogPosition::from_cursor)
+                .transpose()?,
+        };
+        self.audit_log_repo.list(&filter).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guards::MockUserGuards;
+    use crate::infra::repository::audit_log_repository_trait::MockAuditLogRepositoryTrait;
+    use mockall::predicate::eq;
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn get_audit_log_passes_filters() {
+        let mut mock_repo = MockAuditLogRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_authorize()
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Admin)),
+                eq(UserPermission::ViewAuditLog),
+            )
+            .returning(|_, _| Ok(()));
+        mock_repo
+            .expect_list()
+            .withf(|filter| {
+                filter.target_id.as_deref() == Some("user-1")
+                    && filter.action == Some(AuditAction::BanUser)
+                    && filter.first == 20
+            })
+            .returning(|_| {
+                Ok(AuditLogPage {
+                    entries: vec![],
+                    has_next: false,
+                })
+            });
+
+        let handler = GetAuditLogHandler::new(
+            Arc::new(AuditLogRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let query = GetAuditLog {
+            actor_id: None,
+            target_id: Some("user-1".into()),
+            action: Some(AuditAction::BanUser),
+            from: None,
+            to: None,
+            first: 20,
+            after: None,
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Admin));
+        assert!(handler.handle(&ctx, query).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn get_audit_log_rejects_oversized_pages() {
+        let mut mock_repo = MockAuditLogRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_repo.expect_list().never();
+
+        let handler = GetAuditLogHandler::new(
+            Arc::new(AuditLogRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let query = GetAuditLog {
+            actor_id: None,
+            target_id: Some("user-1".into()),
+            action: Some(AuditAction::BanUser),
+            from: None,
+            to: None,
+            first: MAX_AUDIT_LOG_PAGE_SIZE + 1,
+            after: None,
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Admin));
+        let result = handler.handle(&ctx, query).await;
+        assert!(matches!(result, Err(UserDomainError::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn get_audit_log_unauthorized() {
+        let mut mock_repo = MockAuditLogRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_authorize()
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Moderator)),
+                eq(UserPermission::ViewAuditLog),
+            )
+            .returning(|_, _| Err(UserDomainError::Unauthorized));
+        mock_repo.expect_list().never();
+
+        let handler = GetAuditLogHandler::new(
+            Arc::new(AuditLogRepository::Mock(mock_repo)),
+            Arc::new(mock_guard),
+        );
+        let query = GetAuditLog {
+            actor_id: None,
+            target_id: Some("user-1".into()),
+            action: Some(AuditAction::BanUser),
+            from: None,
+            to: None,
+            first: 20,
+            after: None,
+        };
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Moderator));
+        assert!(handler.handle(&ctx, query).await.is_err());
+    }
+}
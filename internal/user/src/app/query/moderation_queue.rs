@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::{StreamExt, future, stream::BoxStream};
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    event_hub::EventHub,
+    guards::permissions::UserPermission,
+    query_handler::QueryHandler,
+};
+
+use crate::domain::{
+    errors::UserDomainError,
+    events::{ModerationQueueUpdate, UserEvent},
+    result::UserDomainResult,
+};
+use crate::guards::UserGuards;
+
+/// Moderation decisions from now on. Moderators and admins only.
+pub struct WatchModerationQueue;
+
+pub struct WatchModerationQueueHandler {
+    events: Arc<EventHub<UserEvent>>,
+    guard: Arc<dyn UserGuards>,
+}
+
+impl WatchModerationQueueHandler {
+    pub fn new(events: Arc<EventHub<UserEvent>>, guard: Arc<dyn UserGuards>) -> Self {
+        Self { events, guard }
+    }
+}
+
+#[async_trait]
+impl QueryHandler<WatchModerationQueue, BoxStream<'static, ModerationQueueUpdate>, UserDomainError>
+    for WatchModerationQueueHandler
+{
+    #[tracing::instrument(name = "query.moderation_queue", skip_all)]
+    async fn handle(
+        &self,
+        ctx: &AppContext,
+        _cmd: WatchModerationQueue,
+    ) -> UserDomainResult<BoxStream<'static, ModerationQueueUpdate>> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard
+            .authorize(auth_user, &UserPermission::ViewModerationQueue)?;
+        let updates = self.events.subscribe().filter_map(|event| {
+            future::ready(match event {
+                UserEvent::ModerationQueueUpdated(update) => Some(update),
+                _ => None,
+            })
+        });
+        Ok(updates.boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::events::{AccountChangeKind, ModerationQueueUpdateKind};
+    use crate::guards::MockUserGuards;
+    use mockall::predicate::eq;
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn watch_moderation_queue_sees_moderation_updates() {
+        let events = Arc::new(EventHub::new(8));
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_authorize()
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Moderator)),
+                eq(UserPermission::ViewModerationQueue),
+            )
+            .returning(|_, _| Ok(()));
+
+        let handler = WatchModerationQueueHandler::new(events.clone(), Arc::new(mock_guard));
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Moderator));
+        let mut updates = handler.handle(&ctx, WatchModerationQueue).await.unwrap();
+
+        events.publish(UserEvent::account_changed(
+            "user-1",
+            AccountChangeKind::Banned,
+            None,
+        ));
+        events.publish(UserEvent::moderation_queue_updated(
+            "user-1",
+            ModerationQueueUpdateKind::UserBanned,
+            "moderator-1",
+        ));
+
+        let update = updates.next().await.unwrap();
+        assert_eq!(update.kind, ModerationQueueUpdateKind::UserBanned);
+        assert_eq!(update.moderator_id, "moderator-1");
+    }
+
+    #[tokio::test]
+    async fn watch_moderation_queue_unauthorized() {
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_authorize()
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::ViewModerationQueue),
+            )
+            .returning(|_, _| Err(UserDomainError::Unauthorized));
+
+        let handler =
+            WatchModerationQueueHandler::new(Arc::new(EventHub::new(8)), Arc::new(mock_guard));
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        assert!(handler.handle(&ctx, WatchModerationQueue).await.is_err());
+    }
+}
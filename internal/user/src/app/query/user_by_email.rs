@@ -29,14 +29,14 @@ impl GetUserByEmailHander {
 
 #[async_trait]
 impl QueryHandler<GetUserByEmail, UserReadModel, UserDomainError> for GetUserByEmailHander {
+    #[tracing::instrument(name = "query.user_by_email", skip_all)]
     async fn handle(
         &self,
         ctx: &AppContext,
         cmd: GetUserByEmail,
     ) -> UserDomainResult<UserReadModel> {
         let auth_user = get_auth_user_from_ctx(&ctx);
-        self.guard
-            .authorize(&auth_user.0.role, &UserPermission::ViewUser)?;
+        self.guard.authorize(auth_user, &UserPermission::ViewUser)?;
         let user = self.user_repo.get_user_by_email(&cmd.email).await?;
         if let Some(found_user) = user {
             return Ok(found_user);
@@ -70,7 +70,10 @@ mod tests {
 
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Regular), eq(UserPermission::ViewUser))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::ViewUser),
+            )
             .returning(|_, _| Ok(()));
 
         mock_user_read_repo
@@ -102,7 +105,10 @@ mod tests {
 
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Regular), eq(UserPermission::ViewUser))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::ViewUser),
+            )
             .returning(|_, _| Ok(()));
 
         mock_user_read_repo
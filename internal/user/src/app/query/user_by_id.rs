@@ -29,10 +29,10 @@ impl GetUserByIdHander {
 
 #[async_trait]
 impl QueryHandler<GetUserById, UserReadModel, UserDomainError> for GetUserByIdHander {
+    #[tracing::instrument(name = "query.user_by_id", skip_all)]
     async fn handle(&self, ctx: &AppContext, cmd: GetUserById) -> UserDomainResult<UserReadModel> {
         let auth_user = get_auth_user_from_ctx(&ctx);
-        self.guard
-            .authorize(&auth_user.0.role, &UserPermission::ViewUser)?;
+        self.guard.authorize(auth_user, &UserPermission::ViewUser)?;
         let user = self.user_repo.get_user_by_id(&cmd.id).await?;
         if let Some(found_user) = user {
             return Ok(found_user);
@@ -66,7 +66,10 @@ mod tests {
 
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Regular), eq(UserPermission::ViewUser))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::ViewUser),
+            )
             .returning(|_, _| Ok(()));
 
         mock_user_read_repo
@@ -98,7 +101,10 @@ mod tests {
 
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Regular), eq(UserPermission::ViewUser))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::ViewUser),
+            )
             .returning(|_, _| Ok(()));
 
         mock_user_read_repo
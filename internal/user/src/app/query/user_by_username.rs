@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    guards::permissions::UserPermission,
+    query_handler::QueryHandler,
+};
+
+use crate::domain::{
+    errors::UserDomainError, result::UserDomainResult, user_read_model::UserReadModel,
+};
+use crate::guards::UserGuards;
+use crate::infra::repository::user_read_model_repository::UserReadModelRepository;
+
+/// Looks a user up by their current or any previous username.
+pub struct GetUserByUsername {
+    pub username: String,
+}
+pub struct GetUserByUsernameHandler {
+    user_repo: Arc<UserReadModelRepository>,
+    guard: Arc<dyn UserGuards>,
+}
+
+impl GetUserByUsernameHandler {
+    pub fn new(user_repo: Arc<UserReadModelRepository>, guard: Arc<dyn UserGuards>) -> Self {
+        Self { user_repo, guard }
+    }
+}
+
+#[async_trait]
+impl QueryHandler<GetUserByUsername, UserReadModel, UserDomainError> for GetUserByUsernameHandler {
+    #[tracing::instrument(name = "query.user_by_username", skip_all)]
+    async fn handle(
+        &self,
+        ctx: &AppContext,
+        cmd: GetUserByUsername,
+    ) -> UserDomainResult<UserReadModel> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard.authorize(auth_user, &UserPermission::ViewUser)?;
+        self.user_repo
+            .get_user_by_username(&cmd.username)
+            .await?
+            .ok_or(UserDomainError::UserNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockall::predicate::eq;
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+    use std::sync::Arc;
+
+    use crate::{
+        guards::MockUserGuards,
+        infra::repository::user_read_model_repository_trait::MockUserReadModelRepositoryTrait,
+    };
+
+    #[tokio::test]
+    async fn get_user_by_username_success() {
+        let mut mock_user_read_repo = MockUserReadModelRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        let cmd = GetUserByUsername {
+            username: "old-username".into(),
+        };
+
+        let username = cmd.username.clone();
+
+        mock_guard
+            .expect_authorize()
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::ViewUser),
+            )
+            .returning(|_, _| Ok(()));
+
+        mock_user_read_repo
+            .expect_get_user_by_username()
+            .withf(move |u| u == username)
+            .returning(|_| Ok(Some(UserReadModel::new_test_user_read_model())));
+
+        let handler = GetUserByUsernameHandler::new(
+            Arc::new(UserReadModelRepository::Mock(mock_user_read_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(result.is_ok())
+    }
+
+    #[tokio::test]
+    async fn get_user_by_username_not_found() {
+        let mut mock_user_read_repo = MockUserReadModelRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_authorize()
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::ViewUser),
+            )
+            .returning(|_, _| Ok(()));
+
+        mock_user_read_repo
+            .expect_get_user_by_username()
+            .returning(|_| Ok(None));
+
+        let handler = GetUserByUsernameHandler::new(
+            Arc::new(UserReadModelRepository::Mock(mock_user_read_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = GetUserByUsername {
+            username: "unknown".into(),
+        };
+        let result = handler.handle(&ctx, cmd).await;
+        assert!(result.is_err())
+    }
+}
@@ -31,10 +31,11 @@ impl GetUsersHandler {
 
 #[async_trait]
 impl QueryHandler<GetUsersOptions, Result, UserDomainError> for GetUsersHandler {
+    #[tracing::instrument(name = "query.users", skip_all)]
     async fn handle(&self, ctx: &AppContext, cmd: GetUsersOptions) -> UserDomainResult<Result> {
         let auth_user = get_auth_user_from_ctx(&ctx);
         self.guard
-            .authorize(&auth_user.0.role, &UserPermission::ListUsers)?;
+            .authorize(auth_user, &UserPermission::ListUsers)?;
         let resp = self.user_repo.get_users(&cmd).await?;
         let result = Result {
             data: resp.users,
@@ -73,7 +74,10 @@ mod tests {
 
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Moderator), eq(UserPermission::ListUsers))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Moderator)),
+                eq(UserPermission::ListUsers),
+            )
             .returning(|_, _| Ok(()));
 
         mock_user_read_repo
@@ -109,7 +113,10 @@ mod tests {
 
         mock_guard
             .expect_authorize()
-            .with(eq(UserRole::Regular), eq(UserPermission::ListUsers))
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::ListUsers),
+            )
             .returning(|_, _| Err(UserDomainError::Unauthorized));
 
         mock_user_read_repo.expect_get_users().never();
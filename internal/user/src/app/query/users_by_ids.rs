@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use shared::{
+    auth::{AppContext, get_auth_user_from_ctx},
+    guards::permissions::UserPermission,
+    query_handler::QueryHandler,
+};
+
+use crate::domain::{
+    errors::UserDomainError, result::UserDomainResult, user_read_model::UserReadModel,
+};
+use crate::guards::UserGuards;
+use crate::infra::repository::user_read_model_repository::UserReadModelRepository;
+
+/// Looks up many users at once, for batching nested user fields.
+pub struct GetUsersByIds {
+    pub ids: Vec<String>,
+}
+
+pub struct GetUsersByIdsHandler {
+    user_repo: Arc<UserReadModelRepository>,
+    guard: Arc<dyn UserGuards>,
+}
+
+impl GetUsersByIdsHandler {
+    pub fn new(user_repo: Arc<UserReadModelRepository>, guard: Arc<dyn UserGuards>) -> Self {
+        Self { user_repo, guard }
+    }
+}
+
+#[async_trait]
+impl QueryHandler<GetUsersByIds, Vec<UserReadModel>, UserDomainError> for GetUsersByIdsHandler {
+    #[tracing::instrument(name = "query.users_by_ids", skip_all)]
+    async fn handle(
+        &self,
+        ctx: &AppContext,
+        cmd: GetUsersByIds,
+    ) -> UserDomainResult<Vec<UserReadModel>> {
+        let auth_user = get_auth_user_from_ctx(ctx);
+        self.guard.authorize(auth_user, &UserPermission::ViewUser)?;
+        self.user_repo.get_users_by_ids(&cmd.ids).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guards::MockUserGuards;
+    use crate::infra::repository::user_read_model_repository_trait::MockUserReadModelRepositoryTrait;
+    use mockall::predicate::eq;
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn get_users_by_ids_success() {
+        let mut mock_user_read_repo = MockUserReadModelRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_authorize()
+            .with(
+                eq(AuthUser::new_test_auth_user(UserRole::Regular)),
+                eq(UserPermission::ViewUser),
+            )
+            .returning(|_, _| Ok(()));
+        mock_user_read_repo
+            .expect_get_users_by_ids()
+            .withf(|ids| ids == ["user_id".to_string(), "other".to_string()])
+            .times(1)
+            .returning(|_| Ok(vec![UserReadModel::new_test_user_read_model()]));
+
+        let handler = GetUsersByIdsHandler::new(
+            Arc::new(UserReadModelRepository::Mock(mock_user_read_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Regular));
+        let cmd = GetUsersByIds {
+            ids: vec!["user_id".into(), "other".into()],
+        };
+        let users = handler.handle(&ctx, cmd).await.unwrap();
+        assert_eq!(users.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn get_users_by_ids_unauthorized() {
+        let mut mock_user_read_repo = MockUserReadModelRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard
+            .expect_authorize()
+            .returning(|_, _| Err(UserDomainError::Unauthorized));
+        mock_user_read_repo.expect_get_users_by_ids().never();
+
+        let handler = GetUsersByIdsHandler::new(
+            Arc::new(UserReadModelRepository::Mock(mock_user_read_repo)),
+            Arc::new(mock_guard),
+        );
+        let ctx = AppContext::new().with_user(AuthUser::guest());
+        let cmd = GetUsersByIds {
+            ids: vec!["user_id".into()],
+        };
+        assert!(handler.handle(&ctx, cmd).await.is_err());
+    }
+}
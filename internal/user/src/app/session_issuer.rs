@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use shared::{
+    auth::token_service::{TokenRequest, TokenService},
+    guards::roles::UserRole,
+};
+
+use crate::domain::{
+    result::UserDomainResult,
+    user::User,
+    user_auth::two_factor::{TwoFactorChallenge, TwoFactorPolicy},
+};
+use crate::infra::repository::user_repository::UserRepository;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignInOutcome {
+    /// Signed in. When the user's role requires 2FA but none is enabled yet,
+    /// the token only carries the `Regular` role so the user can enroll.
+    Token {
+        token: String,
+        two_factor_enrollment_required: bool,
+    },
+    /// 2FA is enabled: the sign-in is completed with `VerifyTwoFactor` and this challenge.
+    TwoFactorChallenge(String),
+}
+
+/// Finishes every first-factor sign-in (OTP or OIDC) the same way, so the
+/// [`TwoFactorPolicy`] cannot be bypassed by picking another first factor.
+pub struct SessionIssuer {
+    user_repo: Arc<UserRepository>,
+    two_factor_policy: TwoFactorPolicy,
+    token_service: Arc<dyn TokenService>,
+}
+
+impl SessionIssuer {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        two_factor_policy: TwoFactorPolicy,
+        token_service: Arc<dyn TokenService>,
+    ) -> Self {
+        Self {
+            user_repo,
+            two_factor_policy,
+            token_service,
+        }
+    }
+
+    pub async fn start_session(&self, user: &User) -> UserDomainResult<SignInOutcome> {
+        if user.two_factor().is_some_and(|tf| tf.is_enabled()) {
+            // Only the challenge is written: the rest of `user` may be stale.
+            let (challenge, pending) = TwoFactorChallenge::start(Utc::now());
+            self.user_repo
+                .set_two_factor_challenge(user.id(), pending)
+                .await?;
+            tracing::info!(
+                "First factor verified for user: {}, awaiting 2FA",
+                user.email()
+            );
+            return Ok(SignInOutcome::TwoFactorChallenge(challenge));
+        }
+
+        let two_factor_enrollment_required = self.two_factor_policy.is_required_for(user.role());
+        let role = if two_factor_enrollment_required {
+            UserRole::Regular
+        } else {
+            user.role().to_owned()
+        };
+        let token = self.token_service.issue(TokenRequest::new(
+            user.email().to_string(),
+            role,
+            user.id().to_string(),
+        ))?;
+        Ok(SignInOutcome::Token {
+            token,
+            two_factor_enrollment_required,
+        })
+    }
+}
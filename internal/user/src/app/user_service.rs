@@ -1,59 +1,236 @@
 use std::sync::Arc;
 
-use crate::infra::repository::otp_repository::OtpRepository;
+use chrono::Duration;
 
+use crate::infra::oidc::oidc_client::OidcClient;
+use crate::infra::repository::{
+    api_token_repository::ApiTokenRepository, audit_log_repository::AuditLogRepository,
+    oidc_auth_request_repository::OidcAuthRequestRepository,
+    otp_issuance_repository::OtpIssuanceRepository, otp_repository::OtpRepository,
+};
+
+use crate::domain::{
+    events::{USER_EVENTS_CAPACITY, UserEvent},
+    user::UsernamePolicy,
+    user_auth::{
+        oidc::OidcProviderConfig, otp::OtpIssuePolicy, otp_hasher::OtpHasher,
+        two_factor::TwoFactorPolicy,
+    },
+};
 use crate::guards::UserGuards;
+use shared::{auth::token_service::TokenService, event_hub::EventHub};
 
 use crate::infra::repository::{
     user_read_model_repository::UserReadModelRepository, user_repository::UserRepository,
 };
 
 use super::{
+    api_token_authenticator::ApiTokenAuthenticator,
+    audit_trail::AuditTrail,
     command::{
         award_badge::AwardBadgeHandler, ban_user::BanUserHandler,
-        change_username::ChangeUsernameHandler, make_moderator::MakeModeratorHandler,
+        change_username::ChangeUsernameHandler, complete_oidc_sign_in::CompleteOidcSignInHandler,
+        confirm_totp::ConfirmTotpHandler, create_api_token::CreateApiTokenHandler,
+        delete_api_token::DeleteApiTokenHandler, disable_totp::DisableTotpHandler,
+        enroll_totp::EnrollTotpHandler, impersonate::ImpersonateHandler,
+        make_moderator::MakeModeratorHandler, resend_otp::ResendOtpHandler,
         revoke_badge::RevokeBadgeHandler, sign_in::SignInHandler, sign_up::SignUpHandler,
-        unban_user::UnbanUserHandler, verify_email_with_otp::VerifyEmailWithOtpHandler,
-        verify_otp::VerifyOtpHandler,
+        start_oidc_sign_in::StartOidcSignInHandler, unban_user::UnbanUserHandler,
+        update_api_token::UpdateApiTokenHandler, verify_email_with_otp::VerifyEmailWithOtpHandler,
+        verify_otp::VerifyOtpHandler, verify_two_factor::VerifyTwoFactorHandler,
     },
+    otp_issuer::OtpIssuer,
     query::{
-        user_by_email::GetUserByEmailHander, user_by_id::GetUserByIdHander, users::GetUsersHandler,
+        account_changes::WatchMyAccountHandler, api_tokens::GetApiTokensHandler,
+        audit_log::GetAuditLogHandler, moderation_queue::WatchModerationQueueHandler,
+        user_by_email::GetUserByEmailHander, user_by_id::GetUserByIdHander,
+        user_by_username::GetUserByUsernameHandler, users::GetUsersHandler,
+        users_by_ids::GetUsersByIdsHandler,
     },
+    session_issuer::SessionIssuer,
 };
 
+/// Policies and secrets of the user service, built from config by the caller.
+pub struct UserServiceSettings {
+    pub username_policy: UsernamePolicy,
+    pub otp_issue_policy: OtpIssuePolicy,
+    pub otp_hasher: Arc<OtpHasher>,
+    pub two_factor_policy: TwoFactorPolicy,
+    pub oidc_providers: Vec<OidcProviderConfig>,
+    pub oidc_client: Arc<OidcClient>,
+    pub token_service: Arc<dyn TokenService>,
+    pub impersonation_ttl: Duration,
+}
+
 pub struct UserService {
     pub command_handler: CommandHandler,
     pub query_handler: QueryHandler,
+    pub api_token_authenticator: Arc<ApiTokenAuthenticator>,
 }
 
 impl UserService {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         user_repo: Arc<UserRepository>,
         user_read_repo: Arc<UserReadModelRepository>,
         guard: Arc<dyn UserGuards>,
         otp_repo: Arc<OtpRepository>,
+        otp_issuance_repo: Arc<OtpIssuanceRepository>,
+        oidc_auth_request_repo: Arc<OidcAuthRequestRepository>,
+        api_token_repo: Arc<ApiTokenRepository>,
+        audit_log_repo: Arc<AuditLogRepository>,
+        settings: UserServiceSettings,
     ) -> Self {
+        let UserServiceSettings {
+            username_policy,
+            otp_issue_policy,
+            otp_hasher,
+            two_factor_policy,
+            oidc_providers,
+            oidc_client,
+            token_service,
+            impersonation_ttl,
+        } = settings;
+        let otp_issuer = Arc::new(OtpIssuer::new(
+            otp_repo.clone(),
+            otp_issuance_repo,
+            otp_issue_policy,
+            otp_hasher.clone(),
+        ));
+        let audit_trail = Arc::new(AuditTrail::new(audit_log_repo.clone()));
+        let events = Arc::new(EventHub::<UserEvent>::new(USER_EVENTS_CAPACITY));
+        let session_issuer = Arc::new(SessionIssuer::new(
+            user_repo.clone(),
+            two_factor_policy.clone(),
+            token_service.clone(),
+        ));
         Self {
             command_handler: CommandHandler {
-                sign_up: SignUpHandler::new(user_repo.clone(), otp_repo.clone()),
-                award_badge: AwardBadgeHandler::new(user_repo.clone(), guard.clone()),
-                revoke_badge: RevokeBadgeHandler::new(user_repo.clone(), guard.clone()),
-                make_moderator: MakeModeratorHandler::new(user_repo.clone(), guard.clone()),
-                ban_user: BanUserHandler::new(user_repo.clone(), guard.clone()),
-                unban_user: UnbanUserHandler::new(user_repo.clone(), guard.clone()),
-                change_username: ChangeUsernameHandler::new(user_repo.clone(), guard.clone()),
-                verify_otp: VerifyOtpHandler::new(user_repo.clone(), otp_repo.clone()),
+                sign_up: SignUpHandler::new(
+                    user_repo.clone(),
+                    otp_repo.clone(),
+                    otp_issuer.clone(),
+                ),
+                award_badge: AwardBadgeHandler::new(
+                    user_repo.clone(),
+                    guard.clone(),
+                    audit_trail.clone(),
+                    events.clone(),
+                ),
+                revoke_badge: RevokeBadgeHandler::new(
+                    user_repo.clone(),
+                    guard.clone(),
+                    audit_trail.clone(),
+                    events.clone(),
+                ),
+                make_moderator: MakeModeratorHandler::new(
+                    user_repo.clone(),
+                    guard.clone(),
+                    audit_trail.clone(),
+                    events.clone(),
+                ),
+                ban_user: BanUserHandler::new(
+                    user_repo.clone(),
+                    guard.clone(),
+                    audit_trail.clone(),
+                    events.clone(),
+                ),
+                unban_user: UnbanUserHandler::new(
+                    user_repo.clone(),
+                    guard.clone(),
+                    audit_trail.clone(),
+                    events.clone(),
+                ),
+                change_username: ChangeUsernameHandler::new(
+                    user_repo.clone(),
+                    guard.clone(),
+                    username_policy,
+                    audit_trail.clone(),
+                ),
+                verify_otp: VerifyOtpHandler::new(
+                    user_repo.clone(),
+                    otp_repo.clone(),
+                    otp_hasher.clone(),
+                    session_issuer.clone(),
+                ),
                 verify_email_with_opt: VerifyEmailWithOtpHandler::new(
                     user_repo.clone(),
                     otp_repo.clone(),
+                    otp_hasher.clone(),
+                    session_issuer.clone(),
+                ),
+                enroll_totp: EnrollTotpHandler::new(
+                    user_repo.clone(),
+                    guard.clone(),
+                    two_factor_policy.clone(),
+                ),
+                confirm_totp: ConfirmTotpHandler::new(
+                    user_repo.clone(),
+                    guard.clone(),
+                    otp_hasher.clone(),
+                ),
+                disable_totp: DisableTotpHandler::new(
+                    user_repo.clone(),
+                    guard.clone(),
+                    otp_hasher.clone(),
+                    two_factor_policy,
+                ),
+                verify_two_factor: VerifyTwoFactorHandler::new(
+                    user_repo.clone(),
+                    otp_hasher.clone(),
+                    token_service.clone(),
+                ),
+                impersonate: ImpersonateHandler::new(
+                    user_repo.clone(),
+                    guard.clone(),
+                    token_service,
+                    audit_trail,
+                    impersonation_ttl,
+                ),
+                create_api_token: CreateApiTokenHandler::new(
+                    api_token_repo.clone(),
+                    guard.clone(),
+                    otp_hasher.clone(),
+                ),
+                update_api_token: UpdateApiTokenHandler::new(api_token_repo.clone(), guard.clone()),
+                delete_api_token: DeleteApiTokenHandler::new(api_token_repo.clone(), guard.clone()),
+                sign_in: SignInHandler::new(user_repo.clone(), otp_issuer.clone()),
+                resend_otp: ResendOtpHandler::new(user_repo.clone(), otp_repo.clone(), otp_issuer),
+                start_oidc_sign_in: StartOidcSignInHandler::new(
+                    oidc_auth_request_repo.clone(),
+                    oidc_client.clone(),
+                    oidc_providers.clone(),
+                ),
+                complete_oidc_sign_in: CompleteOidcSignInHandler::new(
+                    user_repo.clone(),
+                    oidc_auth_request_repo,
+                    oidc_client,
+                    oidc_providers,
+                    session_issuer,
                 ),
-                sign_in: SignInHandler::new(user_repo.clone(), otp_repo.clone()),
             },
             query_handler: QueryHandler {
                 get_user_by_id: GetUserByIdHander::new(user_read_repo.clone(), guard.clone()),
                 get_user_by_email: GetUserByEmailHander::new(user_read_repo.clone(), guard.clone()),
+                get_user_by_username: GetUserByUsernameHandler::new(
+                    user_read_repo.clone(),
+                    guard.clone(),
+                ),
                 get_users: GetUsersHandler::new(user_read_repo.clone(), guard.clone()),
+                get_users_by_ids: Arc::new(GetUsersByIdsHandler::new(
+                    user_read_repo.clone(),
+                    guard.clone(),
+                )),
+                get_api_tokens: GetApiTokensHandler::new(api_token_repo.clone(), guard.clone()),
+                get_audit_log: GetAuditLogHandler::new(audit_log_repo, guard.clone()),
+                watch_my_account: WatchMyAccountHandler::new(events.clone(), guard.clone()),
+                watch_moderation_queue: WatchModerationQueueHandler::new(events, guard.clone()),
             },
+            api_token_authenticator: Arc::new(ApiTokenAuthenticator::new(
+                api_token_repo,
+                user_repo.clone(),
+                otp_hasher,
+            )),
         }
     }
 }
@@ -69,10 +246,28 @@ pub struct CommandHandler {
     pub verify_otp: VerifyOtpHandler,
     pub verify_email_with_opt: VerifyEmailWithOtpHandler,
     pub sign_in: SignInHandler,
+    pub resend_otp: ResendOtpHandler,
+    pub enroll_totp: EnrollTotpHandler,
+    pub confirm_totp: ConfirmTotpHandler,
+    pub disable_totp: DisableTotpHandler,
+    pub verify_two_factor: VerifyTwoFactorHandler,
+    pub start_oidc_sign_in: StartOidcSignInHandler,
+    pub complete_oidc_sign_in: CompleteOidcSignInHandler,
+    pub create_api_token: CreateApiTokenHandler,
+    pub update_api_token: UpdateApiTokenHandler,
+    pub delete_api_token: DeleteApiTokenHandler,
+    pub impersonate: ImpersonateHandler,
 }
 
 pub struct QueryHandler {
     pub get_user_by_id: GetUserByIdHander,
     pub get_user_by_email: GetUserByEmailHander,
+    pub get_user_by_username: GetUserByUsernameHandler,
     pub get_users: GetUsersHandler,
+    /// Shared with the per-request user loader.
+    pub get_users_by_ids: Arc<GetUsersByIdsHandler>,
+    pub get_api_tokens: GetApiTokensHandler,
+    pub get_audit_log: GetAuditLogHandler,
+    pub watch_my_account: WatchMyAccountHandler,
+    pub watch_moderation_queue: WatchModerationQueueHandler,
 }
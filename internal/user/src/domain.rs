@@ -1,4 +1,7 @@
+pub mod audit_log;
+pub mod canonical;
 pub mod errors;
+pub mod events;
 pub mod result;
 pub mod user;
 pub mod user_auth;
@@ -0,0 +1,296 @@
+//! Append-only record of privileged operations: who did what to whom.
+use async_graphql::Enum;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::{
+    auth::AppContext,
+    pagination::{decode_cursor, encode_cursor},
+};
+use uuid::Uuid;
+
+use super::{errors::UserDomainError, result::UserDomainResult, user::User};
+
+pub const MAX_AUDIT_LOG_PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum AuditAction {
+    BanUser,
+    UnbanUser,
+    MakeModerator,
+    AwardBadge,
+    RevokeBadge,
+    Impersonate,
+    /// Only recorded when a user is renamed by someone else.
+    ChangeUsername,
+}
+
+/// A field of the target before and after the operation. `None` means unset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditChange {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// Where the request came from.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct AuditMetadata {
+    pub client_ip: Option<String>,
+    pub api_token_id: Option<String>,
+    /// The user the actor was acting as, when impersonating.
+    pub on_behalf_of: Option<String>,
+    /// Matches the request's logs and trace.
+    pub request_id: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl AuditMetadata {
+    pub fn from_ctx(ctx: &AppContext) -> Self {
+        let user = ctx.user.as_ref();
+        Self {
+            client_ip: ctx.client_ip.clone(),
+            api_token_id: user.and_then(|u| u.api_token_id()).map(Into::into),
+            on_behalf_of: user
+                .filter(|u| u.impersonator().is_some())
+                .map(|u| u.0.id.clone()),
+            request_id: ctx.request_id.clone(),
+            user_agent: ctx.user_agent.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub id: String,
+    pub actor_id: String,
+    pub action: AuditAction,
+    pub target_id: String,
+    pub changes: Vec<AuditChange>,
+    pub metadata: AuditMetadata,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEntry {
+    pub fn new(
+        actor_id: String,
+        action: AuditAction,
+        target_id: String,
+        changes: Vec<AuditChange>,
+        metadata: AuditMetadata,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            actor_id,
+            action,
+            target_id,
+            changes,
+            metadata,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Opaque cursor pointing just after this entry, newest first.
+    pub fn cursor(&self) -> String {
+        encode_cursor(&format!("{}|{}", self.created_at.to_rfc3339(), self.id))
+    }
+
+    /// Whether the entry comes after the `(created_at, id)` position in
+    /// newest-first order.
+    pub fn is_after(&self, position: &AuditLogPosition) -> bool {
+        (self.created_at, self.id.as_str()) < (position.created_at, position.id.as_str())
+    }
+}
+
+/// Decoded form of [`AuditEntry::cursor`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditLogPosition {
+    pub created_at: DateTime<Utc>,
+    pub id: String,
+}
+
+impl AuditLogPosition {
+    pub fn from_cursor(cursor: &str) -> UserDomainResult<Self> {
+        let invalid = || UserDomainError::Validation("Invalid cursor".into());
+        let decoded = decode_cursor(cursor).map_err(|_| invalid())?;
+        let (created_at, id) = decoded.split_once('|').ok_or_else(invalid)?;
+        let created_at = DateTime::parse_from_rfc3339(created_at)
+            .map_err(|_| invalid())?
+            .with_timezone(&Utc);
+        Ok(Self {
+            created_at,
+            id: id.to_string(),
+        })
+    }
+}
+
+/// Filters of the audit log. Entries are returned newest first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditLogFilter {
+    pub actor_id: Option<String>,
+    pub target_id: Option<String>,
+    pub action: Option<AuditAction>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub first: u32,
+    pub after: Option<AuditLogPosition>,
+}
+
+impl AuditLogFilter {
+    /// The newest `first` entries, unfiltered.
+    pub fn new(first: u32) -> Self {
+        Self {
+            actor_id: None,
+            target_id: None,
+            action: None,
+            from: None,
+            to: None,
+            first,
+            after: None,
+        }
+    }
+
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.actor_id
+            .as_ref()
+            .is_none_or(|id| *id == entry.actor_id)
+            && self
+                .target_id
+                .as_ref()
+                .is_none_or(|id| *id == entry.target_id)
+            && self.action.is_none_or(|action| action == entry.action)
+            && self.from.is_none_or(|from| entry.created_at >= from)
+            && self.to.is_none_or(|to| entry.created_at < to)
+            && self
+                .after
+                .as_ref()
+                .is_none_or(|after| entry.is_after(after))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditEntry>,
+    pub has_next: bool,
+}
+
+/// The audited fields of a user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserSnapshot {
+    username: String,
+    role: String,
+    badges: String,
+    ban: Option<String>,
+}
+
+impl UserSnapshot {
+    pub fn of(user: &User) -> Self {
+        Self {
+            username: user.username().to_string(),
+            role: format!("{:?}", user.role()),
+            badges: user.badges().join(","),
+            ban: user
+                .ban_status()
+                .filter(|ban| ban.is_banned())
+                .map(|ban| format!("{:?}: {}", ban.ban_type(), ban.reason_for_ban())),
+        }
+    }
+
+    /// The fields that differ between `self` and `after`.
+    pub fn diff(&self, after: &UserSnapshot) -> Vec<AuditChange> {
+        let fields = [
+            ("username", Some(&self.username), Some(&after.username)),
+            ("role", Some(&self.role), Some(&after.role)),
+            ("badges", Some(&self.badges), Some(&after.badges)),
+            ("ban", self.ban.as_ref(), after.ban.as_ref()),
+        ];
+        fields
+            .into_iter()
+            .filter(|(_, before, after)| before != after)
+            .map(|(field, before, after)| AuditChange {
+                field: field.to_string(),
+                before: before.cloned(),
+                after: after.cloned(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::BanType;
+    use chrono::Duration;
+    use shared::{
+        auth::{AuthUser, RequestMetadata, jwt::Actor},
+        guards::roles::UserRole,
+    };
+
+    #[test]
+    fn diff_lists_only_changed_fields() {
+        let mut user = User::new_test_user(None);
+        let before = UserSnapshot::of(&user);
+        user.ban("Spam".into(), BanType::Indefinite);
+        user.award_badge("early".into());
+
+        let changes = before.diff(&UserSnapshot::of(&user));
+        assert_eq!(
+            changes,
+            vec![
+                AuditChange {
+                    field: "badges".into(),
+                    before: Some("".into()),
+                    after: Some("early".into()),
+                },
+                AuditChange {
+                    field: "ban".into(),
+                    before: None,
+                    after: Some("Indefinite: Spam".into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn cursor_round_trips_and_orders_newest_first() {
+        let older = AuditEntry::new(
+            "admin".into(),
+            AuditAction::BanUser,
+            "user".into(),
+            vec![],
+            AuditMetadata::default(),
+        );
+        let mut newer = older.clone();
+        newer.id = "other".into();
+        newer.created_at = older.created_at + Duration::seconds(1);
+
+        let position = AuditLogPosition::from_cursor(&newer.cursor()).unwrap();
+        assert_eq!(position.id, "other");
+        assert!(older.is_after(&position));
+        assert!(!newer.is_after(&position));
+        assert!(AuditLogPosition::from_cursor("not a cursor").is_err());
+    }
+
+    #[test]
+    fn metadata_names_impersonated_user() {
+        let mut auth_user = AuthUser::new_test_auth_user(UserRole::Regular);
+        auth_user.0.act = Some(Actor {
+            sub: "admin@example.com".into(),
+            id: "admin-id".into(),
+        });
+        let ctx = AppContext::new()
+            .with_user(auth_user)
+            .with_request(&RequestMetadata {
+                request_id: "req-1".into(),
+                client_ip: Some("10.0.0.1".into()),
+                user_agent: Some("curl/8.5.0".into()),
+                ..Default::default()
+            });
+
+        let metadata = AuditMetadata::from_ctx(&ctx);
+        assert_eq!(metadata.client_ip.as_deref(), Some("10.0.0.1"));
+        assert_eq!(metadata.request_id.as_deref(), Some("req-1"));
+        assert_eq!(metadata.user_agent.as_deref(), Some("curl/8.5.0"));
+        assert_eq!(metadata.on_behalf_of.as_deref(), Some("test-user-id"));
+        assert_eq!(metadata.api_token_id, None);
+    }
+}
@@ -0,0 +1,43 @@
+//! Canonical forms of usernames and emails used for uniqueness checks and lookups.
+//! The user-facing values are stored as entered; only the canonical forms are
+//! compared, so `JohnDoe` and `johndoe` are treated as the same username.
+use unicode_normalization::UnicodeNormalization;
+
+pub fn canonical_username(username: &str) -> String {
+    canonicalize(username)
+}
+
+pub fn canonical_email(email: &str) -> String {
+    canonicalize(email)
+}
+
+fn canonicalize(value: &str) -> String {
+    value
+        .nfkc()
+        .collect::<String>()
+        .to_lowercase()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_username_is_case_insensitive() {
+        assert_eq!(canonical_username("JohnDoe"), canonical_username("johndoe"));
+    }
+
+    #[test]
+    fn canonical_username_applies_nfkc() {
+        // Fullwidth letters and the "ﬁ" ligature normalize to their ASCII forms.
+        assert_eq!("john", canonical_username("ＪＯＨＮ"));
+        assert_eq!("fin", canonical_username("ﬁn"));
+    }
+
+    #[test]
+    fn canonical_email_trims_and_lowercases() {
+        assert_eq!("a@x.com", canonical_email("  A@X.com "));
+    }
+}
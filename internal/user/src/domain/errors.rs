@@ -1,5 +1,9 @@
 use super::user_auth::errors::UserAuthError;
-use shared::auth::jwt::JWTError;
+use chrono::{DateTime, Utc};
+use shared::{
+    auth::jwt::JWTError,
+    errors::validation::{FieldError, field_errors, field_names},
+};
 use std::fmt;
 use tracing::error;
 
@@ -13,12 +17,15 @@ pub enum UserDomainError {
     Authorization(UserAuthError),
     Database(String),
     Validation(String),
+    /// Input that failed validation, with each failed rule.
+    InvalidInput(Vec<FieldError>),
     Internal(String),
     InvalidTransaction,
     TransactionFailed,
     UnverifiedEmail,
     UnableToVerifyEmail,
     InvalidToken,
+    UsernameChangeTooSoon(DateTime<Utc>),
 }
 
 impl fmt::Display for UserDomainError {
@@ -32,12 +39,87 @@ impl fmt::Display for UserDomainError {
             Self::Authorization(err) => write!(f, "{}", err),
             Self::Database(msg) => write!(f, "{}", msg),
             Self::Validation(msg) => write!(f, "{}", msg),
+            Self::InvalidInput(fields) => write!(f, "Invalid input: {}", field_names(fields)),
             Self::Internal(msg) => write!(f, "{}", msg),
             Self::InvalidTransaction => write!(f, "Invalid transaction"),
             Self::TransactionFailed => write!(f, "Transaction failed"),
             Self::UnverifiedEmail => write!(f, "Email is not verified"),
             Self::UnableToVerifyEmail => write!(f, "Unable to verify email address"),
             Self::InvalidToken => write!(f, "Invalid token"),
+            Self::UsernameChangeTooSoon(allowed_at) => write!(
+                f,
+                "Username was changed recently. It can be changed again after {}",
+                allowed_at.to_rfc3339()
+            ),
+        }
+    }
+}
+
+impl UserDomainError {
+    /// The variant name, used to label error metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::UserNotFound => "UserNotFound",
+            Self::UsernameTaken => "UsernameTaken",
+            Self::InvalidEmail => "InvalidEmail",
+            Self::Unauthorized => "Unauthorized",
+            Self::UsernameOrEmailTaken => "UsernameOrEmailTaken",
+            Self::Authorization(_) => "Authorization",
+            Self::Database(_) => "Database",
+            Self::Validation(_) => "Validation",
+            Self::InvalidInput(_) => "InvalidInput",
+            Self::Internal(_) => "Internal",
+            Self::InvalidTransaction => "InvalidTransaction",
+            Self::TransactionFailed => "TransactionFailed",
+            Self::UnverifiedEmail => "UnverifiedEmail",
+            Self::UnableToVerifyEmail => "UnableToVerifyEmail",
+            Self::InvalidToken => "InvalidToken",
+            Self::UsernameChangeTooSoon(_) => "UsernameChangeTooSoon",
+        }
+    }
+
+    /// Stable code sent to clients with the error. Clients should branch on
+    /// it rather than on the message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UserNotFound => "USER_NOT_FOUND",
+            Self::UsernameTaken => "USERNAME_TAKEN",
+            Self::InvalidEmail => "INVALID_EMAIL",
+            Self::Unauthorized => "FORBIDDEN",
+            Self::UsernameOrEmailTaken => "USERNAME_OR_EMAIL_TAKEN",
+            Self::Authorization(err) => err.code(),
+            Self::Validation(_) | Self::InvalidInput(_) => "BAD_USER_INPUT",
+            Self::Database(_)
+            | Self::Internal(_)
+            | Self::InvalidTransaction
+            | Self::TransactionFailed => "INTERNAL_SERVER_ERROR",
+            Self::UnverifiedEmail => "UNVERIFIED_EMAIL",
+            Self::UnableToVerifyEmail => "UNABLE_TO_VERIFY_EMAIL",
+            Self::InvalidToken => "UNAUTHENTICATED",
+            Self::UsernameChangeTooSoon(_) => "USERNAME_CHANGE_TOO_SOON",
+        }
+    }
+
+    /// Failures of the server rather than the request. Their details are
+    /// logged but not shown to clients.
+    pub fn is_internal(&self) -> bool {
+        self.code() == "INTERNAL_SERVER_ERROR"
+    }
+
+    /// When the caller may try again, for errors that only last a while.
+    pub fn retry_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::UsernameChangeTooSoon(allowed_at) => Some(*allowed_at),
+            Self::Authorization(err) => err.retry_at(),
+            _ => None,
+        }
+    }
+
+    /// The fields that failed validation, if any.
+    pub fn field_errors(&self) -> &[FieldError] {
+        match self {
+            Self::InvalidInput(fields) => fields,
+            _ => &[],
         }
     }
 }
@@ -53,15 +135,13 @@ impl From<UserAuthError> for UserDomainError {
 impl From<mongodb::error::Error> for UserDomainError {
     fn from(err: mongodb::error::Error) -> Self {
         error!("Mongodb Error: {:#?}", err);
-        Self::Database("Database error".to_string()) // TODO: Return a generic database error instead of the specific error
+        Self::Database("Database error".to_string())
     }
 }
 
 impl From<validator::ValidationErrors> for UserDomainError {
     fn from(err: validator::ValidationErrors) -> Self {
-        let json =
-            serde_json::to_string(&err).unwrap_or_else(|_| format!("{{\"error\": \"{}\"}}", err));
-        Self::Validation(json)
+        Self::InvalidInput(field_errors(&err))
     }
 }
 
@@ -71,3 +151,39 @@ impl From<JWTError> for UserDomainError {
         Self::InvalidToken
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use validator::Validate;
+
+    #[derive(Validate)]
+    struct Input {
+        #[validate(email)]
+        email: String,
+    }
+
+    #[test]
+    fn validation_errors_keep_their_fields() {
+        let input = Input {
+            email: "not-an-email".into(),
+        };
+        let err = UserDomainError::from(input.validate().unwrap_err());
+        assert_eq!(err.code(), "BAD_USER_INPUT");
+        assert_eq!(err.to_string(), "Invalid input: email");
+        assert_eq!(err.field_errors()[0].field, "email");
+        assert_eq!(err.field_errors()[0].code, "email");
+    }
+
+    #[test]
+    fn codes_come_from_the_wrapped_auth_error() {
+        let retry_at = Utc::now() + TimeDelta::seconds(30);
+        let err = UserDomainError::from(UserAuthError::OtpResendTooSoon(retry_at));
+        assert_eq!(err.code(), "OTP_RESEND_TOO_SOON");
+        assert_eq!(err.retry_at(), Some(retry_at));
+        assert!(!err.is_internal());
+        assert!(UserDomainError::from(UserAuthError::Database).is_internal());
+        assert!(UserDomainError::TransactionFailed.is_internal());
+    }
+}
@@ -0,0 +1,70 @@
+//! Events published to live subscribers after a user is changed.
+use async_graphql::Enum;
+use chrono::{DateTime, Utc};
+
+/// Events kept by the user service's hub before slow subscribers skip ahead.
+pub const USER_EVENTS_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UserEvent {
+    AccountChanged(AccountChange),
+    ModerationQueueUpdated(ModerationQueueUpdate),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum AccountChangeKind {
+    Banned,
+    Unbanned,
+    RoleChanged,
+    BadgeAwarded,
+    BadgeRevoked,
+}
+
+/// A change to a user's account made by someone else.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountChange {
+    pub user_id: String,
+    pub kind: AccountChangeKind,
+    /// The ban reason, new role or badge.
+    pub detail: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum ModerationQueueUpdateKind {
+    UserBanned,
+    UserUnbanned,
+}
+
+/// A moderation decision, so moderators watching the queue see each other's work.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModerationQueueUpdate {
+    pub user_id: String,
+    pub kind: ModerationQueueUpdateKind,
+    pub moderator_id: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl UserEvent {
+    pub fn account_changed(user_id: &str, kind: AccountChangeKind, detail: Option<String>) -> Self {
+        Self::AccountChanged(AccountChange {
+            user_id: user_id.to_string(),
+            kind,
+            detail,
+            occurred_at: Utc::now(),
+        })
+    }
+
+    pub fn moderation_queue_updated(
+        user_id: &str,
+        kind: ModerationQueueUpdateKind,
+        moderator_id: &str,
+    ) -> Self {
+        Self::ModerationQueueUpdated(ModerationQueueUpdate {
+            user_id: user_id.to_string(),
+            kind,
+            moderator_id: moderator_id.to_string(),
+            occurred_at: Utc::now(),
+        })
+    }
+}
@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use shared::guards::roles::UserRole;
 
+use super::canonical::{canonical_email, canonical_username};
+use super::user_auth::two_factor::TwoFactor;
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum BanType {
     Definite {
@@ -35,6 +38,39 @@ pub struct Ban {
     ban_type: BanType,
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub struct UsernameChange {
+    username: String,
+    changed_at: DateTime<Utc>,
+}
+
+/// An external account (OIDC provider and subject) the user can sign in with.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LinkedIdentity {
+    provider: String,
+    subject: String,
+    email: Option<String>,
+    linked_at: DateTime<Utc>,
+}
+
+/// Rules applied when a user changes their username.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsernamePolicy {
+    /// Minimum time between two username changes of the same user.
+    pub change_cooldown: Duration,
+    /// How long a released username stays reserved for its previous owner.
+    pub reservation_period: Duration,
+}
+
+impl Default for UsernamePolicy {
+    fn default() -> Self {
+        Self {
+            change_cooldown: Duration::days(30),
+            reservation_period: Duration::days(180),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct User {
     id: String,
@@ -46,6 +82,9 @@ pub struct User {
     updated_at: DateTime<Utc>,
     badges: Vec<String>,
     email_status: EmailStatus,
+    username_history: Vec<UsernameChange>,
+    two_factor: Option<TwoFactor>,
+    identities: Vec<LinkedIdentity>,
 }
 
 impl User {
@@ -60,6 +99,9 @@ impl User {
             updated_at: Utc::now(),
             badges: vec![],
             email_status: EmailStatus::Unverified,
+            username_history: vec![],
+            two_factor: None,
+            identities: vec![],
         }
     }
     pub fn new_with_all_fields(
@@ -83,8 +125,23 @@ impl User {
             updated_at,
             badges,
             email_status,
+            username_history: vec![],
+            two_factor: None,
+            identities: vec![],
         }
     }
+    pub fn with_username_history(mut self, username_history: Vec<UsernameChange>) -> Self {
+        self.username_history = username_history;
+        self
+    }
+    pub fn with_two_factor(mut self, two_factor: Option<TwoFactor>) -> Self {
+        self.two_factor = two_factor;
+        self
+    }
+    pub fn with_identities(mut self, identities: Vec<LinkedIdentity>) -> Self {
+        self.identities = identities;
+        self
+    }
     pub fn ban(&mut self, reason: String, ban_type: BanType) {
         if let Some(ban) = self.ban_status.as_mut() {
             ban.is_banned = true;
@@ -107,8 +164,22 @@ impl User {
         self.updated_at = Utc::now();
     }
     pub fn change_username(&mut self, new_username: String) {
-        self.username = new_username;
-        self.updated_at = Utc::now();
+        let now = Utc::now();
+        let old_username = std::mem::replace(&mut self.username, new_username);
+        // Reclaiming a previous username makes it current again.
+        let canonical = canonical_username(&self.username);
+        self.username_history
+            .retain(|change| canonical_username(&change.username) != canonical);
+        self.username_history
+            .push(UsernameChange::new(old_username, now));
+        self.updated_at = now;
+    }
+    /// Returns the earliest time the username may be changed again, if the user
+    /// is still within the cooldown of their last change.
+    pub fn username_change_blocked_until(&self, cooldown: Duration) -> Option<DateTime<Utc>> {
+        self.last_username_change()
+            .map(|changed_at| *changed_at + cooldown)
+            .filter(|allowed_at| *allowed_at > Utc::now())
     }
     pub fn award_badge(&mut self, badge: String) {
         self.badges.push(badge);
@@ -135,6 +206,9 @@ impl User {
             updated_at: Utc::now(),
             badges: vec![],
             email_status: EmailStatus::Verified,
+            username_history: vec![],
+            two_factor: None,
+            identities: vec![],
         }
     }
     pub fn test_user_id() -> String {
@@ -144,6 +218,18 @@ impl User {
         self.email_status = email_status;
         self.updated_at = Utc::now();
     }
+    pub fn set_two_factor(&mut self, two_factor: Option<TwoFactor>) {
+        self.two_factor = two_factor;
+        self.updated_at = Utc::now();
+    }
+    /// Links an external identity. Linking one that is already linked is a no-op.
+    pub fn link_identity(&mut self, identity: LinkedIdentity) {
+        if self.has_identity(identity.provider(), identity.subject()) {
+            return;
+        }
+        self.identities.push(identity);
+        self.updated_at = Utc::now();
+    }
 }
 
 /// Implement getters;
@@ -160,6 +246,12 @@ impl User {
     pub fn username(&self) -> &str {
         &self.username
     }
+    pub fn canonical_username(&self) -> String {
+        canonical_username(&self.username)
+    }
+    pub fn canonical_email(&self) -> String {
+        canonical_email(&self.email)
+    }
     pub fn role(&self) -> &UserRole {
         &self.role
     }
@@ -184,6 +276,72 @@ impl User {
     pub fn email_status(&self) -> &EmailStatus {
         &self.email_status
     }
+    pub fn username_history(&self) -> &Vec<UsernameChange> {
+        &self.username_history
+    }
+    pub fn last_username_change(&self) -> Option<&DateTime<Utc>> {
+        self.username_history
+            .iter()
+            .map(|change| change.changed_at())
+            .max()
+    }
+    pub fn two_factor(&self) -> Option<&TwoFactor> {
+        self.two_factor.as_ref()
+    }
+    pub fn has_two_factor_enabled(&self) -> bool {
+        self.two_factor.as_ref().is_some_and(|tf| tf.is_enabled())
+    }
+    pub fn identities(&self) -> &Vec<LinkedIdentity> {
+        &self.identities
+    }
+    pub fn has_identity(&self, provider: &str, subject: &str) -> bool {
+        self.identities
+            .iter()
+            .any(|identity| identity.provider == provider && identity.subject == subject)
+    }
+}
+
+impl UsernameChange {
+    pub fn new(username: String, changed_at: DateTime<Utc>) -> Self {
+        Self {
+            username,
+            changed_at,
+        }
+    }
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+    pub fn changed_at(&self) -> &DateTime<Utc> {
+        &self.changed_at
+    }
+}
+
+impl LinkedIdentity {
+    pub fn new(
+        provider: String,
+        subject: String,
+        email: Option<String>,
+        linked_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            provider,
+            subject,
+            email,
+            linked_at,
+        }
+    }
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+    pub fn linked_at(&self) -> &DateTime<Utc> {
+        &self.linked_at
+    }
 }
 
 impl Ban {
@@ -282,7 +440,50 @@ mod tests {
             "expected username: {}, got username: {}",
             new_username,
             user.username()
-        )
+        );
+        assert_eq!(
+            1,
+            user.username_history().len(),
+            "expected username history: {}, got: {}",
+            1,
+            user.username_history().len()
+        );
+        assert_eq!("johndoe", user.username_history()[0].username());
+    }
+
+    #[test]
+    fn change_back_to_previous_username() {
+        let mut user = User::new_test_user(None);
+        user.change_username("johndoe123".into());
+        user.change_username("johndoe".into());
+        let history: Vec<&str> = user
+            .username_history()
+            .iter()
+            .map(|change| change.username())
+            .collect();
+        assert_eq!(vec!["johndoe123"], history);
+
+        user.change_username("JohnDoe123".into());
+        let history: Vec<&str> = user
+            .username_history()
+            .iter()
+            .map(|change| change.username())
+            .collect();
+        assert_eq!(vec!["johndoe"], history);
+    }
+
+    #[test]
+    fn username_change_cooldown() {
+        let mut user = User::new_test_user(None);
+        let cooldown = Duration::days(30);
+        assert_eq!(None, user.username_change_blocked_until(cooldown));
+
+        user.change_username("johndoe123".into());
+        assert!(
+            user.username_change_blocked_until(cooldown).is_some(),
+            "expected username change to be blocked"
+        );
+        assert_eq!(None, user.username_change_blocked_until(Duration::zero()));
     }
 
     #[test]
@@ -338,4 +539,26 @@ mod tests {
             user.role()
         )
     }
+
+    #[test]
+    fn link_identity_once() {
+        let mut user = User::new_test_user(None);
+        let identity = LinkedIdentity::new(
+            "google".into(),
+            "sub-1".into(),
+            Some("johndoe@gmail.com".into()),
+            Utc::now(),
+        );
+        user.link_identity(identity.clone());
+        user.link_identity(identity);
+        user.link_identity(LinkedIdentity::new(
+            "github".into(),
+            "sub-1".into(),
+            None,
+            Utc::now(),
+        ));
+        assert_eq!(2, user.identities().len());
+        assert!(user.has_identity("google", "sub-1"));
+        assert!(!user.has_identity("google", "sub-2"));
+    }
 }
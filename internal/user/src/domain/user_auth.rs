@@ -1,4 +1,8 @@
+pub mod api_token;
 pub mod errors;
+pub mod oidc;
 pub mod otp;
+pub mod otp_hasher;
 pub mod otp_read_model;
 pub mod result;
+pub mod two_factor;
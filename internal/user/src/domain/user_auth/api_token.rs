@@ -0,0 +1,223 @@
+//! Long-lived tokens for bots and scripts.
+//!
+//! A token reads `pat_<id>_<secret>`. Only a keyed hash of the secret is
+//! stored, so the plain token is shown once, when it is created. The id is
+//! not secret and lets a token be looked up without scanning hashes.
+use chrono::{DateTime, Duration, Utc};
+use getset::Getters;
+use shared::guards::permissions::Permission;
+use uuid::Uuid;
+
+use super::{
+    errors::UserAuthError,
+    oidc::utils::random_token,
+    otp::ComparedOtps,
+    otp_hasher::{OtpHash, OtpHasher},
+    result::UserAuthResult,
+};
+
+pub const API_TOKEN_PREFIX: &str = "pat_";
+pub const MAX_API_TOKEN_TTL_DAYS: i64 = 365;
+/// `last_used_at` is only written when it is older than this, so busy bots
+/// do not write on every request.
+pub const LAST_USED_RESOLUTION_SECS: i64 = 60;
+
+#[derive(Debug, Clone, Getters, PartialEq)]
+#[getset(get = "pub")]
+pub struct ApiToken {
+    id: String,
+    user_id: String,
+    name: String,
+    scopes: Vec<Permission>,
+    token_hash: OtpHash,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+}
+
+impl ApiToken {
+    /// Returns the token and its plain value.
+    pub fn issue(
+        user_id: String,
+        name: String,
+        scopes: Vec<Permission>,
+        ttl: Duration,
+        hasher: &OtpHasher,
+        now: DateTime<Utc>,
+    ) -> UserAuthResult<(Self, String)> {
+        let scopes = validate_scopes(scopes)?;
+        if ttl <= Duration::zero() || ttl > Duration::days(MAX_API_TOKEN_TTL_DAYS) {
+            return Err(UserAuthError::InvalidApiTokenExpiry);
+        }
+        let id = Uuid::new_v4().simple().to_string();
+        let secret = random_token();
+        let token = Self {
+            token_hash: hasher.hash(&secret),
+            id: id.clone(),
+            user_id,
+            name,
+            scopes,
+            created_at: now,
+            expires_at: now + ttl,
+            last_used_at: None,
+        };
+        Ok((token, utils::format_token(&id, &secret)))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_all_fields(
+        id: String,
+        user_id: String,
+        name: String,
+        scopes: Vec<Permission>,
+        token_hash: OtpHash,
+        created_at: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+        last_used_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            id,
+            user_id,
+            name,
+            scopes,
+            token_hash,
+            created_at,
+            expires_at,
+            last_used_at,
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+
+    pub fn matches_secret(&self, secret: &str, hasher: &OtpHasher) -> bool {
+        hasher.compare(secret, Some(&self.token_hash.key_id), &self.token_hash.hash)
+            == ComparedOtps::Equal
+    }
+
+    pub fn needs_last_used_update(&self, now: DateTime<Utc>) -> bool {
+        self.last_used_at
+            .is_none_or(|at| now - at >= Duration::seconds(LAST_USED_RESOLUTION_SECS))
+    }
+
+    pub fn rename(&mut self, name: String) {
+        self.name = name;
+    }
+
+    pub fn set_scopes(&mut self, scopes: Vec<Permission>) -> UserAuthResult<()> {
+        self.scopes = validate_scopes(scopes)?;
+        Ok(())
+    }
+
+    pub fn scope_names(&self) -> Vec<String> {
+        self.scopes
+            .iter()
+            .map(|scope| scope.as_scope().to_string())
+            .collect()
+    }
+}
+
+/// A token needs at least one scope; duplicates are dropped.
+fn validate_scopes(scopes: Vec<Permission>) -> UserAuthResult<Vec<Permission>> {
+    let mut unique = Vec::with_capacity(scopes.len());
+    for scope in scopes {
+        if !unique.contains(&scope) {
+            unique.push(scope);
+        }
+    }
+    if unique.is_empty() {
+        return Err(UserAuthError::InvalidApiTokenScopes);
+    }
+    Ok(unique)
+}
+
+pub mod utils {
+    use super::API_TOKEN_PREFIX;
+
+    pub fn format_token(id: &str, secret: &str) -> String {
+        format!("{}{}_{}", API_TOKEN_PREFIX, id, secret)
+    }
+
+    pub fn is_api_token(token: &str) -> bool {
+        token.starts_with(API_TOKEN_PREFIX)
+    }
+
+    /// Splits a token into its id and secret.
+    pub fn parse_token(token: &str) -> Option<(&str, &str)> {
+        token
+            .strip_prefix(API_TOKEN_PREFIX)?
+            .split_once('_')
+            .filter(|(id, secret)| !id.is_empty() && !secret.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issued_token_matches_only_its_secret() {
+        let hasher = OtpHasher::new_test_hasher();
+        let (token, plain) = ApiToken::issue(
+            "user-id123".into(),
+            "ci bot".into(),
+            vec![Permission::ViewUser],
+            Duration::days(30),
+            &hasher,
+            Utc::now(),
+        )
+        .unwrap();
+
+        let (id, secret) = utils::parse_token(&plain).unwrap();
+        assert_eq!(id, token.id());
+        assert!(token.matches_secret(secret, &hasher));
+        assert!(!token.matches_secret("guess", &hasher));
+        assert_ne!(token.token_hash().hash, secret);
+    }
+
+    #[test]
+    fn issue_requires_scopes_and_bounded_expiry() {
+        let hasher = OtpHasher::new_test_hasher();
+        let now = Utc::now();
+
+        let no_scopes = ApiToken::issue(
+            "user-id123".into(),
+            "ci bot".into(),
+            vec![],
+            Duration::days(30),
+            &hasher,
+            now,
+        );
+        assert_eq!(no_scopes.unwrap_err(), UserAuthError::InvalidApiTokenScopes);
+
+        let too_long = ApiToken::issue(
+            "user-id123".into(),
+            "ci bot".into(),
+            vec![Permission::ViewUser],
+            Duration::days(366),
+            &hasher,
+            now,
+        );
+        assert_eq!(too_long.unwrap_err(), UserAuthError::InvalidApiTokenExpiry);
+
+        let (token, _) = ApiToken::issue(
+            "user-id123".into(),
+            "ci bot".into(),
+            vec![Permission::ViewUser, Permission::ViewUser],
+            Duration::days(1),
+            &hasher,
+            now,
+        )
+        .unwrap();
+        assert_eq!(token.scopes(), &vec![Permission::ViewUser]);
+    }
+
+    #[test]
+    fn parse_token_rejects_other_formats() {
+        assert_eq!(utils::parse_token("eyJhbGciOi.x.y"), None);
+        assert_eq!(utils::parse_token("pat_"), None);
+        assert_eq!(utils::parse_token("pat_abc"), None);
+        assert_eq!(utils::parse_token("pat_abc_s_e"), Some(("abc", "s_e")));
+    }
+}
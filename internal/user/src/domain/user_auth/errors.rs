@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use std::fmt;
 use tracing::error;
 
@@ -12,6 +13,22 @@ pub enum UserAuthError {
     Database,
     InvalidTransaction,
     MissMatchOtp,
+    OtpResendTooSoon(DateTime<Utc>),
+    TooManyOtpRequests(DateTime<Utc>),
+    TwoFactorAlreadyEnabled,
+    TwoFactorNotEnabled,
+    TwoFactorNotEnrolled,
+    TwoFactorRequired,
+    InvalidTwoFactorCode,
+    InvalidTwoFactorChallenge,
+    UnknownOidcProvider(String),
+    InvalidOidcState,
+    OidcEmailNotVerified,
+    OidcIdentityAlreadyLinked,
+    OidcProvider(String),
+    ApiTokenNotFound,
+    InvalidApiTokenScopes,
+    InvalidApiTokenExpiry,
 }
 
 impl fmt::Display for UserAuthError {
@@ -26,6 +43,83 @@ impl fmt::Display for UserAuthError {
             Self::InvalidOtp => write!(f, "Invalid otp"),
             Self::InvalidTransaction => write!(f, "Invalid transaction"),
             Self::MissMatchOtp => write!(f, "Otp does not match"),
+            Self::OtpResendTooSoon(retry_at) => write!(
+                f,
+                "An otp was sent recently. A new one can be requested after {}",
+                retry_at.to_rfc3339()
+            ),
+            Self::TooManyOtpRequests(retry_at) => write!(
+                f,
+                "Too many otp requests. Try again after {}",
+                retry_at.to_rfc3339()
+            ),
+            Self::TwoFactorAlreadyEnabled => {
+                write!(f, "Two-factor authentication is already enabled")
+            }
+            Self::TwoFactorNotEnabled => write!(f, "Two-factor authentication is not enabled"),
+            Self::TwoFactorNotEnrolled => write!(f, "Two-factor enrollment has not been started"),
+            Self::TwoFactorRequired => {
+                write!(f, "Two-factor authentication is required for this role")
+            }
+            Self::InvalidTwoFactorCode => write!(f, "Invalid two-factor code"),
+            Self::InvalidTwoFactorChallenge => write!(f, "Invalid or expired two-factor challenge"),
+            Self::UnknownOidcProvider(name) => write!(f, "Unknown identity provider: {}", name),
+            Self::InvalidOidcState => write!(f, "Invalid or expired sign-in state"),
+            Self::OidcEmailNotVerified => {
+                write!(f, "The identity provider has not verified this email")
+            }
+            Self::OidcIdentityAlreadyLinked => {
+                write!(f, "This identity is already linked to another account")
+            }
+            Self::OidcProvider(msg) => write!(f, "Identity provider error: {}", msg),
+            Self::ApiTokenNotFound => write!(f, "API token not found"),
+            Self::InvalidApiTokenScopes => write!(f, "An API token needs at least one scope"),
+            Self::InvalidApiTokenExpiry => write!(
+                f,
+                "API tokens must expire within {} days",
+                super::api_token::MAX_API_TOKEN_TTL_DAYS
+            ),
+        }
+    }
+}
+
+impl UserAuthError {
+    /// Stable code sent to clients with the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::OtpNotFound => "OTP_NOT_FOUND",
+            Self::InvalidToken => "UNAUTHENTICATED",
+            Self::OtpAlreadyUsed => "OTP_ALREADY_USED",
+            Self::OtpExpired => "OTP_EXPIRED",
+            Self::TooManyAttempts => "TOO_MANY_ATTEMPTS",
+            Self::InvalidOtp | Self::MissMatchOtp => "INVALID_OTP",
+            Self::Database | Self::InvalidTransaction => "INTERNAL_SERVER_ERROR",
+            Self::OtpResendTooSoon(_) => "OTP_RESEND_TOO_SOON",
+            Self::TooManyOtpRequests(_) => "TOO_MANY_OTP_REQUESTS",
+            Self::TwoFactorAlreadyEnabled => "TWO_FACTOR_ALREADY_ENABLED",
+            Self::TwoFactorNotEnabled => "TWO_FACTOR_NOT_ENABLED",
+            Self::TwoFactorNotEnrolled => "TWO_FACTOR_NOT_ENROLLED",
+            Self::TwoFactorRequired => "TWO_FACTOR_REQUIRED",
+            Self::InvalidTwoFactorCode => "INVALID_TWO_FACTOR_CODE",
+            Self::InvalidTwoFactorChallenge => "INVALID_TWO_FACTOR_CHALLENGE",
+            Self::UnknownOidcProvider(_) => "UNKNOWN_OIDC_PROVIDER",
+            Self::InvalidOidcState => "INVALID_OIDC_STATE",
+            Self::OidcEmailNotVerified => "OIDC_EMAIL_NOT_VERIFIED",
+            Self::OidcIdentityAlreadyLinked => "OIDC_IDENTITY_ALREADY_LINKED",
+            Self::OidcProvider(_) => "OIDC_PROVIDER_ERROR",
+            Self::ApiTokenNotFound => "API_TOKEN_NOT_FOUND",
+            Self::InvalidApiTokenScopes => "INVALID_API_TOKEN_SCOPES",
+            Self::InvalidApiTokenExpiry => "INVALID_API_TOKEN_EXPIRY",
+        }
+    }
+
+    /// When the caller may try again, for errors that only last a while.
+    pub fn retry_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::OtpResendTooSoon(retry_at) | Self::TooManyOtpRequests(retry_at) => {
+                Some(*retry_at)
+            }
+            _ => None,
         }
     }
 }
@@ -39,6 +133,13 @@ impl From<mongodb::error::Error> for UserAuthError {
     }
 }
 
+impl From<reqwest::Error> for UserAuthError {
+    fn from(err: reqwest::Error) -> Self {
+        error!("OIDC request Error: {:#?}", err);
+        Self::OidcProvider("Request to identity provider failed".into())
+    }
+}
+
 impl From<jsonwebtoken::errors::Error> for UserAuthError {
     fn from(err: jsonwebtoken::errors::Error) -> Self {
         error!("JWT Error: {:#?}", err);
@@ -1,7 +1,7 @@
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use getset::Getters;
 
-use super::{errors::UserAuthError, result::UserAuthResult};
+use super::{errors::UserAuthError, otp_hasher::OtpHash, result::UserAuthResult};
 pub const MAX_ALLOWED_ATTEMPTS: u32 = 5;
 
 #[derive(Debug, Clone, Getters, PartialEq)]
@@ -12,6 +12,8 @@ pub struct OtpEntry {
     used: bool,
     attempts: u32,
     email: String,
+    /// Pepper used for `otp_hash`. `None` for legacy unkeyed hashes.
+    key_id: Option<String>,
 }
 
 impl OtpEntry {
@@ -28,8 +30,27 @@ impl OtpEntry {
             used,
             attempts,
             email,
+            key_id: None,
         }
     }
+    pub fn with_key_id(mut self, key_id: Option<String>) -> Self {
+        self.key_id = key_id;
+        self
+    }
+    /// Builds the entry for a newly issued code. Attempts made against a code that
+    /// is still live carry over, so reissuing cannot reset [`MAX_ALLOWED_ATTEMPTS`].
+    pub fn reissue(
+        previous: Option<&OtpEntry>,
+        email: String,
+        otp_hash: OtpHash,
+        expires_at: i64,
+    ) -> Self {
+        let attempts = previous
+            .filter(|p| !p.is_used() && !p.is_expired())
+            .map_or(0, |p| p.attempts);
+        Self::new(email, false, attempts, otp_hash.hash, expires_at)
+            .with_key_id(Some(otp_hash.key_id))
+    }
     pub fn is_expired(&self) -> bool {
         Utc::now().timestamp_millis() > self.expires_at
     }
@@ -59,6 +80,61 @@ impl OtpEntry {
     }
 }
 
+/// Limits on how often codes are issued. Applied to every flow that sends a
+/// code, per email and per client IP, over a sliding window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtpIssuePolicy {
+    pub min_interval: Duration,
+    pub window: Duration,
+    pub max_per_email: u32,
+    pub max_per_ip: u32,
+}
+
+impl Default for OtpIssuePolicy {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::seconds(60),
+            window: Duration::hours(1),
+            max_per_email: 5,
+            max_per_ip: 20,
+        }
+    }
+}
+
+impl OtpIssuePolicy {
+    /// `email_issued` and `ip_issued` are the issue times within the window, oldest first.
+    pub fn check(
+        &self,
+        now: DateTime<Utc>,
+        email_issued: &[DateTime<Utc>],
+        ip_issued: &[DateTime<Utc>],
+    ) -> UserAuthResult<()> {
+        if let Some(last) = email_issued.last()
+            && *last + self.min_interval > now
+        {
+            return Err(UserAuthError::OtpResendTooSoon(*last + self.min_interval));
+        }
+        self.check_window(now, email_issued, self.max_per_email)?;
+        self.check_window(now, ip_issued, self.max_per_ip)
+    }
+    fn check_window(
+        &self,
+        now: DateTime<Utc>,
+        issued: &[DateTime<Utc>],
+        max: u32,
+    ) -> UserAuthResult<()> {
+        let max = max as usize;
+        if issued.len() < max {
+            return Ok(());
+        }
+        // A slot frees up once enough of the oldest issues have left the window.
+        let retry_at = issued
+            .get(issued.len() - max)
+            .map_or(now + self.window, |at| *at + self.window);
+        Err(UserAuthError::TooManyOtpRequests(retry_at))
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum ComparedOtps {
     Equal,
@@ -75,19 +151,13 @@ pub mod utils {
         let range = Uniform::try_from(0..10).unwrap();
         (0..6).map(|_| rng.sample(&range).to_string()).collect()
     }
+    /// Unkeyed SHA-256 of a code, as stored before peppers. Use
+    /// [`OtpHasher`](super::super::otp_hasher::OtpHasher) for new codes.
     pub fn hash_otp(otp: &str) -> String {
         let mut hasher = Sha256::new();
         hasher.update(otp.as_bytes());
         format!("{:x}", hasher.finalize())
     }
-    pub fn compare_otps(otp: &str, otp_hash: &str) -> ComparedOtps {
-        let hashed_otp = hash_otp(otp);
-        if hashed_otp == otp_hash {
-            ComparedOtps::Equal
-        } else {
-            ComparedOtps::NotEqual
-        }
-    }
     pub fn get_otp_expiration() -> i64 {
         let expiration_time = Utc::now() + Duration::minutes(5);
         expiration_time.timestamp_millis()
@@ -108,11 +178,94 @@ pub mod utils {
             let hashed_otp = hash_otp(otp);
             assert_eq!(hashed_otp.len(), 64);
         }
-        #[test]
-        fn test_compare_otps() {
-            let otp = "123456";
-            let hashed_otp = hash_otp(otp);
-            assert_eq!(compare_otps(otp, &hashed_otp), ComparedOtps::Equal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user_auth::otp_hasher::OtpHasher;
+
+    fn entry(used: bool, attempts: u32, expires_at: i64) -> OtpEntry {
+        OtpEntry::new(
+            "test@example.com".into(),
+            used,
+            attempts,
+            "hash".into(),
+            expires_at,
+        )
+    }
+
+    #[test]
+    fn reissue_carries_over_attempts_of_live_code() {
+        let previous = entry(false, 3, utils::get_otp_expiration());
+        let next = OtpEntry::reissue(
+            Some(&previous),
+            "test@example.com".into(),
+            OtpHasher::new_test_hasher().hash("654321"),
+            utils::get_otp_expiration(),
+        );
+        assert_eq!(*next.attempts(), 3);
+        assert!(!next.is_used());
+        assert_eq!(next.key_id().as_deref(), Some("test"));
+    }
+
+    #[test]
+    fn reissue_resets_attempts_of_expired_or_used_code() {
+        let expired = entry(false, MAX_ALLOWED_ATTEMPTS, 0);
+        let used = entry(true, 2, utils::get_otp_expiration());
+        for previous in [expired, used] {
+            let next = OtpEntry::reissue(
+                Some(&previous),
+                "test@example.com".into(),
+                OtpHasher::new_test_hasher().hash("654321"),
+                utils::get_otp_expiration(),
+            );
+            assert_eq!(*next.attempts(), 0);
         }
     }
+
+    #[test]
+    fn issue_policy_enforces_min_interval() {
+        let policy = OtpIssuePolicy::default();
+        let now = Utc::now();
+        let last = now - Duration::seconds(10);
+        assert_eq!(
+            policy.check(now, &[last], &[]),
+            Err(UserAuthError::OtpResendTooSoon(last + policy.min_interval))
+        );
+        assert!(
+            policy
+                .check(now, &[now - Duration::minutes(2)], &[])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn issue_policy_enforces_max_per_window() {
+        let policy = OtpIssuePolicy {
+            max_per_email: 2,
+            max_per_ip: 3,
+            ..Default::default()
+        };
+        let now = Utc::now();
+        let issued = [now - Duration::minutes(30), now - Duration::minutes(10)];
+        assert_eq!(
+            policy.check(now, &issued, &[]),
+            Err(UserAuthError::TooManyOtpRequests(issued[0] + policy.window))
+        );
+
+        let ip_issued = [
+            now - Duration::minutes(40),
+            now - Duration::minutes(20),
+            now - Duration::minutes(5),
+        ];
+        assert_eq!(
+            policy.check(now, &issued[..1], &ip_issued),
+            Err(UserAuthError::TooManyOtpRequests(
+                ip_issued[0] + policy.window
+            ))
+        );
+        assert!(policy.check(now, &issued[..1], &ip_issued[..2]).is_ok());
+    }
 }
@@ -0,0 +1,193 @@
+//! Keyed hashing of one-time codes.
+//!
+//! Codes are stored as `HMAC-SHA256(pepper, SHA-256(code))`, so reading the
+//! `otps` collection is not enough to brute-force the six digits. Each hash
+//! records the `key_id` of its pepper: the first configured pepper signs new
+//! codes and the others only verify codes issued before a rotation. Entries
+//! written before peppers existed stored the bare `SHA-256(code)`, which is the
+//! inner digest here, so they can be rewrapped without knowing the code.
+use std::fmt;
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use shared::config::AppConfig;
+use subtle::ConstantTimeEq;
+use tracing::warn;
+
+use super::otp::ComparedOtps;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Clone)]
+pub struct OtpPepper {
+    key_id: String,
+    secret: Vec<u8>,
+}
+
+impl OtpPepper {
+    pub fn new(key_id: impl Into<String>, secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            key_id: key_id.into(),
+            secret: secret.into(),
+        }
+    }
+}
+
+impl fmt::Debug for OtpPepper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OtpPepper")
+            .field("key_id", &self.key_id)
+            .field("secret", &"[redacted]")
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtpHash {
+    pub key_id: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct OtpHasher {
+    peppers: Vec<OtpPepper>,
+}
+
+impl OtpHasher {
+    /// The first pepper is used for new codes.
+    pub fn new(peppers: Vec<OtpPepper>) -> Self {
+        assert!(!peppers.is_empty(), "At least one otp pepper is required");
+        Self { peppers }
+    }
+
+    /// Hashes with the peppers of `OTP_PEPPERS`.
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::new(
+            config
+                .otp_peppers
+                .iter()
+                .map(|(key_id, secret)| OtpPepper::new(key_id, secret.expose().as_bytes()))
+                .collect(),
+        )
+    }
+
+    pub fn current_key_id(&self) -> &str {
+        &self.peppers[0].key_id
+    }
+
+    pub fn hash(&self, otp: &str) -> OtpHash {
+        let pepper = &self.peppers[0];
+        OtpHash {
+            key_id: pepper.key_id.clone(),
+            hash: hex::encode(keyed(pepper, &Sha256::digest(otp.as_bytes()))),
+        }
+    }
+
+    /// Rewraps a legacy unkeyed hash with the current pepper. Returns `None`
+    /// when `legacy_hash` is not a hex encoded SHA-256 digest.
+    pub fn wrap_legacy(&self, legacy_hash: &str) -> Option<OtpHash> {
+        let digest = hex::decode(legacy_hash).ok().filter(|d| d.len() == 32)?;
+        let pepper = &self.peppers[0];
+        Some(OtpHash {
+            key_id: pepper.key_id.clone(),
+            hash: hex::encode(keyed(pepper, &digest)),
+        })
+    }
+
+    /// Compares in constant time. Entries without a `key_id` hold a legacy unkeyed hash.
+    pub fn compare(&self, otp: &str, key_id: Option<&str>, otp_hash: &str) -> ComparedOtps {
+        let Ok(expected) = hex::decode(otp_hash) else {
+            return ComparedOtps::NotEqual;
+        };
+        let digest = Sha256::digest(otp.as_bytes());
+        let equal = match key_id {
+            None => bool::from(digest.as_slice().ct_eq(&expected)),
+            Some(key_id) => match self.peppers.iter().find(|p| p.key_id == key_id) {
+                Some(pepper) => mac(pepper, &digest).verify_slice(&expected).is_ok(),
+                None => {
+                    warn!("No otp pepper configured for key id {}", key_id);
+                    false
+                }
+            },
+        };
+        if equal {
+            ComparedOtps::Equal
+        } else {
+            ComparedOtps::NotEqual
+        }
+    }
+
+    /// A single-pepper hasher for tests.
+    pub fn new_test_hasher() -> Self {
+        Self::new(vec![OtpPepper::new("test", "test-pepper")])
+    }
+}
+
+fn mac(pepper: &OtpPepper, digest: &[u8]) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(&pepper.secret).expect("HMAC accepts keys of any length");
+    mac.update(digest);
+    mac
+}
+
+fn keyed(pepper: &OtpPepper, digest: &[u8]) -> Vec<u8> {
+    mac(pepper, digest).finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user_auth::otp::utils::hash_otp;
+
+    fn rotated() -> OtpHasher {
+        OtpHasher::new(vec![
+            OtpPepper::new("v2", "new-pepper"),
+            OtpPepper::new("v1", "old-pepper"),
+        ])
+    }
+
+    #[test]
+    fn hash_is_keyed() {
+        let hash = OtpHasher::new_test_hasher().hash("123456");
+        assert_eq!(hash.key_id, "test");
+        assert_ne!(hash.hash, hash_otp("123456"));
+        assert_ne!(hash.hash, rotated().hash("123456").hash);
+    }
+
+    #[test]
+    fn compare_accepts_codes_hashed_with_previous_pepper() {
+        let old = OtpHasher::new(vec![OtpPepper::new("v1", "old-pepper")]).hash("123456");
+        let hasher = rotated();
+        assert_eq!(
+            hasher.compare("123456", Some(&old.key_id), &old.hash),
+            ComparedOtps::Equal
+        );
+        assert_eq!(
+            hasher.compare("654321", Some(&old.key_id), &old.hash),
+            ComparedOtps::NotEqual
+        );
+        assert_eq!(
+            hasher.compare("123456", Some("unknown"), &old.hash),
+            ComparedOtps::NotEqual
+        );
+    }
+
+    #[test]
+    fn compare_falls_back_to_legacy_hash() {
+        let hasher = OtpHasher::new_test_hasher();
+        let legacy = hash_otp("123456");
+        assert_eq!(hasher.compare("123456", None, &legacy), ComparedOtps::Equal);
+        assert_eq!(
+            hasher.compare("654321", None, &legacy),
+            ComparedOtps::NotEqual
+        );
+    }
+
+    #[test]
+    fn wrap_legacy_matches_keyed_hash() {
+        let hasher = rotated();
+        let wrapped = hasher.wrap_legacy(&hash_otp("123456")).unwrap();
+        assert_eq!(wrapped, hasher.hash("123456"));
+        assert!(hasher.wrap_legacy("not-hex").is_none());
+    }
+}
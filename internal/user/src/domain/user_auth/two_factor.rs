@@ -0,0 +1,375 @@
+//! TOTP (RFC 6238) second factor with single-use recovery codes.
+//!
+//! Enrollment stores a pending secret; the factor is only enabled once the user
+//! proves their authenticator works by confirming a code. Recovery codes are
+//! hashed with the [`OtpHasher`] and removed when used. Signing in with 2FA
+//! enabled is a two step flow: a verified email otp starts a short lived
+//! challenge, which is completed with a TOTP or recovery code.
+use chrono::{DateTime, Duration, Utc};
+use getset::Getters;
+use sha2::{Digest, Sha256};
+use shared::guards::roles::UserRole;
+use subtle::ConstantTimeEq;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use super::{
+    errors::UserAuthError,
+    otp::ComparedOtps,
+    otp_hasher::{OtpHash, OtpHasher},
+    result::UserAuthResult,
+};
+
+pub const TOTP_DIGITS: usize = 6;
+pub const TOTP_STEP_SECS: u64 = 30;
+/// Codes from one step before or after the current one are accepted to allow for clock drift.
+pub const TOTP_SKEW_STEPS: u64 = 1;
+pub const RECOVERY_CODE_COUNT: usize = 10;
+pub const MAX_CHALLENGE_ATTEMPTS: u32 = 5;
+pub const CHALLENGE_TTL_MINUTES: i64 = 5;
+/// Wrong codes in a row, outside of a sign-in challenge, before codes are refused.
+pub const MAX_CODE_ATTEMPTS: u32 = 5;
+/// How long codes are refused after the last of too many wrong ones.
+pub const CODE_LOCKOUT_MINUTES: i64 = 15;
+
+/// Roles that must have 2FA enabled to sign in with their role, and the issuer
+/// shown in authenticator apps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TwoFactorPolicy {
+    pub required_roles: Vec<UserRole>,
+    pub issuer: String,
+}
+
+impl Default for TwoFactorPolicy {
+    fn default() -> Self {
+        Self {
+            required_roles: vec![],
+            issuer: "SocialApp".into(),
+        }
+    }
+}
+
+impl TwoFactorPolicy {
+    pub fn is_required_for(&self, role: &UserRole) -> bool {
+        self.required_roles.contains(role)
+    }
+}
+
+#[derive(Debug, Clone, Getters, PartialEq)]
+#[getset(get = "pub")]
+pub struct TwoFactorChallenge {
+    /// SHA-256 of the challenge token handed to the client.
+    token_hash: String,
+    expires_at: DateTime<Utc>,
+    attempts: u32,
+}
+
+impl TwoFactorChallenge {
+    pub fn new(token_hash: String, expires_at: DateTime<Utc>, attempts: u32) -> Self {
+        Self {
+            token_hash,
+            expires_at,
+            attempts,
+        }
+    }
+    /// A new challenge and the token to hand to the client. Only the hash is kept.
+    pub fn start(now: DateTime<Utc>) -> (String, Self) {
+        let token = utils::generate_challenge_token();
+        let challenge = Self::new(
+            utils::hash_challenge_token(&token),
+            now + Duration::minutes(CHALLENGE_TTL_MINUTES),
+            0,
+        );
+        (token, challenge)
+    }
+}
+
+#[derive(Debug, Clone, Getters, PartialEq)]
+#[getset(get = "pub")]
+pub struct TwoFactor {
+    /// Base32 encoded TOTP secret.
+    secret: String,
+    /// `None` while enrollment is pending confirmation.
+    enabled_at: Option<DateTime<Utc>>,
+    recovery_codes: Vec<OtpHash>,
+    /// The last accepted TOTP step, so a code cannot be replayed within its window.
+    last_used_step: Option<u64>,
+    challenge: Option<TwoFactorChallenge>,
+    /// Wrong codes in a row outside of a challenge, such as when disabling 2FA.
+    failed_attempts: u32,
+    last_failed_at: Option<DateTime<Utc>>,
+}
+
+impl TwoFactor {
+    /// Starts enrollment with a freshly generated secret.
+    pub fn enroll() -> Self {
+        Self::new_with_all_fields(utils::generate_secret(), None, vec![], None, None, 0, None)
+    }
+    pub fn new_with_all_fields(
+        secret: String,
+        enabled_at: Option<DateTime<Utc>>,
+        recovery_codes: Vec<OtpHash>,
+        last_used_step: Option<u64>,
+        challenge: Option<TwoFactorChallenge>,
+        failed_attempts: u32,
+        last_failed_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            secret,
+            enabled_at,
+            recovery_codes,
+            last_used_step,
+            challenge,
+            failed_attempts,
+            last_failed_at,
+        }
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.enabled_at.is_some()
+    }
+    /// The `otpauth://` URI authenticator apps read from a QR code.
+    pub fn provisioning_uri(&self, issuer: &str, account_name: &str) -> String {
+        self.totp(issuer, account_name).get_url()
+    }
+    /// The code an authenticator would show at `now`.
+    pub fn generate_code(&self, now: DateTime<Utc>) -> String {
+        self.totp("", "").generate(now.timestamp().max(0) as u64)
+    }
+    pub fn enable(&mut self, recovery_codes: Vec<OtpHash>, now: DateTime<Utc>) {
+        self.enabled_at = Some(now);
+        self.recovery_codes = recovery_codes;
+    }
+    /// Accepts a TOTP code, or a recovery code which is then consumed.
+    pub fn verify(&mut self, code: &str, hasher: &OtpHasher, now: DateTime<Utc>) -> bool {
+        if code.len() == TOTP_DIGITS && code.chars().all(|c| c.is_ascii_digit()) {
+            self.verify_totp(code, now)
+        } else {
+            self.use_recovery_code(code, hasher)
+        }
+    }
+    pub fn verify_totp(&mut self, code: &str, now: DateTime<Utc>) -> bool {
+        let totp = self.totp("", "");
+        let current = now.timestamp().max(0) as u64 / TOTP_STEP_SECS;
+        let matched = (current.saturating_sub(TOTP_SKEW_STEPS)..=current + TOTP_SKEW_STEPS)
+            .filter(|step| self.last_used_step.is_none_or(|last| *step > last))
+            .find(|step| {
+                bool::from(
+                    totp.generate(step * TOTP_STEP_SECS)
+                        .as_bytes()
+                        .ct_eq(code.as_bytes()),
+                )
+            });
+        if let Some(step) = matched {
+            self.last_used_step = Some(step);
+        }
+        matched.is_some()
+    }
+    pub fn use_recovery_code(&mut self, code: &str, hasher: &OtpHasher) -> bool {
+        let code = utils::normalize_recovery_code(code);
+        let position = self.recovery_codes.iter().position(|recovery| {
+            hasher.compare(&code, Some(&recovery.key_id), &recovery.hash) == ComparedOtps::Equal
+        });
+        if let Some(position) = position {
+            self.recovery_codes.remove(position);
+        }
+        position.is_some()
+    }
+    /// Starts a sign-in challenge and returns its token. Only the hash is kept.
+    pub fn start_challenge(&mut self, now: DateTime<Utc>) -> String {
+        let (token, challenge) = TwoFactorChallenge::start(now);
+        self.challenge = Some(challenge);
+        token
+    }
+    pub fn validate_challenge(&self, token: &str, now: DateTime<Utc>) -> UserAuthResult<()> {
+        let challenge = self
+            .challenge
+            .as_ref()
+            .ok_or(UserAuthError::InvalidTwoFactorChallenge)?;
+        if challenge.expires_at < now {
+            return Err(UserAuthError::InvalidTwoFactorChallenge);
+        }
+        if challenge.attempts >= MAX_CHALLENGE_ATTEMPTS {
+            return Err(UserAuthError::TooManyAttempts);
+        }
+        let token_hash = utils::hash_challenge_token(token);
+        if !bool::from(token_hash.as_bytes().ct_eq(challenge.token_hash.as_bytes())) {
+            return Err(UserAuthError::InvalidTwoFactorChallenge);
+        }
+        Ok(())
+    }
+    pub fn increment_challenge_attempts(&mut self) {
+        if let Some(challenge) = self.challenge.as_mut() {
+            challenge.attempts += 1;
+        }
+    }
+    pub fn clear_challenge(&mut self) {
+        self.challenge = None;
+    }
+    /// Refuses codes after [`MAX_CODE_ATTEMPTS`] wrong ones in a row, until
+    /// [`CODE_LOCKOUT_MINUTES`] have passed since the last.
+    pub fn validate_code_attempts(&self, now: DateTime<Utc>) -> UserAuthResult<()> {
+        if self.failed_attempts >= MAX_CODE_ATTEMPTS && !self.lockout_expired(now) {
+            return Err(UserAuthError::TooManyAttempts);
+        }
+        Ok(())
+    }
+    pub fn record_failed_code(&mut self, now: DateTime<Utc>) {
+        if self.lockout_expired(now) {
+            self.failed_attempts = 0;
+        }
+        self.failed_attempts += 1;
+        self.last_failed_at = Some(now);
+    }
+    fn lockout_expired(&self, now: DateTime<Utc>) -> bool {
+        self.last_failed_at
+            .is_none_or(|at| at + Duration::minutes(CODE_LOCKOUT_MINUTES) <= now)
+    }
+    fn totp(&self, issuer: &str, account_name: &str) -> TOTP {
+        let secret = Secret::Encoded(self.secret.clone())
+            .to_bytes()
+            .expect("TOTP secret is valid base32");
+        TOTP::new_unchecked(
+            Algorithm::SHA1,
+            TOTP_DIGITS,
+            TOTP_SKEW_STEPS as u8,
+            TOTP_STEP_SECS,
+            secret,
+            (!issuer.is_empty()).then(|| issuer.to_string()),
+            account_name.to_string(),
+        )
+    }
+}
+
+pub mod utils {
+    use super::*;
+    use rand::{Rng, seq::IndexedRandom};
+
+    /// Lowercase letters and digits without look-alikes (0/o, 1/l/i).
+    const RECOVERY_CODE_ALPHABET: &[char] = &[
+        'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'j', 'k', 'm', 'n', 'p', 'q', 'r', 's', 't', 'u',
+        'v', 'w', 'x', 'y', 'z', '2', '3', '4', '5', '6', '7', '8', '9',
+    ];
+
+    pub fn generate_secret() -> String {
+        match Secret::generate_secret().to_encoded() {
+            Secret::Encoded(secret) => secret,
+            Secret::Raw(_) => unreachable!("to_encoded returns an encoded secret"),
+        }
+    }
+    /// Plain recovery codes formatted as `xxxxx-xxxxx`, to be shown to the user once.
+    pub fn generate_recovery_codes() -> Vec<String> {
+        let mut rng = rand::rng();
+        (0..RECOVERY_CODE_COUNT)
+            .map(|_| {
+                let code: String = (0..10)
+                    .map(|_| *RECOVERY_CODE_ALPHABET.choose(&mut rng).unwrap())
+                    .collect();
+                format!("{}-{}", &code[..5], &code[5..])
+            })
+            .collect()
+    }
+    /// Recovery codes are compared without separators, whitespace or case.
+    pub fn normalize_recovery_code(code: &str) -> String {
+        code.chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    }
+    pub fn hash_recovery_codes(codes: &[String], hasher: &OtpHasher) -> Vec<OtpHash> {
+        codes
+            .iter()
+            .map(|code| hasher.hash(&normalize_recovery_code(code)))
+            .collect()
+    }
+    pub fn generate_challenge_token() -> String {
+        let bytes: [u8; 32] = rand::rng().random();
+        hex::encode(bytes)
+    }
+    pub fn hash_challenge_token(token: &str) -> String {
+        hex::encode(Sha256::digest(token.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_totp_rejects_replayed_code() {
+        let mut two_factor = TwoFactor::enroll();
+        let now = Utc::now();
+        let code = two_factor.generate_code(now);
+        assert!(two_factor.verify_totp(&code, now));
+        assert!(!two_factor.verify_totp(&code, now));
+    }
+
+    #[test]
+    fn verify_totp_allows_one_step_of_drift() {
+        let mut two_factor = TwoFactor::enroll();
+        let now = Utc::now();
+        let previous = two_factor.generate_code(now - Duration::seconds(TOTP_STEP_SECS as i64));
+        assert!(two_factor.verify_totp(&previous, now));
+
+        let stale = two_factor.generate_code(now - Duration::minutes(5));
+        assert!(!two_factor.verify_totp(&stale, now));
+    }
+
+    #[test]
+    fn recovery_codes_are_single_use() {
+        let hasher = OtpHasher::new_test_hasher();
+        let codes = utils::generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+
+        let mut two_factor = TwoFactor::enroll();
+        two_factor.enable(utils::hash_recovery_codes(&codes, &hasher), Utc::now());
+        assert!(two_factor.verify(&codes[0].to_uppercase(), &hasher, Utc::now()));
+        assert!(!two_factor.verify(&codes[0], &hasher, Utc::now()));
+        assert_eq!(two_factor.recovery_codes().len(), RECOVERY_CODE_COUNT - 1);
+    }
+
+    #[test]
+    fn challenge_expires_and_limits_attempts() {
+        let mut two_factor = TwoFactor::enroll();
+        let now = Utc::now();
+        let token = two_factor.start_challenge(now);
+        assert!(two_factor.validate_challenge(&token, now).is_ok());
+        assert_eq!(
+            two_factor.validate_challenge("other", now),
+            Err(UserAuthError::InvalidTwoFactorChallenge)
+        );
+        assert_eq!(
+            two_factor
+                .validate_challenge(&token, now + Duration::minutes(CHALLENGE_TTL_MINUTES + 1)),
+            Err(UserAuthError::InvalidTwoFactorChallenge)
+        );
+
+        (0..MAX_CHALLENGE_ATTEMPTS).for_each(|_| two_factor.increment_challenge_attempts());
+        assert_eq!(
+            two_factor.validate_challenge(&token, now),
+            Err(UserAuthError::TooManyAttempts)
+        );
+    }
+
+    #[test]
+    fn wrong_codes_lock_out_for_a_while() {
+        let mut two_factor = TwoFactor::enroll();
+        let now = Utc::now();
+        (0..MAX_CODE_ATTEMPTS).for_each(|_| two_factor.record_failed_code(now));
+        assert_eq!(
+            two_factor.validate_code_attempts(now),
+            Err(UserAuthError::TooManyAttempts)
+        );
+
+        let later = now + Duration::minutes(CODE_LOCKOUT_MINUTES);
+        assert!(two_factor.validate_code_attempts(later).is_ok());
+        two_factor.record_failed_code(later);
+        assert_eq!(*two_factor.failed_attempts(), 1);
+    }
+
+    #[test]
+    fn provisioning_uri_contains_issuer_and_secret() {
+        let two_factor = TwoFactor::enroll();
+        let uri = two_factor.provisioning_uri("SocialApp", "john@example.com");
+        assert!(uri.starts_with("otpauth://totp/SocialApp:john%40example.com?"));
+        assert!(uri.contains(&format!("secret={}", two_factor.secret())));
+    }
+}
@@ -2,8 +2,9 @@ use serde::{Deserialize, Serialize};
 
 use chrono::{DateTime, Utc};
 use shared::guards::roles::UserRole;
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub enum BanType {
     Definite {
         from: DateTime<Utc>,
@@ -12,7 +13,7 @@ pub enum BanType {
     Indefinite,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Ban {
     pub is_banned: bool,
     pub reason: String,
@@ -39,10 +40,11 @@ pub struct GetUsersResult {
     pub has_next: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct UserReadModel {
     pub id: String,
     pub username: String,
+    #[schema(format = Email)]
     pub email: String,
     pub role: UserRole,
     pub badges: Vec<String>,
@@ -1,11 +1,9 @@
 use crate::domain::result::UserDomainResult;
-use shared::{
-    auth::AuthUser,
-    guards::{permissions::UserPermission, roles::UserRole},
-};
+use shared::{auth::AuthUser, guards::permissions::UserPermission};
 
 #[cfg_attr(test, mockall::automock)]
 pub trait UserGuards: Send + Sync {
-    fn authorize(&self, role: &UserRole, perm: &UserPermission) -> UserDomainResult<()>;
+    /// Checks the role's permissions and, for scoped tokens, the token's scopes.
+    fn authorize(&self, auth_user: &AuthUser, perm: &UserPermission) -> UserDomainResult<()>;
     fn can_change_username(&self, user_id: &str, auth_user: &AuthUser) -> UserDomainResult<()>;
 }
@@ -1,2 +1,4 @@
+pub mod memoryimpl;
 pub mod mongoimpl;
+pub mod oidc;
 pub mod repository;
@@ -0,0 +1,111 @@
+use std::sync::RwLock;
+
+use crate::domain::{
+    audit_log::{AuditEntry, AuditLogFilter, AuditLogPage},
+    errors::UserDomainError,
+    result::UserDomainResult,
+};
+
+/// Keeps the audit log in process memory, for the in-memory storage engine and tests.
+#[derive(Default)]
+pub struct InMemoryAuditLogRepository {
+    entries: RwLock<Vec<AuditEntry>>,
+}
+
+impl InMemoryAuditLogRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn append(&self, entry: AuditEntry) -> UserDomainResult<()> {
+        self.entries
+            .write()
+            .map_err(|e| UserDomainError::Internal(e.to_string()))?
+            .push(entry);
+        Ok(())
+    }
+
+    pub async fn list(&self, filter: &AuditLogFilter) -> UserDomainResult<AuditLogPage> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|e| UserDomainError::Internal(e.to_string()))?;
+        let mut matching: Vec<AuditEntry> = entries
+            .iter()
+            .filter(|entry| filter.matches(entry))
+            .cloned()
+            .collect();
+        matching.sort_by(|a, b| (b.created_at, &b.id).cmp(&(a.created_at, &a.id)));
+        let has_next = matching.len() > filter.first as usize;
+        matching.truncate(filter.first as usize);
+        Ok(AuditLogPage {
+            entries: matching,
+            has_next,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::audit_log::{AuditAction, AuditLogPosition, AuditMetadata};
+    use chrono::{Duration, Utc};
+
+    #[tokio::test]
+    async fn list_pages_newest_first() {
+        let repo = InMemoryAuditLogRepository::new();
+        for minutes_ago in [3, 1, 2] {
+            let mut entry = AuditEntry::new(
+                "admin-id".into(),
+                AuditAction::BanUser,
+                "user-1".into(),
+                vec![],
+                AuditMetadata::default(),
+            );
+            entry.created_at = Utc::now() - Duration::minutes(minutes_ago);
+            repo.append(entry).await.unwrap();
+        }
+
+        let first_page = repo.list(&AuditLogFilter::new(2)).await.unwrap();
+        assert!(first_page.has_next);
+        assert!(first_page.entries[0].created_at > first_page.entries[1].created_at);
+
+        let mut next = AuditLogFilter::new(2);
+        next.after = Some(AuditLogPosition::from_cursor(&first_page.entries[1].cursor()).unwrap());
+        let second_page = repo.list(&next).await.unwrap();
+        assert!(!second_page.has_next);
+        assert_eq!(second_page.entries.len(), 1);
+        assert!(second_page.entries[0].created_at < first_page.entries[1].created_at);
+    }
+
+    #[tokio::test]
+    async fn list_applies_filters() {
+        let repo = InMemoryAuditLogRepository::new();
+        for (target_id, action, minutes_ago) in [
+            ("user-1", AuditAction::BanUser, 10),
+            ("user-1", AuditAction::AwardBadge, 5),
+            ("user-2", AuditAction::BanUser, 1),
+        ] {
+            let mut entry = AuditEntry::new(
+                "admin-id".into(),
+                action,
+                target_id.into(),
+                vec![],
+                AuditMetadata::default(),
+            );
+            entry.created_at = Utc::now() - Duration::minutes(minutes_ago);
+            repo.append(entry).await.unwrap();
+        }
+
+        let mut by_target = AuditLogFilter::new(10);
+        by_target.target_id = Some("user-1".into());
+        by_target.action = Some(AuditAction::BanUser);
+        let page = repo.list(&by_target).await.unwrap();
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0].action, AuditAction::BanUser);
+
+        let mut recent = AuditLogFilter::new(10);
+        recent.from = Some(Utc::now() - Duration::minutes(6));
+        assert_eq!(repo.list(&recent).await.unwrap().entries.len(), 2);
+    }
+}
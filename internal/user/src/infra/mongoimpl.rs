@@ -1,3 +1,7 @@
+pub mod api_token_repository;
+pub mod audit_log_repository;
+pub mod oidc_auth_request_repository;
+pub mod otp_issuance_repository;
 pub mod otp_respository;
 pub mod user_document;
 pub mod user_read_model_repository;
@@ -0,0 +1,190 @@
+use chrono::{DateTime, Utc};
+use mongodb::{Collection, Database, bson::doc};
+use serde::{Deserialize, Serialize};
+use shared::guards::permissions::Permission;
+
+use crate::domain::user_auth::{api_token::ApiToken, otp_hasher::OtpHash, result::UserAuthResult};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiTokenDocument {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub key_id: String,
+    pub token_hash: String,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub expires_at: DateTime<Utc>,
+    #[serde(
+        default,
+        with = "bson::serde_helpers::chrono_datetime_as_bson_datetime_optional"
+    )]
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiTokenDocument> for ApiToken {
+    fn from(value: ApiTokenDocument) -> Self {
+        ApiToken::new_with_all_fields(
+            value.id,
+            value.user_id,
+            value.name,
+            // Scopes that no longer exist grant nothing.
+            value
+                .scopes
+                .iter()
+                .filter_map(|scope| Permission::from_scope(scope))
+                .collect(),
+            OtpHash {
+                key_id: value.key_id,
+                hash: value.token_hash,
+            },
+            value.created_at,
+            value.expires_at,
+            value.last_used_at,
+        )
+    }
+}
+
+impl From<ApiToken> for ApiTokenDocument {
+    fn from(value: ApiToken) -> Self {
+        ApiTokenDocument {
+            scopes: value.scope_names(),
+            id: value.id().clone(),
+            user_id: value.user_id().clone(),
+            name: value.name().clone(),
+            key_id: value.token_hash().key_id.clone(),
+            token_hash: value.token_hash().hash.clone(),
+            created_at: *value.created_at(),
+            expires_at: *value.expires_at(),
+            last_used_at: *value.last_used_at(),
+        }
+    }
+}
+
+pub struct MongoApiTokenRepository {
+    collection: Collection<ApiTokenDocument>,
+}
+
+impl MongoApiTokenRepository {
+    pub fn new(db: Database) -> Self {
+        let collection = db.collection::<ApiTokenDocument>("api_tokens");
+        Self { collection }
+    }
+
+    pub async fn create_api_token(&self, token: ApiToken) -> UserAuthResult<()> {
+        self.collection
+            .insert_one(ApiTokenDocument::from(token))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_api_token(&self, id: &str) -> UserAuthResult<Option<ApiToken>> {
+        let token = self
+            .collection
+            .find_one(doc! {"_id": id})
+            .await?
+            .map(Into::into);
+        Ok(token)
+    }
+
+    pub async fn list_api_tokens(&self, user_id: &str) -> UserAuthResult<Vec<ApiToken>> {
+        let mut cursor = self
+            .collection
+            .find(doc! {"user_id": user_id})
+            .sort(doc! {"created_at": -1})
+            .await?;
+        let mut tokens = vec![];
+        while cursor.advance().await? {
+            tokens.push(cursor.deserialize_current()?.into());
+        }
+        Ok(tokens)
+    }
+
+    pub async fn update_api_token(&self, token: ApiToken) -> UserAuthResult<()> {
+        self.collection
+            .update_one(
+                doc! {"_id": token.id(), "user_id": token.user_id()},
+                doc! {"$set": {"name": token.name(), "scopes": token.scope_names()}},
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_api_token(&self, user_id: &str, id: &str) -> UserAuthResult<bool> {
+        let result = self
+            .collection
+            .delete_one(doc! {"_id": id, "user_id": user_id})
+            .await?;
+        Ok(result.deleted_count == 1)
+    }
+
+    pub async fn touch_api_token(&self, id: &str, used_at: DateTime<Utc>) -> UserAuthResult<()> {
+        self.collection
+            .update_one(
+                doc! {"_id": id},
+                doc! {"$set": {"last_used_at": bson::DateTime::from_chrono(used_at)}},
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user_auth::otp_hasher::OtpHasher;
+    use chrono::Duration;
+    use shared::test_utils;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_api_token_crud() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let repo = MongoApiTokenRepository::new(db);
+
+        let hasher = OtpHasher::new_test_hasher();
+        let (mut token, _) = ApiToken::issue(
+            "user-1".into(),
+            "ci bot".into(),
+            vec![Permission::ViewUser],
+            Duration::days(30),
+            &hasher,
+            Utc::now(),
+        )
+        .unwrap();
+        let (other_token, _) = ApiToken::issue(
+            "user-2".into(),
+            "ci bot".into(),
+            vec![Permission::ViewUser],
+            Duration::days(30),
+            &hasher,
+            Utc::now(),
+        )
+        .unwrap();
+        repo.create_api_token(token.clone()).await.unwrap();
+        repo.create_api_token(other_token).await.unwrap();
+
+        let listed = repo.list_api_tokens("user-1").await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].token_hash(), token.token_hash());
+
+        token.rename("deploy bot".into());
+        repo.update_api_token(token.clone()).await.unwrap();
+        let used_at = Utc::now();
+        repo.touch_api_token(token.id(), used_at).await.unwrap();
+        let found = repo.get_api_token(token.id()).await.unwrap().unwrap();
+        assert_eq!(found.name(), "deploy bot");
+        assert_eq!(
+            found.last_used_at().map(|at| at.timestamp_millis()),
+            Some(used_at.timestamp_millis())
+        );
+
+        assert!(!repo.delete_api_token("user-2", token.id()).await.unwrap());
+        assert!(repo.delete_api_token("user-1", token.id()).await.unwrap());
+        assert!(repo.get_api_token(token.id()).await.unwrap().is_none());
+    }
+}
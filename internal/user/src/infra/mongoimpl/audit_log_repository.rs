@@ -0,0 +1,193 @@
+use bson::{Document, doc};
+use chrono::{DateTime, Utc};
+use mongodb::{Collection, Database};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{
+    audit_log::{
+        AuditAction, AuditChange, AuditEntry, AuditLogFilter, AuditLogPage, AuditMetadata,
+    },
+    errors::UserDomainError,
+    result::UserDomainResult,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntryDocument {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub actor_id: String,
+    pub action: AuditAction,
+    pub target_id: String,
+    pub changes: Vec<AuditChange>,
+    pub metadata: AuditMetadata,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AuditEntryDocument> for AuditEntry {
+    fn from(value: AuditEntryDocument) -> Self {
+        AuditEntry {
+            id: value.id,
+            actor_id: value.actor_id,
+            action: value.action,
+            target_id: value.target_id,
+            changes: value.changes,
+            metadata: value.metadata,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<AuditEntry> for AuditEntryDocument {
+    fn from(value: AuditEntry) -> Self {
+        AuditEntryDocument {
+            id: value.id,
+            actor_id: value.actor_id,
+            action: value.action,
+            target_id: value.target_id,
+            changes: value.changes,
+            metadata: value.metadata,
+            created_at: value.created_at,
+        }
+    }
+}
+
+pub struct MongoAuditLogRepository {
+    collection: Collection<AuditEntryDocument>,
+}
+
+impl MongoAuditLogRepository {
+    pub fn new(db: Database) -> Self {
+        let collection = db.collection::<AuditEntryDocument>("audit_log");
+        Self { collection }
+    }
+
+    pub async fn append(&self, entry: AuditEntry) -> UserDomainResult<()> {
+        self.collection
+            .insert_one(AuditEntryDocument::from(entry))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list(&self, filter: &AuditLogFilter) -> UserDomainResult<AuditLogPage> {
+        let mut query = Document::new();
+        if let Some(actor_id) = &filter.actor_id {
+            query.insert("actor_id", actor_id);
+        }
+        if let Some(target_id) = &filter.target_id {
+            query.insert("target_id", target_id);
+        }
+        if let Some(action) = &filter.action {
+            let action =
+                bson::to_bson(action).map_err(|e| UserDomainError::Internal(e.to_string()))?;
+            query.insert("action", action);
+        }
+        let mut created_at = Document::new();
+        if let Some(from) = filter.from {
+            created_at.insert("$gte", bson::DateTime::from_chrono(from));
+        }
+        if let Some(to) = filter.to {
+            created_at.insert("$lt", bson::DateTime::from_chrono(to));
+        }
+        if !created_at.is_empty() {
+            query.insert("created_at", created_at);
+        }
+        if let Some(after) = &filter.after {
+            let after_at = bson::DateTime::from_chrono(after.created_at);
+            query.insert(
+                "$or",
+                vec![
+                    doc! {"created_at": {"$lt": after_at}},
+                    doc! {"created_at": after_at, "_id": {"$lt": &after.id}},
+                ],
+            );
+        }
+
+        let mut cursor = self
+            .collection
+            .find(query)
+            .sort(doc! {"created_at": -1, "_id": -1})
+            .limit(filter.first as i64 + 1)
+            .await?;
+        let mut entries: Vec<AuditEntry> = vec![];
+        while cursor.advance().await? {
+            entries.push(cursor.deserialize_current()?.into());
+        }
+        let has_next = entries.len() > filter.first as usize;
+        entries.truncate(filter.first as usize);
+        Ok(AuditLogPage { entries, has_next })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::audit_log::AuditLogPosition;
+    use chrono::Duration;
+    use shared::test_utils;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_audit_log_append_and_list() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let repo = MongoAuditLogRepository::new(db);
+
+        let entries = [
+            ("user-1", AuditAction::MakeModerator, 3),
+            ("user-1", AuditAction::BanUser, 2),
+            ("user-2", AuditAction::BanUser, 1),
+        ]
+        .map(|(target_id, action, minutes_ago)| {
+            let mut entry = AuditEntry::new(
+                "admin-id".into(),
+                action,
+                target_id.into(),
+                vec![AuditChange {
+                    field: "role".into(),
+                    before: Some("Regular".into()),
+                    after: Some("Moderator".into()),
+                }],
+                AuditMetadata {
+                    client_ip: Some("10.0.0.1".into()),
+                    ..Default::default()
+                },
+            );
+            // Stored with millisecond precision.
+            let created_at = Utc::now() - Duration::minutes(minutes_ago);
+            entry.created_at =
+                DateTime::from_timestamp_millis(created_at.timestamp_millis()).unwrap();
+            entry
+        });
+        for entry in &entries {
+            repo.append(entry.clone()).await.unwrap();
+        }
+        let oldest = entries[0].clone();
+
+        let mut filter = AuditLogFilter {
+            actor_id: Some("admin-id".into()),
+            target_id: Some("user-1".into()),
+            action: None,
+            from: None,
+            to: None,
+            first: 1,
+            after: None,
+        };
+        let page = repo.list(&filter).await.unwrap();
+        assert!(page.has_next);
+        assert_eq!(page.entries[0].action, AuditAction::BanUser);
+
+        filter.after = Some(AuditLogPosition::from_cursor(&page.entries[0].cursor()).unwrap());
+        let page = repo.list(&filter).await.unwrap();
+        assert!(!page.has_next);
+        assert_eq!(page.entries, vec![oldest]);
+
+        filter.after = None;
+        filter.target_id = None;
+        filter.action = Some(AuditAction::BanUser);
+        assert_eq!(
+            repo.list(&filter).await.unwrap().entries[0].target_id,
+            "user-2"
+        );
+    }
+}
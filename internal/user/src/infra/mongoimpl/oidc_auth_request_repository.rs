@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use mongodb::{Collection, Database, bson::doc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::user_auth::{oidc::OidcAuthRequest, result::UserAuthResult};
+
+/// A pending OIDC sign-in keyed by its `state`. Abandoned requests are removed
+/// by a TTL index on `expire_at`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OidcAuthRequestDocument {
+    #[serde(rename = "_id")]
+    pub state: String,
+    pub provider: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    pub link_user_id: Option<String>,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub expire_at: DateTime<Utc>,
+}
+
+impl From<OidcAuthRequestDocument> for OidcAuthRequest {
+    fn from(value: OidcAuthRequestDocument) -> Self {
+        OidcAuthRequest::new_with_all_fields(
+            value.state,
+            value.provider,
+            value.nonce,
+            value.code_verifier,
+            value.link_user_id,
+            value.expire_at,
+        )
+    }
+}
+
+impl From<OidcAuthRequest> for OidcAuthRequestDocument {
+    fn from(value: OidcAuthRequest) -> Self {
+        OidcAuthRequestDocument {
+            state: value.state().clone(),
+            provider: value.provider().clone(),
+            nonce: value.nonce().clone(),
+            code_verifier: value.code_verifier().clone(),
+            link_user_id: value.link_user_id().clone(),
+            expire_at: *value.expires_at(),
+        }
+    }
+}
+
+pub struct MongoOidcAuthRequestRepository {
+    collection: Collection<OidcAuthRequestDocument>,
+}
+
+impl MongoOidcAuthRequestRepository {
+    pub fn new(db: Database) -> Self {
+        let collection = db.collection::<OidcAuthRequestDocument>("oidc_auth_requests");
+        Self { collection }
+    }
+
+    pub async fn save_auth_request(&self, request: OidcAuthRequest) -> UserAuthResult<()> {
+        self.collection
+            .insert_one(OidcAuthRequestDocument::from(request))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn take_auth_request(&self, state: &str) -> UserAuthResult<Option<OidcAuthRequest>> {
+        let request = self
+            .collection
+            .find_one_and_delete(doc! {"_id": state})
+            .await?
+            .map(Into::into);
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::test_utils;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_take_auth_request_once() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let repo = MongoOidcAuthRequestRepository::new(db);
+
+        let request = OidcAuthRequest::new("mock".into(), None, Utc::now());
+        repo.save_auth_request(request.clone()).await.unwrap();
+
+        let taken = repo.take_auth_request(request.state()).await.unwrap();
+        assert_eq!(
+            taken.as_ref().map(|r| r.code_verifier()),
+            Some(request.code_verifier())
+        );
+        assert!(
+            repo.take_auth_request(request.state())
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
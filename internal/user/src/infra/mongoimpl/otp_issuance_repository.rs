@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use mongodb::{Collection, Database, bson::doc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::user_auth::result::UserAuthResult;
+
+/// One issued code, keyed by `email:<canonical email>` or `ip:<address>`.
+/// Documents are removed by a TTL index on `expire_at` once they leave the window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OtpIssuanceDocument {
+    pub key: String,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub issued_at: DateTime<Utc>,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub expire_at: DateTime<Utc>,
+}
+
+pub struct MongoOtpIssuanceRepository {
+    collection: Collection<OtpIssuanceDocument>,
+}
+
+impl MongoOtpIssuanceRepository {
+    pub fn new(db: Database) -> Self {
+        let collection = db.collection::<OtpIssuanceDocument>("otp_issuances");
+        Self { collection }
+    }
+
+    pub async fn record_issuance(
+        &self,
+        key: &str,
+        issued_at: DateTime<Utc>,
+        expire_at: DateTime<Utc>,
+    ) -> UserAuthResult<()> {
+        self.collection
+            .insert_one(OtpIssuanceDocument {
+                key: key.to_string(),
+                issued_at,
+                expire_at,
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_issuances_since(
+        &self,
+        key: &str,
+        since: DateTime<Utc>,
+    ) -> UserAuthResult<Vec<DateTime<Utc>>> {
+        let mut cursor = self
+            .collection
+            .find(doc! {"key": key, "issued_at": {"$gt": bson::DateTime::from_chrono(since)}})
+            .sort(doc! {"issued_at": 1})
+            .await?;
+        let mut issued = vec![];
+        while cursor.advance().await? {
+            issued.push(cursor.deserialize_current()?.issued_at);
+        }
+        Ok(issued)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use shared::test_utils;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_get_issuances_since() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let repo = MongoOtpIssuanceRepository::new(db);
+
+        let now = Utc::now();
+        let old = now - Duration::hours(2);
+        let recent = now - Duration::minutes(5);
+        for at in [recent, old] {
+            repo.record_issuance("email:test@example.com", at, at + Duration::hours(1))
+                .await
+                .unwrap();
+        }
+        repo.record_issuance("ip:127.0.0.1", now, now + Duration::hours(1))
+            .await
+            .unwrap();
+
+        let issued = repo
+            .get_issuances_since("email:test@example.com", now - Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(issued.len(), 1);
+        assert_eq!(issued[0].timestamp_millis(), recent.timestamp_millis());
+    }
+}
@@ -1,5 +1,11 @@
-use crate::domain::user_auth::{errors::UserAuthError, otp::OtpEntry, result::UserAuthResult};
-use mongodb::{Collection, Database, bson::doc};
+use crate::domain::{
+    canonical::canonical_email,
+    user_auth::{errors::UserAuthError, otp::OtpEntry, result::UserAuthResult},
+};
+use mongodb::{
+    Collection, Database,
+    bson::{DateTime as BsonDateTime, doc},
+};
 use serde::{Deserialize, Serialize};
 use shared::db_transactions::DBTransaction;
 
@@ -9,7 +15,14 @@ pub struct OtpDocument {
     pub expires_at: i64,
     pub used: bool,
     pub attempts: u32,
+    /// Stored in canonical form, see [`canonical_email`].
     pub email: String,
+    /// BSON date mirror of `expires_at`, read by the TTL index on `otps`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expire_at: Option<BsonDateTime>,
+    /// Pepper used for `otp_hash`, absent on legacy unkeyed hashes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
 }
 
 impl From<OtpDocument> for OtpEntry {
@@ -21,6 +34,7 @@ impl From<OtpDocument> for OtpEntry {
             doc.otp_hash,
             doc.expires_at,
         )
+        .with_key_id(doc.key_id)
     }
 }
 impl Into<OtpDocument> for OtpEntry {
@@ -30,7 +44,9 @@ impl Into<OtpDocument> for OtpEntry {
             expires_at: self.expires_at().to_owned(),
             used: self.used().to_owned(),
             attempts: self.attempts().to_owned(),
-            email: self.email().to_string(),
+            email: canonical_email(self.email()),
+            expire_at: Some(BsonDateTime::from_millis(*self.expires_at())),
+            key_id: self.key_id().clone(),
         }
     }
 }
@@ -82,14 +98,14 @@ impl MongoOtpRepository {
     pub async fn get_otp_by_user_email(&self, email: &str) -> UserAuthResult<Option<OtpEntry>> {
         let otp = self
             .collection
-            .find_one(doc! {"email": email})
+            .find_one(doc! {"email": canonical_email(email)})
             .await?
             .map(|doc| doc.into());
         Ok(otp)
     }
 
     pub async fn delete_otp(&self, email: &str) -> UserAuthResult<()> {
-        let filter = doc! { "email": email };
+        let filter = doc! { "email": canonical_email(email) };
         self.collection.delete_one(filter).await?;
         Ok(())
     }
@@ -1,10 +1,15 @@
 use bson::DateTime as BsonDateTime;
 use serde::{Deserialize, Serialize};
 
+use crate::domain::canonical::canonical_username;
 use crate::domain::user::Ban as BanDomain;
 use crate::domain::user::BanType as BanTypeDomain;
 use crate::domain::user::EmailStatus;
+use crate::domain::user::LinkedIdentity;
 use crate::domain::user::User;
+use crate::domain::user::UsernameChange;
+use crate::domain::user_auth::otp_hasher::OtpHash;
+use crate::domain::user_auth::two_factor::{TwoFactor, TwoFactorChallenge};
 use crate::domain::user_read_model::Ban as BanReadModel;
 use crate::domain::user_read_model::BanType as BanTypeReadModel;
 use crate::domain::user_read_model::UserReadModel;
@@ -32,12 +37,70 @@ pub struct Ban {
     pub ban_type: BanType,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UsernameChangeDocument {
+    pub username: String,
+    #[serde(default)]
+    pub username_canonical: String,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LinkedIdentityDocument {
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub linked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RecoveryCodeDocument {
+    pub key_id: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TwoFactorChallengeDocument {
+    pub token_hash: String,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub expires_at: DateTime<Utc>,
+    pub attempts: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TwoFactorDocument {
+    pub secret: String,
+    #[serde(
+        default,
+        with = "bson::serde_helpers::chrono_datetime_as_bson_datetime_optional"
+    )]
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub recovery_codes: Vec<RecoveryCodeDocument>,
+    pub last_used_step: Option<i64>,
+    pub challenge: Option<TwoFactorChallengeDocument>,
+    #[serde(default)]
+    pub failed_attempts: u32,
+    #[serde(
+        default,
+        with = "bson::serde_helpers::chrono_datetime_as_bson_datetime_optional"
+    )]
+    pub last_failed_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct UserDocument {
     #[serde(rename = "_id")]
     pub id: String,
     pub username: String,
     pub email: String,
+    /// Lowercased, NFKC-normalized and trimmed username, backed by a unique index.
+    #[serde(default)]
+    pub username_canonical: String,
+    /// Lowercased, NFKC-normalized and trimmed email, backed by a unique index.
+    #[serde(default)]
+    pub email_canonical: String,
     pub role: UserRole,
     pub badges: Vec<String>,
     #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
@@ -46,6 +109,13 @@ pub struct UserDocument {
     pub updated_at: DateTime<Utc>,
     pub ban_status: Option<Ban>,
     pub email_status: EmailStatus,
+    #[serde(default)]
+    pub username_history: Vec<UsernameChangeDocument>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub two_factor: Option<TwoFactorDocument>,
+    /// Linked OIDC identities, unique per `(provider, subject)` across users.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub identities: Vec<LinkedIdentityDocument>,
 }
 
 impl From<UserDocument> for UserReadModel {
@@ -80,6 +150,11 @@ impl From<UserDocument> for User {
             };
             BanDomain::new(b.reason.clone(), b.is_banned, b.banned_at, ban_type)
         });
+        let username_history = value
+            .username_history
+            .into_iter()
+            .map(|change| UsernameChange::new(change.username, change.changed_at))
+            .collect();
 
         User::new_with_all_fields(
             value.id,
@@ -92,6 +167,22 @@ impl From<UserDocument> for User {
             value.badges,
             value.email_status,
         )
+        .with_username_history(username_history)
+        .with_two_factor(value.two_factor.map(Into::into))
+        .with_identities(
+            value
+                .identities
+                .into_iter()
+                .map(|identity| {
+                    LinkedIdentity::new(
+                        identity.provider,
+                        identity.subject,
+                        identity.email,
+                        identity.linked_at,
+                    )
+                })
+                .collect(),
+        )
     }
 }
 impl Into<UserDocument> for User {
@@ -115,12 +206,92 @@ impl Into<UserDocument> for User {
             id: self.id().to_string(),
             username: self.username().to_string(),
             email: self.email().to_string(),
+            username_canonical: self.canonical_username(),
+            email_canonical: self.canonical_email(),
             role: self.role().to_owned(),
             badges: self.badges().to_owned(),
             created_at: truncate_chrono(self.joined_at()),
             updated_at: truncate_chrono(self.updated_at()),
             ban_status,
             email_status: self.email_status().to_owned(),
+            username_history: self
+                .username_history()
+                .iter()
+                .map(|change| UsernameChangeDocument {
+                    username: change.username().to_string(),
+                    username_canonical: canonical_username(change.username()),
+                    changed_at: truncate_chrono(change.changed_at()),
+                })
+                .collect(),
+            two_factor: self.two_factor().map(Into::into),
+            identities: self
+                .identities()
+                .iter()
+                .map(|identity| LinkedIdentityDocument {
+                    provider: identity.provider().to_string(),
+                    subject: identity.subject().to_string(),
+                    email: identity.email().map(str::to_string),
+                    linked_at: truncate_chrono(identity.linked_at()),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<TwoFactorDocument> for TwoFactor {
+    fn from(value: TwoFactorDocument) -> Self {
+        TwoFactor::new_with_all_fields(
+            value.secret,
+            value.enabled_at,
+            value
+                .recovery_codes
+                .into_iter()
+                .map(|code| OtpHash {
+                    key_id: code.key_id,
+                    hash: code.hash,
+                })
+                .collect(),
+            value.last_used_step.map(|step| step as u64),
+            value.challenge.map(|challenge| {
+                TwoFactorChallenge::new(
+                    challenge.token_hash,
+                    challenge.expires_at,
+                    challenge.attempts,
+                )
+            }),
+            value.failed_attempts,
+            value.last_failed_at,
+        )
+    }
+}
+
+impl From<&TwoFactorChallenge> for TwoFactorChallengeDocument {
+    fn from(value: &TwoFactorChallenge) -> Self {
+        TwoFactorChallengeDocument {
+            token_hash: value.token_hash().clone(),
+            expires_at: truncate_chrono(value.expires_at()),
+            attempts: *value.attempts(),
+        }
+    }
+}
+
+impl From<&TwoFactor> for TwoFactorDocument {
+    fn from(value: &TwoFactor) -> Self {
+        TwoFactorDocument {
+            secret: value.secret().clone(),
+            enabled_at: value.enabled_at().as_ref().map(truncate_chrono),
+            recovery_codes: value
+                .recovery_codes()
+                .iter()
+                .map(|code| RecoveryCodeDocument {
+                    key_id: code.key_id.clone(),
+                    hash: code.hash.clone(),
+                })
+                .collect(),
+            last_used_step: value.last_used_step().map(|step| step as i64),
+            challenge: value.challenge().as_ref().map(Into::into),
+            failed_attempts: *value.failed_attempts(),
+            last_failed_at: value.last_failed_at().as_ref().map(truncate_chrono),
         }
     }
 }
@@ -4,6 +4,7 @@ use futures::stream::StreamExt;
 use mongodb::{Collection, Database, options::FindOptions};
 
 use crate::domain::{
+    canonical::{canonical_email, canonical_username},
     errors::UserDomainError,
     result::UserDomainResult,
     user_read_model::{GetUsersOptions, GetUsersResult, SortDirection, UserReadModel},
@@ -69,14 +70,64 @@ impl MongoUserReadModelRepository {
             .map(|doc| doc.into());
         Ok(doc)
     }
+    /// Loads all of `ids` with a single `$in` query.
+    pub async fn get_users_by_ids(&self, ids: &[String]) -> UserDomainResult<Vec<UserReadModel>> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut cursor = self.collection.find(doc! {"_id": {"$in": ids}}).await?;
+        let mut users = Vec::with_capacity(ids.len());
+        while let Some(doc) = cursor.next().await {
+            users.push(doc?.into());
+        }
+        Ok(users)
+    }
     pub async fn get_user_by_email(&self, email: &str) -> UserDomainResult<Option<UserReadModel>> {
         let doc = self
             .collection
-            .find_one(doc! {"email": email})
+            .find_one(doc! {"email_canonical": canonical_email(email)})
             .await?
             .map(|doc| doc.into());
         Ok(doc)
     }
+    /// Resolves a username to its current owner. Falls back to the username
+    /// history so that links using a previous username keep working; when
+    /// several users held the name, the one who released it last wins.
+    pub async fn get_user_by_username(
+        &self,
+        username: &str,
+    ) -> UserDomainResult<Option<UserReadModel>> {
+        let username = canonical_username(username);
+        if let Some(doc) = self
+            .collection
+            .find_one(doc! {"username_canonical": &username})
+            .await?
+        {
+            return Ok(Some(doc.into()));
+        }
+
+        let mut cursor = self
+            .collection
+            .find(doc! {"username_history.username_canonical": &username})
+            .await?;
+
+        let mut latest: Option<(DateTime<Utc>, UserDocument)> = None;
+        while let Some(doc) = cursor.next().await {
+            let doc = doc?;
+            let released_at = doc
+                .username_history
+                .iter()
+                .filter(|change| change.username_canonical == username)
+                .map(|change| change.changed_at)
+                .max();
+            if let Some(released_at) = released_at
+                && latest.as_ref().is_none_or(|(at, _)| released_at > *at)
+            {
+                latest = Some((released_at, doc));
+            }
+        }
+        Ok(latest.map(|(_, doc)| doc.into()))
+    }
 }
 
 #[cfg(test)]
@@ -100,6 +151,37 @@ mod tests {
         assert_eq!(user_from_db.id, user.id());
     }
 
+    #[tokio::test]
+    async fn get_users_by_ids() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        insert_many_users(db.clone(), 5).await;
+        let user_repo = MongoUserReadModelRepository::new(db.clone());
+        let all = user_repo
+            .get_users(&GetUsersOptions {
+                first: 5,
+                after: None,
+                sort_direction: SortDirection::ASC,
+            })
+            .await
+            .unwrap()
+            .users;
+
+        let ids = vec![all[0].id.clone(), all[3].id.clone(), "missing".to_string()];
+        let mut found: Vec<String> = user_repo
+            .get_users_by_ids(&ids)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|user| user.id)
+            .collect();
+        found.sort();
+        let mut expected = vec![all[0].id.clone(), all[3].id.clone()];
+        expected.sort();
+        assert_eq!(found, expected);
+        assert!(user_repo.get_users_by_ids(&[]).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn get_user_by_email() {
         let client = test_utils::setup_test_mongo().await;
@@ -115,6 +197,39 @@ mod tests {
         assert_eq!(user_from_db.email, user.email());
     }
 
+    #[tokio::test]
+    async fn get_user_by_username() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let user = User::new_test_user(None);
+        insert_user(db.clone(), user.clone()).await;
+        let user_repo = MongoUserReadModelRepository::new(db.clone());
+        let user_from_db = user_repo
+            .get_user_by_username(user.username())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user_from_db.id, user.id());
+    }
+
+    #[tokio::test]
+    async fn get_user_by_previous_username() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let mut user = User::new_test_user(None);
+        let old_username = user.username().to_string();
+        user.change_username("johndoe-renamed".into());
+        insert_user(db.clone(), user.clone()).await;
+        let user_repo = MongoUserReadModelRepository::new(db.clone());
+        let user_from_db = user_repo
+            .get_user_by_username(&old_username.to_uppercase())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user_from_db.id, user.id());
+        assert_eq!(user_from_db.username, "johndoe-renamed");
+    }
+
     #[tokio::test]
     async fn get_users() {
         let client = test_utils::setup_test_mongo().await;
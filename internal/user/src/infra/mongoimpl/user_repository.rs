@@ -1,13 +1,20 @@
-use mongodb::{Collection, Database, bson::doc};
+use chrono::{DateTime, Utc};
+use mongodb::{
+    Collection, Database,
+    bson::doc,
+    error::{Error as MongoError, ErrorKind, WriteFailure},
+};
 
 use crate::domain::{
+    canonical::{canonical_email, canonical_username},
     errors::UserDomainError,
     result::UserDomainResult,
     user::{EmailStatus, User},
+    user_auth::{errors::UserAuthError, two_factor::TwoFactorChallenge},
 };
 use shared::db_transactions::{DBTransaction, RepoDB};
 
-use super::user_document::UserDocument;
+use super::user_document::{TwoFactorChallengeDocument, UserDocument};
 
 pub struct MongoUserRepository {
     collection: Collection<UserDocument>,
@@ -43,7 +50,10 @@ impl MongoUserRepository {
     }
     pub async fn create_account(&self, user: User) -> UserDomainResult<()> {
         let user: UserDocument = user.into();
-        self.collection.insert_one(user).await?;
+        self.collection
+            .insert_one(user)
+            .await
+            .map_err(map_duplicate_key_error)?;
         Ok(())
     }
 
@@ -95,6 +105,36 @@ impl MongoUserRepository {
         self.find_and_update_user(user_id, update_fn).await
     }
 
+    pub async fn update_two_factor<F: FnOnce(&mut User) + Send>(
+        &self,
+        user_id: &str,
+        update_fn: F,
+    ) -> UserDomainResult<()> {
+        self.find_and_update_user(user_id, update_fn).await
+    }
+
+    /// Sets only the sign-in challenge, so recovery codes used or 2FA
+    /// disabled since the user was read are left as they are.
+    pub async fn set_two_factor_challenge(
+        &self,
+        user_id: &str,
+        challenge: TwoFactorChallenge,
+    ) -> UserDomainResult<()> {
+        let challenge = bson::to_bson(&TwoFactorChallengeDocument::from(&challenge))
+            .map_err(|e| UserDomainError::Internal(e.to_string()))?;
+        let result = self
+            .collection
+            .update_one(
+                doc! {"_id": user_id, "two_factor.enabled_at": {"$ne": null}},
+                doc! {"$set": {"two_factor.challenge": challenge}},
+            )
+            .await?;
+        if result.matched_count == 0 {
+            return Err(UserAuthError::TwoFactorNotEnabled.into());
+        }
+        Ok(())
+    }
+
     pub async fn get_user_by_id(&self, user_id: &str) -> UserDomainResult<Option<User>> {
         let user = self
             .collection
@@ -104,14 +144,45 @@ impl MongoUserRepository {
 
         Ok(user)
     }
+
+    pub async fn link_identity<F: FnOnce(&mut User) + Send>(
+        &self,
+        user_id: &str,
+        update_fn: F,
+    ) -> UserDomainResult<()> {
+        self.find_and_update_user(user_id, update_fn).await
+    }
+
+    pub async fn get_user_by_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> UserDomainResult<Option<User>> {
+        let filter = doc! {
+            "identities": {"$elemMatch": {"provider": provider, "subject": subject}}
+        };
+        let user = self
+            .collection
+            .find_one(filter)
+            .await?
+            .map(|doc| doc.into());
+        Ok(user)
+    }
+
     pub async fn get_user_by_username_or_email(
         &self,
         username: &str,
         email: &str,
     ) -> UserDomainResult<Option<User>> {
+        let filter = doc! {
+            "$or": [
+                {"username_canonical": canonical_username(username)},
+                {"email_canonical": canonical_email(email)}
+            ]
+        };
         let user = self
             .collection
-            .find_one(doc! {"$or": [{"username": username}, {"email": email}]})
+            .find_one(filter)
             .await?
             .map(|doc| doc.into());
         Ok(user)
@@ -125,8 +196,8 @@ impl MongoUserRepository {
     ) -> UserDomainResult<bool> {
         let mut filter = doc! {
             "$or": [
-                {"email": email},
-                {"username": username}
+                {"email_canonical": canonical_email(email)},
+                {"username_canonical": canonical_username(username)}
             ]
         };
         if let Some(status) = email_status {
@@ -136,6 +207,24 @@ impl MongoUserRepository {
         let user = self.collection.find_one(filter).await?;
         Ok(user.is_some())
     }
+    pub async fn username_reserved(
+        &self,
+        username: &str,
+        released_after: DateTime<Utc>,
+        user_id: &str,
+    ) -> UserDomainResult<bool> {
+        let filter = doc! {
+            "_id": {"$ne": user_id},
+            "username_history": {
+                "$elemMatch": {
+                    "username_canonical": canonical_username(username),
+                    "changed_at": {"$gt": released_after}
+                }
+            }
+        };
+        let user = self.collection.find_one(filter).await?;
+        Ok(user.is_some())
+    }
     pub async fn upsert_user<'a>(
         &self,
         user: User,
@@ -143,8 +232,8 @@ impl MongoUserRepository {
     ) -> UserDomainResult<()> {
         let filter = doc! {
             "$or": [
-                {"email": user.email()},
-                {"username": user.username()}
+                {"email_canonical": user.canonical_email()},
+                {"username_canonical": user.canonical_username()}
             ]
         };
         let user: UserDocument = user.into();
@@ -155,24 +244,40 @@ impl MongoUserRepository {
         if let Some(tx) = tx {
             match tx {
                 DBTransaction::MongoDb(session) => {
-                    fr.session(session).await?;
+                    fr.session(session).await.map_err(map_duplicate_key_error)?;
                 }
                 _ => {
                     return Err(UserDomainError::InvalidTransaction);
                 }
             }
         } else {
-            fr.await?;
+            fr.await.map_err(map_duplicate_key_error)?;
         }
         Ok(())
     }
 }
 
+const DUPLICATE_KEY_ERROR_CODE: i32 = 11000;
+
+/// Maps violations of the unique canonical username/email indexes to a domain error.
+fn map_duplicate_key_error(err: MongoError) -> UserDomainError {
+    let is_duplicate_key = match err.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(e)) => e.code == DUPLICATE_KEY_ERROR_CODE,
+        ErrorKind::Command(e) => e.code == DUPLICATE_KEY_ERROR_CODE,
+        _ => false,
+    };
+    if is_duplicate_key {
+        return UserDomainError::UsernameOrEmailTaken;
+    }
+    err.into()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::domain::user::BanType;
 
     use super::*;
+    use mongodb::{IndexModel, options::IndexOptions};
     use shared::test_utils;
     use utils::{assert_user_in_db_equals, get_user_from_db, insert_user};
     use uuid::Uuid;
@@ -203,6 +308,55 @@ mod tests {
         assert_eq!(true, res);
     }
 
+    #[tokio::test]
+    async fn test_user_exists_ignores_case() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+
+        let user = User::new_test_user(None);
+        insert_user(db.clone(), user.clone()).await;
+
+        let user_repo = MongoUserRepository::new(db.clone());
+        let res = user_repo
+            .user_exists(
+                &user.username().to_uppercase(),
+                &format!(" {} ", user.email().to_uppercase()),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(res);
+    }
+
+    #[tokio::test]
+    async fn test_unique_canonical_username_and_email() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+
+        let user_repo = MongoUserRepository::new(db.clone());
+        // Mirrors the index created by the infra migrations.
+        db.collection::<UserDocument>("users")
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"email_canonical": 1})
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await
+            .unwrap();
+
+        let user = User::new_test_user(None);
+        user_repo.create_account(user.clone()).await.unwrap();
+
+        let duplicate = User::new(
+            user.email().to_uppercase(),
+            "another_username".into(),
+            user.role().to_owned(),
+        );
+        let result = user_repo.create_account(duplicate).await;
+        assert!(matches!(result, Err(UserDomainError::UsernameOrEmailTaken)));
+    }
+
     #[tokio::test]
     async fn test_get_user_by_id() {
         let client = test_utils::setup_test_mongo().await;
@@ -339,6 +493,177 @@ mod tests {
         assert_eq!(expected_username.to_string(), user_from_db.username);
     }
 
+    #[tokio::test]
+    async fn test_update_two_factor() {
+        use crate::domain::user_auth::{otp_hasher::OtpHasher, two_factor::TwoFactor};
+
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4().to_string()));
+        let user = User::new_test_user(None);
+        insert_user(db.clone(), user.clone()).await;
+
+        let mut two_factor = TwoFactor::enroll();
+        two_factor.enable(
+            vec![OtpHasher::new_test_hasher().hash("recovery")],
+            chrono::Utc::now(),
+        );
+        two_factor.start_challenge(chrono::Utc::now());
+        let expected = two_factor.clone();
+
+        let user_repo = MongoUserRepository::new(db.clone());
+        user_repo
+            .update_two_factor(user.id(), |u| u.set_two_factor(Some(two_factor)))
+            .await
+            .unwrap();
+        let user_from_db: User = get_user_from_db(db.clone(), user.id()).await.into();
+        let stored = user_from_db.two_factor().unwrap();
+        assert!(stored.is_enabled());
+        assert_eq!(expected.secret(), stored.secret());
+        assert_eq!(expected.recovery_codes(), stored.recovery_codes());
+        assert_eq!(
+            expected.challenge().as_ref().map(|c| c.token_hash()),
+            stored.challenge().as_ref().map(|c| c.token_hash())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_two_factor_challenge_keeps_recovery_codes_used_meanwhile() {
+        use crate::domain::user_auth::{
+            otp_hasher::OtpHasher,
+            two_factor::{TwoFactor, TwoFactorChallenge},
+        };
+
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let hasher = OtpHasher::new_test_hasher();
+        let mut two_factor = TwoFactor::enroll();
+        two_factor.enable(
+            vec![hasher.hash("first"), hasher.hash("second")],
+            chrono::Utc::now(),
+        );
+        let mut user = User::new_test_user(None);
+        user.set_two_factor(Some(two_factor));
+        insert_user(db.clone(), user.clone()).await;
+
+        let user_repo = MongoUserRepository::new(db.clone());
+        // A recovery code is used after the user was read for the challenge.
+        user_repo
+            .update_two_factor(user.id(), move |u| {
+                let mut two_factor = u.two_factor().unwrap().clone();
+                assert!(two_factor.use_recovery_code("first", &hasher));
+                u.set_two_factor(Some(two_factor));
+            })
+            .await
+            .unwrap();
+        let (_, challenge) = TwoFactorChallenge::start(chrono::Utc::now());
+        user_repo
+            .set_two_factor_challenge(user.id(), challenge.clone())
+            .await
+            .unwrap();
+
+        let user_from_db: User = get_user_from_db(db.clone(), user.id()).await.into();
+        let stored = user_from_db.two_factor().unwrap();
+        assert_eq!(1, stored.recovery_codes().len());
+        assert_eq!(
+            Some(challenge.token_hash()),
+            stored.challenge().as_ref().map(|c| c.token_hash())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_two_factor_challenge_requires_enabled_two_factor() {
+        use crate::domain::user_auth::two_factor::TwoFactorChallenge;
+
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let user = User::new_test_user(None);
+        insert_user(db.clone(), user.clone()).await;
+
+        let user_repo = MongoUserRepository::new(db.clone());
+        let (_, challenge) = TwoFactorChallenge::start(chrono::Utc::now());
+        let result = user_repo
+            .set_two_factor_challenge(user.id(), challenge)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(UserDomainError::Authorization(
+                UserAuthError::TwoFactorNotEnabled
+            ))
+        ));
+        let user_from_db: User = get_user_from_db(db.clone(), user.id()).await.into();
+        assert!(user_from_db.two_factor().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_link_identity_and_get_user_by_identity() {
+        use crate::domain::user::LinkedIdentity;
+
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let user = User::new_test_user(None);
+        insert_user(db.clone(), user.clone()).await;
+
+        let user_repo = MongoUserRepository::new(db.clone());
+        user_repo
+            .link_identity(user.id(), |u| {
+                u.link_identity(LinkedIdentity::new(
+                    "google".into(),
+                    "sub-1".into(),
+                    Some(u.email().to_string()),
+                    chrono::Utc::now(),
+                ))
+            })
+            .await
+            .unwrap();
+
+        let found = user_repo
+            .get_user_by_identity("google", "sub-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.id(), found.id());
+        assert!(
+            user_repo
+                .get_user_by_identity("github", "sub-1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_username_reserved() {
+        let client = test_utils::setup_test_mongo().await;
+        let db = client.database(&format!("test_db-{}", Uuid::new_v4()));
+        let mut user = User::new_test_user(None);
+        let old_username = user.username().to_string();
+        user.change_username("new_username".into());
+
+        insert_user(db.clone(), user.clone()).await;
+
+        let user_repo = MongoUserRepository::new(db.clone());
+        let since = Utc::now() - chrono::Duration::days(1);
+
+        let reserved = user_repo
+            .username_reserved(&old_username, since, "another-user-id")
+            .await
+            .unwrap();
+        assert!(reserved);
+
+        let reserved_for_owner = user_repo
+            .username_reserved(&old_username, since, user.id())
+            .await
+            .unwrap();
+        assert!(!reserved_for_owner);
+
+        let reservation_expired = user_repo
+            .username_reserved(&old_username, Utc::now(), "another-user-id")
+            .await
+            .unwrap();
+        assert!(!reservation_expired);
+    }
+
     #[tokio::test]
     async fn test_upsert_user() {
         let client = test_utils::setup_test_mongo().await;
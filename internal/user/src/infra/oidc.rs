@@ -0,0 +1,3 @@
+pub mod http_oidc_client;
+pub mod oidc_client;
+pub mod oidc_client_trait;
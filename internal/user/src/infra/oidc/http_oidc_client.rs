@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header, jwk::JwkSet};
+use reqwest::Url;
+use serde::Deserialize;
+use tokio::sync::RwLock;
+
+use crate::domain::user_auth::{
+    errors::UserAuthError,
+    oidc::{OidcAuthRequest, OidcIdentity, OidcProviderConfig},
+    result::UserAuthResult,
+};
+
+/// The parts of the discovery document the relying party uses.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    nonce: Option<String>,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    preferred_username: Option<String>,
+}
+
+/// Talks to providers over HTTP. Discovery documents are fetched once per
+/// issuer; signing keys are fetched on every exchange so rotations are picked up.
+pub struct HttpOidcClient {
+    http: reqwest::Client,
+    metadata: RwLock<HashMap<String, ProviderMetadata>>,
+}
+
+impl Default for HttpOidcClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpOidcClient {
+    pub fn new() -> Self {
+        let http = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("Unable to build the OIDC http client");
+        Self {
+            http,
+            metadata: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn discover(
+        &self,
+        provider: &OidcProviderConfig,
+    ) -> UserAuthResult<ProviderMetadata> {
+        if let Some(metadata) = self.metadata.read().await.get(&provider.issuer) {
+            return Ok(metadata.clone());
+        }
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            provider.issuer.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = self
+            .http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if metadata.issuer.trim_end_matches('/') != provider.issuer.trim_end_matches('/') {
+            return Err(UserAuthError::OidcProvider(
+                "Discovery document issuer does not match".into(),
+            ));
+        }
+        self.metadata
+            .write()
+            .await
+            .insert(provider.issuer.clone(), metadata.clone());
+        Ok(metadata)
+    }
+
+    pub async fn authorization_url(
+        &self,
+        provider: &OidcProviderConfig,
+        request: &OidcAuthRequest,
+    ) -> UserAuthResult<String> {
+        let metadata = self.discover(provider).await?;
+        let url = Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", provider.client_id.as_str()),
+                ("redirect_uri", provider.redirect_uri.as_str()),
+                ("scope", provider.scopes.join(" ").as_str()),
+                ("state", request.state().as_str()),
+                ("nonce", request.nonce().as_str()),
+                ("code_challenge", request.code_challenge().as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|_| UserAuthError::OidcProvider("Invalid authorization endpoint".into()))?;
+        Ok(url.into())
+    }
+
+    pub async fn exchange_code(
+        &self,
+        provider: &OidcProviderConfig,
+        request: &OidcAuthRequest,
+        code: &str,
+    ) -> UserAuthResult<OidcIdentity> {
+        let metadata = self.discover(provider).await?;
+        let response = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", provider.redirect_uri.as_str()),
+                ("client_id", provider.client_id.as_str()),
+                ("client_secret", provider.client_secret.as_str()),
+                ("code_verifier", request.code_verifier().as_str()),
+            ])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(UserAuthError::OidcProvider(
+                "Authorization code was rejected".into(),
+            ));
+        }
+        let TokenResponse { id_token } = response.json().await?;
+        let claims = self
+            .validate_id_token(provider, &metadata, &id_token)
+            .await?;
+        if claims.nonce.as_deref() != Some(request.nonce().as_str()) {
+            return Err(UserAuthError::InvalidOidcState);
+        }
+        Ok(OidcIdentity {
+            provider: provider.name.clone(),
+            subject: claims.sub,
+            email: claims.email,
+            email_verified: claims.email_verified,
+            preferred_username: claims.preferred_username,
+        })
+    }
+
+    /// Checks the signature, issuer, audience and expiry of `id_token`. HMAC
+    /// signed tokens use the client secret as key, as OIDC Core 10.1 specifies.
+    async fn validate_id_token(
+        &self,
+        provider: &OidcProviderConfig,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+    ) -> UserAuthResult<IdTokenClaims> {
+        let header = decode_header(id_token)?;
+        let key = match header.alg {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                DecodingKey::from_secret(provider.client_secret.as_bytes())
+            }
+            _ => {
+                let jwks: JwkSet = self
+                    .http
+                    .get(&metadata.jwks_uri)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                let jwk = match &header.kid {
+                    Some(kid) => jwks.find(kid),
+                    None => jwks.keys.first(),
+                }
+                .ok_or(UserAuthError::InvalidToken)?;
+                DecodingKey::from_jwk(jwk)?
+            }
+        };
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&provider.client_id]);
+        let data = decode::<IdTokenClaims>(id_token, &key, &validation)?;
+        Ok(data.claims)
+    }
+}
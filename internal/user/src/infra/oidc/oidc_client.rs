@@ -0,0 +1,42 @@
+use crate::domain::user_auth::{
+    oidc::{OidcAuthRequest, OidcIdentity, OidcProviderConfig},
+    result::UserAuthResult,
+};
+
+use super::http_oidc_client::HttpOidcClient;
+
+#[cfg(test)]
+use super::oidc_client_trait::OidcClientTrait;
+
+pub enum OidcClient {
+    Http(HttpOidcClient),
+    #[cfg(test)]
+    Mock(super::oidc_client_trait::MockOidcClientTrait),
+}
+
+impl OidcClient {
+    pub async fn authorization_url(
+        &self,
+        provider: &OidcProviderConfig,
+        request: &OidcAuthRequest,
+    ) -> UserAuthResult<String> {
+        match self {
+            OidcClient::Http(client) => client.authorization_url(provider, request).await,
+            #[cfg(test)]
+            OidcClient::Mock(mock) => mock.authorization_url(provider, request).await,
+        }
+    }
+
+    pub async fn exchange_code(
+        &self,
+        provider: &OidcProviderConfig,
+        request: &OidcAuthRequest,
+        code: &str,
+    ) -> UserAuthResult<OidcIdentity> {
+        match self {
+            OidcClient::Http(client) => client.exchange_code(provider, request, code).await,
+            #[cfg(test)]
+            OidcClient::Mock(mock) => mock.exchange_code(provider, request, code).await,
+        }
+    }
+}
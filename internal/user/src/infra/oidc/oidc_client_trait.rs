@@ -0,0 +1,20 @@
+use crate::domain::user_auth::{
+    oidc::{OidcAuthRequest, OidcIdentity, OidcProviderConfig},
+    result::UserAuthResult,
+};
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait OidcClientTrait {
+    async fn authorization_url(
+        &self,
+        provider: &OidcProviderConfig,
+        request: &OidcAuthRequest,
+    ) -> UserAuthResult<String>;
+    async fn exchange_code(
+        &self,
+        provider: &OidcProviderConfig,
+        request: &OidcAuthRequest,
+        code: &str,
+    ) -> UserAuthResult<OidcIdentity>;
+}
@@ -1,3 +1,11 @@
+pub mod api_token_repository;
+pub mod api_token_repository_trait;
+pub mod audit_log_repository;
+pub mod audit_log_repository_trait;
+pub mod oidc_auth_request_repository;
+pub mod oidc_auth_request_repository_trait;
+pub mod otp_issuance_repository;
+pub mod otp_issuance_repository_trait;
 pub mod otp_repository;
 pub mod otp_repository_trait;
 pub mod user_read_model_repository;
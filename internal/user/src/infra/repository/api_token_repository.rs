@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+
+use crate::domain::user_auth::{api_token::ApiToken, result::UserAuthResult};
+
+use crate::infra::mongoimpl::api_token_repository::MongoApiTokenRepository;
+
+#[cfg(test)]
+use super::api_token_repository_trait::ApiTokenRepositoryTrait;
+
+pub enum ApiTokenRepository {
+    MongoDb(MongoApiTokenRepository),
+    #[cfg(test)]
+    Mock(super::api_token_repository_trait::MockApiTokenRepositoryTrait),
+}
+
+impl ApiTokenRepository {
+    #[tracing::instrument(name = "api_token_repository.create_api_token", skip_all)]
+    pub async fn create_api_token(&self, token: ApiToken) -> UserAuthResult<()> {
+        match self {
+            ApiTokenRepository::MongoDb(repo) => repo.create_api_token(token).await,
+            #[cfg(test)]
+            ApiTokenRepository::Mock(mock) => mock.create_api_token(token).await,
+        }
+    }
+
+    #[tracing::instrument(name = "api_token_repository.get_api_token", skip_all)]
+    pub async fn get_api_token(&self, id: &str) -> UserAuthResult<Option<ApiToken>> {
+        match self {
+            ApiTokenRepository::MongoDb(repo) => repo.get_api_token(id).await,
+            #[cfg(test)]
+            ApiTokenRepository::Mock(mock) => mock.get_api_token(id).await,
+        }
+    }
+
+    #[tracing::instrument(name = "api_token_repository.list_api_tokens", skip_all)]
+    pub async fn list_api_tokens(&self, user_id: &str) -> UserAuthResult<Vec<ApiToken>> {
+        match self {
+            ApiTokenRepository::MongoDb(repo) => repo.list_api_tokens(user_id).await,
+            #[cfg(test)]
+            ApiTokenRepository::Mock(mock) => mock.list_api_tokens(user_id).await,
+        }
+    }
+
+    #[tracing::instrument(name = "api_token_repository.update_api_token", skip_all)]
+    pub async fn update_api_token(&self, token: ApiToken) -> UserAuthResult<()> {
+        match self {
+            ApiTokenRepository::MongoDb(repo) => repo.update_api_token(token).await,
+            #[cfg(test)]
+            ApiTokenRepository::Mock(mock) => mock.update_api_token(token).await,
+        }
+    }
+
+    #[tracing::instrument(name = "api_token_repository.delete_api_token", skip_all)]
+    pub async fn delete_api_token(&self, user_id: &str, id: &str) -> UserAuthResult<bool> {
+        match self {
+            ApiTokenRepository::MongoDb(repo) => repo.delete_api_token(user_id, id).await,
+            #[cfg(test)]
+            ApiTokenRepository::Mock(mock) => mock.delete_api_token(user_id, id).await,
+        }
+    }
+
+    #[tracing::instrument(name = "api_token_repository.touch_api_token", skip_all)]
+    pub async fn touch_api_token(&self, id: &str, used_at: DateTime<Utc>) -> UserAuthResult<()> {
+        match self {
+            ApiTokenRepository::MongoDb(repo) => repo.touch_api_token(id, used_at).await,
+            #[cfg(test)]
+            ApiTokenRepository::Mock(mock) => mock.touch_api_token(id, used_at).await,
+        }
+    }
+}
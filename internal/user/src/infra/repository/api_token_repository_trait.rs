@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+
+use crate::domain::user_auth::{api_token::ApiToken, result::UserAuthResult};
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait ApiTokenRepositoryTrait {
+    async fn create_api_token(&self, token: ApiToken) -> UserAuthResult<()>;
+    async fn get_api_token(&self, id: &str) -> UserAuthResult<Option<ApiToken>>;
+    async fn list_api_tokens(&self, user_id: &str) -> UserAuthResult<Vec<ApiToken>>;
+    /// Saves the name and scopes of an existing token.
+    async fn update_api_token(&self, token: ApiToken) -> UserAuthResult<()>;
+    /// Returns `false` when `user_id` has no token with this id.
+    async fn delete_api_token(&self, user_id: &str, id: &str) -> UserAuthResult<bool>;
+    async fn touch_api_token(&self, id: &str, used_at: DateTime<Utc>) -> UserAuthResult<()>;
+}
@@ -0,0 +1,41 @@
+use crate::domain::{
+    audit_log::{AuditEntry, AuditLogFilter, AuditLogPage},
+    result::UserDomainResult,
+};
+
+use crate::infra::{
+    memoryimpl::audit_log_repository::InMemoryAuditLogRepository,
+    mongoimpl::audit_log_repository::MongoAuditLogRepository,
+};
+
+#[cfg(test)]
+use super::audit_log_repository_trait::AuditLogRepositoryTrait;
+
+pub enum AuditLogRepository {
+    MongoDb(MongoAuditLogRepository),
+    InMemory(InMemoryAuditLogRepository),
+    #[cfg(test)]
+    Mock(super::audit_log_repository_trait::MockAuditLogRepositoryTrait),
+}
+
+impl AuditLogRepository {
+    #[tracing::instrument(name = "audit_log_repository.append", skip_all)]
+    pub async fn append(&self, entry: AuditEntry) -> UserDomainResult<()> {
+        match self {
+            AuditLogRepository::MongoDb(repo) => repo.append(entry).await,
+            AuditLogRepository::InMemory(repo) => repo.append(entry).await,
+            #[cfg(test)]
+            AuditLogRepository::Mock(mock) => mock.append(entry).await,
+        }
+    }
+
+    #[tracing::instrument(name = "audit_log_repository.list", skip_all)]
+    pub async fn list(&self, filter: &AuditLogFilter) -> UserDomainResult<AuditLogPage> {
+        match self {
+            AuditLogRepository::MongoDb(repo) => repo.list(filter).await,
+            AuditLogRepository::InMemory(repo) => repo.list(filter).await,
+            #[cfg(test)]
+            AuditLogRepository::Mock(mock) => mock.list(filter).await,
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use crate::domain::{
+    audit_log::{AuditEntry, AuditLogFilter, AuditLogPage},
+    result::UserDomainResult,
+};
+
+/// Entries are only ever appended.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait AuditLogRepositoryTrait {
+    async fn append(&self, entry: AuditEntry) -> UserDomainResult<()>;
+    async fn list(&self, filter: &AuditLogFilter) -> UserDomainResult<AuditLogPage>;
+}
@@ -0,0 +1,32 @@
+use crate::domain::user_auth::{oidc::OidcAuthRequest, result::UserAuthResult};
+
+use crate::infra::mongoimpl::oidc_auth_request_repository::MongoOidcAuthRequestRepository;
+
+#[cfg(test)]
+use super::oidc_auth_request_repository_trait::OidcAuthRequestRepositoryTrait;
+
+pub enum OidcAuthRequestRepository {
+    MongoDb(MongoOidcAuthRequestRepository),
+    #[cfg(test)]
+    Mock(super::oidc_auth_request_repository_trait::MockOidcAuthRequestRepositoryTrait),
+}
+
+impl OidcAuthRequestRepository {
+    #[tracing::instrument(name = "oidc_auth_request_repository.save_auth_request", skip_all)]
+    pub async fn save_auth_request(&self, request: OidcAuthRequest) -> UserAuthResult<()> {
+        match self {
+            OidcAuthRequestRepository::MongoDb(repo) => repo.save_auth_request(request).await,
+            #[cfg(test)]
+            OidcAuthRequestRepository::Mock(mock) => mock.save_auth_request(request).await,
+        }
+    }
+
+    #[tracing::instrument(name = "oidc_auth_request_repository.take_auth_request", skip_all)]
+    pub async fn take_auth_request(&self, state: &str) -> UserAuthResult<Option<OidcAuthRequest>> {
+        match self {
+            OidcAuthRequestRepository::MongoDb(repo) => repo.take_auth_request(state).await,
+            #[cfg(test)]
+            OidcAuthRequestRepository::Mock(mock) => mock.take_auth_request(state).await,
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use crate::domain::user_auth::{oidc::OidcAuthRequest, result::UserAuthResult};
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait OidcAuthRequestRepositoryTrait {
+    async fn save_auth_request(&self, request: OidcAuthRequest) -> UserAuthResult<()>;
+    /// Removes and returns the request for `state`, so each callback is accepted once.
+    async fn take_auth_request(&self, state: &str) -> UserAuthResult<Option<OidcAuthRequest>>;
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+
+use crate::domain::user_auth::result::UserAuthResult;
+
+use crate::infra::mongoimpl::otp_issuance_repository::MongoOtpIssuanceRepository;
+
+#[cfg(test)]
+use super::otp_issuance_repository_trait::OtpIssuanceRepositoryTrait;
+
+pub enum OtpIssuanceRepository {
+    MongoDb(MongoOtpIssuanceRepository),
+    #[cfg(test)]
+    Mock(super::otp_issuance_repository_trait::MockOtpIssuanceRepositoryTrait),
+}
+
+impl OtpIssuanceRepository {
+    #[tracing::instrument(name = "otp_issuance_repository.record_issuance", skip_all)]
+    pub async fn record_issuance(
+        &self,
+        key: &str,
+        issued_at: DateTime<Utc>,
+        expire_at: DateTime<Utc>,
+    ) -> UserAuthResult<()> {
+        match self {
+            OtpIssuanceRepository::MongoDb(repo) => {
+                repo.record_issuance(key, issued_at, expire_at).await
+            }
+            #[cfg(test)]
+            OtpIssuanceRepository::Mock(mock) => {
+                mock.record_issuance(key, issued_at, expire_at).await
+            }
+        }
+    }
+
+    /// Issue times for `key` after `since`, oldest first.
+    #[tracing::instrument(name = "otp_issuance_repository.get_issuances_since", skip_all)]
+    pub async fn get_issuances_since(
+        &self,
+        key: &str,
+        since: DateTime<Utc>,
+    ) -> UserAuthResult<Vec<DateTime<Utc>>> {
+        match self {
+            OtpIssuanceRepository::MongoDb(repo) => repo.get_issuances_since(key, since).await,
+            #[cfg(test)]
+            OtpIssuanceRepository::Mock(mock) => mock.get_issuances_since(key, since).await,
+        }
+    }
+}
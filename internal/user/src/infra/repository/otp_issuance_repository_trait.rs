@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+
+use crate::domain::user_auth::result::UserAuthResult;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait OtpIssuanceRepositoryTrait {
+    async fn record_issuance(
+        &self,
+        key: &str,
+        issued_at: DateTime<Utc>,
+        expire_at: DateTime<Utc>,
+    ) -> UserAuthResult<()>;
+    async fn get_issuances_since(
+        &self,
+        key: &str,
+        since: DateTime<Utc>,
+    ) -> UserAuthResult<Vec<DateTime<Utc>>>;
+}
@@ -12,6 +12,7 @@ pub enum OtpRepository {
 }
 
 impl OtpRepository {
+    #[tracing::instrument(name = "otp_repository.get_otp_by_user_email", skip_all)]
     pub async fn get_otp_by_user_email(&self, email: &str) -> UserAuthResult<Option<OtpEntry>> {
         match self {
             OtpRepository::MongoDb(repo) => repo.get_otp_by_user_email(email).await,
@@ -20,6 +21,7 @@ impl OtpRepository {
         }
     }
 
+    #[tracing::instrument(name = "otp_repository.upsert_otp", skip_all)]
     pub async fn upsert_otp<'a>(
         &self,
         otp: OtpEntry,
@@ -32,6 +34,7 @@ impl OtpRepository {
         }
     }
 
+    #[tracing::instrument(name = "otp_repository.delete_otp", skip_all)]
     pub async fn delete_otp(&self, email: &str) -> UserAuthResult<()> {
         match self {
             OtpRepository::MongoDb(repo) => repo.delete_otp(email).await,
@@ -16,6 +16,7 @@ pub enum UserReadModelRepository {
 }
 
 impl UserReadModelRepository {
+    #[tracing::instrument(name = "user_read_model_repository.get_users", skip_all)]
     pub async fn get_users(&self, opts: &GetUsersOptions) -> UserDomainResult<GetUsersResult> {
         match self {
             UserReadModelRepository::MongoDb(repo) => repo.get_users(opts).await,
@@ -24,6 +25,7 @@ impl UserReadModelRepository {
         }
     }
 
+    #[tracing::instrument(name = "user_read_model_repository.get_user_by_id", skip_all)]
     pub async fn get_user_by_id(&self, id: &str) -> UserDomainResult<Option<UserReadModel>> {
         match self {
             UserReadModelRepository::MongoDb(repo) => repo.get_user_by_id(id).await,
@@ -32,6 +34,16 @@ impl UserReadModelRepository {
         }
     }
 
+    #[tracing::instrument(name = "user_read_model_repository.get_users_by_ids", skip_all)]
+    pub async fn get_users_by_ids(&self, ids: &[String]) -> UserDomainResult<Vec<UserReadModel>> {
+        match self {
+            UserReadModelRepository::MongoDb(repo) => repo.get_users_by_ids(ids).await,
+            #[cfg(test)]
+            UserReadModelRepository::Mock(repo) => repo.get_users_by_ids(ids).await,
+        }
+    }
+
+    #[tracing::instrument(name = "user_read_model_repository.get_user_by_email", skip_all)]
     pub async fn get_user_by_email(&self, email: &str) -> UserDomainResult<Option<UserReadModel>> {
         match self {
             UserReadModelRepository::MongoDb(repo) => repo.get_user_by_email(email).await,
@@ -39,4 +51,16 @@ impl UserReadModelRepository {
             UserReadModelRepository::Mock(repo) => repo.get_user_by_email(email).await,
         }
     }
+
+    #[tracing::instrument(name = "user_read_model_repository.get_user_by_username", skip_all)]
+    pub async fn get_user_by_username(
+        &self,
+        username: &str,
+    ) -> UserDomainResult<Option<UserReadModel>> {
+        match self {
+            UserReadModelRepository::MongoDb(repo) => repo.get_user_by_username(username).await,
+            #[cfg(test)]
+            UserReadModelRepository::Mock(repo) => repo.get_user_by_username(username).await,
+        }
+    }
 }
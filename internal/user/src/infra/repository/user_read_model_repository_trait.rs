@@ -8,5 +8,9 @@ use crate::domain::{
 pub trait UserReadModelRepositoryTrait: Send + Sync {
     async fn get_users(&self, opts: &GetUsersOptions) -> UserDomainResult<GetUsersResult>;
     async fn get_user_by_id(&self, id: &str) -> UserDomainResult<Option<UserReadModel>>;
+    /// Users with any of `ids`, in no particular order. Unknown ids are skipped.
+    async fn get_users_by_ids(&self, ids: &[String]) -> UserDomainResult<Vec<UserReadModel>>;
     async fn get_user_by_email(&self, email: &str) -> UserDomainResult<Option<UserReadModel>>;
+    async fn get_user_by_username(&self, username: &str)
+    -> UserDomainResult<Option<UserReadModel>>;
 }
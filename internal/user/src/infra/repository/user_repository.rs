@@ -1,13 +1,18 @@
+use chrono::{DateTime, Utc};
+
 use crate::infra::mongoimpl::user_repository::MongoUserRepository;
 
 use crate::domain::result::UserDomainResult;
 use crate::domain::user::{EmailStatus, User};
+use crate::domain::user_auth::two_factor::TwoFactorChallenge;
 
 use shared::db_transactions::{DBTransaction, RepoDB};
 
 #[cfg(test)]
 use super::user_repository_trait::UserRepositoryTrait;
 
+// The mock variant is the large one and only exists in tests, so boxing it buys nothing.
+#[cfg_attr(test, allow(clippy::large_enum_variant))]
 pub enum UserRepository {
     MongoDb(MongoUserRepository),
     #[cfg(test)]
@@ -15,6 +20,7 @@ pub enum UserRepository {
 }
 
 impl UserRepository {
+    #[tracing::instrument(name = "user_repository.create_account", skip_all)]
     pub async fn create_account(&self, user: User) -> UserDomainResult<()> {
         match self {
             UserRepository::MongoDb(repo) => repo.create_account(user).await,
@@ -22,6 +28,7 @@ impl UserRepository {
             UserRepository::Mock(repo) => repo.create_account(user).await,
         }
     }
+    #[tracing::instrument(name = "user_repository.make_moderator", skip_all)]
     pub async fn make_moderator<F: FnOnce(&mut User) + Send + 'static>(
         &self,
         user_id: &str,
@@ -33,6 +40,7 @@ impl UserRepository {
             UserRepository::Mock(repo) => repo.make_moderator(user_id, update_fn).await,
         }
     }
+    #[tracing::instrument(name = "user_repository.change_username", skip_all)]
     pub async fn change_username<F: FnOnce(&mut User) + Send + 'static>(
         &self,
         user_id: &str,
@@ -44,6 +52,7 @@ impl UserRepository {
             UserRepository::Mock(repo) => repo.change_username(user_id, update_fn).await,
         }
     }
+    #[tracing::instrument(name = "user_repository.award_badge", skip_all)]
     pub async fn award_badge<F: FnOnce(&mut User) + Send + 'static>(
         &self,
         user_id: &str,
@@ -55,6 +64,7 @@ impl UserRepository {
             UserRepository::Mock(repo) => repo.award_badge(user_id, update_fn).await,
         }
     }
+    #[tracing::instrument(name = "user_repository.revoke_badge", skip_all)]
     pub async fn revoke_badge<F: FnOnce(&mut User) + Send + 'static>(
         &self,
         user_id: &str,
@@ -66,6 +76,7 @@ impl UserRepository {
             UserRepository::Mock(repo) => repo.revoke_badge(user_id, update_fn).await,
         }
     }
+    #[tracing::instrument(name = "user_repository.ban_user", skip_all)]
     pub async fn ban_user<F: FnOnce(&mut User) + Send + 'static>(
         &self,
         user_id: &str,
@@ -77,6 +88,7 @@ impl UserRepository {
             UserRepository::Mock(repo) => repo.ban_user(user_id, update_fn).await,
         }
     }
+    #[tracing::instrument(name = "user_repository.unban_user", skip_all)]
     pub async fn unban_user<F: FnOnce(&mut User) + Send + 'static>(
         &self,
         user_id: &str,
@@ -88,6 +100,45 @@ impl UserRepository {
             UserRepository::Mock(repo) => repo.unban_user(user_id, update_fn).await,
         }
     }
+    #[tracing::instrument(name = "user_repository.update_two_factor", skip_all)]
+    pub async fn update_two_factor<F: FnOnce(&mut User) + Send + 'static>(
+        &self,
+        user_id: &str,
+        update_fn: F,
+    ) -> UserDomainResult<()> {
+        match self {
+            UserRepository::MongoDb(repo) => repo.update_two_factor(user_id, update_fn).await,
+            #[cfg(test)]
+            UserRepository::Mock(repo) => repo.update_two_factor(user_id, update_fn).await,
+        }
+    }
+    #[tracing::instrument(name = "user_repository.set_two_factor_challenge", skip_all)]
+    pub async fn set_two_factor_challenge(
+        &self,
+        user_id: &str,
+        challenge: TwoFactorChallenge,
+    ) -> UserDomainResult<()> {
+        match self {
+            UserRepository::MongoDb(repo) => {
+                repo.set_two_factor_challenge(user_id, challenge).await
+            }
+            #[cfg(test)]
+            UserRepository::Mock(repo) => repo.set_two_factor_challenge(user_id, challenge).await,
+        }
+    }
+    #[tracing::instrument(name = "user_repository.link_identity", skip_all)]
+    pub async fn link_identity<F: FnOnce(&mut User) + Send + 'static>(
+        &self,
+        user_id: &str,
+        update_fn: F,
+    ) -> UserDomainResult<()> {
+        match self {
+            UserRepository::MongoDb(repo) => repo.link_identity(user_id, update_fn).await,
+            #[cfg(test)]
+            UserRepository::Mock(repo) => repo.link_identity(user_id, update_fn).await,
+        }
+    }
+    #[tracing::instrument(name = "user_repository.get_user_by_id", skip_all)]
     pub async fn get_user_by_id(&self, user_id: &str) -> UserDomainResult<Option<User>> {
         match self {
             UserRepository::MongoDb(repo) => repo.get_user_by_id(user_id).await,
@@ -95,6 +146,19 @@ impl UserRepository {
             UserRepository::Mock(repo) => repo.get_user_by_id(user_id).await,
         }
     }
+    #[tracing::instrument(name = "user_repository.get_user_by_identity", skip_all)]
+    pub async fn get_user_by_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> UserDomainResult<Option<User>> {
+        match self {
+            UserRepository::MongoDb(repo) => repo.get_user_by_identity(provider, subject).await,
+            #[cfg(test)]
+            UserRepository::Mock(repo) => repo.get_user_by_identity(provider, subject).await,
+        }
+    }
+    #[tracing::instrument(name = "user_repository.get_user_by_username_or_email", skip_all)]
     pub async fn get_user_by_username_or_email(
         &self,
         username: &str,
@@ -108,6 +172,7 @@ impl UserRepository {
             UserRepository::Mock(repo) => repo.get_user_by_username_or_email(username, email).await,
         }
     }
+    #[tracing::instrument(name = "user_repository.user_exists", skip_all)]
     pub async fn user_exists(
         &self,
         username: &str,
@@ -120,6 +185,26 @@ impl UserRepository {
             UserRepository::Mock(repo) => repo.user_exists(username, email, email_status).await,
         }
     }
+    #[tracing::instrument(name = "user_repository.username_reserved", skip_all)]
+    pub async fn username_reserved(
+        &self,
+        username: &str,
+        released_after: DateTime<Utc>,
+        user_id: &str,
+    ) -> UserDomainResult<bool> {
+        match self {
+            UserRepository::MongoDb(repo) => {
+                repo.username_reserved(username, released_after, user_id)
+                    .await
+            }
+            #[cfg(test)]
+            UserRepository::Mock(repo) => {
+                repo.username_reserved(username, released_after, user_id)
+                    .await
+            }
+        }
+    }
+    #[tracing::instrument(name = "user_repository.upsert_user", skip_all)]
     pub async fn upsert_user<'a>(
         &self,
         user: User,
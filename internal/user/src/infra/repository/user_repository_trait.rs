@@ -1,5 +1,8 @@
+use chrono::{DateTime, Utc};
+
 use crate::domain::result::UserDomainResult;
 use crate::domain::user::{EmailStatus, User};
+use crate::domain::user_auth::two_factor::TwoFactorChallenge;
 
 use shared::db_transactions::{DBTransaction, RepoDB};
 
@@ -46,8 +49,32 @@ pub trait UserRepositoryTrait: Send + Sync {
         update_fn: F,
     ) -> UserDomainResult<()>;
 
+    async fn update_two_factor<F: FnOnce(&mut User) + Send + 'static>(
+        &self,
+        user_id: &str,
+        update_fn: F,
+    ) -> UserDomainResult<()>;
+
+    async fn set_two_factor_challenge(
+        &self,
+        user_id: &str,
+        challenge: TwoFactorChallenge,
+    ) -> UserDomainResult<()>;
+
+    async fn link_identity<F: FnOnce(&mut User) + Send + 'static>(
+        &self,
+        user_id: &str,
+        update_fn: F,
+    ) -> UserDomainResult<()>;
+
     async fn get_user_by_id(&self, user_id: &str) -> UserDomainResult<Option<User>>;
 
+    async fn get_user_by_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> UserDomainResult<Option<User>>;
+
     async fn get_user_by_username_or_email(
         &self,
         username: &str,
@@ -61,6 +88,15 @@ pub trait UserRepositoryTrait: Send + Sync {
         email_status: Option<EmailStatus>,
     ) -> UserDomainResult<bool>;
 
+    /// Checks whether `username` was released by a user other than `user_id`
+    /// after `released_after`, i.e. it is still reserved for its previous owner.
+    async fn username_reserved(
+        &self,
+        username: &str,
+        released_after: DateTime<Utc>,
+        user_id: &str,
+    ) -> UserDomainResult<bool>;
+
     async fn upsert_user<'a>(
         &self,
         user: User,
@@ -1,6 +1,12 @@
+use crate::domain::audit_log::{AuditAction, AuditChange, AuditEntry, AuditLogPage};
+use crate::domain::events::{
+    AccountChange, AccountChangeKind, ModerationQueueUpdate, ModerationQueueUpdateKind,
+};
+use crate::domain::user_auth::api_token::ApiToken;
 use crate::domain::user_read_model::{Ban, BanType as DomainBanType, UserReadModel};
-use async_graphql::{Enum, Object};
-use shared::types::graphql_scalars::DateTimeScalar;
+use crate::ports::user_loader::load_user;
+use async_graphql::{Context, Enum, Object, Result};
+use shared::{guards::permissions::Permission, types::graphql_scalars::DateTimeScalar};
 
 #[derive(Clone, Copy, Eq, PartialEq, Debug, Enum)]
 #[graphql(remote = "shared::guards::roles::UserRole")]
@@ -80,3 +86,151 @@ impl Ban {
         }
     }
 }
+
+// Resolvers are renamed: the getters of `ApiToken` already use the field names.
+#[Object(name = "ApiToken")]
+impl ApiToken {
+    #[graphql(name = "id")]
+    async fn resolve_id(&self) -> String {
+        self.id().to_owned()
+    }
+    #[graphql(name = "name")]
+    async fn resolve_name(&self) -> String {
+        self.name().to_owned()
+    }
+    #[graphql(name = "scopes")]
+    async fn resolve_scopes(&self) -> Vec<Permission> {
+        self.scopes().to_owned()
+    }
+    #[graphql(name = "createdAt")]
+    async fn resolve_created_at(&self) -> DateTimeScalar {
+        (*self.created_at()).into()
+    }
+    #[graphql(name = "expiresAt")]
+    async fn resolve_expires_at(&self) -> DateTimeScalar {
+        (*self.expires_at()).into()
+    }
+    #[graphql(name = "lastUsedAt")]
+    async fn resolve_last_used_at(&self) -> Option<DateTimeScalar> {
+        self.last_used_at().map(Into::into)
+    }
+}
+
+#[Object(name = "AuditEntry")]
+impl AuditEntry {
+    async fn id(&self) -> String {
+        self.id.to_owned()
+    }
+    async fn actor_id(&self) -> String {
+        self.actor_id.to_owned()
+    }
+    #[graphql(complexity = "5 + child_complexity")]
+    async fn actor(&self, ctx: &Context<'_>) -> Result<Option<UserReadModel>> {
+        load_user(ctx, &self.actor_id).await
+    }
+    async fn action(&self) -> AuditAction {
+        self.action
+    }
+    async fn target_id(&self) -> String {
+        self.target_id.to_owned()
+    }
+    #[graphql(complexity = "5 + child_complexity")]
+    async fn target(&self, ctx: &Context<'_>) -> Result<Option<UserReadModel>> {
+        load_user(ctx, &self.target_id).await
+    }
+    async fn changes(&self) -> Vec<AuditChange> {
+        self.changes.to_owned()
+    }
+    async fn client_ip(&self) -> Option<String> {
+        self.metadata.client_ip.to_owned()
+    }
+    async fn api_token_id(&self) -> Option<String> {
+        self.metadata.api_token_id.to_owned()
+    }
+    /// The user the actor was acting as, when impersonating.
+    async fn on_behalf_of(&self) -> Option<String> {
+        self.metadata.on_behalf_of.to_owned()
+    }
+    /// Id of the request that made the change, as found in the server logs.
+    async fn request_id(&self) -> Option<String> {
+        self.metadata.request_id.to_owned()
+    }
+    async fn user_agent(&self) -> Option<String> {
+        self.metadata.user_agent.to_owned()
+    }
+    async fn created_at(&self) -> DateTimeScalar {
+        self.created_at.into()
+    }
+    /// Pass as `after` to continue from this entry.
+    #[graphql(name = "cursor")]
+    async fn resolve_cursor(&self) -> String {
+        self.cursor()
+    }
+}
+
+#[Object(name = "AuditChange")]
+impl AuditChange {
+    async fn field(&self) -> String {
+        self.field.to_owned()
+    }
+    async fn before(&self) -> Option<String> {
+        self.before.to_owned()
+    }
+    async fn after(&self) -> Option<String> {
+        self.after.to_owned()
+    }
+}
+
+#[Object(name = "AuditLogPage")]
+impl AuditLogPage {
+    async fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.to_owned()
+    }
+    async fn has_next(&self) -> bool {
+        self.has_next
+    }
+    async fn end_cursor(&self) -> Option<String> {
+        self.entries.last().map(AuditEntry::cursor)
+    }
+}
+
+#[Object(name = "AccountChange")]
+impl AccountChange {
+    async fn user_id(&self) -> String {
+        self.user_id.to_owned()
+    }
+    async fn kind(&self) -> AccountChangeKind {
+        self.kind
+    }
+    /// The ban reason, new role or badge.
+    async fn detail(&self) -> Option<String> {
+        self.detail.to_owned()
+    }
+    async fn occurred_at(&self) -> DateTimeScalar {
+        self.occurred_at.into()
+    }
+}
+
+#[Object(name = "ModerationQueueUpdate")]
+impl ModerationQueueUpdate {
+    async fn user_id(&self) -> String {
+        self.user_id.to_owned()
+    }
+    #[graphql(complexity = "5 + child_complexity")]
+    async fn user(&self, ctx: &Context<'_>) -> Result<Option<UserReadModel>> {
+        load_user(ctx, &self.user_id).await
+    }
+    async fn kind(&self) -> ModerationQueueUpdateKind {
+        self.kind
+    }
+    async fn moderator_id(&self) -> String {
+        self.moderator_id.to_owned()
+    }
+    #[graphql(complexity = "5 + child_complexity")]
+    async fn moderator(&self, ctx: &Context<'_>) -> Result<Option<UserReadModel>> {
+        load_user(ctx, &self.moderator_id).await
+    }
+    async fn occurred_at(&self) -> DateTimeScalar {
+        self.occurred_at.into()
+    }
+}
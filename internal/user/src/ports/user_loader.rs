@@ -0,0 +1,95 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_graphql::{
+    Context, Result,
+    dataloader::{DataLoader, Loader},
+};
+use shared::{auth::AppContext, query_handler::QueryHandler};
+
+use crate::app::query::users_by_ids::{GetUsersByIds, GetUsersByIdsHandler};
+use crate::domain::{errors::UserDomainError, user_read_model::UserReadModel};
+
+/// Batches the user lookups of one GraphQL request into a single query.
+/// Built per request, so lookups are authorized as the caller.
+pub struct UserLoader {
+    handler: Arc<GetUsersByIdsHandler>,
+    ctx: AppContext,
+}
+
+impl UserLoader {
+    pub fn new(handler: Arc<GetUsersByIdsHandler>, ctx: AppContext) -> Self {
+        Self { handler, ctx }
+    }
+}
+
+impl Loader<String> for UserLoader {
+    type Value = UserReadModel;
+    type Error = UserDomainError;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, UserReadModel>, Self::Error> {
+        let users = self
+            .handler
+            .handle(&self.ctx, GetUsersByIds { ids: keys.to_vec() })
+            .await?;
+        Ok(users
+            .into_iter()
+            .map(|user| (user.id.clone(), user))
+            .collect())
+    }
+}
+
+/// Goes through the request's loader, so all users asked for while resolving
+/// one level of the response are fetched together.
+pub async fn load_user(ctx: &Context<'_>, id: &str) -> Result<Option<UserReadModel>> {
+    let loader = ctx.data::<DataLoader<UserLoader>>()?;
+    Ok(loader.load_one(id.to_string()).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guards::MockUserGuards;
+    use crate::infra::repository::{
+        user_read_model_repository::UserReadModelRepository,
+        user_read_model_repository_trait::MockUserReadModelRepositoryTrait,
+    };
+    use shared::{auth::AuthUser, guards::roles::UserRole};
+
+    #[tokio::test]
+    async fn concurrent_loads_are_batched() {
+        let mut mock_user_read_repo = MockUserReadModelRepositoryTrait::new();
+        let mut mock_guard = MockUserGuards::new();
+
+        mock_guard.expect_authorize().returning(|_, _| Ok(()));
+        mock_user_read_repo
+            .expect_get_users_by_ids()
+            .times(1)
+            .returning(|ids| {
+                Ok(ids
+                    .iter()
+                    .filter(|id| *id != "missing")
+                    .map(|id| {
+                        let mut user = UserReadModel::new_test_user_read_model();
+                        user.id = id.clone();
+                        user
+                    })
+                    .collect())
+            });
+
+        let handler = Arc::new(GetUsersByIdsHandler::new(
+            Arc::new(UserReadModelRepository::Mock(mock_user_read_repo)),
+            Arc::new(mock_guard),
+        ));
+        let ctx = AppContext::new().with_user(AuthUser::new_test_auth_user(UserRole::Admin));
+        let loader = DataLoader::new(UserLoader::new(handler, ctx), tokio::spawn);
+
+        let (first, second, missing) = tokio::join!(
+            loader.load_one("user-1".to_string()),
+            loader.load_one("user-2".to_string()),
+            loader.load_one("missing".to_string()),
+        );
+        assert_eq!(first.unwrap().unwrap().id, "user-1");
+        assert_eq!(second.unwrap().unwrap().id, "user-2");
+        assert!(missing.unwrap().is_none());
+    }
+}
@@ -1,11 +1,15 @@
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
 use axum::{
-    extract::FromRequestParts,
+    extract::{ConnectInfo, FromRequestParts},
     http::{StatusCode, header, request::Parts},
     response::{IntoResponse, Response},
 };
-use shared::auth::{
-    AuthUser,
-    jwt::{Claims, verify_jwt},
+use ports::app_service::AppService;
+use shared::auth::{AuthUser, RequestMetadata, jwt::Claims, token_service::TokenService};
+use user::{
+    app::api_token_authenticator::ApiTokenAuthenticator,
+    domain::user_auth::api_token::utils::is_api_token,
 };
 
 #[derive(Debug, Clone)]
@@ -18,6 +22,10 @@ where
     type Rejection = Response;
 
     async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // Already resolved by a middleware, such as the rate limiter.
+        if let Some(auth_user) = parts.extensions.get::<AxumAuthUser>() {
+            return Ok(auth_user.clone());
+        }
         let auth_header = parts
             .headers
             .get(header::AUTHORIZATION)
@@ -28,21 +36,165 @@ where
             return Ok(AxumAuthUser(Claims::guest_claims()));
         }
         let token = auth_header.unwrap();
-        let jwt_result = verify_jwt(token);
-        if let Err(..) = jwt_result {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                "Invalid or missing JWT".to_string(),
-            )
-                .into_response());
+        let token_service = parts
+            .extensions
+            .get::<Arc<dyn TokenService>>()
+            .expect("TokenService extension is not installed");
+        let authenticator = parts
+            .extensions
+            .get::<Arc<ApiTokenAuthenticator>>()
+            .expect("ApiTokenAuthenticator extension is not installed");
+        match authenticate_token(token, token_service.as_ref(), authenticator).await {
+            Ok(auth_user) => {
+                let auth_user = AxumAuthUser(auth_user.0);
+                parts.extensions.insert(auth_user.clone());
+                Ok(auth_user)
+            }
+            Err(message) => Err((StatusCode::UNAUTHORIZED, message.to_string()).into_response()),
         }
-        let claims = jwt_result.unwrap();
-        Ok(AxumAuthUser(claims))
     }
 }
 
+/// Resolves a bearer token, either an API token or a JWT, to its user.
+pub async fn authenticate_token(
+    token: &str,
+    token_service: &dyn TokenService,
+    authenticator: &ApiTokenAuthenticator,
+) -> Result<AuthUser, &'static str> {
+    if is_api_token(token) {
+        return authenticator
+            .authenticate(token)
+            .await
+            .map_err(|_| "Invalid or expired API token");
+    }
+    token_service
+        .verify(token)
+        .map(AuthUser::new)
+        .map_err(|_| "Invalid or missing JWT")
+}
+
 impl From<AxumAuthUser> for AuthUser {
     fn from(value: AxumAuthUser) -> Self {
         AuthUser(value.0)
     }
 }
+
+/// The caller's address. Behind `trusted_proxy_hops` proxies it is the
+/// `X-Forwarded-For` entry added by the outermost of them, counted from the
+/// right, since entries further left are whatever the client sent. Otherwise
+/// it is the peer address of the connection.
+#[derive(Debug, Clone)]
+pub struct AxumClientIp(pub Option<String>);
+
+impl<S> FromRequestParts<S> for AxumClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let trusted_proxy_hops = parts
+            .extensions
+            .get::<AppService>()
+            .expect("AppService extension is not installed")
+            .config
+            .trusted_proxy_hops;
+        let forwarded = parts
+            .headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|h| h.to_str().ok())
+            .collect::<Vec<_>>()
+            .join(",");
+        if let Some(ip) = forwarded_client_ip(&forwarded, trusted_proxy_hops) {
+            return Ok(AxumClientIp(Some(ip)));
+        }
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string());
+        Ok(AxumClientIp(peer))
+    }
+}
+
+/// The entry `hops` from the right of an `X-Forwarded-For` list. When the list
+/// is shorter, the request did not pass every proxy and the leftmost entry,
+/// added by the first one it did pass, is used.
+fn forwarded_client_ip(forwarded: &str, hops: usize) -> Option<String> {
+    if hops == 0 {
+        return None;
+    }
+    let entries = forwarded
+        .split(',')
+        .map(str::trim)
+        .filter(|ip| !ip.is_empty())
+        .collect::<Vec<_>>();
+    let index = entries.len().saturating_sub(hops);
+    entries.get(index).map(|ip| ip.to_string())
+}
+
+/// The [`RequestMetadata`] attached by [`crate::request_metadata::propagate`].
+#[derive(Debug, Clone)]
+pub struct AxumRequestMetadata(pub RequestMetadata);
+
+impl<S> FromRequestParts<S> for AxumRequestMetadata
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let metadata = parts
+            .extensions
+            .get::<RequestMetadata>()
+            .cloned()
+            .expect("Request metadata middleware is not installed");
+        Ok(AxumRequestMetadata(metadata))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forwarded_for_is_ignored_without_trusted_proxies() {
+        assert_eq!(forwarded_client_ip("203.0.113.9", 0), None);
+        assert_eq!(forwarded_client_ip("198.51.100.1, 203.0.113.9", 0), None);
+    }
+
+    #[test]
+    fn forwarded_for_with_one_entry_per_proxy() {
+        assert_eq!(
+            forwarded_client_ip("203.0.113.9", 1).as_deref(),
+            Some("203.0.113.9")
+        );
+        assert_eq!(
+            forwarded_client_ip("203.0.113.9, 10.0.0.2", 2).as_deref(),
+            Some("203.0.113.9")
+        );
+    }
+
+    #[test]
+    fn forwarded_for_shorter_than_hops_uses_leftmost_entry() {
+        assert_eq!(
+            forwarded_client_ip("203.0.113.9", 2).as_deref(),
+            Some("203.0.113.9")
+        );
+        assert_eq!(forwarded_client_ip("", 2), None);
+        assert_eq!(forwarded_client_ip(" , ", 1), None);
+    }
+
+    #[test]
+    fn forwarded_for_ignores_client_supplied_entries() {
+        // The client sent the first two entries itself; the proxy appended the last.
+        assert_eq!(
+            forwarded_client_ip("1.2.3.4, 5.6.7.8, 203.0.113.9", 1).as_deref(),
+            Some("203.0.113.9")
+        );
+        assert_eq!(
+            forwarded_client_ip("1.2.3.4,203.0.113.9,10.0.0.2", 2).as_deref(),
+            Some("203.0.113.9")
+        );
+    }
+}
@@ -1,20 +1,32 @@
 use async_graphql::http::{
     GraphQLPlaygroundConfig, GraphiQLSource, graphiql_source, playground_source,
 };
-use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
-use axum::extract::State;
-use axum::response::{Html, IntoResponse};
-use shared::auth::AuthUser;
+use async_graphql::{Data, Error};
+use async_graphql_axum::{GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket};
+use axum::Extension;
+use axum::extract::{State, WebSocketUpgrade};
+use axum::response::{Html, IntoResponse, Response};
+use serde_json::Value;
+use shared::auth::{AuthUser, token_service::TokenService};
+use std::sync::Arc;
+use user::app::api_token_authenticator::ApiTokenAuthenticator;
 
-use super::extractors::AxumAuthUser;
+use super::extractors::{AxumAuthUser, AxumRequestMetadata, authenticate_token};
+
+pub const GRAPHQL_WS_PATH: &str = "/graphql/ws";
 
 pub async fn graphql_playground() -> impl IntoResponse {
-    Html(playground_source(GraphQLPlaygroundConfig::new("/graphql")))
+    Html(playground_source(
+        GraphQLPlaygroundConfig::new("/graphql").subscription_endpoint(GRAPHQL_WS_PATH),
+    ))
 }
 
 pub async fn graphiql() -> impl IntoResponse {
     Html(graphiql_source(
-        &GraphiQLSource::build().endpoint("/graphql").finish(),
+        &GraphiQLSource::build()
+            .endpoint("/graphql")
+            .subscription_endpoint(GRAPHQL_WS_PATH)
+            .finish(),
         None,
     ))
 }
@@ -22,10 +34,53 @@ pub async fn graphiql() -> impl IntoResponse {
 pub async fn graphql_handler(
     State(schema): State<ports::graphql::AppSchema>,
     auth_user: AxumAuthUser,
+    AxumRequestMetadata(metadata): AxumRequestMetadata,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema
-        .execute(req.into_inner().data::<AuthUser>(auth_user.into()))
-        .await
-        .into()
+    let req = req
+        .into_inner()
+        .data::<AuthUser>(auth_user.into())
+        .data(metadata);
+    schema.execute(req).await.into()
+}
+
+/// Serves subscriptions over `graphql-transport-ws`. Browsers cannot set headers
+/// on a WebSocket, so the bearer token is read from the `Authorization` entry of
+/// the connection-init payload instead. Without one the connection is a guest.
+pub async fn graphql_ws_handler(
+    State(schema): State<ports::graphql::AppSchema>,
+    Extension(token_service): Extension<Arc<dyn TokenService>>,
+    Extension(authenticator): Extension<Arc<ApiTokenAuthenticator>>,
+    AxumRequestMetadata(metadata): AxumRequestMetadata,
+    protocol: GraphQLProtocol,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade
+        .protocols(["graphql-transport-ws"])
+        .on_upgrade(move |stream| {
+            GraphQLWebSocket::new(stream, schema, protocol)
+                .on_connection_init(move |payload| async move {
+                    let auth_user = match bearer_token(&payload) {
+                        Some(token) => {
+                            authenticate_token(token, token_service.as_ref(), &authenticator)
+                                .await
+                                .map_err(Error::new)?
+                        }
+                        None => AuthUser::guest(),
+                    };
+                    let mut data = Data::default();
+                    data.insert(auth_user);
+                    data.insert(metadata);
+                    Ok(data)
+                })
+                .serve()
+        })
+}
+
+fn bearer_token(payload: &Value) -> Option<&str> {
+    payload
+        .get("Authorization")
+        .or_else(|| payload.get("authorization"))
+        .and_then(Value::as_str)
+        .and_then(|value| value.strip_prefix("Bearer "))
 }
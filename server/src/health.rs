@@ -0,0 +1,31 @@
+use axum::{Extension, Json, http::StatusCode, response::IntoResponse};
+use ports::app_service::AppService;
+use serde_json::json;
+
+/// Liveness: the process is up and serving requests. Checks no dependencies,
+/// so a slow database does not get the process restarted.
+pub async fn healthz() -> impl IntoResponse {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Readiness: storage answers and every startup job the app needs, such as
+/// migrations, has finished. Answers 503 until then.
+pub async fn readyz(Extension(app_service): Extension<AppService>) -> impl IntoResponse {
+    let storage = app_service.storage.health().await;
+    let ready = storage.healthy && app_service.jobs.is_ready();
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(json!({
+            "status": if ready { "ready" } else { "not_ready" },
+            "checks": {
+                "storage": storage,
+                "background_jobs": app_service.jobs.statuses(),
+            }
+        })),
+    )
+}
@@ -1,3 +1,11 @@
-pub mod graphql;
-pub mod rest {}
 pub mod extractors;
+pub mod graphql;
+pub mod health;
+pub mod metrics;
+pub mod rate_limit;
+pub mod request_metadata;
+pub mod rest;
+pub mod security;
+pub mod shutdown;
+pub mod telemetry;
+pub mod well_known;
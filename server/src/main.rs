@@ -1,47 +1,251 @@
-use async_graphql::EmptySubscription;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
 use axum::{
-    Router,
+    Extension, Router,
+    extract::DefaultBodyLimit,
+    middleware,
     routing::{get, post},
-    serve,
 };
+use axum_server::{Handle, tls_rustls::RustlsConfig};
 
-use infra::storage::StorageEngine;
+use infra::{
+    oidc_mock::MockOidcProvider,
+    storage::{AppStorage, StorageEngine},
+};
 use ports::{
     app_service::AppService,
-    graphql::{AppSchema, Mutation, Query},
+    graphql::{
+        AppSchema, Mutation, Query, Subscription,
+        errors::ErrorCodes,
+        impersonation_log::ImpersonationLog,
+        loaders::Loaders,
+        metrics::Metrics,
+        persisted_queries::{OperationAllowList, automatic_persisted_queries},
+        query_limits::QueryLimits,
+        rate_limit::RateLimit,
+    },
+};
+use server::{
+    graphql::{GRAPHQL_WS_PATH, graphiql, graphql_handler, graphql_playground, graphql_ws_handler},
+    health, metrics, request_metadata, rest, security, shutdown, telemetry, well_known,
+};
+use shared::{
+    auth::token_service::{JwtTokenService, TokenService},
+    config::{AppConfig, CliArgs, Environment},
 };
-use server::graphql::{graphiql, graphql_handler, graphql_playground};
-use shared::config::Config;
-use tracing::info;
+use tower_http::limit::RequestBodyLimitLayer;
+use tracing::{info, warn};
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt().init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cli = CliArgs::parse(&args).unwrap_or_else(|err| exit_with(err));
+    let command: Vec<&str> = cli.command.iter().map(String::as_str).collect();
+    let config = AppConfig::load(&cli).unwrap_or_else(|err| exit_with(err));
+    match command.as_slice() {
+        [] => {}
+        ["config", "check"] => {
+            println!("{:#?}", config);
+            return;
+        }
+        ["migrate", subcommand @ ..] => {
+            tracing_subscriber::fmt().init();
+            return migrate(&config, subcommand.first().copied()).await;
+        }
+        _ => exit_with(format!("Unknown command: {}", cli.command.join(" "))),
+    }
+    let config = Arc::new(config);
+    let _telemetry = telemetry::init(&config);
 
-    let app_service = AppService::build(StorageEngine::MongoDB).await;
+    let jwt_service =
+        JwtTokenService::from_config(&config).expect("Unable to load JWT signing keys");
+    info!("Signing tokens with key {}", jwt_service.current_key_id());
+    let token_service: Arc<dyn TokenService> = Arc::new(jwt_service);
 
-    let schema = AppSchema::build(Query::default(), Mutation::default(), EmptySubscription)
-        .data(app_service)
+    let app_service = AppService::build(
+        StorageEngine::MongoDB,
+        config.clone(),
+        token_service.clone(),
+    )
+    .await;
+    let jobs = app_service.jobs.clone();
+    let api_token_authenticator = app_service
+        .services
+        .user_service
+        .api_token_authenticator
+        .clone();
+
+    let mut schema = AppSchema::build(
+        Query::default(),
+        Mutation::default(),
+        Subscription::default(),
+    )
+    .data(app_service.clone());
+    // Production clients are built against a known schema; no need to serve it.
+    if config.rust_environment == Environment::Production {
+        schema = schema.disable_introspection();
+    }
+    if let Some(path) = &config.graphql_allow_list {
+        let allow_list =
+            OperationAllowList::from_file(path).expect("Unable to read the GraphQL allow list");
+        info!(
+            "Accepting only allow-listed GraphQL operations from {}",
+            path
+        );
+        schema = schema.extension(allow_list);
+    }
+    let schema = schema
+        .extension(automatic_persisted_queries(config.graphql_apq_cache_size))
+        .extension(RateLimit)
+        .extension(QueryLimits::from_config(&config))
+        .extension(ImpersonationLog)
+        .extension(Loaders)
+        .extension(Metrics)
+        .extension(ErrorCodes)
         .finish();
 
-    let router = Router::new()
+    let mut router = Router::new()
         .route("/graphql", post(graphql_handler))
-        .route("/playground", get(graphql_playground))
-        .route("/graphiql", get(graphiql))
+        .route("/.well-known/jwks.json", get(well_known::jwks))
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
+        .route("/metrics", get(metrics::metrics))
+        .nest(rest::API_V1_PATH, rest::router(app_service.clone()));
+    let explorers_enabled = config.rust_environment != Environment::Production;
+    if explorers_enabled {
+        router = router
+            .route("/playground", get(graphql_playground))
+            .route("/graphiql", get(graphiql));
+    }
+    let mut router = router
+        .layer(middleware::from_fn(metrics::track_requests))
+        // Added after the metrics layer: a subscription would be timed as one
+        // request lasting the whole connection.
+        .route(GRAPHQL_WS_PATH, get(graphql_ws_handler))
+        .layer(RequestBodyLimitLayer::new(config.max_request_body_bytes))
+        .layer(DefaultBodyLimit::max(config.max_request_body_bytes))
+        .layer(security::cors(&config.cors_allowed_origins))
+        .layer(middleware::from_fn(security::security_headers))
+        .layer(middleware::from_fn(request_metadata::propagate))
+        // Outside the request metadata layer, which reads the config to find the client IP.
+        .layer(Extension(app_service))
+        .layer(Extension(token_service))
+        .layer(Extension(api_token_authenticator))
         .with_state(schema);
+    if config.tls.is_some() {
+        router = router.layer(security::strict_transport_security());
+    }
 
-    let config = Config::build();
-
-    info!("GraphQL API at http:://localhost:{}/graphql", config.port);
+    // Config validation ensures the mock provider is configured when enabled.
+    if config.oidc_mock_provider
+        && let Some(provider) = config
+            .oidc_providers
+            .iter()
+            .find(|provider| provider.name == "mock")
+    {
+        let mock = MockOidcProvider::new(
+            provider.issuer.clone(),
+            provider.client_id.clone(),
+            provider.client_secret.expose().to_string(),
+        );
+        router = router.nest("/mock-oidc", mock.router());
+        info!("Mock OIDC provider at {}", provider.issuer);
+    }
 
+    let scheme = if config.tls.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    info!(
+        "GraphQL API at {}://localhost:{}/graphql",
+        scheme, config.port
+    );
+    if explorers_enabled {
+        info!(
+            "GraphQL Playground at {}://localhost:{}/playground",
+            scheme, config.port
+        );
+    }
     info!(
-        "GraphQL Playground at http:://localhost:{}/playground",
-        config.port
+        "REST API docs at {}://localhost:{}{}/docs",
+        scheme,
+        config.port,
+        rest::API_V1_PATH
     );
-    info!("Axum server at http:://localhost:{}", config.port);
 
-    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", config.port))
-        .await
-        .unwrap();
-    serve(listener, router).await.unwrap();
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
+    let shutdown_timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    let handle = Handle::new();
+    tokio::spawn({
+        let handle = handle.clone();
+        async move {
+            shutdown::signal().await;
+            info!(
+                "Shutting down, waiting up to {:?} for in-flight requests",
+                shutdown_timeout
+            );
+            handle.graceful_shutdown(Some(shutdown_timeout));
+        }
+    });
+
+    let service = router.into_make_service_with_connect_info::<SocketAddr>();
+    match &config.tls {
+        Some(tls) => {
+            rustls::crypto::ring::default_provider()
+                .install_default()
+                .expect("Unable to install the TLS crypto provider");
+            let tls_config = RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path)
+                .await
+                .expect("Unable to load the TLS certificate and key");
+            info!("Axum server at https://localhost:{}", config.port);
+            axum_server::bind_rustls(addr, tls_config)
+                .handle(handle)
+                .serve(service)
+                .await
+        }
+        None => {
+            info!("Axum server at http://localhost:{}", config.port);
+            axum_server::bind(addr).handle(handle).serve(service).await
+        }
+    }
+    .expect("Server failed");
+
+    if !jobs.drain(shutdown_timeout).await {
+        warn!("Background jobs were still running at shutdown");
+    }
+    info!("Server stopped");
+}
+
+/// Prints `err`, such as every invalid setting, and exits.
+fn exit_with(err: impl std::fmt::Display) -> ! {
+    eprintln!("{}", err);
+    std::process::exit(1)
+}
+
+/// `server migrate` applies pending migrations, `server migrate status` lists them.
+async fn migrate(config: &AppConfig, subcommand: Option<&str>) {
+    let storage = AppStorage::connect(StorageEngine::MongoDB, config).await;
+    match subcommand {
+        None => {
+            let applied = storage.migrate().await.expect("Migration failed");
+            info!("Applied migrations: {:?}", applied);
+        }
+        Some("status") => {
+            let status = storage
+                .migration_status()
+                .await
+                .expect("Unable to read migration status");
+            for migration in status {
+                let applied_at = migration
+                    .applied_at
+                    .map_or("pending".to_string(), |at| at.to_rfc3339());
+                println!(
+                    "{:>4}  {:<45} {}",
+                    migration.version, migration.name, applied_at
+                );
+            }
+        }
+        Some(other) => panic!("Unknown migrate subcommand: {}", other),
+    }
 }
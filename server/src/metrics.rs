@@ -0,0 +1,53 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use shared::metrics::{Counter, Histogram, LATENCY_BUCKETS, render};
+use user::{app::metrics::record_error, domain::errors::UserDomainError};
+
+static HTTP_REQUESTS: Counter = Counter::new(
+    "http_requests_total",
+    "HTTP requests served, by method, route and status",
+);
+static HTTP_REQUEST_DURATION: Histogram = Histogram::new(
+    "http_request_duration_seconds",
+    "HTTP request latency, by method and route",
+    LATENCY_BUCKETS,
+);
+
+/// Prometheus scrape endpoint.
+pub async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render(),
+    )
+}
+
+/// Records every request by its route pattern rather than its path, so ids
+/// in paths do not create new series. REST handlers leave the
+/// `UserDomainError` they failed with on the response to be counted here.
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched".to_string(), |path| path.as_str().to_string());
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    HTTP_REQUEST_DURATION
+        .observe_duration(&[("method", &method), ("route", &route)], started.elapsed());
+    HTTP_REQUESTS.inc(&[
+        ("method", &method),
+        ("route", &route),
+        ("status", response.status().as_str()),
+    ]);
+    if let Some(err) = response.extensions().get::<UserDomainError>() {
+        record_error("rest", &route, err);
+    }
+    response
+}
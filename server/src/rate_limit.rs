@@ -0,0 +1,72 @@
+use axum::{
+    Json, RequestExt,
+    extract::{MatchedPath, Request, State},
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use ports::app_service::AppService;
+use serde_json::json;
+use shared::{
+    auth::{AuthUser, RequestMetadata},
+    rate_limit::RateLimited,
+};
+
+use crate::{extractors::AxumAuthUser, rest::API_V1_PATH};
+
+/// Counts REST requests against the caller's buckets. Routes under `/auth`
+/// also take from the auth bucket of their route. Refused requests get a 429
+/// with `Retry-After`. Requests with a bad token are counted by IP and left
+/// for the handler to reject.
+pub async fn limit_requests(
+    State(app_service): State<AppService>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let user = request
+        .extract_parts::<AxumAuthUser>()
+        .await
+        .ok()
+        .map(AuthUser::from);
+    let client_ip = request
+        .extensions()
+        .get::<RequestMetadata>()
+        .and_then(|metadata| metadata.client_ip.clone());
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().trim_start_matches(API_V1_PATH).to_string());
+
+    let limiter = &app_service.rate_limiter;
+    let mut checked = limiter.check_api(user.as_ref(), client_ip.as_deref()).await;
+    if let Some(operation) = route.as_deref().filter(|route| route.starts_with("/auth/"))
+        && checked.is_ok()
+    {
+        checked = limiter
+            .check_auth(operation, user.as_ref(), client_ip.as_deref())
+            .await;
+    }
+    match checked {
+        Ok(()) => next.run(request).await,
+        Err(limited) => too_many_requests(limited),
+    }
+}
+
+fn too_many_requests(limited: RateLimited) -> Response {
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(json!({
+            "error": {
+                "code": "RATE_LIMITED",
+                "message": limited.to_string(),
+                "retry_after": limited.retry_after_secs(),
+            }
+        })),
+    )
+        .into_response();
+    response.headers_mut().insert(
+        header::RETRY_AFTER,
+        HeaderValue::from(limited.retry_after_secs()),
+    );
+    response
+}
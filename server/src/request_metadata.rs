@@ -0,0 +1,247 @@
+use axum::{
+    RequestExt,
+    extract::{MatchedPath, Request},
+    http::{HeaderMap, HeaderValue, header},
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::{global, propagation::Extractor, trace::TraceContextExt};
+use shared::auth::RequestMetadata;
+use tracing::{Instrument, Span, field::Empty, info_span, warn};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+use crate::extractors::AxumClientIp;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Opens the span every log line and child span of a request belongs to, and
+/// attaches [`RequestMetadata`] for the handlers. A valid `X-Request-Id` from
+/// the caller is kept, otherwise one is generated, and it is echoed on the
+/// response. A `traceparent` header makes the span part of the caller's trace.
+pub async fn propagate(mut request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    let request_id = headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map_or_else(|| Uuid::new_v4().to_string(), String::from);
+    let user_agent = header_str(headers, header::USER_AGENT.as_str());
+    let locale = headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(preferred_locale);
+
+    let method = request.method().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched".to_string(), |path| path.as_str().to_string());
+    let span = info_span!(
+        "http_request",
+        otel.name = format!("{} {}", method, route),
+        otel.kind = "server",
+        http.request.method = %method,
+        http.route = %route,
+        url.path = %request.uri().path(),
+        request_id = %request_id,
+        http.response.status_code = Empty,
+    );
+    let trace_id = join_caller_trace(&span, request.headers());
+
+    let AxumClientIp(client_ip) = request
+        .extract_parts::<AxumClientIp>()
+        .await
+        .unwrap_or_else(|never| match never {});
+    request.extensions_mut().insert(RequestMetadata {
+        request_id: request_id.clone(),
+        trace_id,
+        client_ip,
+        user_agent,
+        locale,
+    });
+
+    let mut response = next.run(request).instrument(span.clone()).await;
+    span.record("http.response.status_code", response.status().as_u16());
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Makes `span` a child of the `traceparent` in `headers`, if any, and returns
+/// the trace id for [`RequestMetadata`]: the exported span's when spans are
+/// exported, otherwise the caller's, so logs can still be matched to the
+/// caller's trace.
+fn join_caller_trace(span: &Span, headers: &HeaderMap) -> Option<String> {
+    let parent =
+        global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+    if parent.span().span_context().is_valid()
+        && let Err(err) = span.set_parent(parent.clone())
+    {
+        warn!("Unable to continue the caller's trace: {}", err);
+    }
+    [span.context(), parent]
+        .iter()
+        .map(|cx| cx.span().span_context().clone())
+        .find(|span_context| span_context.is_valid())
+        .map(|span_context| span_context.trace_id().to_string())
+}
+
+/// Request ids end up in logs and response headers, so only short, printable
+/// ASCII ids are taken from callers.
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+fn header_str(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+}
+
+/// The language of an `Accept-Language` header with the highest `q` weight,
+/// such as `fr` from `en;q=0.1, fr;q=0.9`. Languages without a weight count
+/// as `q=1`, ties go to the one listed first, and `q=0` or the `*` wildcard
+/// never match.
+fn preferred_locale(accept_language: &str) -> Option<String> {
+    let mut preferred: Option<(&str, f32)> = None;
+    for entry in accept_language.split(',') {
+        let mut params = entry.split(';');
+        let tag = params.next().unwrap_or_default().trim();
+        let weight = params
+            .filter_map(|param| param.trim().strip_prefix("q="))
+            .map(|q| q.trim().parse::<f32>().unwrap_or(0.0))
+            .next()
+            .unwrap_or(1.0);
+        if tag.is_empty() || tag == "*" || !(weight > 0.0 && weight <= 1.0) {
+            continue;
+        }
+        if preferred.is_none_or(|(_, best)| weight > best) {
+            preferred = Some((tag, weight));
+        }
+    }
+    preferred.map(|(tag, _)| tag.to_string())
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+    fn traceparent(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("traceparent", HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn preferred_locale_follows_q_weights() {
+        assert_eq!(
+            preferred_locale("en;q=0.1, fr;q=0.9"),
+            Some("fr".to_string())
+        );
+        assert_eq!(
+            preferred_locale("de;q=0.5, en-GB"),
+            Some("en-GB".to_string())
+        );
+        assert_eq!(
+            preferred_locale("en-GB,en;q=0.9"),
+            Some("en-GB".to_string())
+        );
+        assert_eq!(
+            preferred_locale("en;q=0.8, fr;q=0.8"),
+            Some("en".to_string())
+        );
+        assert_eq!(
+            preferred_locale("en; q=0.2, fr ; q=0.3"),
+            Some("fr".to_string())
+        );
+    }
+
+    #[test]
+    fn preferred_locale_skips_wildcards_and_excluded_languages() {
+        assert_eq!(preferred_locale("*, de;q=0.5"), Some("de".to_string()));
+        assert_eq!(preferred_locale("en;q=0, fr;q=0.1"), Some("fr".to_string()));
+        assert_eq!(
+            preferred_locale("en;q=abc, fr;q=0.1"),
+            Some("fr".to_string())
+        );
+        assert_eq!(preferred_locale("en;q=2, fr;q=0.1"), Some("fr".to_string()));
+        assert_eq!(preferred_locale("*"), None);
+        assert_eq!(preferred_locale(""), None);
+    }
+
+    #[test]
+    fn request_ids_must_be_short_printable_ascii() {
+        assert!(is_valid_request_id("req-1"));
+        assert!(is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN)));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+        assert!(!is_valid_request_id("req 1"));
+        assert!(!is_valid_request_id("req-1\n"));
+        assert!(!is_valid_request_id("réq-1"));
+    }
+
+    #[test]
+    fn exported_spans_continue_the_callers_trace() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = info_span!("http_request");
+            let headers = traceparent(&format!("00-{}-00f067aa0ba902b7-01", TRACE_ID));
+
+            assert_eq!(
+                join_caller_trace(&span, &headers).as_deref(),
+                Some(TRACE_ID)
+            );
+            assert_eq!(
+                span.context().span().span_context().trace_id().to_string(),
+                TRACE_ID
+            );
+        });
+    }
+
+    #[test]
+    fn unexported_spans_keep_the_callers_trace_id() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let span = info_span!("http_request");
+
+        let headers = traceparent(&format!("00-{}-00f067aa0ba902b7-01", TRACE_ID));
+        assert_eq!(
+            join_caller_trace(&span, &headers).as_deref(),
+            Some(TRACE_ID)
+        );
+        assert_eq!(
+            join_caller_trace(&span, &traceparent("00-invalid-01")),
+            None
+        );
+        assert_eq!(join_caller_trace(&span, &HeaderMap::new()), None);
+    }
+}
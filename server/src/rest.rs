@@ -0,0 +1,66 @@
+//! Versioned REST API for clients that cannot use GraphQL. It calls the same
+//! command and query handlers as the GraphQL API.
+use axum::{Router, middleware, routing::get};
+use ports::app_service::AppService;
+use shared::auth::{AppContext, AuthUser};
+use utoipa::OpenApi;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    extractors::{AxumAuthUser, AxumRequestMetadata},
+    rate_limit::limit_requests,
+};
+
+pub mod auth;
+pub mod error;
+pub mod impersonation_log;
+pub mod openapi;
+pub mod users;
+
+pub const API_V1_PATH: &str = "/api/v1";
+
+/// The API operations. Each one is added with `routes!`, which takes its
+/// method and path from the handler's `#[utoipa::path]`, so the router and
+/// [`openapi::document`] cannot disagree.
+fn api_routes() -> OpenApiRouter<AppService> {
+    OpenApiRouter::with_openapi(openapi::ApiDoc::openapi())
+        .routes(routes!(auth::sign_up))
+        .routes(routes!(auth::sign_in))
+        .routes(routes!(auth::verify_otp))
+        .routes(routes!(auth::verify_two_factor))
+        .routes(routes!(users::list_users))
+        .routes(routes!(users::get_user_by_id))
+        .routes(routes!(users::get_user_by_email))
+        .routes(routes!(users::get_user_by_username))
+        .routes(routes!(users::ban_user))
+        .routes(routes!(users::unban_user))
+        .routes(routes!(users::make_moderator))
+        .routes(routes!(users::award_badge))
+        .routes(routes!(users::revoke_badge))
+}
+
+/// Routes of `/api/v1`, to be nested under [`API_V1_PATH`].
+pub fn router<S>(app_service: AppService) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let (api, _) = api_routes().split_for_parts();
+    api.route("/openapi.json", get(openapi::openapi_json))
+        .route("/docs", get(openapi::swagger_ui))
+        .route("/redoc", get(openapi::redoc))
+        .route_layer(middleware::from_fn(error::add_request_id))
+        .route_layer(middleware::from_fn(
+            impersonation_log::log_impersonated_requests,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_service.clone(),
+            limit_requests,
+        ))
+        .with_state(app_service)
+}
+
+fn app_ctx(auth_user: AxumAuthUser, request: AxumRequestMetadata) -> AppContext {
+    AppContext::new()
+        .with_user(AuthUser::from(auth_user))
+        .with_request(&request.0)
+}
@@ -0,0 +1,176 @@
+use axum::{Json, extract::State, http::StatusCode};
+use ports::app_service::AppService;
+use serde::Serialize;
+use shared::command_handler::CommandHanlder;
+use user::app::{
+    command::{
+        sign_in::SignIn, sign_up::SignUp, verify_otp::VerifyOtp, verify_two_factor::VerifyTwoFactor,
+    },
+    session_issuer::SignInOutcome,
+};
+
+use utoipa::ToSchema;
+
+use super::{
+    app_ctx,
+    error::{ApiError, ErrorResponse},
+};
+use crate::extractors::{AxumAuthUser, AxumRequestMetadata};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MessageResponse {
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TokenResponse {
+    pub token: String,
+}
+
+/// Either `token` is set, or `two_factor_challenge` must be completed with
+/// `/auth/verify-two-factor`.
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct SignInResponse {
+    pub token: Option<String>,
+    pub two_factor_challenge: Option<String>,
+    pub two_factor_enrollment_required: bool,
+}
+
+impl From<SignInOutcome> for SignInResponse {
+    fn from(outcome: SignInOutcome) -> Self {
+        match outcome {
+            SignInOutcome::Token {
+                token,
+                two_factor_enrollment_required,
+            } => SignInResponse {
+                token: Some(token),
+                two_factor_enrollment_required,
+                ..Default::default()
+            },
+            SignInOutcome::TwoFactorChallenge(challenge) => SignInResponse {
+                two_factor_challenge: Some(challenge),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/sign-up",
+    tag = "auth",
+    summary = "Create an account and send a sign in OTP",
+    request_body = SignUp,
+    responses(
+        (status = 201, description = "Account created", body = MessageResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 409, description = "Username or email taken", body = ErrorResponse),
+    )
+)]
+pub async fn sign_up(
+    State(app_service): State<AppService>,
+    auth_user: AxumAuthUser,
+    request: AxumRequestMetadata,
+    Json(cmd): Json<SignUp>,
+) -> Result<(StatusCode, Json<MessageResponse>), ApiError> {
+    app_service
+        .services
+        .user_service
+        .command_handler
+        .sign_up
+        .handle(&app_ctx(auth_user, request), cmd)
+        .await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(MessageResponse {
+            message: "Sign up was successful. Please check your email for the OTP.".to_string(),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/sign-in",
+    tag = "auth",
+    summary = "Send a sign in OTP",
+    request_body = SignIn,
+    responses(
+        (status = 200, description = "OTP sent", body = MessageResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    )
+)]
+pub async fn sign_in(
+    State(app_service): State<AppService>,
+    auth_user: AxumAuthUser,
+    request: AxumRequestMetadata,
+    Json(cmd): Json<SignIn>,
+) -> Result<Json<MessageResponse>, ApiError> {
+    app_service
+        .services
+        .user_service
+        .command_handler
+        .sign_in
+        .handle(&app_ctx(auth_user, request), cmd)
+        .await?;
+    Ok(Json(MessageResponse {
+        message: "Please check your email for the OTP to complete your sign in process."
+            .to_string(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify-otp",
+    tag = "auth",
+    summary = "Exchange an OTP for an access token or a 2FA challenge",
+    request_body = VerifyOtp,
+    responses(
+        (status = 200, description = "Signed in", body = SignInResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    )
+)]
+pub async fn verify_otp(
+    State(app_service): State<AppService>,
+    auth_user: AxumAuthUser,
+    request: AxumRequestMetadata,
+    Json(cmd): Json<VerifyOtp>,
+) -> Result<Json<SignInResponse>, ApiError> {
+    let outcome = app_service
+        .services
+        .user_service
+        .command_handler
+        .verify_otp
+        .handle(&app_ctx(auth_user, request), cmd)
+        .await?;
+    Ok(Json(outcome.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify-two-factor",
+    tag = "auth",
+    summary = "Complete a 2FA challenge with a TOTP or recovery code",
+    request_body = VerifyTwoFactor,
+    responses(
+        (status = 200, description = "Signed in", body = TokenResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    )
+)]
+pub async fn verify_two_factor(
+    State(app_service): State<AppService>,
+    auth_user: AxumAuthUser,
+    request: AxumRequestMetadata,
+    Json(cmd): Json<VerifyTwoFactor>,
+) -> Result<Json<TokenResponse>, ApiError> {
+    let token = app_service
+        .services
+        .user_service
+        .command_handler
+        .verify_two_factor
+        .handle(&app_ctx(auth_user, request), cmd)
+        .await?;
+    Ok(Json(TokenResponse { token }))
+}
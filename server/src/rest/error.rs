@@ -0,0 +1,249 @@
+use axum::{
+    Json,
+    body::Body,
+    extract::Request,
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use serde::Serialize;
+use shared::{auth::RequestMetadata, errors::validation::FieldError};
+use tracing::error;
+use user::domain::{errors::UserDomainError, user_auth::errors::UserAuthError};
+use utoipa::ToSchema;
+
+/// A `UserDomainError` answered with the matching HTTP status and a
+/// `{"error": {"code": ..., "message": ...}}` body. Invalid input also lists
+/// the failed `fields`, and errors that pass with time give `retry_after`
+/// in seconds. Server errors only give their code and, through
+/// [`add_request_id`], the id of the request to quote when reporting them.
+#[derive(Debug)]
+pub struct ApiError(pub UserDomainError);
+
+/// The body of every error response.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: ErrorBody,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    /// Stable error code, such as `USER_NOT_FOUND` or `BAD_USER_INPUT`.
+    pub code: String,
+    pub message: String,
+    /// Failed validation rules, for `BAD_USER_INPUT`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+    /// Seconds to wait before retrying.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<i64>,
+    /// Id of the request, for server errors. It is also on the server's log
+    /// lines and in the `X-Request-Id` header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ErrorResponse {
+    /// The body of a server error, which hides what went wrong.
+    fn internal(code: &str, request_id: Option<String>) -> Self {
+        Self {
+            error: ErrorBody {
+                code: code.to_string(),
+                message: "Internal server error".to_string(),
+                fields: vec![],
+                retry_after: None,
+                request_id,
+            },
+        }
+    }
+}
+
+impl From<UserDomainError> for ApiError {
+    fn from(err: UserDomainError) -> Self {
+        Self(err)
+    }
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self(UserDomainError::Validation(message.into()))
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match &self.0 {
+            UserDomainError::UserNotFound => StatusCode::NOT_FOUND,
+            UserDomainError::UsernameTaken
+            | UserDomainError::UsernameOrEmailTaken
+            | UserDomainError::UsernameChangeTooSoon(_) => StatusCode::CONFLICT,
+            UserDomainError::InvalidEmail
+            | UserDomainError::Validation(_)
+            | UserDomainError::InvalidInput(_)
+            | UserDomainError::UnableToVerifyEmail => StatusCode::BAD_REQUEST,
+            UserDomainError::Unauthorized | UserDomainError::UnverifiedEmail => {
+                StatusCode::FORBIDDEN
+            }
+            UserDomainError::InvalidToken => StatusCode::UNAUTHORIZED,
+            UserDomainError::Authorization(err) => auth_error_status(err),
+            UserDomainError::Database(_)
+            | UserDomainError::Internal(_)
+            | UserDomainError::InvalidTransaction
+            | UserDomainError::TransactionFailed => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+fn auth_error_status(err: &UserAuthError) -> StatusCode {
+    match err {
+        UserAuthError::InvalidToken => StatusCode::UNAUTHORIZED,
+        UserAuthError::OtpNotFound
+        | UserAuthError::OtpAlreadyUsed
+        | UserAuthError::OtpExpired
+        | UserAuthError::InvalidOtp
+        | UserAuthError::MissMatchOtp
+        | UserAuthError::InvalidTwoFactorCode
+        | UserAuthError::InvalidTwoFactorChallenge
+        | UserAuthError::UnknownOidcProvider(_)
+        | UserAuthError::InvalidOidcState
+        | UserAuthError::InvalidApiTokenScopes
+        | UserAuthError::InvalidApiTokenExpiry => StatusCode::BAD_REQUEST,
+        UserAuthError::TooManyAttempts
+        | UserAuthError::OtpResendTooSoon(_)
+        | UserAuthError::TooManyOtpRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+        UserAuthError::TwoFactorRequired | UserAuthError::OidcEmailNotVerified => {
+            StatusCode::FORBIDDEN
+        }
+        UserAuthError::TwoFactorAlreadyEnabled
+        | UserAuthError::TwoFactorNotEnabled
+        | UserAuthError::TwoFactorNotEnrolled
+        | UserAuthError::OidcIdentityAlreadyLinked => StatusCode::CONFLICT,
+        UserAuthError::ApiTokenNotFound => StatusCode::NOT_FOUND,
+        UserAuthError::OidcProvider(_) => StatusCode::BAD_GATEWAY,
+        UserAuthError::Database | UserAuthError::InvalidTransaction => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let retry_after = self
+            .0
+            .retry_at()
+            .map(|retry_at| (retry_at - Utc::now()).num_seconds().max(1));
+        // Server side failures are logged, not shown to the caller.
+        let body = if status.is_server_error() {
+            error!("REST request failed: {}", self.0);
+            ErrorResponse::internal(self.0.code(), None)
+        } else {
+            ErrorResponse {
+                error: ErrorBody {
+                    code: self.0.code().to_string(),
+                    message: self.0.to_string(),
+                    fields: self.0.field_errors().to_vec(),
+                    retry_after,
+                    request_id: None,
+                },
+            }
+        };
+        let mut response = (status, Json(body)).into_response();
+        if let Some(retry_after) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        response.extensions_mut().insert(self.0);
+        response
+    }
+}
+
+/// Adds the request id to the body of server errors, which `ApiError` cannot
+/// see, like the `correlationId` of GraphQL errors.
+pub async fn add_request_id(request: Request, next: Next) -> Response {
+    let request_id = request
+        .extensions()
+        .get::<RequestMetadata>()
+        .map(|request| request.request_id.clone());
+    let mut response = next.run(request).await;
+    if response.status().is_server_error()
+        && let Some(request_id) = request_id
+        && let Some(err) = response.extensions().get::<UserDomainError>()
+        && let Ok(body) = serde_json::to_vec(&ErrorResponse::internal(err.code(), Some(request_id)))
+    {
+        *response.body_mut() = Body::from(body);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, routing::get};
+    use http_body_util::BodyExt;
+    use serde_json::{Value, json};
+    use tower::ServiceExt;
+
+    async fn call(err: UserDomainError, metadata: Option<RequestMetadata>) -> (StatusCode, Value) {
+        let app = Router::new()
+            .route("/", get(move || async move { ApiError(err) }))
+            .route_layer(axum::middleware::from_fn(add_request_id));
+        let mut request = Request::new(Body::empty());
+        if let Some(metadata) = metadata {
+            request.extensions_mut().insert(metadata);
+        }
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn metadata(request_id: &str) -> RequestMetadata {
+        RequestMetadata {
+            request_id: request_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn server_errors_give_the_request_id() {
+        let (status, body) = call(
+            UserDomainError::Internal("connection reset".to_string()),
+            Some(metadata("req-1")),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            body,
+            json!({
+                "error": {
+                    "code": "INTERNAL_SERVER_ERROR",
+                    "message": "Internal server error",
+                    "request_id": "req-1",
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_left_as_they_are() {
+        let (status, body) = call(UserDomainError::UserNotFound, Some(metadata("req-1"))).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], "USER_NOT_FOUND");
+        assert_eq!(body["error"].get("request_id"), None);
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_masked_without_request_metadata() {
+        let (_, body) = call(
+            UserDomainError::Internal("connection reset".to_string()),
+            None,
+        )
+        .await;
+
+        assert_eq!(body["error"]["message"], "Internal server error");
+        assert_eq!(body["error"].get("request_id"), None);
+    }
+}
@@ -0,0 +1,45 @@
+use axum::{
+    RequestExt,
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use shared::auth::AuthUser;
+use tracing::info;
+
+use crate::extractors::AxumAuthUser;
+
+/// Logs every REST request made with an impersonation token, naming both the
+/// admin and the user acted as, like the GraphQL `ImpersonationLog` extension.
+pub async fn log_impersonated_requests(mut request: Request, next: Next) -> Response {
+    let auth_user = request
+        .extract_parts::<AxumAuthUser>()
+        .await
+        .ok()
+        .map(AuthUser::from);
+    let Some((auth_user, actor)) = auth_user
+        .as_ref()
+        .and_then(|user| user.impersonator().map(|actor| (user, actor)))
+    else {
+        return next.run(request).await;
+    };
+
+    let route = format!(
+        "{} {}",
+        request.method(),
+        request
+            .extensions()
+            .get::<MatchedPath>()
+            .map_or(request.uri().path(), |path| path.as_str())
+    );
+    let response = next.run(request).await;
+    info!(
+        impersonator_id = %actor.id,
+        subject_id = %auth_user.0.id,
+        route = %route,
+        status = response.status().as_u16(),
+        success = response.status().is_success(),
+        "Action under impersonation"
+    );
+    response
+}
@@ -0,0 +1,223 @@
+//! OpenAPI 3 description of `/api/v1`, plus Swagger UI and Redoc pages that
+//! render it. The paths come from the `#[utoipa::path]` attribute of each
+//! handler registered in [`super::api_routes`].
+use std::sync::LazyLock;
+
+use axum::{
+    Json,
+    response::{Html, IntoResponse},
+};
+use utoipa::{
+    Modify, OpenApi, ToSchema,
+    openapi::{
+        self, HeaderBuilder, ObjectBuilder, RefOr, ResponseBuilder, Type,
+        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    },
+};
+
+use super::{API_V1_PATH, error::ErrorResponse};
+
+const SWAGGER_UI_VERSION: &str = "5.17.14";
+const REDOC_VERSION: &str = "2.1.5";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Social App API",
+        version = "1.0.0",
+        description = "REST access to the same operations as the GraphQL API. Send a JWT or an API token as `Authorization: Bearer <token>`; requests without one are made as a guest. Requests are rate limited per user, or per IP for guests, and the auth routes each have a stricter limit of their own."
+    ),
+    servers((url = "/api/v1")),
+    security(("bearerAuth" = []), ()),
+    modifiers(&BearerAuth)
+)]
+pub struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "bearerAuth",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+    }
+}
+
+/// Adds the error responses every operation can return.
+struct CommonErrors;
+
+impl Modify for CommonErrors {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        let error = |description: &str| {
+            ResponseBuilder::new().description(description).content(
+                "application/json",
+                openapi::ContentBuilder::new()
+                    .schema(Some(RefOr::Ref(openapi::Ref::from_schema_name(
+                        ErrorResponse::name(),
+                    ))))
+                    .build(),
+            )
+        };
+        for path in openapi.paths.paths.values_mut() {
+            for operation in [
+                &mut path.get,
+                &mut path.post,
+                &mut path.put,
+                &mut path.patch,
+                &mut path.delete,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                let responses = &mut operation.responses.responses;
+                responses.insert("401".into(), error("Invalid bearer token").build().into());
+                responses.insert(
+                    "429".into(),
+                    error("Too many requests")
+                        .header(
+                            "Retry-After",
+                            HeaderBuilder::new()
+                                .description(Some("Seconds to wait before retrying"))
+                                .schema(ObjectBuilder::new().schema_type(Type::Integer))
+                                .build(),
+                        )
+                        .build()
+                        .into(),
+                );
+                responses.insert("500".into(), error("Internal server error").build().into());
+            }
+        }
+    }
+}
+
+static DOCUMENT: LazyLock<openapi::OpenApi> = LazyLock::new(|| {
+    let mut document = super::api_routes().into_openapi();
+    document.info.license = None;
+    CommonErrors.modify(&mut document);
+    document
+});
+
+pub fn document() -> &'static openapi::OpenApi {
+    &DOCUMENT
+}
+
+pub async fn openapi_json() -> impl IntoResponse {
+    Json(document())
+}
+
+pub async fn swagger_ui() -> impl IntoResponse {
+    Html(format!(
+        r##"<!DOCTYPE html>
+<html>
+  <head>
+    <title>Social App API</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@{version}/swagger-ui.css" />
+  </head>
+  <body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@{version}/swagger-ui-bundle.js"></script>
+    <script>
+      SwaggerUIBundle({{ url: "{path}/openapi.json", dom_id: "#swagger-ui" }});
+    </script>
+  </body>
+</html>"##,
+        version = SWAGGER_UI_VERSION,
+        path = API_V1_PATH
+    ))
+}
+
+pub async fn redoc() -> impl IntoResponse {
+    Html(format!(
+        r##"<!DOCTYPE html>
+<html>
+  <head>
+    <title>Social App API</title>
+  </head>
+  <body>
+    <redoc spec-url="{path}/openapi.json"></redoc>
+    <script src="https://cdn.redoc.ly/redoc/v{version}/bundles/redoc.standalone.js"></script>
+  </body>
+</html>"##,
+        version = REDOC_VERSION,
+        path = API_V1_PATH
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rest::users::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+    use serde_json::{Value, json};
+
+    fn document_json() -> Value {
+        serde_json::to_value(document()).unwrap()
+    }
+
+    #[test]
+    fn document_lists_every_route() {
+        let document = document_json();
+        let mut operations: Vec<String> = document["paths"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .flat_map(|(path, item)| {
+                item.as_object()
+                    .unwrap()
+                    .keys()
+                    .map(move |method| format!("{} {}", method.to_uppercase(), path))
+            })
+            .collect();
+        operations.sort();
+
+        assert_eq!(
+            operations,
+            vec![
+                "DELETE /users/{id}/badges/{badge}",
+                "GET /users",
+                "GET /users/by-email/{email}",
+                "GET /users/by-username/{username}",
+                "GET /users/{id}",
+                "POST /auth/sign-in",
+                "POST /auth/sign-up",
+                "POST /auth/verify-otp",
+                "POST /auth/verify-two-factor",
+                "POST /users/{id}/badges",
+                "POST /users/{id}/ban",
+                "POST /users/{id}/make-moderator",
+                "POST /users/{id}/unban",
+            ]
+        );
+    }
+
+    #[test]
+    fn every_operation_documents_common_errors() {
+        let document = document_json();
+        for (path, item) in document["paths"].as_object().unwrap() {
+            for (method, operation) in item.as_object().unwrap() {
+                for status in ["401", "429", "500"] {
+                    assert_eq!(
+                        operation["responses"][status]["content"]["application/json"]["schema"],
+                        json!({ "$ref": "#/components/schemas/ErrorResponse" }),
+                        "{} {} is missing {}",
+                        method,
+                        path,
+                        status
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn page_size_limits_match_the_handler() {
+        let document = document_json();
+        let first = &document["paths"]["/users"]["get"]["parameters"][0];
+        assert_eq!(first["name"], "first");
+        assert_eq!(first["schema"]["maximum"], json!(MAX_PAGE_SIZE));
+        assert_eq!(first["schema"]["default"], json!(DEFAULT_PAGE_SIZE));
+    }
+}
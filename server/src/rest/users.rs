@@ -0,0 +1,382 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, Utc};
+use ports::app_service::AppService;
+use serde::{Deserialize, Serialize};
+use shared::{command_handler::CommandHanlder, query_handler::QueryHandler};
+use user::{
+    app::{
+        command::{
+            award_badge::AwardBadge, ban_user::BanUser, make_moderator::MakeModerator,
+            revoke_badge::RevokeBadge, unban_user::UnbanUser,
+        },
+        query::{
+            user_by_email::GetUserByEmail, user_by_id::GetUserById,
+            user_by_username::GetUserByUsername,
+        },
+    },
+    domain::{
+        user::BanType,
+        user_read_model::{GetUsersOptions, SortDirection, UserReadModel},
+    },
+};
+
+use utoipa::{IntoParams, ToSchema};
+
+use super::{
+    app_ctx,
+    error::{ApiError, ErrorResponse},
+};
+use crate::extractors::{AxumAuthUser, AxumRequestMetadata};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListUsersParams {
+    #[param(minimum = 1, maximum = 100, default = 20)]
+    pub first: Option<u32>,
+    /// `end_cursor` of the previous page.
+    pub after: Option<String>,
+    /// `asc` or `desc` by sign up time. Newest first by default.
+    pub sort: Option<String>,
+}
+
+impl TryFrom<ListUsersParams> for GetUsersOptions {
+    type Error = ApiError;
+
+    fn try_from(params: ListUsersParams) -> Result<Self, Self::Error> {
+        let first = params.first.unwrap_or(DEFAULT_PAGE_SIZE);
+        if !(1..=MAX_PAGE_SIZE).contains(&first) {
+            return Err(ApiError::bad_request(format!(
+                "first must be between 1 and {}",
+                MAX_PAGE_SIZE
+            )));
+        }
+        if let Some(after) = &params.after {
+            DateTime::parse_from_rfc3339(after)
+                .map_err(|_| ApiError::bad_request("after is not a valid cursor"))?;
+        }
+        let sort_direction = match params.sort.as_deref() {
+            None | Some("desc") => SortDirection::DESC,
+            Some("asc") => SortDirection::ASC,
+            Some(_) => return Err(ApiError::bad_request("sort must be asc or desc")),
+        };
+        Ok(GetUsersOptions {
+            first,
+            after: params.after,
+            sort_direction,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserPage {
+    pub data: Vec<UserReadModel>,
+    pub has_next: bool,
+    /// Pass as `after` to get the next page.
+    pub end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BanUserBody {
+    pub reason: String,
+    /// End of the ban. The ban is indefinite when omitted.
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AwardBadgeBody {
+    pub badge: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "users",
+    summary = "List users",
+    params(ListUsersParams),
+    responses(
+        (status = 200, description = "A page of users", body = UserPage),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Not allowed", body = ErrorResponse),
+    )
+)]
+pub async fn list_users(
+    State(app_service): State<AppService>,
+    auth_user: AxumAuthUser,
+    request: AxumRequestMetadata,
+    Query(params): Query<ListUsersParams>,
+) -> Result<Json<UserPage>, ApiError> {
+    let page = app_service
+        .services
+        .user_service
+        .query_handler
+        .get_users
+        .handle(&app_ctx(auth_user, request), params.try_into()?)
+        .await?;
+    let end_cursor = page.data.last().map(|user| user.created_at.to_rfc3339());
+    Ok(Json(UserPage {
+        data: page.data,
+        has_next: page.pagination_info.has_next,
+        end_cursor,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{id}",
+    tag = "users",
+    summary = "Get a user by id",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "The user", body = UserReadModel),
+        (status = 403, description = "Not allowed", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_user_by_id(
+    State(app_service): State<AppService>,
+    auth_user: AxumAuthUser,
+    request: AxumRequestMetadata,
+    Path(id): Path<String>,
+) -> Result<Json<UserReadModel>, ApiError> {
+    let user = app_service
+        .services
+        .user_service
+        .query_handler
+        .get_user_by_id
+        .handle(&app_ctx(auth_user, request), GetUserById { id })
+        .await?;
+    Ok(Json(user))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/by-email/{email}",
+    tag = "users",
+    summary = "Get a user by email",
+    params(("email" = String, Path)),
+    responses(
+        (status = 200, description = "The user", body = UserReadModel),
+        (status = 403, description = "Not allowed", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_user_by_email(
+    State(app_service): State<AppService>,
+    auth_user: AxumAuthUser,
+    request: AxumRequestMetadata,
+    Path(email): Path<String>,
+) -> Result<Json<UserReadModel>, ApiError> {
+    let user = app_service
+        .services
+        .user_service
+        .query_handler
+        .get_user_by_email
+        .handle(&app_ctx(auth_user, request), GetUserByEmail { email })
+        .await?;
+    Ok(Json(user))
+}
+
+/// Resolves current and previous usernames.
+#[utoipa::path(
+    get,
+    path = "/users/by-username/{username}",
+    tag = "users",
+    summary = "Get a user by current or previous username",
+    params(("username" = String, Path)),
+    responses(
+        (status = 200, description = "The user", body = UserReadModel),
+        (status = 403, description = "Not allowed", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_user_by_username(
+    State(app_service): State<AppService>,
+    auth_user: AxumAuthUser,
+    request: AxumRequestMetadata,
+    Path(username): Path<String>,
+) -> Result<Json<UserReadModel>, ApiError> {
+    let user = app_service
+        .services
+        .user_service
+        .query_handler
+        .get_user_by_username
+        .handle(&app_ctx(auth_user, request), GetUserByUsername { username })
+        .await?;
+    Ok(Json(user))
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/ban",
+    tag = "moderation",
+    summary = "Ban a user",
+    params(("id" = String, Path)),
+    request_body = BanUserBody,
+    responses(
+        (status = 204, description = "Done"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Not allowed", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    )
+)]
+pub async fn ban_user(
+    State(app_service): State<AppService>,
+    auth_user: AxumAuthUser,
+    request: AxumRequestMetadata,
+    Path(user_id): Path<String>,
+    Json(body): Json<BanUserBody>,
+) -> Result<StatusCode, ApiError> {
+    let ban_type = match body.until {
+        Some(to) => BanType::Definite {
+            from: Utc::now(),
+            to,
+        },
+        None => BanType::Indefinite,
+    };
+    app_service
+        .services
+        .user_service
+        .command_handler
+        .ban_user
+        .handle(
+            &app_ctx(auth_user, request),
+            BanUser {
+                user_id,
+                reason: body.reason,
+                ban_type,
+            },
+        )
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/unban",
+    tag = "moderation",
+    summary = "Lift a user's ban",
+    params(("id" = String, Path)),
+    responses(
+        (status = 204, description = "Done"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Not allowed", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    )
+)]
+pub async fn unban_user(
+    State(app_service): State<AppService>,
+    auth_user: AxumAuthUser,
+    request: AxumRequestMetadata,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    app_service
+        .services
+        .user_service
+        .command_handler
+        .unban_user
+        .handle(&app_ctx(auth_user, request), UnbanUser { user_id })
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/make-moderator",
+    tag = "moderation",
+    summary = "Promote a user to moderator",
+    params(("id" = String, Path)),
+    responses(
+        (status = 204, description = "Done"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Not allowed", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    )
+)]
+pub async fn make_moderator(
+    State(app_service): State<AppService>,
+    auth_user: AxumAuthUser,
+    request: AxumRequestMetadata,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    app_service
+        .services
+        .user_service
+        .command_handler
+        .make_moderator
+        .handle(&app_ctx(auth_user, request), MakeModerator { user_id })
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/badges",
+    tag = "moderation",
+    summary = "Award a badge",
+    params(("id" = String, Path)),
+    request_body = AwardBadgeBody,
+    responses(
+        (status = 204, description = "Done"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Not allowed", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    )
+)]
+pub async fn award_badge(
+    State(app_service): State<AppService>,
+    auth_user: AxumAuthUser,
+    request: AxumRequestMetadata,
+    Path(user_id): Path<String>,
+    Json(body): Json<AwardBadgeBody>,
+) -> Result<StatusCode, ApiError> {
+    app_service
+        .services
+        .user_service
+        .command_handler
+        .award_badge
+        .handle(
+            &app_ctx(auth_user, request),
+            AwardBadge {
+                user_id,
+                badge: body.badge,
+            },
+        )
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/{id}/badges/{badge}",
+    tag = "moderation",
+    summary = "Revoke a badge",
+    params(("id" = String, Path), ("badge" = String, Path)),
+    responses(
+        (status = 204, description = "Done"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Not allowed", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    )
+)]
+pub async fn revoke_badge(
+    State(app_service): State<AppService>,
+    auth_user: AxumAuthUser,
+    request: AxumRequestMetadata,
+    Path((user_id, badge)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    app_service
+        .services
+        .user_service
+        .command_handler
+        .revoke_badge
+        .handle(&app_ctx(auth_user, request), RevokeBadge { user_id, badge })
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,24 @@
+use tokio::signal;
+use tracing::info;
+
+/// Resolves on Ctrl+C, or on SIGTERM as sent by container runtimes before
+/// they kill the process.
+pub async fn signal() {
+    let ctrl_c = async {
+        signal::ctrl_c().await.expect("Unable to listen for Ctrl+C");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Unable to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received Ctrl+C"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
@@ -0,0 +1,68 @@
+//! Logging and tracing setup. Logs go to stdout as text or JSON; spans are
+//! also exported over OTLP when `OTEL_EXPORTER_OTLP_ENDPOINT` is set. The level
+//! comes from `RUST_LOG` and defaults to `info`.
+use opentelemetry::{global, trace::TracerProvider as _};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
+use shared::config::{AppConfig, LogFormat};
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry, fmt, layer::Layered, layer::SubscriberExt, util::SubscriberInitExt,
+};
+
+type BoxedLayer = Box<dyn Layer<Layered<EnvFilter, Registry>> + Send + Sync>;
+
+/// Flushes exported spans when dropped. Keep it alive until the server stops.
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take()
+            && let Err(err) = provider.shutdown()
+        {
+            eprintln!("Unable to flush spans: {}", err);
+        }
+    }
+}
+
+pub fn init(config: &AppConfig) -> Telemetry {
+    // `traceparent` is read from and written to requests whether or not spans
+    // are exported, so callers' trace ids still show up in the logs.
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let mut layers: Vec<BoxedLayer> = vec![match config.log_format {
+        LogFormat::Text => fmt::layer().boxed(),
+        LogFormat::Json => fmt::layer().json().boxed(),
+    }];
+
+    let provider = config.otel_exporter_otlp_endpoint.as_ref().map(|endpoint| {
+        let exporter = SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()
+            .expect("Unable to create the OTLP span exporter");
+        SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(config.otel_service_name.clone())
+                    .build(),
+            )
+            .build()
+    });
+    if let Some(provider) = &provider {
+        let tracer = provider.tracer(config.otel_service_name.clone());
+        layers.push(tracing_opentelemetry::layer().with_tracer(tracer).boxed());
+    }
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(layers)
+        .init();
+    if let Some(endpoint) = &config.otel_exporter_otlp_endpoint {
+        tracing::info!("Exporting spans to {}", endpoint);
+    }
+    Telemetry { provider }
+}
@@ -0,0 +1,10 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, response::IntoResponse};
+use shared::auth::token_service::TokenService;
+
+/// Public keys that verify our access tokens, for services that do not hold
+/// the signing key.
+pub async fn jwks(Extension(token_service): Extension<Arc<dyn TokenService>>) -> impl IntoResponse {
+    Json(token_service.jwks())
+}